@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use log::debug;
+use msnp::msnp::error::CommandError;
+use msnp::msnp::raw_command_parser::{RawCommand, RawCommandParser};
+use msnp::shared::traits::{IntoBytes, TryFromRawCommand};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+
+use crate::error::ClientError;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+//A client side MSNP socket, generic over the server command set (NS or SB).
+pub struct MsnpConnection<C: TryFromRawCommand<Err = CommandError>> {
+    name: &'static str,
+    read: OwnedReadHalf,
+    write: OwnedWriteHalf,
+    parser: RawCommandParser,
+    parsed: VecDeque<RawCommand>,
+    //Commands received while waiting for something else, handed out by the next recv.
+    backlog: VecDeque<C>,
+    tr_id: u128,
+    timeout: Duration,
+}
+
+impl<C: TryFromRawCommand<Err = CommandError>> MsnpConnection<C> {
+
+    pub async fn connect(name: &'static str, address: &str) -> Result<Self, ClientError> {
+        let socket = TcpStream::connect(address).await?;
+        let (read, write) = socket.into_split();
+
+        Ok(Self {
+            name,
+            read,
+            write,
+            parser: RawCommandParser::new(),
+            parsed: VecDeque::new(),
+            backlog: VecDeque::new(),
+            tr_id: 0,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn next_tr_id(&mut self) -> u128 {
+        self.tr_id += 1;
+        self.tr_id
+    }
+
+    pub async fn send(&mut self, command: impl IntoBytes) -> Result<(), ClientError> {
+        let bytes = command.into_bytes();
+        debug!("{} >> | {}", self.name, String::from_utf8_lossy(&bytes).lines().next().unwrap_or_default());
+        self.write.write_all(&bytes).await?;
+        Ok(())
+    }
+
+    pub async fn recv(&mut self) -> Result<C, ClientError> {
+        if let Some(command) = self.backlog.pop_front() {
+            return Ok(command);
+        }
+
+        let timeout = self.timeout;
+        tokio::time::timeout(timeout, self.read_command())
+            .await
+            .map_err(|_| ClientError::Timeout { expected: format!("a command from {}", self.name) })?
+    }
+
+    //Reads until `matcher` accepts a command, everything it hands back is kept for later recv calls.
+    //Commands are handed back boxed, a whole server command is too big to return in a Result.
+    pub async fn wait_for<T>(&mut self, expected: &str, mut matcher: impl FnMut(C) -> Result<T, Box<C>>) -> Result<T, ClientError> {
+        let mut skipped = VecDeque::new();
+        let mut candidates = std::mem::take(&mut self.backlog);
+
+        let found = loop {
+            let command = match candidates.pop_front() {
+                Some(command) => command,
+                None => match tokio::time::timeout(self.timeout, self.read_command()).await {
+                    Ok(command) => command?,
+                    Err(_) => break None,
+                }
+            };
+
+            match matcher(command) {
+                Ok(found) => break Some(found),
+                Err(command) => skipped.push_back(*command),
+            }
+        };
+
+        skipped.append(&mut candidates);
+        self.backlog = skipped;
+
+        found.ok_or(ClientError::Timeout { expected: expected.to_string() })
+    }
+
+    async fn read_command(&mut self) -> Result<C, ClientError> {
+        let mut buffer = [0u8; 2048];
+
+        while self.parsed.is_empty() {
+            let bytes_read = self.read.read(&mut buffer).await?;
+            if bytes_read == 0 {
+                return Err(ClientError::Closed);
+            }
+
+            self.parsed.extend(self.parser.parse_message(&buffer[..bytes_read])?);
+        }
+
+        let raw = self.parsed.pop_front().expect("parsed commands not to be empty");
+        debug!("{} << | {}", self.name, raw.get_command());
+        Ok(C::try_from_raw(raw)?)
+    }
+
+    pub async fn shutdown(mut self) -> Result<(), ClientError> {
+        self.write.shutdown().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use msnp::msnp::notification::command::command::NotificationServerCommand;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    use super::MsnpConnection;
+
+    #[tokio::test]
+    async fn wait_for_keeps_skipped_commands() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"QNG 50\r\nVER 1 MSNP18\r\n").await.unwrap();
+            socket
+        });
+
+        let mut connection = MsnpConnection::<NotificationServerCommand>::connect("NS", &address).await.unwrap();
+        let _socket = server.await.unwrap();
+
+        let tr_id = connection.wait_for("VER", |command| match command {
+            NotificationServerCommand::VER(ver) => Ok(ver.tr_id),
+            other => Err(Box::new(other)),
+        }).await.unwrap();
+        assert_eq!(tr_id, 1);
+
+        let skipped = connection.recv().await.unwrap();
+        assert!(matches!(skipped, NotificationServerCommand::QNG(50)));
+    }
+}
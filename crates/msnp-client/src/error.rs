@@ -0,0 +1,36 @@
+use msnp::msnp::error::CommandError;
+use msnp::shared::command::err::MsnpError;
+use msnp::shared::errors::IdentifierError;
+use msnp::soap::error::SoapMarshallError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Command(#[from] CommandError),
+
+    #[error(transparent)]
+    Identifier(#[from] IdentifierError),
+
+    #[error("Timed out waiting for: {}", .expected)]
+    Timeout { expected: String },
+
+    #[error("Connection closed by the server")]
+    Closed,
+
+    #[error(transparent)]
+    Passport(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Soap(#[from] SoapMarshallError),
+
+    #[error("Unexpected reply: {}", .reply)]
+    UnexpectedReply { reply: String },
+
+    #[error("Server answered with error {} for transaction {}", .error, .tr_id)]
+    Server { error: MsnpError, tr_id: u128 }
+}
@@ -0,0 +1,5 @@
+pub mod error;
+pub mod connection;
+pub mod passport;
+pub mod notification;
+pub mod switchboard;
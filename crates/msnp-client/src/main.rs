@@ -0,0 +1,69 @@
+use std::env;
+
+use anyhow::anyhow;
+use log::{info, LevelFilter};
+use msnp::shared::models::presence_status::PresenceStatus;
+use msnp_client::notification::NotificationClient;
+
+const USAGE: &str = "usage: msnp-client <ns_address> <rst2_url> <email> <password> [--to <email> --message <text>]";
+
+struct Args {
+    ns_address: String,
+    rst2_url: String,
+    email: String,
+    password: String,
+    to: Option<String>,
+    message: Option<String>,
+}
+
+fn parse_args() -> Result<Args, anyhow::Error> {
+    let mut positional = Vec::new();
+    let mut to = None;
+    let mut message = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => to = Some(args.next().ok_or(anyhow!("--to needs a value"))?),
+            "--message" => message = Some(args.next().ok_or(anyhow!("--message needs a value"))?),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [ns_address, rst2_url, email, password]: [String; 4] = positional.try_into().map_err(|_| anyhow!(USAGE))?;
+    Ok(Args { ns_address, rst2_url, email, password, to, message })
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    env_logger::Builder::new().filter_level(LevelFilter::Info).parse_default_env().init();
+
+    let args = parse_args()?;
+
+    let mut notification = NotificationClient::connect(&args.ns_address).await?;
+    notification.login(&args.email, &args.password, &args.rst2_url).await?;
+    notification.change_presence(PresenceStatus::NLN).await?;
+
+    if let (Some(to), Some(message)) = (&args.to, &args.message) {
+        let mut switchboard = notification.request_switchboard().await?;
+        switchboard.invite(to).await?;
+        switchboard.wait_for_join(to).await?;
+        switchboard.send_text(message).await?;
+        info!("Message delivered to {}", to);
+        switchboard.leave().await?;
+    }
+
+    //Print whatever the server pushes until it goes quiet.
+    loop {
+        match notification.recv().await {
+            Ok(command) => info!("NS << {}", command),
+            Err(msnp_client::error::ClientError::Timeout { .. }) => continue,
+            Err(e) => {
+                info!("Stopping: {}", e);
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
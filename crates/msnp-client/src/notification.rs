@@ -0,0 +1,268 @@
+use std::str::FromStr;
+
+use log::info;
+use msnp::msnp::notification::command::chg::ChgClient;
+use msnp::msnp::notification::command::command::NotificationServerCommand;
+use msnp::msnp::notification::command::cvr::CvrClient;
+use msnp::msnp::notification::command::usr::{AuthOperationTypeClient, OperationTypeServer, SsoPhaseClient, SsoPhaseServer, UsrClient};
+use msnp::msnp::notification::command::ver::VerClient;
+use msnp::msnp::notification::command::xfr::{ClientRequestType, ServerRequestType, XfrClient};
+use msnp::msnp::notification::command::rng::RngServer;
+use msnp::msnp::notification::models::adl_payload::ADLPayload;
+use msnp::msnp::notification::models::endpoint_guid::EndpointGuid;
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
+use msnp::msnp::raw_command_parser::RawCommand;
+use msnp::shared::models::capabilities::ClientCapabilities;
+use msnp::shared::models::email_address::EmailAddress;
+use msnp::shared::models::endpoint_id::EndpointId;
+use msnp::shared::models::presence_status::PresenceStatus;
+use msnp::shared::models::uuid::Uuid;
+use msnp::shared::traits::IntoBytes;
+
+use crate::connection::MsnpConnection;
+use crate::error::ClientError;
+use crate::passport;
+use crate::switchboard::SwitchboardClient;
+
+//Tachyon never checks the MBI response, any base64 blob does.
+const PLACEHOLDER_CHALLENGE: &str = "AQAAAAIAAABsYwQAAAAyMDYw";
+
+pub struct NotificationClient {
+    connection: MsnpConnection<NotificationServerCommand>,
+    http_client: reqwest::Client,
+    endpoint_id: Option<EndpointId>,
+}
+
+impl NotificationClient {
+
+    pub async fn connect(address: &str) -> Result<Self, ClientError> {
+        Ok(Self {
+            connection: MsnpConnection::connect("NS", address).await?,
+            http_client: reqwest::Client::new(),
+            endpoint_id: None,
+        })
+    }
+
+    pub fn connection(&mut self) -> &mut MsnpConnection<NotificationServerCommand> {
+        &mut self.connection
+    }
+
+    pub fn endpoint_id(&self) -> Option<&EndpointId> {
+        self.endpoint_id.as_ref()
+    }
+
+    //VER, CVR then the two USR SSO phases, the ticket comes from the RST2 endpoint.
+    pub async fn login(&mut self, email_addr: &str, password: &str, rst2_url: &str) -> Result<EndpointId, ClientError> {
+        let email = EmailAddress::from_str(email_addr)?;
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(VerClient::new(tr_id, MsnpVersion::MSNP18, MsnpVersion::MSNP17)).await?;
+        self.expect(tr_id, "VER", |command| match command {
+            NotificationServerCommand::VER(ver) if ver.tr_id == tr_id => Ok(ver),
+            other => Err(Box::new(other)),
+        }).await?;
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(CvrClient::new(tr_id, 0x0409, "winnt".into(), "6.1.1".into(), "i386".into(), "MSNMSGR".into(), "14.0.8117.0416".into(), "msmsgs".into(), email.to_string())).await?;
+        self.expect(tr_id, "CVR", |command| match command {
+            NotificationServerCommand::CVR(cvr) if cvr.tr_id == tr_id => Ok(cvr),
+            other => Err(Box::new(other)),
+        }).await?;
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(UsrClient { tr_id, auth_type: AuthOperationTypeClient::Sso(SsoPhaseClient::I { email_addr: email.clone() }) }).await?;
+        self.expect(tr_id, "USR SSO S", |command| match command {
+            NotificationServerCommand::USR(usr) if usr.tr_id == tr_id && matches!(usr.auth_type, OperationTypeServer::Sso(SsoPhaseServer::S { .. })) => Ok(usr),
+            other => Err(Box::new(other)),
+        }).await?;
+
+        let ticket_token = passport::request_ticket(&self.http_client, rst2_url, email.as_str(), password).await?;
+        let endpoint_guid = EndpointGuid(Uuid::new());
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(UsrClient { tr_id, auth_type: AuthOperationTypeClient::Sso(SsoPhaseClient::S { ticket_token, challenge: PLACEHOLDER_CHALLENGE.to_string(), endpoint_guid: Some(endpoint_guid.clone()) }) }).await?;
+        self.expect(tr_id, "USR OK", |command| match command {
+            NotificationServerCommand::USR(usr) if usr.tr_id == tr_id && matches!(usr.auth_type, OperationTypeServer::Ok { .. }) => Ok(usr),
+            other => Err(Box::new(other)),
+        }).await?;
+
+        info!("Logged in as {}", email);
+        let endpoint_id = EndpointId::new(email, Some(endpoint_guid));
+        self.endpoint_id = Some(endpoint_id.clone());
+        Ok(endpoint_id)
+    }
+
+    pub async fn adl(&mut self, payload: ADLPayload) -> Result<(), ClientError> {
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(RawCommand::with_payload(&format!("ADL {}", tr_id), payload.into_bytes())).await?;
+        self.expect_ok(tr_id, "ADL").await
+    }
+
+    pub async fn change_presence(&mut self, presence_status: PresenceStatus) -> Result<(), ClientError> {
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(ChgClient { tr_id, presence_status, client_capabilities: ClientCapabilities::default(), avatar: None }).await?;
+        self.expect(tr_id, "CHG", |command| match command {
+            NotificationServerCommand::CHG(chg) if chg.tr_id == tr_id => Ok(chg),
+            other => Err(Box::new(other)),
+        }).await?;
+        Ok(())
+    }
+
+    //XFR for a new switchboard, then authenticate on it with the token we were given.
+    pub async fn request_switchboard(&mut self) -> Result<SwitchboardClient, ClientError> {
+        let endpoint_id = self.logged_in_endpoint()?;
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(XfrClient::new(tr_id, ClientRequestType::Switchboard)).await?;
+        let xfr = self.expect(tr_id, "XFR SB", |command| match command {
+            NotificationServerCommand::XFR(xfr) if xfr.tr_id == tr_id => Ok(xfr),
+            other => Err(Box::new(other)),
+        }).await?;
+
+        match xfr.request_type {
+            ServerRequestType::Switchboard { address, auth_token, .. } => {
+                SwitchboardClient::open(&address.to_string(), endpoint_id, auth_token).await
+            },
+            ServerRequestType::NotificationServer { address, .. } => {
+                Err(ClientError::UnexpectedReply { reply: format!("XFR {} NS {}", tr_id, address) })
+            }
+        }
+    }
+
+    pub async fn wait_for_ring(&mut self) -> Result<RngServer, ClientError> {
+        self.connection.wait_for("RNG", |command| match command {
+            NotificationServerCommand::RNG(rng) => Ok(rng),
+            other => Err(Box::new(other)),
+        }).await
+    }
+
+    pub async fn answer_ring(&mut self, ring: RngServer) -> Result<SwitchboardClient, ClientError> {
+        let endpoint_id = self.logged_in_endpoint()?;
+        SwitchboardClient::answer(ring, endpoint_id).await
+    }
+
+    pub async fn recv(&mut self) -> Result<NotificationServerCommand, ClientError> {
+        self.connection.recv().await
+    }
+
+    pub async fn sign_out(mut self) -> Result<(), ClientError> {
+        self.connection.send(RawCommand::without_payload("OUT")).await?;
+        self.connection.shutdown().await
+    }
+
+    fn logged_in_endpoint(&self) -> Result<EndpointId, ClientError> {
+        self.endpoint_id.clone().ok_or(ClientError::UnexpectedReply { reply: String::from("Not logged in") })
+    }
+
+    async fn expect_ok(&mut self, tr_id: u128, operand: &str) -> Result<(), ClientError> {
+        self.expect(tr_id, operand, |command| match command {
+            NotificationServerCommand::OK(ok) if ok.tr_id == tr_id && ok.operand == operand => Ok(()),
+            other => Err(Box::new(other)),
+        }).await
+    }
+
+    //Waits for the reply to `tr_id`, an ERR for the same transaction ends the wait too.
+    async fn expect<T>(&mut self, tr_id: u128, expected: &str, mut matcher: impl FnMut(NotificationServerCommand) -> Result<T, Box<NotificationServerCommand>>) -> Result<T, ClientError> {
+        self.connection.wait_for(expected, |command| match command {
+            NotificationServerCommand::ERR(err) if err.tr_id == tr_id => Ok(Err(ClientError::Server { error: err.msnp_error, tr_id })),
+            other => matcher(other).map(Ok),
+        }).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use msnp::msnp::notification::models::adl_payload::ADLPayload;
+    use msnp::shared::models::ticket_token::TicketToken;
+    use msnp::shared::models::uuid::Uuid;
+    use msnp::soap::passport::rst2::response::factory::RST2ResponseFactory;
+    use msnp::soap::traits::xml::ToXml;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    use super::NotificationClient;
+
+    //Answers a single RST2 POST with a ticket for messengerclear.live.com.
+    async fn mock_rst2(listener: TcpListener) {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(socket);
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await.unwrap();
+
+        let response = RST2ResponseFactory::get_rst2_success_response(TicketToken("mock_ticket".into()), "aeon@test.com".into(), Uuid::new()).to_xml().unwrap();
+        let reply = format!("HTTP/1.1 200 OK\r\nContent-Type: application/soap+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", response.len(), response);
+        reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+    }
+
+    //Plays the Notification Server side of a login followed by an ADL, returns the ticket the client sent.
+    async fn mock_ns(listener: TcpListener) -> String {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(socket);
+        let mut ticket = String::new();
+
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                return ticket;
+            }
+            let split: Vec<&str> = line.trim_end().split(' ').collect();
+            let tr_id = split[1];
+
+            let reply = match split.as_slice() {
+                ["VER", ..] => format!("VER {} MSNP18\r\n", tr_id),
+                ["CVR", ..] => format!("CVR {} 14.0.8117.0416 14.0.8117.0416 14.0.8117.0416 http://localhost http://localhost\r\n", tr_id),
+                ["USR", _, "SSO", "I", ..] => format!("USR {} SSO S MBI_KEY_OLD bm9uY2U=\r\n", tr_id),
+                ["USR", _, "SSO", "S", raw_ticket, ..] => {
+                    ticket = raw_ticket.to_string();
+                    format!("USR {} OK aeon@test.com 1 0\r\n", tr_id)
+                },
+                ["ADL", _, payload_size] => {
+                    let mut payload = vec![0; payload_size.parse().unwrap()];
+                    reader.read_exact(&mut payload).await.unwrap();
+                    format!("ADL {} OK\r\n", tr_id)
+                },
+                _ => panic!("Unexpected command: {}", line),
+            };
+            reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+
+            if split[0] == "ADL" {
+                return ticket;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn login_and_adl_against_mock_servers() {
+        let ns_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ns_address = ns_listener.local_addr().unwrap().to_string();
+        let rst2_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rst2_url = format!("http://{}/RST2.srf", rst2_listener.local_addr().unwrap());
+
+        let ns = tokio::spawn(mock_ns(ns_listener));
+        let rst2 = tokio::spawn(mock_rst2(rst2_listener));
+
+        let mut client = NotificationClient::connect(&ns_address).await.unwrap();
+        let endpoint_id = client.login("aeon@test.com", "password", &rst2_url).await.unwrap();
+        assert_eq!(endpoint_id.email_addr.as_str(), "aeon@test.com");
+        assert!(endpoint_id.endpoint_guid.is_some());
+
+        client.adl(ADLPayload::default()).await.unwrap();
+
+        rst2.await.unwrap();
+        assert_eq!(ns.await.unwrap(), "t=mock_ticket");
+    }
+}
@@ -0,0 +1,30 @@
+use msnp::shared::models::ticket_token::TicketToken;
+use msnp::soap::passport::rst2::request::factory::RST2RequestFactory;
+use msnp::soap::passport::rst2::response::RST2ResponseMessageSoapEnvelope;
+use msnp::soap::traits::xml::{ToXml, TryFromXml};
+
+use crate::error::ClientError;
+
+const MESSENGER_CLEAR: &str = "messengerclear.live.com";
+
+//Signs in against the RST2 endpoint and returns the ticket the Notification Server expects in USR SSO S.
+pub async fn request_ticket(http_client: &reqwest::Client, rst2_url: &str, email_addr: &str, password: &str) -> Result<TicketToken, ClientError> {
+    let request = RST2RequestFactory::get_rst2_request(email_addr, password);
+
+    let response = http_client.post(rst2_url)
+        .header("Content-Type", "application/soap+xml")
+        .body(request.to_xml()?)
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        return Err(ClientError::UnexpectedReply { reply: format!("RST2 {}: {}", status, body) });
+    }
+
+    let envelope = RST2ResponseMessageSoapEnvelope::try_from_xml(&body)?;
+    envelope.get_ticket_token(MESSENGER_CLEAR)
+        .ok_or(ClientError::UnexpectedReply { reply: format!("RST2 response had no {} token", MESSENGER_CLEAR) })
+}
@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use msnp::msnp::notification::command::rng::RngServer;
+use msnp::msnp::raw_command_parser::RawCommand;
+use msnp::msnp::switchboard::command::ans::AnsClient;
+use msnp::msnp::switchboard::command::cal::CalClient;
+use msnp::msnp::switchboard::command::command::SwitchboardServerCommand;
+use msnp::msnp::switchboard::command::joi::JoiServer;
+use msnp::msnp::switchboard::command::msg::{MsgAcknowledgment, MsgClient, MsgPayload};
+use msnp::msnp::switchboard::command::usr::UsrClient;
+use msnp::msnp::switchboard::models::session_id::SessionId;
+use msnp::p2p::v2::p2p_transport_packet::P2PTransportPacket;
+use msnp::shared::models::email_address::EmailAddress;
+use msnp::shared::models::endpoint_id::EndpointId;
+use msnp::shared::payload::msg::p2p_msg_payload::P2PMessagePayload;
+use msnp::shared::payload::msg::text_plain_msg::TextPlainMessagePayload;
+
+use crate::connection::MsnpConnection;
+use crate::error::ClientError;
+
+pub struct SwitchboardClient {
+    connection: MsnpConnection<SwitchboardServerCommand>,
+    endpoint_id: EndpointId,
+}
+
+impl SwitchboardClient {
+
+    //A switchboard we asked for with XFR.
+    pub(crate) async fn open(address: &str, endpoint_id: EndpointId, auth_token: String) -> Result<Self, ClientError> {
+        let mut client = Self { connection: MsnpConnection::connect("SB", address).await?, endpoint_id };
+
+        let tr_id = client.connection.next_tr_id();
+        client.connection.send(UsrClient { tr_id, endpoint_id: client.endpoint_id.clone(), token: auth_token }).await?;
+        client.expect(tr_id, "USR OK", |command| match command {
+            SwitchboardServerCommand::USR(usr) if usr.tr_id == tr_id => Ok(()),
+            other => Err(Box::new(other)),
+        }).await?;
+
+        Ok(client)
+    }
+
+    //A switchboard we were invited to with RNG.
+    pub(crate) async fn answer(ring: RngServer, endpoint_id: EndpointId) -> Result<Self, ClientError> {
+        let mut client = Self { connection: MsnpConnection::connect("SB", &ring.address.to_string()).await?, endpoint_id };
+
+        let session_id: u16 = ring.session_id.into();
+        let tr_id = client.connection.next_tr_id();
+        client.connection.send(AnsClient { tr_id, endpoint_id: client.endpoint_id.clone(), token: ring.ticket_token, session_id: session_id as u64 }).await?;
+        client.expect_ok(tr_id, "ANS").await?;
+
+        Ok(client)
+    }
+
+    pub fn connection(&mut self) -> &mut MsnpConnection<SwitchboardServerCommand> {
+        &mut self.connection
+    }
+
+    pub async fn invite(&mut self, email_addr: &str) -> Result<SessionId, ClientError> {
+        let email_addr = EmailAddress::from_str(email_addr)?;
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(CalClient { tr_id, email_addr }).await?;
+        self.expect(tr_id, "CAL RINGING", |command| match command {
+            SwitchboardServerCommand::CAL(cal) if cal.tr_id == tr_id => Ok(cal.session_id),
+            other => Err(Box::new(other)),
+        }).await
+    }
+
+    pub async fn wait_for_join(&mut self, email_addr: &str) -> Result<JoiServer, ClientError> {
+        let email_addr = EmailAddress::from_str(email_addr)?;
+
+        self.connection.wait_for("JOI", |command| match command {
+            SwitchboardServerCommand::JOI(joi) if joi.endpoint_id.email_addr == email_addr => Ok(joi),
+            other => Err(Box::new(other)),
+        }).await
+    }
+
+    pub async fn send_text(&mut self, body: &str) -> Result<(), ClientError> {
+        self.send_message(MsgAcknowledgment::AckA, MsgPayload::TextPlain(TextPlainMessagePayload::new_with_default_style(body))).await
+    }
+
+    pub async fn send_p2p(&mut self, receiver: EndpointId, packet: P2PTransportPacket) -> Result<(), ClientError> {
+        let payload = P2PMessagePayload::new(self.endpoint_id.clone(), receiver, packet, None);
+        self.send_message(MsgAcknowledgment::AckD, MsgPayload::P2P(payload)).await
+    }
+
+    //Only U messages go unanswered, everything else waits for its ACK.
+    pub async fn send_message(&mut self, ack_type: MsgAcknowledgment, payload: MsgPayload) -> Result<(), ClientError> {
+        let wants_ack = !matches!(ack_type, MsgAcknowledgment::NoAck);
+
+        let tr_id = self.connection.next_tr_id();
+        self.connection.send(MsgClient { tr_id, ack_type, payload }).await?;
+
+        if !wants_ack {
+            return Ok(());
+        }
+
+        self.expect(tr_id, "ACK", |command| match command {
+            SwitchboardServerCommand::ACK(ack) if ack.tr_id == tr_id => Ok(Ok(())),
+            SwitchboardServerCommand::NAK(nak) if nak.tr_id == tr_id => Ok(Err(ClientError::UnexpectedReply { reply: format!("NAK {}", tr_id) })),
+            other => Err(Box::new(other)),
+        }).await?
+    }
+
+    pub async fn recv(&mut self) -> Result<SwitchboardServerCommand, ClientError> {
+        self.connection.recv().await
+    }
+
+    pub async fn leave(mut self) -> Result<(), ClientError> {
+        self.connection.send(RawCommand::without_payload("OUT")).await?;
+        self.connection.shutdown().await
+    }
+
+    async fn expect_ok(&mut self, tr_id: u128, operand: &str) -> Result<(), ClientError> {
+        self.expect(tr_id, operand, |command| match command {
+            SwitchboardServerCommand::OK(ok) if ok.tr_id == tr_id && ok.operand == operand => Ok(()),
+            other => Err(Box::new(other)),
+        }).await
+    }
+
+    async fn expect<T>(&mut self, tr_id: u128, expected: &str, mut matcher: impl FnMut(SwitchboardServerCommand) -> Result<T, Box<SwitchboardServerCommand>>) -> Result<T, ClientError> {
+        self.connection.wait_for(expected, |command| match command {
+            SwitchboardServerCommand::ERR(err) if err.tr_id == tr_id => Ok(Err(ClientError::Server { error: err.msnp_error, tr_id })),
+            other => matcher(other).map(Ok),
+        }).await?
+    }
+}
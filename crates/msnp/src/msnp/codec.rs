@@ -0,0 +1,222 @@
+use std::str::{from_utf8, FromStr};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use log::warn;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::msnp::error::{CommandError, PayloadError};
+use crate::msnp::raw_command_parser::RawCommand;
+
+//Fits an initial ADL or a full P2P chunk, a bogus length won't make us buffer forever.
+pub const DEFAULT_MAX_PAYLOAD_SIZE: usize = 128 * 1024;
+
+//Command lines are a handful of arguments, this much data without a \r\n isn't MSNP.
+const MAX_COMMAND_LINE_SIZE: usize = 4096;
+
+//Frames MSNP commands, payloads are split off the read buffer instead of copied.
+//Any error ends a FramedRead, so only the hard size limits are errors and cost the client its connection:
+//a payload over max_payload_size or a command line over MAX_COMMAND_LINE_SIZE.
+//Lines that can't be parsed, including a bad payload length, are logged and skipped until the next valid command line.
+pub struct MsnpCodec {
+    max_payload_size: usize,
+    //Command line already decoded, waiting for the rest of its payload.
+    pending: Option<RawCommand>,
+}
+
+impl MsnpCodec {
+
+    pub fn new(max_payload_size: usize) -> Self {
+        Self {
+            max_payload_size,
+            pending: None,
+        }
+    }
+
+    fn decode_command_line(&mut self, src: &mut BytesMut) -> Result<Option<RawCommand>, CommandError> {
+        loop {
+            let Some(line_end) = src.windows(2).position(|window| window == b"\r\n") else {
+                if src.len() > MAX_COMMAND_LINE_SIZE {
+                    return Err(CommandError::CommandLineTooLong { size: src.len() });
+                }
+                return Ok(None);
+            };
+
+            let line = src.split_to(line_end + 2);
+            let command = match from_utf8(&line[..line_end]) {
+                Ok(command) => command,
+                Err(e) => {
+                    warn!("Skipping line that isn't UTF-8: {}", e);
+                    continue;
+                }
+            };
+
+            if !has_valid_operand(command) {
+                warn!("Skipping line with a bad operand: {}", command);
+                continue;
+            }
+
+            match RawCommand::from_str(command) {
+                Ok(command) => return Ok(Some(command)),
+                //Without a length we can't tell where the payload ends, its lines get skipped as bad operands.
+                Err(e) => warn!("Skipping malformed command {}: {}", command, e),
+            }
+        }
+    }
+}
+
+impl Default for MsnpCodec {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PAYLOAD_SIZE)
+    }
+}
+
+//Operands are three uppercase letters, or digits for errors: "911 3"
+fn has_valid_operand(command: &str) -> bool {
+    command.split_whitespace().next()
+        .is_some_and(|operand| operand.len() == 3 && operand.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()))
+}
+
+impl Decoder for MsnpCodec {
+    type Item = RawCommand;
+    type Error = CommandError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let mut command = match self.pending.take() {
+            Some(pending) => pending,
+            None => match self.decode_command_line(src)? {
+                Some(command) => command,
+                None => return Ok(None),
+            }
+        };
+
+        let payload_size = command.get_expected_payload_size();
+        if payload_size > self.max_payload_size {
+            return Err(PayloadError::PayloadTooLarge { size: payload_size, max_size: self.max_payload_size }.into());
+        }
+
+        if src.len() < payload_size {
+            src.reserve(payload_size - src.len());
+            self.pending = Some(command);
+            return Ok(None);
+        }
+
+        command.payload = src.split_to(payload_size).freeze();
+        Ok(Some(command))
+    }
+}
+
+impl Encoder<RawCommand> for MsnpCodec {
+    type Error = CommandError;
+
+    fn encode(&mut self, item: RawCommand, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let command_line = if item.payload.is_empty() {
+            format!("{}\r\n", item.command)
+        } else {
+            format!("{} {}\r\n", item.command, item.payload.len())
+        };
+
+        dst.reserve(command_line.len() + item.payload.len());
+        dst.put_slice(command_line.as_bytes());
+        dst.put_slice(&item.payload);
+        Ok(())
+    }
+}
+
+//Already serialized commands, as produced by IntoBytes.
+impl Encoder<Bytes> for MsnpCodec {
+    type Error = CommandError;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::from_utf8;
+
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use crate::msnp::error::{CommandError, PayloadError};
+    use crate::msnp::raw_command_parser::RawCommand;
+
+    use super::MsnpCodec;
+
+    #[test]
+    fn decode_payload_across_reads() {
+        let mut codec = MsnpCodec::default();
+        let mut buffer = BytesMut::from("BLP 9 AL\r\nADL 6 15\r\n<ml l=\"1\">");
+
+        let blp = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(blp.get_command(), "BLP 9 AL");
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(b"</ml>CHG 11 NLN 0\r\n");
+
+        let adl = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(adl.get_command(), "ADL 6 15");
+        assert_eq!(from_utf8(adl.get_payload()).unwrap(), "<ml l=\"1\"></ml>");
+
+        let chg = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(chg.get_operand(), "CHG");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_rejects_oversized_payload() {
+        let mut codec = MsnpCodec::new(10);
+        let mut buffer = BytesMut::from("MSG 1 U 11\r\n");
+
+        let result = codec.decode(&mut buffer);
+        assert!(matches!(result, Err(CommandError::PayloadError(PayloadError::PayloadTooLarge { size: 11, max_size: 10 }))));
+    }
+
+    #[test]
+    fn decode_rejects_unterminated_line() {
+        let mut codec = MsnpCodec::default();
+        let mut buffer = BytesMut::from(vec![b'A'; 5000].as_slice());
+
+        assert!(matches!(codec.decode(&mut buffer), Err(CommandError::CommandLineTooLong { size: 5000 })));
+    }
+
+    #[test]
+    fn decode_skips_bad_operand() {
+        let mut codec = MsnpCodec::default();
+        let mut buffer = BytesMut::from("hello there\r\nOUT\r\n");
+
+        let out = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(out.get_operand(), "OUT");
+    }
+
+    #[test]
+    fn decode_resyncs_after_bad_payload_length() {
+        let mut codec = MsnpCodec::default();
+        let mut buffer = BytesMut::from("ADL 6 sdfdasdf\r\n<ml l=\"1\"></ml>\r\nPNG\r\n");
+
+        let png = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(png.get_operand(), "PNG");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn decode_skips_non_utf8_line() {
+        let mut codec = MsnpCodec::default();
+        let mut buffer = BytesMut::from(&b"CHG 1 \xff\xfe\r\nOUT\r\n"[..]);
+
+        let out = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(out.get_operand(), "OUT");
+    }
+
+    #[test]
+    fn encode_raw_command() {
+        let mut codec = MsnpCodec::default();
+        let mut buffer = BytesMut::new();
+
+        codec.encode(RawCommand::with_payload("GCF 0", b"<Policies/>".to_vec()), &mut buffer).unwrap();
+        codec.encode(RawCommand::without_payload("OUT"), &mut buffer).unwrap();
+
+        assert_eq!(&buffer[..], b"GCF 0 11\r\n<Policies/>OUT\r\n");
+    }
+}
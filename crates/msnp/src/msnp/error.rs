@@ -30,6 +30,12 @@ pub enum CommandError {
     #[error("No command to extract in buffer: {:?}", .buffer)]
     NoCommandToExtract { buffer: Vec<u8>},
 
+    #[error("Command line exceeded {} bytes without a terminator", .size)]
+    CommandLineTooLong { size: usize },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     #[error(transparent)]
     UTF8Error(#[from] Utf8Error),
     #[error(transparent)]
@@ -86,10 +92,8 @@ pub enum PayloadError {
     ParseIntError(#[from] ParseIntError),
     #[error("Payload was missing from command {}", .command)]
     MissingPayload{command: String},
-    #[error("Payload was bigger ({}b) than expected {}: {:?}", .overflowing_size, .expected_size, .payload)]
-    PayloadSizeExceed {
-        expected_size: usize, overflowing_size: usize, payload: Vec<u8>
-    },
+    #[error("Payload of {}b is over the {}b limit", .size, .max_size)]
+    PayloadTooLarge { size: usize, max_size: usize },
     #[error(transparent)]
     HexDecodeError(#[from] FromHexError),
     #[error(transparent)]
@@ -2,4 +2,5 @@ pub mod notification;
 pub mod switchboard;
 pub mod error;
 pub mod raw_command_parser;
+pub mod codec;
 pub mod models;
\ No newline at end of file
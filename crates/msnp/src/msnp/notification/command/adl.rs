@@ -36,7 +36,7 @@ impl TryFromRawCommand for AdlClient {
             Err(PayloadError::MissingPayload { command: command.command })?;
         }
 
-        let payload = ADLPayload::try_from_bytes(command.payload)?;
+        let payload = ADLPayload::try_from_bytes(Vec::from(command.payload))?;
 
         Ok(Self{
             tr_id,
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use strum_macros::Display;
 
 use crate::msnp::notification::command::blp::BlpServer;
@@ -7,6 +9,7 @@ use crate::msnp::notification::command::iln::IlnServer;
 use crate::msnp::notification::command::msg::MsgServer;
 use crate::msnp::notification::command::nfy::NfyServer;
 use crate::msnp::notification::command::nln::NlnServer;
+use crate::msnp::notification::command::fln::FlnServer;
 use crate::msnp::notification::command::not::NotServer;
 use crate::msnp::notification::command::prp::PrpServer;
 use crate::msnp::notification::command::put::{PutClient, PutServer};
@@ -26,7 +29,8 @@ use crate::msnp::notification::command::url::{UrlClient, UrlServer};
 use crate::shared::command::err::ErrCommand;
 use crate::shared::command::nak::NakServer;
 use crate::shared::command::ok::OkCommand;
-use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
 
 use super::{adl::{AdlClient, RmlClient}, blp::BlpClient, chg::ChgClient, cvr::CvrClient, prp::PrpClient, usr::UsrClient, uun::UunClient, uux::UuxClient, ver::VerClient};
 
@@ -107,6 +111,7 @@ pub enum NotificationServerCommand {
     NOT(NotServer),
     ILN(IlnServer),
     NLN(NlnServer),
+    FLN(FlnServer),
     PUT(PutServer),
     SDG(SdgServer),
     XFR(XfrServer),
@@ -122,8 +127,50 @@ pub enum NotificationServerCommand {
 impl TryFromRawCommand for NotificationServerCommand {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        //Errors only carry a code and a tr_id: "911 3"
+        if raw.get_operand().chars().all(|c| c.is_ascii_digit()) {
+            return Ok(NotificationServerCommand::ERR(ErrCommand::try_from_raw(raw)?));
+        }
+
+        //Acknowledgments reuse the operand of the command they answer: "ADL 6 OK". PUT carries a payload size after it.
+        if raw.get_operand() != "PUT" && raw.command_split.len() == 3 && raw.command_split[2] == "OK" {
+            return Ok(NotificationServerCommand::OK(OkCommand::try_from_raw(raw)?));
+        }
+
+        let out = match raw.get_operand() {
+            "VER" => NotificationServerCommand::VER(VerServer::try_from_raw(raw)?),
+            "CVR" => NotificationServerCommand::CVR(CvrServer::try_from_raw(raw)?),
+            "MSG" => NotificationServerCommand::MSG(MsgServer::try_from_raw(raw)?),
+            "QNG" => {
+                let raw_timeout = raw.command_split.get(1).ok_or(CommandError::MissingArgument(raw.command.clone(), "timeout".into(), 1))?;
+                NotificationServerCommand::QNG(u32::from_str(raw_timeout)?)
+            },
+            "NAK" => NotificationServerCommand::NAK(NakServer::try_from_raw(raw)?),
+            "USR" => NotificationServerCommand::USR(UsrServer::try_from_raw(raw)?),
+            "UUX" => NotificationServerCommand::UUX(UuxServer::try_from_raw(raw)?),
+            "UBM" => NotificationServerCommand::UBM(UbmServer::try_from_raw(raw)?),
+            "UBX" => NotificationServerCommand::UBX(UbxServer::try_from_raw(raw)?),
+            "FQY" => NotificationServerCommand::FQY(FqyServer::try_from_raw(raw)?),
+            "CHG" => NotificationServerCommand::CHG(ChgServer::try_from_raw(raw)?),
+            "NFY" => NotificationServerCommand::NFY(NfyServer::try_from_raw(raw)?),
+            "BLP" => NotificationServerCommand::BLP(BlpServer::try_from_raw(raw)?),
+            "NOT" => NotificationServerCommand::NOT(NotServer::try_from_raw(raw)?),
+            "ILN" => NotificationServerCommand::ILN(IlnServer::try_from_raw(raw)?),
+            "NLN" => NotificationServerCommand::NLN(NlnServer::try_from_raw(raw)?),
+            "FLN" => NotificationServerCommand::FLN(FlnServer::try_from_raw(raw)?),
+            "PUT" => NotificationServerCommand::PUT(PutServer::try_from_raw(raw)?),
+            "SDG" => NotificationServerCommand::SDG(SdgServer::try_from_raw(raw)?),
+            "XFR" => NotificationServerCommand::XFR(XfrServer::try_from_raw(raw)?),
+            "RNG" => NotificationServerCommand::RNG(RngServer::try_from_raw(raw)?),
+            "PRP" => NotificationServerCommand::PRP(PrpServer::try_from_raw(raw)?),
+            "URL" => NotificationServerCommand::URL(UrlServer::try_from_raw(raw)?),
+            "UBN" => NotificationServerCommand::UBN(UbnServer::try_from_raw(raw)?),
+            "OUT" => NotificationServerCommand::OUT,
+            _ => NotificationServerCommand::RAW(raw)
+        };
+
+        Ok(out)
     }
 
 
@@ -150,6 +197,7 @@ impl IntoBytes for NotificationServerCommand {
             NotificationServerCommand::NFY(content) => {content.into_bytes()}
             NotificationServerCommand::PUT(content) => {content.into_bytes()}
             NotificationServerCommand::NLN(content) => { content.into_bytes() }
+            NotificationServerCommand::FLN(content) => { content.into_bytes() }
             NotificationServerCommand::SDG(content) => { content.into_bytes() }
             NotificationServerCommand::XFR(content) => { content.into_bytes() }
             NotificationServerCommand::RNG(content) => { content.into_bytes() }
@@ -162,3 +210,45 @@ impl IntoBytes for NotificationServerCommand {
         }
     }
 }
+
+impl IntoVersionedBytes for NotificationServerCommand {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        match self {
+            NotificationServerCommand::ILN(content) => content.into_versioned_bytes(version),
+            NotificationServerCommand::NLN(content) => content.into_versioned_bytes(version),
+            NotificationServerCommand::FLN(content) => content.into_versioned_bytes(version),
+            NotificationServerCommand::UBX(content) => content.into_versioned_bytes(version),
+            other => other.into_bytes()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::msnp::notification::command::command::NotificationServerCommand;
+    use crate::msnp::raw_command_parser::RawCommandParser;
+    use crate::shared::command::err::MsnpError;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+
+    fn parse(raw: &str) -> NotificationServerCommand {
+        let raw_command = RawCommandParser::new().parse_message(raw.as_bytes()).unwrap().remove(0);
+        NotificationServerCommand::try_from_raw(raw_command).unwrap()
+    }
+
+    #[test]
+    fn server_command_round_trips() {
+        for raw in ["VER 1 MSNP18\r\n", "QNG 50\r\n", "ADL 6 OK\r\n", "PUT 7 OK 0\r\n", "XFR 12 SB 127.0.0.1:1864 CKI 4uth_t0k3n\r\n", "FLN 1:aeon@test.com 0:0\r\n", "OUT\r\n"] {
+            assert_eq!(raw, String::from_utf8(parse(raw).into_bytes()).unwrap());
+        }
+    }
+
+    #[test]
+    fn server_command_dispatch() {
+        assert!(matches!(parse("ADL 6 OK\r\n"), NotificationServerCommand::OK(ok) if ok.operand == "ADL"));
+        assert!(matches!(parse("PUT 7 OK 0\r\n"), NotificationServerCommand::PUT(put) if put.tr_id == 7));
+        assert!(matches!(parse("911 3\r\n"), NotificationServerCommand::ERR(err) if err.msnp_error == MsnpError::ServerIsBusy2));
+        assert!(matches!(parse("GCF 1 0\r\n"), NotificationServerCommand::RAW(_)));
+    }
+}
@@ -85,6 +85,28 @@ impl TryFromRawCommand for CvrClient {
     }
 }
 
+impl Display for CvrClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CVR {tr_id} 0x{region_code:04x} {os_type} {os_version} {cpu_arch} {msnp_lib_name} {client_ver} {client_name} {email_addr}\r\n",
+            tr_id = self.tr_id,
+            region_code = self.region_code,
+            os_type = self.os_type,
+            os_version = self.os_version,
+            cpu_arch = self.cpu_arch,
+            msnp_lib_name = self.msnp_lib_name,
+            client_ver = self.client_ver,
+            client_name = self.client_name,
+            email_addr = self.email_addr
+        )
+    }
+}
+
+impl IntoBytes for CvrClient {
+    fn into_bytes(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
 pub struct CvrServer {
     pub tr_id: u128,
     pub rec_client_ver: String,
@@ -134,8 +156,31 @@ impl Display for CvrServer {
 impl TryFromRawCommand for CvrServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let rec_client_ver = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "rec_client_ver".into(), 2))?;
+
+        let rec_client_ver2 = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "rec_client_ver2".into(), 3))?;
+
+        let min_client_ver = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "min_client_ver".into(), 4))?;
+
+        let client_dl_url = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "client_dl_url".into(), 5))?;
+
+        let client_info_url = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "client_info_url".into(), 6))?;
+
+        Ok(CvrServer {
+            tr_id,
+            rec_client_ver,
+            rec_client_ver2,
+            min_client_ver,
+            client_dl_url,
+            client_info_url,
+        })
     }
 }
 
@@ -147,15 +192,26 @@ impl IntoBytes for CvrServer {
 
 #[cfg(test)]
 mod tests {
-    use crate::msnp::notification::command::cvr::CvrClient;
+    use crate::msnp::notification::command::cvr::{CvrClient, CvrServer};
     use crate::msnp::raw_command_parser::RawCommand;
-    use crate::shared::traits::TryFromRawCommand;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
     use std::str::FromStr;
 
 
     #[test]
     fn deser_test() {
        let cvr =  CvrClient::try_from_raw(RawCommand::from_str("CVR 2 0x0409 winnt 6.2.0 i386 MSNMSGR 14.0.8117.0416 msmsgs aeontest3@shlasouf.local").unwrap()).unwrap();
+       assert_eq!("CVR 2 0x0409 winnt 6.2.0 i386 MSNMSGR 14.0.8117.0416 msmsgs aeontest3@shlasouf.local\r\n", String::from_utf8(cvr.into_bytes()).unwrap());
+    }
+
+    #[test]
+    fn server_deser_test() {
+        let raw = "CVR 2 14.0.8117.0416 14.0.8117.0416 14.0.8117.0416 http://download.live.com/?sku=messenger http://download.live.com/?sku=messenger\r\n";
+        let cvr = CvrServer::try_from_raw(RawCommand::from_str(raw).unwrap()).unwrap();
+
+        assert_eq!(cvr.tr_id, 2);
+        assert_eq!(cvr.min_client_ver, "14.0.8117.0416");
+        assert_eq!(raw, String::from_utf8(cvr.into_bytes()).unwrap());
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use crate::msnp::error::CommandError;
+use crate::msnp::raw_command_parser::RawCommand;
+use crate::shared::models::capabilities::ClientCapabilities;
+use crate::shared::models::network_id_email::NetworkIdEmail;
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::shared::models::capabilities::ClientCapabilities;
+    use crate::shared::models::email_address::EmailAddress;
+    use crate::shared::models::network_id::NetworkId;
+    use crate::shared::models::network_id_email::NetworkIdEmail;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::msnp::notification::models::msnp_version::MsnpVersion;
+    use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
+
+    use super::FlnServer;
+
+    #[test]
+    pub fn test_fln_des_round_trip() {
+        let fln = FlnServer::try_from_raw(RawCommand::from_str("FLN 1:test@shlasouf.local;via=9:test@live.fr 0:0").unwrap()).unwrap();
+
+        assert_eq!(fln.target_user.to_string(), "1:test@shlasouf.local");
+        assert_eq!(fln.via.as_ref().unwrap().to_string(), "9:test@live.fr");
+
+        assert_eq!("FLN 1:test@shlasouf.local;via=9:test@live.fr 0:0\r\n", String::from_utf8(fln.into_bytes()).unwrap());
+    }
+
+    #[test]
+    pub fn test_fln_legacy_ser() {
+        let fln = FlnServer::new(NetworkIdEmail::new(NetworkId::WindowsLive, EmailAddress::from_str("test@shlasouf.local").unwrap()));
+
+        assert_eq!("FLN test@shlasouf.local 1 0\r\n", String::from_utf8(fln.into_versioned_bytes(MsnpVersion::MSNP15)).unwrap());
+    }
+
+    #[test]
+    pub fn test_fln_ser() {
+        let fln = FlnServer {
+            target_user: NetworkIdEmail::new(NetworkId::WindowsLive, EmailAddress::from_str("test@shlasouf.local").unwrap()),
+            via: None,
+            client_capabilities: ClientCapabilities::new(0, 0),
+        };
+
+        assert_eq!("FLN 1:test@shlasouf.local 0:0\r\n", String::from_utf8(fln.into_versioned_bytes(MsnpVersion::MSNP18)).unwrap());
+    }
+}
+
+pub struct FlnServer {
+    pub target_user: NetworkIdEmail,
+    pub via: Option<NetworkIdEmail>,
+    pub client_capabilities: ClientCapabilities,
+}
+
+impl FlnServer {
+    pub fn new(target_user: NetworkIdEmail) -> Self {
+        Self {
+            target_user,
+            via: None,
+            client_capabilities: ClientCapabilities::new(0, 0),
+        }
+    }
+}
+
+impl TryFromRawCommand for FlnServer {
+    type Err = CommandError;
+
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_target_user = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "target_user".into(), 1))?;
+        let (target_user, via) = match raw_target_user.split_once(";via=") {
+            Some((target_user, via)) => (NetworkIdEmail::from_str(target_user)?, Some(NetworkIdEmail::from_str(via)?)),
+            None => (NetworkIdEmail::from_str(&raw_target_user)?, None),
+        };
+
+        let client_capabilities = match split.pop_front() {
+            Some(raw_capabilities) => ClientCapabilities::from_str(&raw_capabilities)?,
+            None => ClientCapabilities::new(0, 0)
+        };
+
+        Ok(Self {
+            target_user,
+            via,
+            client_capabilities,
+        })
+    }
+}
+
+impl IntoBytes for FlnServer {
+    fn into_bytes(self) -> Vec<u8> {
+        let target_user = match self.via {
+            None => self.target_user.to_string(),
+            Some(via) => format!("{};via={}", self.target_user, via)
+        };
+
+        format!("FLN {} {}\r\n", target_user, self.client_capabilities).into_bytes()
+    }
+}
+
+impl IntoVersionedBytes for FlnServer {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        if version.supports_circles() {
+            return self.into_bytes();
+        }
+
+        format!("FLN {} {} {}\r\n", self.target_user.email, self.target_user.network_id.clone() as i32, self.client_capabilities.to_versioned_string(version)).into_bytes()
+    }
+}
@@ -44,7 +44,7 @@ impl TryFromRawCommand for FqyClient {
             Err(PayloadError::MissingPayload { command: command.command })?;
         }
 
-        let payload = ADLPayload::try_from_bytes(command.payload)?;
+        let payload = ADLPayload::try_from_bytes(Vec::from(command.payload))?;
 
         Ok(Self{
             tr_id,
@@ -5,7 +5,11 @@ use crate::shared::models::display_name::DisplayName;
 use crate::shared::models::msn_object::MsnObject;
 use crate::shared::models::network_id_email::NetworkIdEmail;
 use crate::shared::models::presence_status::PresenceStatus;
-use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand, TryFromSplit};
+use crate::msnp::notification::command::nln::{format_legacy_presence, NlnServer};
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
+use std::str::FromStr;
+
 pub struct IlnServer {
     pub tr_id: u128,
     pub presence_status: PresenceStatus,
@@ -20,12 +24,39 @@ pub struct IlnServer {
 impl TryFromRawCommand for IlnServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let nln = NlnServer::try_from_split(split, &raw.command)?;
+
+        Ok(Self {
+            tr_id,
+            presence_status: nln.presence_status,
+            target_user: nln.target_user,
+            via: nln.via,
+            display_name: nln.display_name,
+            client_capabilities: nln.client_capabilities,
+            avatar: nln.avatar,
+            badge_url: nln.badge_url,
+        })
     }
 
 }
 
+impl IntoVersionedBytes for IlnServer {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        if version.supports_circles() {
+            return self.into_bytes();
+        }
+
+        format!("ILN {} {}\r\n", self.tr_id, format_legacy_presence(&self.presence_status, &self.target_user, &self.display_name, &self.client_capabilities, self.avatar, version)).into_bytes()
+    }
+}
+
 impl IntoBytes for IlnServer {
     fn into_bytes(self) -> Vec<u8> {
 
@@ -74,10 +105,24 @@ mod tests {
     use crate::shared::models::network_id::NetworkId;
     use crate::shared::models::network_id_email::NetworkIdEmail;
     use crate::shared::models::presence_status::PresenceStatus;
-    use crate::shared::traits::IntoBytes;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
 
     use super::IlnServer;
 
+    #[test]
+    pub fn test_iln_des() {
+        let iln = IlnServer::try_from_raw(RawCommand::from_str("ILN 7 AWY 1:test@shlasouf.local Testo%20Testa 2788999228:48 0 http://badge.jpg").unwrap()).unwrap();
+
+        assert_eq!(iln.tr_id, 7);
+        assert_eq!(iln.presence_status, PresenceStatus::AWY);
+        assert_eq!(iln.display_name.value(), "Testo Testa");
+        assert!(iln.avatar.is_none());
+        assert_eq!(iln.badge_url.as_deref(), Some("http://badge.jpg"));
+
+        assert_eq!("ILN 7 AWY 1:test@shlasouf.local Testo%20Testa 2788999228:48 0 http://badge.jpg\r\n", String::from_utf8(iln.into_bytes()).unwrap());
+    }
+
     #[test]
     pub fn test_nln_via_ser_msn_obj() {
 
@@ -18,6 +18,7 @@ pub mod sdg;
 pub mod nfy;
 pub mod put;
 pub mod nln;
+pub mod fln;
 pub mod xfr;
 pub mod rng;
 pub mod fqy;
@@ -3,6 +3,7 @@ use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::models::display_name::DisplayName;
 use crate::shared::payload::msg::raw_msg_payload::RawMsgPayload;
 use crate::shared::traits::{IntoBytes, TryFromBytes, TryFromRawCommand};
+use std::str::FromStr;
 
 pub struct MsgServer {
     pub sender: String,
@@ -14,8 +15,22 @@ pub struct MsgServer {
 impl TryFromRawCommand for MsgServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let sender = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "sender".into(), 1))?;
+
+        let raw_display_name = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "display_name".into(), 2))?;
+        let display_name = DisplayName::from_str(&raw_display_name)?;
+
+        let payload = MsgPayload::try_from_bytes(Vec::from(raw.payload))?;
+
+        Ok(Self {
+            sender,
+            display_name,
+            payload,
+        })
     }
 
 }
@@ -36,8 +51,8 @@ pub enum MsgPayload {
 
 impl TryFromBytes for MsgPayload {
     type Err = PayloadError;
-    fn try_from_bytes(_bytes: Vec<u8>) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Err> {
+        Ok(MsgPayload::Raw(RawMsgPayload::try_from_bytes(bytes)?))
     }
 }
 
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use strum_macros::{Display, EnumString};
 
 use crate::msnp::error::CommandError;
@@ -22,8 +24,16 @@ pub enum NfyOperation {
 impl TryFromRawCommand for NfyServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_operation = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "operation".into(), 1))?;
+        let operation = NfyOperation::from_str(&raw_operation)?;
+
+        let payload = RawNfyPayload::try_from_bytes(Vec::from(raw.payload))?;
+
+        Ok(Self { operation, payload })
     }
     
 }
@@ -5,7 +5,10 @@ use crate::shared::models::display_name::DisplayName;
 use crate::shared::models::msn_object::MsnObject;
 use crate::shared::models::network_id_email::NetworkIdEmail;
 use crate::shared::models::presence_status::PresenceStatus;
-use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand, TryFromSplit};
+use std::collections::VecDeque;
+use std::str::FromStr;
 
 #[cfg(test)]
 mod tests {
@@ -18,10 +21,45 @@ mod tests {
     use crate::shared::models::network_id::NetworkId;
     use crate::shared::models::network_id_email::NetworkIdEmail;
     use crate::shared::models::presence_status::PresenceStatus;
-    use crate::shared::traits::IntoBytes;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::msnp::notification::models::msnp_version::MsnpVersion;
+    use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
 
     use super::NlnServer;
 
+    #[test]
+    pub fn test_nln_des_round_trip() {
+        let nln = NlnServer {
+            presence_status: PresenceStatus::BSY,
+            target_user: NetworkIdEmail::new(NetworkId::WindowsLive, EmailAddress::from_str("test@shlasouf.local").unwrap()),
+            via: Some(NetworkIdEmail::new(NetworkId::Circle, EmailAddress::from_str("test@live.fr").unwrap())),
+            display_name: DisplayName::new_from_ref("Testo Testa"),
+            client_capabilities: ClientCapabilities::new(0,0),
+            avatar: Some(MSNObjectFactory::get_display_picture(&Vec::new(), &EmailAddress::from_str("test@shlasouf.local").unwrap(), "blabla.tmp".into(), FriendlyName::new("blabla.jpg"))),
+            badge_url: Some("http://badge.jpg".into()),
+        };
+
+        let serialized = String::from_utf8(nln.into_bytes()).unwrap();
+        let nln = NlnServer::try_from_raw(RawCommand::from_str(&serialized).unwrap()).unwrap();
+
+        assert_eq!(nln.presence_status, PresenceStatus::BSY);
+        assert_eq!(nln.target_user.to_string(), "1:test@shlasouf.local");
+        assert_eq!(nln.via.as_ref().unwrap().to_string(), "9:test@live.fr");
+        assert_eq!(nln.display_name.value(), "Testo Testa");
+        assert_eq!(nln.avatar.as_ref().unwrap().creator, "test@shlasouf.local");
+        assert_eq!(nln.badge_url.as_deref(), Some("http://badge.jpg"));
+
+        assert_eq!(serialized, String::from_utf8(nln.into_bytes()).unwrap());
+    }
+
+    #[test]
+    pub fn test_nln_des_no_msn_obj() {
+        let nln = NlnServer::try_from_raw(RawCommand::from_str("NLN NLN 1:test@shlasouf.local Testo 0:0 0").unwrap()).unwrap();
+        assert!(nln.avatar.is_none());
+        assert!(nln.via.is_none());
+        assert!(nln.badge_url.is_none());
+    }
+
     #[test]
     pub fn test_nln_via_ser_msn_obj() {
 
@@ -83,6 +121,24 @@ mod tests {
         assert_eq!("NLN BSY 1:test@shlasouf.local Testo 0:0 0\r\n", &nln_deser);
     }
 
+    #[test]
+    pub fn test_nln_legacy_ser() {
+
+        let nln = NlnServer {
+            presence_status: PresenceStatus::BSY,
+            target_user: NetworkIdEmail::new(NetworkId::WindowsLive, EmailAddress::from_str("test@shlasouf.local").unwrap()),
+            via: Some(NetworkIdEmail::new(NetworkId::Circle, EmailAddress::from_str("test@live.fr").unwrap())),
+            display_name: DisplayName::new_from_ref("Testo"),
+            client_capabilities: ClientCapabilities::new(1985855532, 48),
+            avatar: None,
+            badge_url: Some("http://badge.jpg".into()),
+        };
+
+        let nln_deser = String::from_utf8(nln.into_versioned_bytes(MsnpVersion::MSNP15)).unwrap();
+
+        assert_eq!("NLN BSY test@shlasouf.local 1 Testo 1985855532 0\r\n", &nln_deser);
+    }
+
     #[test]
     pub fn test_nln_ser_no_msn_obj_badge() {
 
@@ -121,12 +177,52 @@ pub struct NlnServer {
 impl TryFromRawCommand for NlnServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        NlnServer::try_from_split(split, &raw.command)
     }
 
 }
 
+//Everything after the operand, ILN carries the same arguments behind its tr_id.
+impl TryFromSplit for NlnServer {
+    type Err = CommandError;
+
+    fn try_from_split(mut split: VecDeque<String>, command: &str) -> Result<Self, Self::Err> where Self: Sized {
+        let raw_presence_status = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "presence_status".into(), 1))?;
+        let presence_status = PresenceStatus::from_str(&raw_presence_status)?;
+
+        let raw_target_user = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "target_user".into(), 2))?;
+        let (target_user, via) = match raw_target_user.split_once(";via=") {
+            Some((target_user, via)) => (NetworkIdEmail::from_str(target_user)?, Some(NetworkIdEmail::from_str(via)?)),
+            None => (NetworkIdEmail::from_str(&raw_target_user)?, None),
+        };
+
+        let raw_display_name = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "display_name".into(), 3))?;
+        let display_name = DisplayName::from_str(&raw_display_name)?;
+
+        let raw_capabilities = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "client_capabilities".into(), 4))?;
+        let client_capabilities = ClientCapabilities::from_str(&raw_capabilities)?;
+
+        let raw_avatar = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "avatar".into(), 5))?;
+        let avatar = if raw_avatar != "0" { Some(MsnObject::from_str(&urlencoding::decode(&raw_avatar)?)?) } else { None };
+
+        let badge_url = split.pop_front();
+
+        Ok(Self {
+            presence_status,
+            target_user,
+            via,
+            display_name,
+            client_capabilities,
+            avatar,
+            badge_url,
+        })
+    }
+}
+
 impl IntoBytes for NlnServer {
 
     fn into_bytes(self) -> Vec<u8> {
@@ -163,3 +259,25 @@ impl IntoBytes for NlnServer {
         out.into_bytes()
     }
 }
+
+impl IntoVersionedBytes for NlnServer {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        if version.supports_circles() {
+            return self.into_bytes();
+        }
+
+        format!("NLN {}\r\n", format_legacy_presence(&self.presence_status, &self.target_user, &self.display_name, &self.client_capabilities, self.avatar, version)).into_bytes()
+    }
+}
+
+//Pre-MSNP18 presence: "email network_id" instead of "network_id:email", no circles and no badge.
+pub(crate) fn format_legacy_presence(presence_status: &PresenceStatus, target_user: &NetworkIdEmail, display_name: &DisplayName, client_capabilities: &ClientCapabilities, avatar: Option<MsnObject>, version: MsnpVersion) -> String {
+    format!("{presence_status} {email} {network_id} {display_name} {capab} {avatar}",
+            presence_status = presence_status,
+            email = target_user.email,
+            network_id = target_user.network_id.clone() as i32,
+            display_name = display_name,
+            capab = client_capabilities.to_versioned_string(version),
+            avatar = avatar.map(|a| a.to_string()).unwrap_or("0".into())
+    )
+}
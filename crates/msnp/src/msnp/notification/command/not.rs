@@ -3,6 +3,8 @@ use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::traits::{IntoBytes, TryFromBytes, TryFromRawCommand};
 use crate::soap::error::SoapMarshallError;
 use crate::soap::traits::xml::ToXml;
+use anyhow::anyhow;
+use yaserde::de::from_str;
 use yaserde::ser::to_string_with_config;
 use yaserde_derive::{YaDeserialize, YaSerialize};
 
@@ -30,8 +32,13 @@ impl IntoBytes for NotificationPayloadType {
 impl TryFromRawCommand for NotServer {
     type Err = PayloadError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let payload = match NotificationPayload::try_from_bytes(Vec::from(raw.payload.clone())) {
+            Ok(payload) => NotificationPayloadType::Normal(payload),
+            Err(_) => NotificationPayloadType::Raw(String::from_utf8(Vec::from(raw.payload))?)
+        };
+
+        Ok(Self { payload })
     }
 
 }
@@ -51,8 +58,9 @@ impl IntoBytes for NotServer {
 impl TryFromBytes for NotificationPayload {
     type Err = PayloadError;
 
-    fn try_from_bytes(_bytes: Vec<u8>) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Err> where Self: Sized {
+        let payload = String::from_utf8(bytes)?;
+        from_str::<NotificationPayload>(&payload).map_err(|e| PayloadError::StringPayloadParsingError { payload, source: anyhow!("Couldn't parse Notification Payload: {}", e) })
     }
 
 }
@@ -381,7 +389,10 @@ pub mod factories {
 mod tests {
     use std::str::FromStr;
 
+    use crate::msnp::notification::command::not::{NotServer, NotificationPayloadType};
+    use crate::msnp::raw_command_parser::RawCommand;
     use crate::shared::models::email_address::EmailAddress;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
     use crate::soap::traits::xml::ToXml;
     use crate::{msnp::notification::command::not::factories::NotificationFactory, shared::models::msn_user::MsnUser};
 
@@ -407,4 +418,12 @@ mod tests {
         let notif_legacy = NotificationFactory::test(&msn_user.uuid, &msn_user.endpoint_id.email_addr.as_str());
         assert_eq!(notif.to_xml().unwrap().as_str(), notif_legacy.replace("\r\n", ""));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn not_des_raw_payload() {
+        let not = NotServer::try_from_raw(RawCommand::with_payload("NOT 11", b"not xml at all".to_vec())).unwrap();
+
+        assert!(matches!(&not.payload, NotificationPayloadType::Raw(raw) if raw == "not xml at all"));
+        assert_eq!(b"NOT 14\r\nnot xml at all".to_vec(), not.into_bytes());
+    }
+}
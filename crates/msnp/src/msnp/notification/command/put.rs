@@ -27,7 +27,7 @@ impl TryFromRawCommand for PutClient {
         let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
         let tr_id = u128::from_str(&raw_tr_id)?;
 
-        let payload = RawNfyPayload::try_from_bytes(raw.payload)?;
+        let payload = RawNfyPayload::try_from_bytes(Vec::from(raw.payload))?;
 
         Ok(PutClient {
             tr_id,
@@ -53,14 +53,20 @@ impl IntoBytes for PutClient {
 
 //Todo handle errors ?
 pub struct PutServer {
-    tr_id: u128
+    pub tr_id: u128
 }
 
 impl TryFromRawCommand for PutServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        Ok(PutServer { tr_id })
     }
 
 }
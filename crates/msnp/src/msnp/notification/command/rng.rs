@@ -7,14 +7,15 @@ use crate::shared::models::email_address::EmailAddress;
 use crate::shared::models::ticket_token::TicketToken;
 use crate::shared::traits::{IntoBytes, TryFromRawCommand};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 pub struct RngServer {
-    session_id: SessionId,
-    address: IpAddress,
-    auth_type: AuthenticationMethod,
-    ticket_token: TicketToken,
-    inviter_passport: EmailAddress,
-    inviter_name: String,
+    pub session_id: SessionId,
+    pub address: IpAddress,
+    pub auth_type: AuthenticationMethod,
+    pub ticket_token: TicketToken,
+    pub inviter_passport: EmailAddress,
+    pub inviter_name: String,
 }
 
 impl RngServer {
@@ -37,7 +38,34 @@ impl TryFromRawCommand for RngServer {
     where
         Self: Sized
     {
-        todo!()
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_session_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "session_id".into(), 1))?;
+        let session_id = SessionId::from(u16::from_str(&raw_session_id)?);
+
+        let raw_address = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "address".into(), 2))?;
+        let address = IpAddress::from_str(&raw_address).map_err(|e| CommandError::ArgumentParseError { argument: raw_address.clone(), command: raw.command.clone(), source: e })?;
+
+        let raw_auth_type = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "auth_type".into(), 3))?;
+        let auth_type = AuthenticationMethod::from_str(&raw_auth_type)?;
+
+        let raw_ticket_token = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "ticket_token".into(), 4))?;
+        let ticket_token = TicketToken::from_str(&raw_ticket_token)?;
+
+        let raw_inviter_passport = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "inviter_passport".into(), 5))?;
+        let inviter_passport = EmailAddress::from_str(&raw_inviter_passport)?;
+
+        let inviter_name = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "inviter_name".into(), 6))?;
+
+        Ok(Self {
+            session_id,
+            address,
+            auth_type,
+            ticket_token,
+            inviter_passport,
+            inviter_name,
+        })
     }
 
 }
@@ -59,4 +87,22 @@ impl Display for RngServer {
                inviter_name = self.inviter_name
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::msnp::notification::command::rng::RngServer;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+    use std::str::FromStr;
+
+    #[test]
+    fn rng_des_round_trip() {
+        let raw = "RNG 1337 127.0.0.1:1864 CKI t=s3cr3t aeontest@shlasouf.local aeontest\r\n";
+        let rng = RngServer::try_from_raw(RawCommand::from_str(raw).unwrap()).unwrap();
+
+        assert_eq!(rng.ticket_token, "s3cr3t");
+        assert_eq!(rng.address.port, 1864);
+        assert_eq!(rng.inviter_name, "aeontest");
+        assert_eq!(raw, String::from_utf8(rng.into_bytes()).unwrap());
+    }
+}
@@ -22,7 +22,7 @@ impl TryFromRawCommand for SdgClient {
         let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
         let tr_id = u128::from_str(&raw_tr_id)?;
 
-        let payload = RawNfyPayload::try_from_bytes(raw.payload)?;
+        let payload = RawNfyPayload::try_from_bytes(Vec::from(raw.payload))?;
 
         Ok(SdgClient {
             tr_id,
@@ -100,7 +100,7 @@ impl TryFromRawCommand for UbmServer {
         let raw_message_type = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "message-type".into(), 5))?;
         let message_type = MessageType::from_u32(u32::from_str(&raw_message_type)?).ok_or(CommandError::ArgumentParseError { argument: raw_message_type.to_string(), command: raw.command.clone(), source: anyhow!("Couldn't parse int to UserNotificationType") })?;
 
-        let payload = UbmPayload::parse_payload(message_type, raw.payload)?;
+        let payload = UbmPayload::parse_payload(message_type, Vec::from(raw.payload))?;
 
         Ok(Self {
             contact_sender,
@@ -1,3 +1,8 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::msnp::error::CommandError;
 use crate::msnp::notification::command::uun::{UserNotificationType, UunPayload};
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::models::endpoint_id::EndpointId;
@@ -24,8 +29,20 @@ impl UbnServer {
 impl TryFromRawCommand for UbnServer {
     type Err = anyhow::Error;
 
-    fn try_from_raw(value: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_source = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "source".into(), 1))?;
+        let source = EndpointId::from_str(&raw_source)?;
+
+        let raw_notification_type = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "notification_type".into(), 2))?;
+        let notification_type: UserNotificationTypeServer = num::FromPrimitive::from_u32(u32::from_str(&raw_notification_type)?)
+                                                        .ok_or(anyhow!("Couldn't parse int to UserNotificationType: {}", raw_notification_type))?;
+
+        let payload = UbnPayload::parse_uun_payload(notification_type, Vec::from(raw.payload))?;
+
+        Ok(Self { source, payload })
     }
 }
 
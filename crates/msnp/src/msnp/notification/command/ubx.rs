@@ -1,9 +1,14 @@
 use crate::msnp::error::{CommandError, PayloadError};
 use crate::msnp::notification::models::endpoint_data::{EndpointData, PrivateEndpointData};
+use crate::msnp::notification::models::profile_extras::{encode_msn_object_field, ProfileExtras};
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::models::network_id_email::NetworkIdEmail;
-use crate::shared::traits::{IntoBytes, TryFromBytes, TryFromRawCommand};
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromBytes, TryFromRawCommand};
+use anyhow::anyhow;
 use std::fmt::Display;
+use std::str::FromStr;
+use yaserde::de::from_str;
 use yaserde::ser::to_string_with_config;
 use yaserde_derive::{YaDeserialize, YaSerialize};
 
@@ -13,14 +18,30 @@ mod tests {
     use crate::msnp::notification::command::ubx::{ExtendedPresenceContent, UbxPayload, UbxServer};
     use crate::msnp::notification::models::endpoint_data::EndpointData;
     use crate::msnp::notification::models::endpoint_guid::EndpointGuid;
+    use crate::msnp::notification::models::profile_extras::ProfileExtras;
+    use crate::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObjectType};
     use crate::shared::models::capabilities::ClientCapabilities;
     use crate::shared::models::email_address::EmailAddress;
     use crate::shared::models::network_id::NetworkId;
     use crate::shared::models::network_id_email::NetworkIdEmail;
     use crate::shared::models::uuid::Uuid;
-    use crate::shared::traits::IntoBytes;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::msnp::notification::models::msnp_version::MsnpVersion;
+    use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
     use std::str::FromStr;
 
+    #[test]
+    pub fn ubx_extended_presence_des_test() {
+        let payload = "<Data><PSM>Hello</PSM><CurrentMedia></CurrentMedia><EndpointData id=\"{00000000-0000-0000-0000-000000000000}\"><Capabilities>0:0</Capabilities></EndpointData></Data>";
+        let ubx = UbxServer::try_from_raw(RawCommand::with_payload("UBX 1:aeon@lukewarmmail.com;via=9:circle@live.fr 163", payload.as_bytes().to_vec())).unwrap();
+
+        assert_eq!(ubx.target_user.to_string(), "1:aeon@lukewarmmail.com");
+        assert_eq!(ubx.via.as_ref().unwrap().to_string(), "9:circle@live.fr");
+
+        let UbxPayload::ExtendedPresence(content) = &ubx.payload;
+        assert_eq!(content.psm, "Hello");
+    }
+
     #[test]
     pub fn ubx_extended_presence_ser_test() {
         let ubx = UbxServer {
@@ -29,6 +50,10 @@ mod tests {
             payload: UbxPayload::ExtendedPresence(ExtendedPresenceContent {
                 psm: "Hello".to_string(),
                 current_media: "".to_string(),
+                ddp: None,
+                signature_sound: None,
+                scene: None,
+                color_scheme: None,
                 endpoint_data: EndpointData {
                     machine_guid: Some(EndpointGuid(Uuid::nil())),
                     capabilities: ClientCapabilities::new(0,0),
@@ -43,6 +68,46 @@ mod tests {
 
         assert_eq!("UBX 1:aeon@lukewarmmail.com 163\r\n<Data><PSM>Hello</PSM><CurrentMedia></CurrentMedia><EndpointData id=\"{00000000-0000-0000-0000-000000000000}\"><Capabilities>0:0</Capabilities></EndpointData></Data>", deser);
     }
+
+    #[test]
+    pub fn ubx_legacy_ser_test() {
+        let ubx = UbxServer {
+            target_user: NetworkIdEmail::new(NetworkId::WindowsLive, EmailAddress::from_str("aeon@lukewarmmail.com").unwrap()),
+            via: Some(NetworkIdEmail::new(NetworkId::Circle, EmailAddress::from_str("circle@live.fr").unwrap())),
+            payload: UbxPayload::ExtendedPresence(ExtendedPresenceContent {
+                psm: "Hello".to_string(),
+                current_media: "".to_string(),
+                endpoint_data: EndpointData {
+                    machine_guid: Some(EndpointGuid(Uuid::nil())),
+                    capabilities: ClientCapabilities::new(0,0),
+                },
+                ..Default::default()
+            }),
+        };
+
+        let deser = String::from_utf8(ubx.into_versioned_bytes(MsnpVersion::MSNP15)).unwrap();
+
+        assert_eq!("UBX aeon@lukewarmmail.com 1 58\r\n<Data><PSM>Hello</PSM><CurrentMedia></CurrentMedia></Data>", deser);
+    }
+
+    #[test]
+    pub fn ubx_extended_presence_with_profile_extras_ser_test() {
+        let scene = MSNObjectFactory::get_from_sha1d(MsnObjectType::Scene, "Bn6Jj0zm14uX0anWlUNJRa3cajA=".to_string(), 41543, "aeon@lukewarmmail.com".to_string(), "0".to_string(), FriendlyName::default());
+
+        let mut content = ExtendedPresenceContent::default();
+        content.set_profile_extras(&ProfileExtras {
+            ddp: None,
+            signature_sound: None,
+            scene: Some(scene),
+            color_scheme: Some("-3".to_string()),
+        });
+
+        let serialized = content.to_string();
+
+        assert!(serialized.contains("<Scene>%3Cmsnobj%20Creator%3D%22aeon%40lukewarmmail.com%22%20Type%3D%2216%22"));
+        assert!(serialized.contains("<ColorScheme>-3</ColorScheme>"));
+        assert!(!serialized.contains("<DDP>"));
+    }
 }
 
 
@@ -56,8 +121,19 @@ pub struct UbxServer {
 impl TryFromRawCommand for UbxServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> where Self: Sized {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_target_user = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "target_user".into(), 1))?;
+        let (target_user, via) = match raw_target_user.split_once(";via=") {
+            Some((target_user, via)) => (NetworkIdEmail::from_str(target_user)?, Some(NetworkIdEmail::from_str(via)?)),
+            None => (NetworkIdEmail::from_str(&raw_target_user)?, None),
+        };
+
+        let payload = UbxPayload::try_from_bytes(Vec::from(raw.payload))?;
+
+        Ok(Self { target_user, via, payload })
     }
 
 }
@@ -86,6 +162,19 @@ impl IntoBytes for UbxServer {
     }
 }
 
+impl IntoVersionedBytes for UbxServer {
+
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        if version.supports_circles() {
+            return self.into_bytes();
+        }
+
+        let mut payload = self.payload.into_versioned_bytes(version);
+        let mut cmd = format!("UBX {email} {network_id} {payload_size}\r\n", email = self.target_user.email, network_id = self.target_user.network_id.clone() as i32, payload_size = payload.len()).into_bytes();
+        cmd.append(&mut payload);
+        cmd
+    }
+}
 
 pub enum UbxPayload {
     ExtendedPresence(ExtendedPresenceContent)
@@ -94,8 +183,10 @@ pub enum UbxPayload {
 impl TryFromBytes for UbxPayload {
     type Err = PayloadError;
 
-    fn try_from_bytes(_bytes: Vec<u8>) -> Result<Self, Self::Err> where Self: Sized {
-        todo!()
+    fn try_from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Err> where Self: Sized {
+        let payload = String::from_utf8(bytes)?;
+        let content = from_str::<ExtendedPresenceContent>(&payload).map_err(|e| PayloadError::StringPayloadParsingError { payload: payload.clone(), source: anyhow!("Couldn't parse UBX Payload: {}", e) })?;
+        Ok(UbxPayload::ExtendedPresence(content))
     }
 
 }
@@ -110,6 +201,64 @@ impl IntoBytes for UbxPayload {
     }
 }
 
+impl IntoVersionedBytes for UbxPayload {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        match self {
+            UbxPayload::ExtendedPresence(content) => {
+                if version.supports_endpoints() {
+                    content.to_string().into_bytes()
+                } else {
+                    LegacyPresenceContent::from(content).to_string().into_bytes()
+                }
+            }
+        }
+    }
+}
+
+//MSNP15 predates MPOP and rejects endpoint data in the UBX payload
+#[derive(Debug, Clone, Default, YaSerialize)]
+#[yaserde(rename="Data")]
+struct LegacyPresenceContent {
+    #[yaserde(rename = "PSM")]
+    psm: String,
+    #[yaserde(rename = "CurrentMedia")]
+    current_media: String,
+    #[yaserde(rename = "DDP")]
+    ddp: Option<String>,
+    #[yaserde(rename = "SignatureSound")]
+    signature_sound: Option<String>,
+    #[yaserde(rename = "Scene")]
+    scene: Option<String>,
+    #[yaserde(rename = "ColorScheme")]
+    color_scheme: Option<String>,
+}
+
+impl From<ExtendedPresenceContent> for LegacyPresenceContent {
+    fn from(content: ExtendedPresenceContent) -> Self {
+        Self {
+            psm: content.psm,
+            current_media: content.current_media,
+            ddp: content.ddp,
+            signature_sound: content.signature_sound,
+            scene: content.scene,
+            color_scheme: content.color_scheme,
+        }
+    }
+}
+
+impl Display for LegacyPresenceContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let yaserde_cfg = yaserde::ser::Config{
+            perform_indent: false,
+            write_document_declaration: false,
+            indent_string: None
+        };
+
+        let serialized = to_string_with_config(self, &yaserde_cfg).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", serialized)
+    }
+}
+
 #[derive(Debug, Clone, Default, YaSerialize, YaDeserialize)]
 #[yaserde(rename="Data")]
 pub struct ExtendedPresenceContent {
@@ -117,12 +266,29 @@ pub struct ExtendedPresenceContent {
     pub psm: String,
     #[yaserde(rename = "CurrentMedia")]
     pub current_media: String,
+    #[yaserde(rename = "DDP")]
+    pub ddp: Option<String>,
+    #[yaserde(rename = "SignatureSound")]
+    pub signature_sound: Option<String>,
+    #[yaserde(rename = "Scene")]
+    pub scene: Option<String>,
+    #[yaserde(rename = "ColorScheme")]
+    pub color_scheme: Option<String>,
     #[yaserde(rename = "EndpointData")]
     pub endpoint_data: EndpointData,
     #[yaserde(rename = "PrivateEndpointData")]
     pub private_endpoint_data: Option<PrivateEndpointData>
 }
 
+impl ExtendedPresenceContent {
+    pub fn set_profile_extras(&mut self, extras: &ProfileExtras) {
+        self.ddp = encode_msn_object_field(&extras.ddp);
+        self.signature_sound = encode_msn_object_field(&extras.signature_sound);
+        self.scene = encode_msn_object_field(&extras.scene);
+        self.color_scheme = extras.color_scheme.clone();
+    }
+}
+
 impl Display for ExtendedPresenceContent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 
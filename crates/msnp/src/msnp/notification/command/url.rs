@@ -109,11 +109,11 @@ impl TryFromRawCommand for UrlClient {
 }
 
 pub struct UrlServer {
-    tr_id: u128,
-    main_url: String,
-    post_url: String,
+    pub tr_id: u128,
+    pub main_url: String,
+    pub post_url: String,
     //2 for windows live
-    url_type: u8
+    pub url_type: u8
 }
 
 
@@ -134,6 +134,27 @@ impl Display for UrlServer {
     }
 }
 
+impl TryFromRawCommand for UrlServer {
+    type Err = CommandError;
+
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let main_url = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "main_url".into(), 2))?;
+
+        let post_url = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "post_url".into(), 3))?;
+
+        let raw_url_type = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "url_type".into(), 4))?;
+        let url_type = u8::from_str(&raw_url_type)?;
+
+        Ok(Self::new(tr_id, main_url, post_url, url_type))
+    }
+}
+
 impl IntoBytes for UrlServer {
     fn into_bytes(self) -> Vec<u8> {
         self.to_string().into_bytes()
@@ -202,4 +223,17 @@ mod tests {
 
     }
 
+    #[test]
+    fn server_url_deser() {
+        let command_str = "URL 1 /cgi-bin/HoTMaiL https://login.live.com/ppsecure/md5auth.srf?lc=1033 0\r\n";
+        let raw_command = RawCommandParser::new().parse_message(command_str.as_bytes()).unwrap().remove(0);
+
+        let deser = UrlServer::try_from_raw(raw_command).unwrap();
+
+        assert_eq!(deser.tr_id, 1);
+        assert_eq!(deser.main_url, "/cgi-bin/HoTMaiL");
+        assert_eq!(deser.url_type, 0);
+        assert_eq!(command_str, deser.to_string());
+    }
+
 }
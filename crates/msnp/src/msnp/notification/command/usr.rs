@@ -38,6 +38,22 @@ impl TryFromRawCommand for UsrClient {
     }
 }
 
+impl core::fmt::Display for UsrClient {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.auth_type {
+            AuthOperationTypeClient::Sso(content) => write!(f, "{operand} {tr_id} SSO {content}\r\n", operand = OPERAND, tr_id = self.tr_id, content = content),
+            AuthOperationTypeClient::Sha(content) => write!(f, "{operand} {tr_id} SHA {content}\r\n", operand = OPERAND, tr_id = self.tr_id, content = content),
+        }
+    }
+}
+
+impl IntoBytes for UsrClient {
+    fn into_bytes(self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
 #[derive(Display)]
 pub enum AuthOperationTypeClient {
     #[strum(serialize = "SSO")]
@@ -68,6 +84,14 @@ pub enum ShaPhaseClient {
     }
 }
 
+impl core::fmt::Display for ShaPhaseClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaPhaseClient::A { circle_ticket } => write!(f, "A {}", circle_ticket)
+        }
+    }
+}
+
 impl TryFromSplit for ShaPhaseClient{
     type Err = CommandError;
 
@@ -95,11 +119,22 @@ pub enum SsoPhaseClient {
     S {
         ticket_token: TicketToken,
         challenge: String,
-        endpoint_guid: EndpointGuid,
+        //Only sent from MSNP16 on (MPOP)
+        endpoint_guid: Option<EndpointGuid>,
     },
 }
 
 
+impl core::fmt::Display for SsoPhaseClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SsoPhaseClient::I { email_addr } => write!(f, "I {}", email_addr),
+            SsoPhaseClient::S { ticket_token, challenge, endpoint_guid: Some(endpoint_guid) } => write!(f, "S {} {} {}", ticket_token, challenge, endpoint_guid),
+            SsoPhaseClient::S { ticket_token, challenge, endpoint_guid: None } => write!(f, "S {} {}", ticket_token, challenge)
+        }
+    }
+}
+
 impl TryFromSplit for SsoPhaseClient {
     type Err = CommandError;
 
@@ -117,8 +152,10 @@ impl TryFromSplit for SsoPhaseClient {
 
                 let challenge = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "challenge".into(), 6))?;
 
-                let raw_endpoint_guid = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "endpoint_guid".into(), 7))?;
-                let endpoint_guid = EndpointGuid::from_str(&raw_endpoint_guid)?;
+                let endpoint_guid = match split.pop_front() {
+                    Some(raw_endpoint_guid) => Some(EndpointGuid::from_str(&raw_endpoint_guid)?),
+                    None => None
+                };
 
                 Ok(SsoPhaseClient::S {
                     ticket_token,
@@ -143,6 +180,34 @@ pub enum OperationTypeServer {
     },
 }
 
+impl TryFromSplit for OperationTypeServer {
+    type Err = CommandError;
+
+    fn try_from_split(mut split: VecDeque<String>, command: &str) -> Result<Self, Self::Err> {
+        let raw_op_type = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "operation_type".into(), 2))?;
+
+        match raw_op_type.as_str() {
+            "SSO" => Ok(OperationTypeServer::Sso(SsoPhaseServer::try_from_split(split, command)?)),
+            "OK" => {
+                let raw_email_addr = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "email_addr".into(), 3))?;
+                let email_addr = EmailAddress::from_str(&raw_email_addr)?;
+
+                let raw_verified = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "verified".into(), 4))?;
+                let raw_unknown_arg = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "unknown_arg".into(), 5))?;
+
+                Ok(OperationTypeServer::Ok {
+                    email_addr,
+                    verified: u8::from_str(&raw_verified)? != 0,
+                    unknown_arg: u8::from_str(&raw_unknown_arg)? != 0,
+                })
+            },
+            _ => {
+                Err(CommandError::ArgumentParseError { argument: raw_op_type.to_string(), command: command.to_string(), source: anyhow!("Unknown operation type") })
+            }
+        }
+    }
+}
+
 impl core::fmt::Display for OperationTypeServer {
 
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -162,6 +227,28 @@ pub enum SsoPhaseServer {
     S { policy: AuthPolicy, nonce: String },
 }
 
+impl TryFromSplit for SsoPhaseServer {
+    type Err = CommandError;
+
+    fn try_from_split(mut split: VecDeque<String>, command: &str) -> Result<Self, Self::Err> {
+        let raw_sso_phase = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "sso_phase".into(), 3))?;
+
+        match raw_sso_phase.as_str() {
+            "S" => {
+                let raw_policy = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "policy".into(), 4))?;
+                let policy = AuthPolicy::from_str(&raw_policy)?;
+
+                let nonce = split.pop_front().ok_or(CommandError::MissingArgument(command.to_string(), "nonce".into(), 5))?;
+
+                Ok(SsoPhaseServer::S { policy, nonce })
+            },
+            _ => {
+                Err(CommandError::ArgumentParseError { argument: raw_sso_phase.to_string(), command: command.to_string(), source: anyhow!("Unknown sso phase") })
+            }
+        }
+    }
+}
+
 impl core::fmt::Display for SsoPhaseServer{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
@@ -203,8 +290,19 @@ impl core::fmt::Display for UsrServer {
 impl TryFromRawCommand for UsrServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let auth_type = OperationTypeServer::try_from_split(split, &raw.command)?;
+
+        Ok(Self {
+            tr_id,
+            auth_type,
+        })
     }
 
 }
@@ -223,10 +321,18 @@ mod tests {
     use crate::msnp::raw_command_parser::RawCommand;
     use crate::msnp::{error::CommandError, notification::command::usr::{AuthOperationTypeClient, SsoPhaseClient}};
     use crate::shared::models::email_address::EmailAddress;
-    use crate::shared::traits::TryFromRawCommand;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
 
     use super::{AuthPolicy, OperationTypeServer, SsoPhaseServer, UsrClient, UsrServer};
 
+    #[test]
+    fn client_sso_round_trip() {
+        for raw in ["USR 3 SSO I login@test.com\r\n", "USR 4 SSO S t=ticket== challenge {F52973B6-C926-4BAD-9BA8-7C1E840E4AB0}\r\n"] {
+            let usr = UsrClient::try_from_raw(RawCommand::from_str(raw).unwrap()).unwrap();
+            assert_eq!(raw.to_lowercase(), String::from_utf8(usr.into_bytes()).unwrap().to_lowercase());
+        }
+    }
+
     #[test]
     fn client_sso_i_des_success() {
         let usr1 = UsrClient::try_from_raw(RawCommand::from_str("USR 3 SSO I login@test.com").unwrap()).unwrap();
@@ -258,9 +364,11 @@ mod tests {
     }
 
     #[test]
-    fn client_sso_i_des_failure_3() {
-        let usr1 = UsrClient::try_from_raw(RawCommand::from_str("USR 4 SSO S t=ssotoken ???charabia").unwrap());
-        assert!(matches!(&usr1, Err(CommandError::MissingArgument { .. })));
+    fn client_sso_s_des_without_endpoint_guid() {
+        //MSNP15 clients don't send an endpoint guid
+        let usr1 = UsrClient::try_from_raw(RawCommand::from_str("USR 4 SSO S t=ssotoken ???charabia").unwrap()).unwrap();
+        assert!(matches!(&usr1.auth_type, AuthOperationTypeClient::Sso(SsoPhaseClient::S { endpoint_guid: None, .. })));
+        assert_eq!("USR 4 SSO S t=ssotoken ???charabia\r\n", usr1.to_string());
     }
 
     #[test]
@@ -289,7 +397,7 @@ mod tests {
             if let SsoPhaseClient::S { ticket_token, challenge, endpoint_guid } = content {
                 assert_eq!("t=ssotoken", ticket_token.to_string());
                 assert_eq!("???charabia", challenge);
-                assert_eq!("{55192CF5-588E-4ABE-9CDF-395B616ED85B}", endpoint_guid.to_string());
+                assert_eq!("{55192CF5-588E-4ABE-9CDF-395B616ED85B}", endpoint_guid.as_ref().unwrap().to_string());
 
             }
 
@@ -320,6 +428,21 @@ mod tests {
         assert_eq!("USR 2 OK Xx-taytay-xX@hotmail.com 1 0\r\n", ser);
     }
 
+    #[test]
+    fn server_sso_s_des() {
+        let usr = UsrServer::try_from_raw(RawCommand::from_str("USR 1 SSO S MBI_KEY_OLD n0nce").unwrap()).unwrap();
+        assert_eq!(1, usr.tr_id);
+        assert!(matches!(&usr.auth_type, OperationTypeServer::Sso(SsoPhaseServer::S { policy: AuthPolicy::MbiKeyOld, nonce }) if nonce == "n0nce"));
+        assert_eq!("USR 1 SSO S MBI_KEY_OLD n0nce\r\n", usr.to_string());
+    }
+
+    #[test]
+    fn server_ok_des() {
+        let usr = UsrServer::try_from_raw(RawCommand::from_str("USR 2 OK Xx-taytay-xX@hotmail.com 1 0").unwrap()).unwrap();
+        assert!(matches!(&usr.auth_type, OperationTypeServer::Ok { verified: true, unknown_arg: false, .. }));
+        assert_eq!("USR 2 OK Xx-taytay-xX@hotmail.com 1 0\r\n", usr.to_string());
+    }
+
 
 
 }
@@ -133,7 +133,7 @@ impl TryFromRawCommand for UumClient {
 
         let message_type = MessageType::from_u32(u32::from_str(&raw_message_type)?).ok_or(CommandError::ArgumentParseError { argument: raw_message_type.to_string(), command: raw.command.clone(), source: anyhow!("Couldn't parse int to UserNotificationType") })?;
 
-        let payload = UumPayload::parse_payload(message_type, raw.payload)?;
+        let payload = UumPayload::parse_payload(message_type, Vec::from(raw.payload))?;
 
         Ok(Self {
             tr_id,
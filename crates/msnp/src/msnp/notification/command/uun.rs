@@ -39,7 +39,7 @@ impl TryFromRawCommand for UunClient {
         let notification_type: UserNotificationType = num::FromPrimitive::from_u32(u32::from_str(&raw_notification_type)?)
                                                         .ok_or(CommandError::ArgumentParseError { argument: raw_notification_type.to_string(), command: raw.command, source: anyhow!("Couldn't parse int to UserNotificationType") })?;
 
-        let payload = UunPayload::parse_uun_payload(notification_type, raw.payload)?;
+        let payload = UunPayload::parse_uun_payload(notification_type, Vec::from(raw.payload))?;
 
         Ok(Self { tr_id, destination, payload })
 
@@ -81,7 +81,7 @@ impl IntoBytes for UunPayload {
 }
 
 impl UunPayload {
-    fn parse_uun_payload(payload_type: UserNotificationType, payload: Vec<u8>) -> Result<Self, PayloadError>{
+    pub(crate) fn parse_uun_payload(payload_type: UserNotificationType, payload: Vec<u8>) -> Result<Self, PayloadError>{
         Ok(match payload_type {
             UserNotificationType::DisconnectClient => {
                 Self::DisconnectClient
@@ -1,6 +1,7 @@
 use std::{fmt::Display, str::FromStr};
 
 use crate::msnp::{error::{CommandError, PayloadError}, notification::models::endpoint_data::PrivateEndpointData, raw_command_parser::RawCommand};
+use crate::msnp::notification::models::profile_extras::PersonalMessageContent;
 use crate::shared::traits::{TryFromRawCommand, TryFromBytes, IntoBytes};
 
 pub struct Uux {
@@ -37,7 +38,7 @@ impl TryFromRawCommand for Uux {
 
         let payload_size = raw.expected_payload_size;
 
-        let payload = if payload_size > 0 { Some(UuxPayload::try_from_bytes(raw.payload)?) } else { None };
+        let payload = if payload_size > 0 { Some(UuxPayload::try_from_bytes(Vec::from(raw.payload))?) } else { None };
 
         Ok(Self{
             tr_id,
@@ -66,6 +67,7 @@ impl Uux {
 
 pub enum UuxPayload {
     PrivateEndpointData(PrivateEndpointData),
+    PersonalMessage(PersonalMessageContent),
     Unknown(String)
 }
 
@@ -90,6 +92,8 @@ impl FromStr for UuxPayload {
     fn from_str(payload: &str) -> Result<Self, Self::Err> {
         if payload.starts_with("<PrivateEndpointData>") {
             Ok(Self::PrivateEndpointData(PrivateEndpointData::from_str(payload)?))
+        } else if payload.starts_with("<Data>") {
+            Ok(Self::PersonalMessage(PersonalMessageContent::from_str(payload)?))
         } else {
             Ok(Self::Unknown(payload.to_string()))
         }
@@ -103,6 +107,9 @@ impl Display for UuxPayload {
             UuxPayload::PrivateEndpointData(payload) => {
                 write!(f, "{}", payload)
             },
+            UuxPayload::PersonalMessage(payload) => {
+                write!(f, "{}", payload)
+            },
             UuxPayload::Unknown(payload) => {
                 write!(f, "{}", payload)
             }
@@ -141,6 +148,16 @@ mod tests {
         assert!(matches!(uux.payload, Some(UuxPayload::PrivateEndpointData(_))));
     }
 
+    #[test]
+    fn request_deserialization_personal_message_payload() {
+        let payload = "<Data><PSM>Hi my dude</PSM><CurrentMedia></CurrentMedia><MachineGuid>&#x7B;F52973B6-C926-4BAD-9BA8-7C1E840E4AB0&#x7D;</MachineGuid><DDP></DDP><SignatureSound></SignatureSound><Scene></Scene><ColorScheme></ColorScheme></Data>";
+
+        let uux = UuxClient::try_from_raw(RawCommand::with_payload(&format!("UUX 10 {}\r\n", payload.len()), payload.as_bytes().to_vec())).unwrap();
+
+        assert_eq!(10, uux.tr_id);
+        assert!(matches!(uux.payload, Some(UuxPayload::PersonalMessage(_))));
+    }
+
     #[test]
     fn request_deserialization_no_payload() {
 
@@ -7,6 +7,7 @@ pub struct VerClient {
     pub tr_id: u128,
     pub first_candidate : MsnpVersion,
     pub second_candidate : MsnpVersion,
+    pub additional_candidates: Vec<MsnpVersion>,
     pub cvr: String
 }
 
@@ -17,10 +18,15 @@ impl VerClient {
             tr_id,
             first_candidate,
             second_candidate,
+            additional_candidates: Vec::new(),
             cvr: "CVR0".to_string()
         }
     }
 
+    pub fn candidates(&self) -> impl Iterator<Item = &MsnpVersion> {
+        [&self.first_candidate, &self.second_candidate].into_iter().chain(self.additional_candidates.iter())
+    }
+
     pub fn get_response_for(&self, agreed_version: MsnpVersion) -> VerServer {
         VerServer::new(self.tr_id, agreed_version)
     }
@@ -42,9 +48,12 @@ impl TryFromRawCommand for VerClient {
         let raw_second_candidate = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "second_candidate".into(), 3))?;
         let second_candidate = MsnpVersion::from_str(&raw_second_candidate)?;
 
-        let cvr = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "cvr".into(), 4))?;
+        let cvr = split.pop_back().ok_or(CommandError::MissingArgument(raw.command.clone(), "cvr".into(), 4))?;
 
-        Ok(VerClient {tr_id, first_candidate, second_candidate, cvr})
+        //Older clients list every version they know, we skip the ones we've never heard of
+        let additional_candidates = split.iter().filter_map(|candidate| MsnpVersion::from_str(candidate).ok()).collect();
+
+        Ok(VerClient {tr_id, first_candidate, second_candidate, additional_candidates, cvr})
 
     }
 }
@@ -58,7 +67,11 @@ impl IntoBytes for VerClient {
 impl Display for VerClient {
 
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{operand} {tr_id} {first_candidate} {second_candidate} {cvr}\r\n",operand = "VER", tr_id = self.tr_id, first_candidate = self.first_candidate, second_candidate = self.second_candidate, cvr = self.cvr)
+        write!(f, "{operand} {tr_id} {first_candidate} {second_candidate} ",operand = "VER", tr_id = self.tr_id, first_candidate = self.first_candidate, second_candidate = self.second_candidate)?;
+        for candidate in &self.additional_candidates {
+            write!(f, "{} ", candidate)?;
+        }
+        write!(f, "{}\r\n", self.cvr)
     }
 }
 
@@ -139,6 +152,16 @@ mod tests {
         assert_eq!(request.to_string().as_str(), "VER 1 MSNP17 MSNP17 CVR0\r\n");
     }
 
+    #[test]
+    fn request_deserialization_legacy_candidates() {
+        let request = VerClient::try_from_raw(RawCommand::from_str("VER 1 MSNP15 MSNP14 MSNP13 CVR0").unwrap()).unwrap();
+        assert_eq!(request.first_candidate, MsnpVersion::MSNP15);
+        assert_eq!(request.additional_candidates, vec![MsnpVersion::MSNP13]);
+        assert_eq!(request.candidates().max(), Some(&MsnpVersion::MSNP15));
+        assert_eq!(&request.cvr, "CVR0");
+        assert_eq!(request.to_string().as_str(), "VER 1 MSNP15 MSNP14 MSNP13 CVR0\r\n");
+    }
+
     #[test]
     fn response_deserialization_success() {
         let response = VerServer::try_from_raw(RawCommand::from_str("VER 1 MSNP18").unwrap()).unwrap();
@@ -3,6 +3,7 @@ use crate::msnp::notification::models::ip_address::IpAddress;
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::msnp::switchboard::models::auth_method::AuthenticationMethod;
 use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use anyhow::anyhow;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use strum_macros::{Display, EnumString};
@@ -118,7 +119,41 @@ impl TryFromRawCommand for XfrServer {
     where
         Self: Sized
     {
-        todo!()
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let raw_request_type = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "request_type".into(), 2))?;
+
+        let raw_address = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "address".into(), 3))?;
+        let address = IpAddress::from_str(&raw_address).map_err(|e| CommandError::ArgumentParseError { argument: raw_address.clone(), command: raw.command.clone(), source: e })?;
+
+        let request_type = match raw_request_type.as_str() {
+            "SB" => {
+                let raw_authentication_method = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "authentication_method".into(), 4))?;
+                let authentication_method = AuthenticationMethod::from_str(&raw_authentication_method)?;
+
+                let auth_token = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "auth_token".into(), 5))?;
+
+                ServerRequestType::Switchboard { address, authentication_method, auth_token }
+            },
+            "NS" => {
+                let raw_unknown = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "unknown".into(), 4))?;
+                let unknown = u32::from_str(&raw_unknown)?;
+
+                let raw_current_address = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "current_address".into(), 5))?;
+                let current_address = IpAddress::from_str(&raw_current_address).map_err(|e| CommandError::ArgumentParseError { argument: raw_current_address.clone(), command: raw.command.clone(), source: e })?;
+
+                ServerRequestType::NotificationServer { address, unknown, current_address }
+            },
+            _ => {
+                return Err(CommandError::ArgumentParseError { argument: raw_request_type.clone(), command: raw.command.clone(), source: anyhow!("Unknown XFR request type") });
+            }
+        };
+
+        Ok(Self { tr_id, request_type })
     }
 
 }
@@ -144,7 +179,7 @@ impl Display for XfrServer {
 
 #[cfg(test)]
 mod tests {
-    use crate::msnp::notification::command::xfr::{ClientRequestType, XfrClient, XfrServer};
+    use crate::msnp::notification::command::xfr::{ClientRequestType, ServerRequestType, XfrClient, XfrServer};
     use crate::msnp::raw_command_parser::RawCommand;
     use crate::shared::traits::TryFromRawCommand;
     use std::str::FromStr;
@@ -179,4 +214,20 @@ mod tests {
         let ser = xfr_server.to_string();
         assert_eq!("XFR 12 SB 127.0.0.1:1864 CKI 4uth_t0k3n\r\n", ser);
     }
+
+    #[test]
+    fn request_deserialization_server_sb() {
+        let xfr_server = XfrServer::try_from_raw(RawCommand::from_str("XFR 12 SB 127.0.0.1:1864 CKI 4uth_t0k3n\r\n").unwrap()).unwrap();
+
+        assert_eq!(12, xfr_server.tr_id);
+        assert!(matches!(&xfr_server.request_type, ServerRequestType::Switchboard { auth_token, .. } if auth_token == "4uth_t0k3n"));
+        assert_eq!("XFR 12 SB 127.0.0.1:1864 CKI 4uth_t0k3n\r\n", xfr_server.to_string());
+    }
+
+    #[test]
+    fn request_deserialization_server_ns() {
+        let xfr_server = XfrServer::try_from_raw(RawCommand::from_str("XFR 12 NS 127.0.0.1:1862 0 127.0.0.1:1863\r\n").unwrap()).unwrap();
+
+        assert!(matches!(&xfr_server.request_type, ServerRequestType::NotificationServer { current_address, .. } if current_address.port == 1863));
+    }
 }
\ No newline at end of file
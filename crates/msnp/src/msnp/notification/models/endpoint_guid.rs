@@ -27,8 +27,16 @@ impl YaSerialize for EndpointGuid {
 }
 
 impl YaDeserialize for EndpointGuid {
-    fn deserialize<R: Read>(_reader: &mut Deserializer<R>) -> Result<Self, String> {
-        todo!()
+    fn deserialize<R: Read>(reader: &mut Deserializer<R>) -> Result<Self, String> {
+        if let xml::reader::XmlEvent::StartElement { .. } = reader.peek()?.to_owned() {
+            let _next = reader.next_event();
+        }
+        if let xml::reader::XmlEvent::Characters(text) = reader.peek()?.to_owned() {
+            let _next = reader.next_event();
+            EndpointGuid::from_payload_str(&text).map_err(|e| e.to_string())
+        } else {
+            Err("Characters missing".to_string())
+        }
     }
 }
 
@@ -45,8 +53,59 @@ impl FromStr for EndpointGuid {
         Uuid::from_str(trimmed).map(|uuid: Uuid| EndpointGuid(uuid)).map_err(|e| CommandError::ArgumentParseError { argument: endpoint_guid.to_string(), command: String::new(), source: e.into() })    }
 }
 
+impl EndpointGuid {
+    //Commands always brace the GUID, XML payloads from some clients don't.
+    pub fn from_payload_str(endpoint_guid: &str) -> Result<Self, CommandError> {
+        let trimmed = endpoint_guid.trim();
+        if trimmed.starts_with('{') {
+            return EndpointGuid::from_str(trimmed);
+        }
+
+        Uuid::from_str(trimmed).map(EndpointGuid).map_err(|e| CommandError::ArgumentParseError { argument: endpoint_guid.to_string(), command: String::new(), source: e.into() })
+    }
+}
+
 impl Display for EndpointGuid {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{{{guid}}}", guid = self.0)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use yaserde_derive::YaDeserialize;
+
+    use crate::msnp::notification::models::endpoint_guid::EndpointGuid;
+
+    #[derive(Debug, Default, YaDeserialize)]
+    struct WithGuid {
+        #[yaserde(rename = "id", attribute)]
+        id: Option<EndpointGuid>,
+        #[yaserde(rename = "Guid")]
+        guid: Option<EndpointGuid>,
+    }
+
+    #[test]
+    fn braced_and_unbraced() {
+        let braced = EndpointGuid::from_str("{F52973B6-C926-4BAD-9BA8-7C1E840E4AB0}").unwrap();
+        let unbraced = EndpointGuid::from_payload_str("f52973b6-c926-4bad-9ba8-7c1e840e4ab0").unwrap();
+        assert_eq!(braced.to_string(), unbraced.to_string());
+        assert!(EndpointGuid::from_str("f52973b6-c926-4bad-9ba8-7c1e840e4ab0").is_err());
+    }
+
+    #[test]
+    fn invalid_guid_is_an_error() {
+        assert!(EndpointGuid::from_payload_str("{F52973B6-C926-4BAD-9BA8-7C1E840E4AB0").is_err());
+        assert!(EndpointGuid::from_payload_str("not a guid").is_err());
+        assert!(yaserde::de::from_str::<WithGuid>("<WithGuid id=\"{nope}\"></WithGuid>").is_err());
+    }
+
+    #[test]
+    fn deserialize_attribute_and_element() {
+        let parsed = yaserde::de::from_str::<WithGuid>("<WithGuid id=\"{00000000-0000-0000-0000-000000000001}\"><Guid>00000000-0000-0000-0000-000000000002</Guid></WithGuid>").unwrap();
+        assert_eq!(parsed.id.unwrap().to_string(), "{00000000-0000-0000-0000-000000000001}");
+        assert_eq!(parsed.guid.unwrap().to_string(), "{00000000-0000-0000-0000-000000000002}");
+    }
+}
@@ -2,4 +2,5 @@ pub mod endpoint_data;
 pub mod msnp_version;
 pub mod endpoint_guid;
 pub mod ip_address;
-pub mod adl_payload;
\ No newline at end of file
+pub mod adl_payload;
+pub mod profile_extras;
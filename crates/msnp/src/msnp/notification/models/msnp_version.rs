@@ -1,8 +1,46 @@
 use strum_macros::{Display, EnumString};
 
-#[derive(Debug, EnumString, Display, PartialEq)]
+#[derive(Debug, Clone, Copy, EnumString, Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MsnpVersion {
+    MSNP13,
+    MSNP14,
+    MSNP15,
+    MSNP16,
     MSNP17,
     MSNP18,
     MSNP21
-}
\ No newline at end of file
+}
+
+impl MsnpVersion {
+
+    //MSNP18 addresses contacts as "1:email" and introduced circles
+    pub fn supports_circles(&self) -> bool {
+        *self >= MsnpVersion::MSNP18
+    }
+
+    //MPOP: extended capabilities & endpoint data in UBX
+    pub fn supports_endpoints(&self) -> bool {
+        *self >= MsnpVersion::MSNP16
+    }
+
+}
+
+impl Default for MsnpVersion {
+    fn default() -> Self {
+        MsnpVersion::MSNP18
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MsnpVersion;
+
+    #[test]
+    fn versions_are_ordered() {
+        assert!(MsnpVersion::MSNP15 < MsnpVersion::MSNP18);
+        assert!(!MsnpVersion::MSNP15.supports_endpoints());
+        assert!(MsnpVersion::MSNP16.supports_endpoints());
+        assert!(!MsnpVersion::MSNP17.supports_circles());
+        assert!(MsnpVersion::MSNP18.supports_circles());
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use yaserde::de::from_str;
+use yaserde::ser::to_string_with_config;
+use yaserde_derive::{YaDeserialize, YaSerialize};
+
+use crate::msnp::error::PayloadError;
+use crate::shared::models::msn_object::MsnObject;
+
+/* WLM 2009 sends the scene, colour scheme, signature sound & dynamic display picture along with the PSM.
+UUX 10 224
+<Data><PSM>Hi my dude</PSM><CurrentMedia></CurrentMedia><MachineGuid>&#x7B;F52973B6-C926-4BAD-9BA8-7C1E840E4AB0&#x7D;</MachineGuid><DDP></DDP><SignatureSound></SignatureSound><Scene></Scene><ColorScheme></ColorScheme></Data>
+ */
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileExtras {
+    pub ddp: Option<MsnObject>,
+    pub signature_sound: Option<MsnObject>,
+    pub scene: Option<MsnObject>,
+    //Signed ARGB, as WLM sends it
+    pub color_scheme: Option<String>,
+}
+
+impl ProfileExtras {
+    pub fn is_empty(&self) -> bool {
+        self.ddp.is_none() && self.signature_sound.is_none() && self.scene.is_none() && self.color_scheme.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, YaSerialize, YaDeserialize)]
+#[yaserde(rename = "Data")]
+pub struct PersonalMessageContent {
+    #[yaserde(rename = "PSM")]
+    pub psm: String,
+    #[yaserde(rename = "CurrentMedia")]
+    pub current_media: String,
+    #[yaserde(rename = "MachineGuid")]
+    pub machine_guid: Option<String>,
+    #[yaserde(rename = "DDP")]
+    pub ddp: Option<String>,
+    #[yaserde(rename = "SignatureSound")]
+    pub signature_sound: Option<String>,
+    #[yaserde(rename = "Scene")]
+    pub scene: Option<String>,
+    #[yaserde(rename = "ColorScheme")]
+    pub color_scheme: Option<String>,
+}
+
+impl PersonalMessageContent {
+    pub fn profile_extras(&self) -> Result<ProfileExtras, PayloadError> {
+        Ok(ProfileExtras {
+            ddp: decode_msn_object_field(&self.ddp)?,
+            signature_sound: decode_msn_object_field(&self.signature_sound)?,
+            scene: decode_msn_object_field(&self.scene)?,
+            color_scheme: self.color_scheme.clone().filter(|color| !color.is_empty()),
+        })
+    }
+}
+
+impl FromStr for PersonalMessageContent {
+    type Err = PayloadError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_str::<PersonalMessageContent>(s).map_err(|e| PayloadError::StringPayloadParsingError { payload: s.to_string(), source: anyhow!("Couldn't parse PersonalMessage Payload: {}", e) })
+    }
+}
+
+impl Display for PersonalMessageContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let yaserde_cfg = yaserde::ser::Config{
+            perform_indent: false,
+            write_document_declaration: false,
+            indent_string: None
+        };
+
+        let serialized = to_string_with_config(self, &yaserde_cfg).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", serialized)
+    }
+}
+
+//MSNObjects are URL encoded in the Data payloads, empty elements mean the object was removed.
+pub(crate) fn decode_msn_object_field(field: &Option<String>) -> Result<Option<MsnObject>, PayloadError> {
+    let Some(raw) = field.as_ref().filter(|raw| !raw.is_empty()) else {
+        return Ok(None);
+    };
+
+    let decoded = urlencoding::decode(raw).map_err(|e| PayloadError::StringPayloadParsingError { payload: raw.clone(), source: anyhow!("MSNObject was not URL encoded: {}", e) })?;
+    Ok(Some(MsnObject::from_str(&decoded)?))
+}
+
+pub(crate) fn encode_msn_object_field(msn_object: &Option<MsnObject>) -> Option<String> {
+    msn_object.as_ref().map(|msn_object| msn_object.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::msnp::notification::models::profile_extras::PersonalMessageContent;
+    use crate::shared::models::msn_object::MsnObjectType;
+
+    #[test]
+    fn deserialize_empty_extras() {
+        let payload = "<Data><PSM>Hi my dude</PSM><CurrentMedia></CurrentMedia><MachineGuid>&#x7B;F52973B6-C926-4BAD-9BA8-7C1E840E4AB0&#x7D;</MachineGuid><DDP></DDP><SignatureSound></SignatureSound><Scene></Scene><ColorScheme></ColorScheme></Data>";
+
+        let content = PersonalMessageContent::from_str(payload).unwrap();
+        assert_eq!(content.psm, "Hi my dude");
+
+        let extras = content.profile_extras().unwrap();
+        assert!(extras.is_empty());
+    }
+
+    #[test]
+    fn deserialize_scene_and_color_scheme() {
+        let payload = "<Data><PSM></PSM><CurrentMedia></CurrentMedia><MachineGuid>&#x7B;F52973B6-C926-4BAD-9BA8-7C1E840E4AB0&#x7D;</MachineGuid><DDP></DDP><SignatureSound></SignatureSound><Scene>%3Cmsnobj%20Creator%3D%22aeon%40test.com%22%20Type%3D%2216%22%20SHA1D%3D%22Bn6Jj0zm14uX0anWlUNJRa3cajA%3D%22%20Size%3D%2241543%22%20Location%3D%220%22%20Friendly%3D%22AAA%3D%22%2F%3E</Scene><ColorScheme>-3</ColorScheme></Data>";
+
+        let extras = PersonalMessageContent::from_str(payload).unwrap().profile_extras().unwrap();
+
+        let scene = extras.scene.unwrap();
+        assert_eq!(scene.obj_type, MsnObjectType::Scene);
+        assert_eq!(scene.size, 41543);
+        assert_eq!(scene.sha1d, "Bn6Jj0zm14uX0anWlUNJRa3cajA=");
+        assert_eq!(extras.color_scheme.as_deref(), Some("-3"));
+        assert!(extras.ddp.is_none());
+    }
+}
@@ -1,125 +1,39 @@
-use std::{fmt::{self, Debug}, str::{from_utf8, FromStr}};
+use std::{fmt::{self, Debug}, str::FromStr};
 use std::collections::VecDeque;
 
 use anyhow::anyhow;
-use log::{debug, info};
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::Decoder;
 
+use crate::msnp::codec::MsnpCodec;
 use crate::shared::command::command::split_raw_command_no_arg;
 use crate::shared::traits::{IntoBytes, TryFromRawCommand};
 
-use super::error::{CommandError, PayloadError};
+use super::error::CommandError;
 
+//Buffers raw reads for callers that don't drive a Framed stream.
 pub struct RawCommandParser {
-    incomplete_command: Option<RawCommand>
-} 
+    codec: MsnpCodec,
+    buffer: BytesMut
+}
 
 impl RawCommandParser {
 
     pub fn new() -> Self {
-        RawCommandParser { incomplete_command: None }
+        RawCommandParser { codec: MsnpCodec::default(), buffer: BytesMut::new() }
     }
 
     pub fn parse_message(&mut self, message: &[u8]) -> Result<Vec<RawCommand>, CommandError> {
-        let mut out: Vec<RawCommand> = Vec::new();
-
-        let mut bytes_to_handle = message;
-
-
-        //Handle previous chunks
-        if let Some(mut incomplete) = self.incomplete_command.take() {
-            let remaining_bytes = incomplete.get_missing_bytes_count();
-
-            info!("previous message was chunked!");
-
-            if message.len() >= remaining_bytes {
-                //incomplete will be complete
-                info!("no longer chunked!");
-
-                incomplete.extend_payload_from_slice(&message[..remaining_bytes])?;
-                out.push(incomplete);
-
-                bytes_to_handle = &bytes_to_handle[remaining_bytes..message.len()];
-            } else {
-                //still not complete
-                info!("still chunked!");
-            
-                incomplete.payload.extend_from_slice(&message[..message.len()]);
-                self.incomplete_command = Some(incomplete);
-                return Ok(out);
-            }
-        }
-
-
-        while bytes_to_handle.len() >= 5 {
-            //If it's bigger than CMD\r\n
+        self.buffer.extend_from_slice(message);
 
-            let operand = from_utf8(&bytes_to_handle[0..3]);
-
-            if operand.is_err() {
-                info!("Skipping bad operand");
-                break;
-            }
-
-            let operand = operand.expect("to never fail");
-
-            if operand.is_ascii() && operand.chars().all(|c| c.is_ascii_uppercase()) {
-                let terminators_index = bytes_to_handle.windows(2).enumerate().find_map(|(index, content)| {
-                    if content[0] as char == '\r' && content[1] as char == '\n' {
-                        Some(index+1)
-                    } else {
-                        None
-                    }
-                });
-
-                if terminators_index.is_none() {
-                    break;
-                }
-    
-                let terminators_index = terminators_index.expect("to never fail");
-
-                let command = from_utf8(&bytes_to_handle[0..=terminators_index-2])?;
-
-                let mut raw_command = RawCommand::from_str(command)?;
-
-                let payload_size = raw_command.get_expected_payload_size();
-
-                let after_term_index = terminators_index+1;
-
-                if payload_size == 0 {
-                    bytes_to_handle=&bytes_to_handle[terminators_index+1..bytes_to_handle.len()];
-                    out.push(raw_command);
-                } else {
-                    //We have a payload
-                    //Compare against the bytes available after the command line
-                    if payload_size > bytes_to_handle.len() - after_term_index {
-                        //Chunked
-                        let payload = &bytes_to_handle[after_term_index..bytes_to_handle.len()];
-
-                        if !payload.is_empty() {
-                            raw_command.extend_payload_from_slice(payload)?;
-
-                        }
-                        self.incomplete_command = Some(raw_command);
-                        break;
-                    } else {
-                        //Not chunked
-                        let payload = &bytes_to_handle[after_term_index..after_term_index+payload_size];
-                        raw_command.extend_payload_from_slice(payload)?;
-                        bytes_to_handle=&bytes_to_handle[after_term_index+payload_size..bytes_to_handle.len()];
-                        out.push(raw_command);
-                    }
-                }
-
-
-            } else {
-                info!("Skipping bad operand");
-                break;
-            }
+        let mut out: Vec<RawCommand> = Vec::new();
+        while let Some(command) = self.codec.decode(&mut self.buffer)? {
+            out.push(command);
         }
-        return Ok(out);
 
+        Ok(out)
     }
-    
+
 }
 
  fn extract_expected_payload_size(split: &[&str]) -> Result<usize, CommandError> {
@@ -128,6 +42,8 @@ impl RawCommandParser {
         }
 
         let expected_payload_size = match split.last() {
+            //Server acks of payload commands (ADL 6 OK) carry no length and no payload
+            Some(&"OK") => 0,
             Some(last) => {
                 last.parse::<usize>().map_err(|e| CommandError::MalformedPayloadCommand { source: e.into() })?
             },
@@ -140,7 +56,7 @@ impl RawCommandParser {
     }
 
 fn is_payload_command(operand: &str) -> bool {
-    matches!(operand, "ADL" | "RML" | "UUX" | "UUN" | "UUM" | "UBM" | "MSG" | "NOT" | "QRY" | "FQY" | "PUT" | "DEL" | "VAS" | "SDC" | "SDG")
+    matches!(operand, "ADL" | "RML" | "UUX" | "UUN" | "UUM" | "UBM" | "MSG" | "NOT" | "QRY" | "FQY" | "PUT" | "DEL" | "VAS" | "SDC" | "SDG" | "UBX" | "UBN" | "NFY" | "GCF")
 }
 
 impl FromStr for RawCommand {
@@ -153,7 +69,7 @@ impl FromStr for RawCommand {
         Ok(RawCommand {
             command: command.to_string(),
             command_split: command_split.iter().map(|e| e.to_string()).collect(),
-            payload: Vec::with_capacity(payload_size),
+            payload: Bytes::new(),
             expected_payload_size: payload_size,
         })
     }
@@ -163,7 +79,7 @@ pub struct RawCommand {
     pub(crate) command: String,
     pub(crate) command_split: VecDeque<String>,
     pub(crate) expected_payload_size: usize,
-    pub(crate) payload: Vec<u8>
+    pub(crate) payload: Bytes
 }
 
 impl RawCommand {
@@ -173,12 +89,13 @@ impl RawCommand {
             command: command.to_string(),
             command_split,
             expected_payload_size: 0,
-            payload: Vec::with_capacity(0),
+            payload: Bytes::new(),
         }
 
     }
 
-    pub fn with_payload(command: &str, payload: Vec<u8>) -> Self {
+    pub fn with_payload(command: &str, payload: impl Into<Bytes>) -> Self {
+        let payload = payload.into();
         let command_split =  split_raw_command_no_arg(command).iter().map(|e| e.to_string()).collect();
 
         Self {
@@ -193,12 +110,17 @@ impl RawCommand {
         &self.command_split[0]
     }
 
+    //Second token of most commands, absent from the likes of OUT or server pushed commands
+    pub fn get_tr_id(&self) -> Option<u128> {
+        self.command_split.get(1).and_then(|raw_tr_id| u128::from_str(raw_tr_id).ok())
+    }
+
     pub fn get_command(&self) -> &str {
         &self.command
     }
 
     pub fn get_payload(&self) -> &[u8] {
-        self.payload.as_slice()
+        &self.payload
     }
 
     pub fn get_expected_payload_size(&self) -> usize {
@@ -213,16 +135,6 @@ impl RawCommand {
         self.expected_payload_size - self.payload.len()
     }
 
-    pub fn extend_payload_from_slice(&mut self, slice: &[u8]) -> Result<(), PayloadError> {
-        debug!("capacity: {} - future size: {}", self.payload.capacity(), self.payload.len() + slice.len());
-        if self.payload.len() + slice.len() > self.payload.capacity() {
-            Err(PayloadError::PayloadSizeExceed { expected_size: self.expected_payload_size, overflowing_size: self.payload.len() + slice.len(), payload: self.payload.clone() })
-        } else {
-            self.payload.extend_from_slice(slice);
-            Ok(())
-        }
-
-    }
 
 }
 
@@ -249,14 +161,14 @@ impl TryFromRawCommand for RawCommand {
 
 impl IntoBytes for RawCommand {
 
-    fn into_bytes(mut self) -> Vec<u8> {
+    fn into_bytes(self) -> Vec<u8> {
         let mut cmd = if !self.payload.is_empty() {
             format!("{} {}\r\n", &self.command, self.payload.len())
         } else {
             format!("{}\r\n", &self.command)
         }.into_bytes();
 
-        cmd.append(&mut self.payload);
+        cmd.extend_from_slice(&self.payload);
         cmd
     }
 }
@@ -264,9 +176,9 @@ impl IntoBytes for RawCommand {
 
 #[cfg(test)]
 mod tests {
-    use std::str::from_utf8;
+    use std::str::{from_utf8, FromStr};
 
-    use crate::msnp::raw_command_parser::RawCommandParser;
+    use crate::msnp::raw_command_parser::{RawCommand, RawCommandParser};
 
     #[test]
     fn test_one_simple_command_old() {
@@ -321,9 +233,26 @@ mod tests {
         let command = String::from("ADL 6 sdfdasdf\r\n<ml l=\"1\"></ml>");
 
         //Act
-        let parsed = parser.parse_message(command.as_bytes());
-        
-        assert!(parsed.is_err());
+        let parsed = parser.parse_message(command.as_bytes()).unwrap();
+
+        //The bad command is dropped instead of ending the stream
+        assert!(parsed.is_empty());
+        assert!(RawCommand::from_str("ADL 6 sdfdasdf").is_err());
+    }
+
+    #[test]
+    fn test_payload_command_ack() {
+        let mut parser = RawCommandParser::new();
+        let command = String::from("ADL 6 OK\r\nPUT 7 OK 0\r\nTST 1 TST\r\n");
+
+        let parsed = parser.parse_message(command.as_bytes()).unwrap();
+
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].command, "ADL 6 OK");
+        assert_eq!(parsed[0].expected_payload_size, 0);
+        assert_eq!(parsed[1].command, "PUT 7 OK 0");
+        assert_eq!(parsed[1].expected_payload_size, 0);
+        assert_eq!(parsed[2].get_operand(), "TST");
     }
 
     #[test]
@@ -368,6 +297,18 @@ mod tests {
         assert_eq!(parsed[2].get_operand(), "CHG");
     }
 
+    #[test]
+    fn test_error_command() {
+        let mut parser = RawCommandParser::new();
+        let command = String::from("911 3\r\nOUT\r\n");
+
+        let parsed = parser.parse_message(command.as_bytes()).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].get_operand(), "911");
+        assert_eq!(parsed[1].get_operand(), "OUT");
+    }
+
     #[test]
     fn test_chunked() {
       //  let command = String::from("MOV 4 WOOWOO\r\nADL 6 15\r\n");
@@ -437,4 +378,13 @@ mod tests {
           println!("size in message: {}, size with len(): {}", payload_command.get_expected_payload_size(), payload_command.payload.len());
           assert!(payload_command.is_complete() == true);
     }
+
+    #[test]
+    fn test_tr_id() {
+        use std::str::FromStr;
+        use crate::msnp::raw_command_parser::RawCommand;
+
+        assert_eq!(Some(7), RawCommand::from_str("CAL 7 aeon@test.com").unwrap().get_tr_id());
+        assert_eq!(None, RawCommand::from_str("OUT").unwrap().get_tr_id());
+    }
 }
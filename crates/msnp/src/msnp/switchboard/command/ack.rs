@@ -1,10 +1,11 @@
 use crate::msnp::error::CommandError;
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use std::str::FromStr;
 
 // SB >> ACK 2
 pub struct AckServer {
-    tr_id: u128
+    pub tr_id: u128
 }
 
 impl AckServer {
@@ -16,8 +17,14 @@ impl AckServer {
 impl TryFromRawCommand for AckServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        Ok(Self { tr_id })
     }
 
 }
@@ -5,7 +5,7 @@ use crate::shared::command::ok::OkCommand;
 use crate::shared::models::b64_string::Base64String;
 use crate::shared::models::endpoint_id::EndpointId;
 use crate::shared::models::ticket_token::TicketToken;
-use crate::shared::traits::TryFromRawCommand;
+use crate::shared::traits::{IntoBytes, TryFromRawCommand};
 
 // Answers an XFR command from the Notification Sever, joining a Switchboard
 // >>> ANS 3 aeontest@shl.local;{F52973B6-C926-4BAD-9BA8-7C1E840E4AB0} base64token 4060759068338340280
@@ -45,6 +45,12 @@ impl TryFromRawCommand for AnsClient {
     }
 }
 
+impl IntoBytes for AnsClient {
+    fn into_bytes(self) -> Vec<u8> {
+        format!("ANS {} {} {} {}\r\n", self.tr_id, self.endpoint_id, self.token, self.session_id).into_bytes()
+    }
+}
+
 impl AnsClient {
 
     pub fn get_ok_response(&self) -> OkCommand {
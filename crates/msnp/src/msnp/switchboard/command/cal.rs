@@ -32,6 +32,13 @@ impl TryFromRawCommand for CalClient {
     }
 }
 
+impl IntoBytes for CalClient {
+
+    fn into_bytes(self) -> Vec<u8> {
+        format!("CAL {} {}\r\n", self.tr_id, self.email_addr).into_bytes()
+    }
+}
+
 #[derive(Display, EnumString)]
 pub enum CalServerFunction {
     RINGING,
@@ -46,8 +53,20 @@ pub struct CalServer {
 impl TryFromRawCommand for CalServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let raw_function = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "function".into(), 2))?;
+        let function = CalServerFunction::from_str(&raw_function)?;
+
+        let raw_session_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "session_id".into(), 3))?;
+        let session_id = SessionId::from(u16::from_str(&raw_session_id)?);
+
+        Ok(CalServer { tr_id, function, session_id })
     }
 
 }
@@ -8,8 +8,10 @@ use crate::msnp::switchboard::command::joi::JoiServer;
 use crate::msnp::switchboard::command::msg::{MsgClient, MsgServer};
 use crate::shared::command::nak::NakServer;
 use crate::msnp::switchboard::command::usr::{UsrClient, UsrServer};
+use crate::shared::command::err::ErrCommand;
 use crate::shared::command::ok::OkCommand;
-use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
 use strum_macros::Display;
 
 #[derive(Display)]
@@ -48,6 +50,7 @@ pub enum SwitchboardServerCommand {
     MSG(MsgServer),
     IRO(IroServer),
     JOI(JoiServer),
+    ERR(ErrCommand),
     OUT,
     RAW(RawCommand)
 }
@@ -55,8 +58,28 @@ pub enum SwitchboardServerCommand {
 impl TryFromRawCommand for SwitchboardServerCommand {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        if raw.get_operand().chars().all(|c| c.is_ascii_digit()) {
+            return Ok(SwitchboardServerCommand::ERR(ErrCommand::try_from_raw(raw)?));
+        }
+
+        //"USR 55 aeontest@shl.local aeontest@shl.local OK" also ends with OK, plain acknowledgments only carry a tr_id.
+        if raw.command_split.len() == 3 && raw.command_split[2] == "OK" {
+            return Ok(SwitchboardServerCommand::OK(OkCommand::try_from_raw(raw)?));
+        }
+
+        let out = match raw.get_operand() {
+            "USR" => SwitchboardServerCommand::USR(UsrServer::try_from_raw(raw)?),
+            "CAL" => SwitchboardServerCommand::CAL(CalServer::try_from_raw(raw)?),
+            "ACK" => SwitchboardServerCommand::ACK(AckServer::try_from_raw(raw)?),
+            "NAK" => SwitchboardServerCommand::NAK(NakServer::try_from_raw(raw)?),
+            "MSG" => SwitchboardServerCommand::MSG(MsgServer::try_from_raw(raw)?),
+            "IRO" => SwitchboardServerCommand::IRO(IroServer::try_from_raw(raw)?),
+            "JOI" => SwitchboardServerCommand::JOI(JoiServer::try_from_raw(raw)?),
+            "OUT" => SwitchboardServerCommand::OUT,
+            _ => SwitchboardServerCommand::RAW(raw),
+        };
+        Ok(out)
     }
 
 }
@@ -72,7 +95,45 @@ impl IntoBytes for SwitchboardServerCommand {
             SwitchboardServerCommand::MSG(command) => command.into_bytes(),
             SwitchboardServerCommand::IRO(command) => command.into_bytes(),
             SwitchboardServerCommand::JOI(command) => command.into_bytes(),
+            SwitchboardServerCommand::ERR(command) => command.into_bytes(),
             SwitchboardServerCommand::OUT => b"OUT\r\n".to_vec(),
             SwitchboardServerCommand::RAW(command) => command.into_bytes(),
         }    }
-}
\ No newline at end of file
+}
+
+impl IntoVersionedBytes for SwitchboardServerCommand {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        match self {
+            SwitchboardServerCommand::IRO(command) => command.into_versioned_bytes(version),
+            SwitchboardServerCommand::JOI(command) => command.into_versioned_bytes(version),
+            other => other.into_bytes()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msnp::raw_command_parser::RawCommandParser;
+    use crate::msnp::switchboard::command::command::SwitchboardServerCommand;
+    use crate::shared::command::err::MsnpError;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+
+    fn parse(raw: &str) -> SwitchboardServerCommand {
+        let raw_command = RawCommandParser::new().parse_message(raw.as_bytes()).unwrap().remove(0);
+        SwitchboardServerCommand::try_from_raw(raw_command).unwrap()
+    }
+
+    #[test]
+    fn server_command_round_trips() {
+        for raw in ["USR 55 aeontest@shl.local aeontest OK\r\n", "CAL 58 RINGING 4324\r\n", "ACK 2\r\n", "NAK 3\r\n", "216 4\r\n", "JOI aeon@lukewarmail.com Aeon 2789003324:48\r\n", "OUT\r\n"] {
+            assert_eq!(raw, String::from_utf8(parse(raw).into_bytes()).unwrap());
+        }
+    }
+
+    #[test]
+    fn server_command_dispatch() {
+        assert!(matches!(parse("USR 55 aeontest@shl.local aeontest OK\r\n"), SwitchboardServerCommand::USR(usr) if usr.tr_id == 55));
+        assert!(matches!(parse("ACK 2\r\n"), SwitchboardServerCommand::ACK(ack) if ack.tr_id == 2));
+        assert!(matches!(parse("217 3\r\n"), SwitchboardServerCommand::ERR(err) if err.tr_id == 3 && err.msnp_error == MsnpError::PrincipalNotOnline));
+    }
+}
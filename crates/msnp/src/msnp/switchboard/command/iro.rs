@@ -1,21 +1,24 @@
 use crate::msnp::error::CommandError;
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::models::capabilities::ClientCapabilities;
 use crate::shared::models::endpoint_id::EndpointId;
-use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
+use std::str::FromStr;
 
 // Initial Roster sent after an ANS command
 // SB >> IRO 1 1 2 aeon@lukewarmail.com Aeon 2789003324:48
 // SB >> IRO 2 2 2 aeon@lukewarmail.com;{4059a9be-d326-4394-bc29-3d4f7a7c757a} Aeon 2789003324:48
+// Before MSNP16 there are no endpoints and no extended capabilities: IRO 1 1 1 aeon@lukewarmail.com Aeon 2789003324
 // If MPOP (Multiple Points of Presence) Is Enabled, All participants need to join with an endpoint (more than once)
 // tr_id is the same one as the ANS command
 pub struct IroServer {
-    tr_id: u128,
-    index: u32,
-    roster_count: u32,
-    endpoint_id: EndpointId,
-    display_name: String,
-    capabilities: ClientCapabilities
+    pub tr_id: u128,
+    pub index: u32,
+    pub roster_count: u32,
+    pub endpoint_id: EndpointId,
+    pub display_name: String,
+    pub capabilities: ClientCapabilities
 }
 
 impl IroServer {
@@ -34,8 +37,28 @@ impl IroServer {
 impl TryFromRawCommand for IroServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let raw_index = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "index".into(), 2))?;
+        let index = u32::from_str(&raw_index)?;
+
+        let raw_roster_count = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "roster_count".into(), 3))?;
+        let roster_count = u32::from_str(&raw_roster_count)?;
+
+        let raw_endpoint_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "endpoint_id".into(), 4))?;
+        let endpoint_id = EndpointId::from_str(&raw_endpoint_id)?;
+
+        let display_name = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "display_name".into(), 5))?;
+
+        let raw_capabilities = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "capabilities".into(), 6))?;
+        let capabilities = ClientCapabilities::from_str(&raw_capabilities)?;
+
+        Ok(Self::new(tr_id, index, roster_count, display_name, endpoint_id, capabilities))
     }
 
 }
@@ -44,4 +67,50 @@ impl IntoBytes for IroServer {
     fn into_bytes(self) -> Vec<u8> {
         format!("IRO {tr_id} {index} {roster_count} {endpoint_id} {display_name} {capabilities}\r\n", tr_id = self.tr_id, index = self.index, roster_count = self.roster_count, endpoint_id =  self.endpoint_id, display_name = self.display_name, capabilities = self.capabilities).into_bytes()
     }
-}
\ No newline at end of file
+}
+
+impl IntoVersionedBytes for IroServer {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        if version.supports_endpoints() {
+            return self.into_bytes();
+        }
+
+        format!("IRO {tr_id} {index} {roster_count} {email_addr} {display_name} {capabilities}\r\n", tr_id = self.tr_id, index = self.index, roster_count = self.roster_count, email_addr = self.endpoint_id.email_addr, display_name = self.display_name, capabilities = self.capabilities.to_versioned_string(version)).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msnp::notification::models::msnp_version::MsnpVersion;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::msnp::switchboard::command::iro::IroServer;
+    use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
+    use std::str::FromStr;
+
+    #[test]
+    fn iro_des_round_trip() {
+        let raw = "IRO 2 2 2 aeon@lukewarmail.com;{4059a9be-d326-4394-bc29-3d4f7a7c757a} Aeon 2789003324:48\r\n";
+        let iro = IroServer::try_from_raw(RawCommand::from_str(raw).unwrap()).unwrap();
+
+        assert_eq!(iro.index, 2);
+        assert_eq!(iro.endpoint_id.email_addr.as_str(), "aeon@lukewarmail.com");
+        assert!(iro.endpoint_id.endpoint_guid.is_some());
+        assert_eq!(raw.to_lowercase(), String::from_utf8(iro.into_bytes()).unwrap().to_lowercase());
+    }
+
+    #[test]
+    fn iro_legacy_ser() {
+        let raw = "IRO 2 2 2 aeon@lukewarmail.com;{4059a9be-d326-4394-bc29-3d4f7a7c757a} Aeon 2789003324:48\r\n";
+        let iro = IroServer::try_from_raw(RawCommand::from_str(raw).unwrap()).unwrap();
+
+        assert_eq!("IRO 2 2 2 aeon@lukewarmail.com Aeon 2789003324\r\n", String::from_utf8(iro.into_versioned_bytes(MsnpVersion::MSNP15)).unwrap());
+    }
+
+    #[test]
+    fn iro_ser() {
+        let raw = "IRO 2 2 2 aeon@lukewarmail.com;{4059a9be-d326-4394-bc29-3d4f7a7c757a} Aeon 2789003324:48\r\n";
+        let iro = IroServer::try_from_raw(RawCommand::from_str(raw).unwrap()).unwrap();
+
+        assert_eq!(raw.to_lowercase(), String::from_utf8(iro.into_versioned_bytes(MsnpVersion::MSNP18)).unwrap().to_lowercase());
+    }
+}
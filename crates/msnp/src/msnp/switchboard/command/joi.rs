@@ -1,13 +1,16 @@
 use crate::msnp::error::CommandError;
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::models::capabilities::ClientCapabilities;
 use crate::shared::models::endpoint_id::EndpointId;
-use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
+use std::str::FromStr;
 
 //Notifies a client that someone has joined the SB
 //SB >> JOI aeon@lukewarmail.com Aeon 2789003324:48
 //SB >> JOI aeon@lukewarmail.com;{4059a9be-d326-4394-bc29-3d4f7a7c757a} Aeon 2789003324:48
 //Like IRO, if MPOP is enabled, Send multiple Join, one without endpoint and then all the endpoints that are present in the SB.
+//Before MSNP16: JOI aeon@lukewarmail.com Aeon 2789003324
 
 pub struct JoiServer {
     pub endpoint_id: EndpointId,
@@ -18,8 +21,19 @@ pub struct JoiServer {
 impl TryFromRawCommand for JoiServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_endpoint_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "endpoint_id".into(), 1))?;
+        let endpoint_id = EndpointId::from_str(&raw_endpoint_id)?;
+
+        let display_name = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "display_name".into(), 2))?;
+
+        let raw_capabilities = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "capabilities".into(), 3))?;
+        let capabilities = ClientCapabilities::from_str(&raw_capabilities)?;
+
+        Ok(Self { endpoint_id, display_name, capabilities })
     }
 
 }
@@ -29,4 +43,47 @@ impl IntoBytes for JoiServer {
     fn into_bytes(self) -> Vec<u8> {
         format!("JOI {endpoint_id} {display_name} {capabilities}\r\n",endpoint_id =  self.endpoint_id, display_name = self.display_name, capabilities = self.capabilities).into_bytes()
     }
+}
+
+impl IntoVersionedBytes for JoiServer {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8> {
+        if version.supports_endpoints() {
+            return self.into_bytes();
+        }
+
+        format!("JOI {email_addr} {display_name} {capabilities}\r\n", email_addr = self.endpoint_id.email_addr, display_name = self.display_name, capabilities = self.capabilities.to_versioned_string(version)).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msnp::notification::models::msnp_version::MsnpVersion;
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::msnp::switchboard::command::joi::JoiServer;
+    use crate::shared::traits::{IntoBytes, IntoVersionedBytes, TryFromRawCommand};
+    use std::str::FromStr;
+
+    const RAW_JOI: &str = "JOI aeon@lukewarmail.com;{4059a9be-d326-4394-bc29-3d4f7a7c757a} Aeon 2789003324:48\r\n";
+
+    #[test]
+    fn joi_des_round_trip() {
+        let joi = JoiServer::try_from_raw(RawCommand::from_str(RAW_JOI).unwrap()).unwrap();
+
+        assert_eq!(joi.endpoint_id.email_addr.as_str(), "aeon@lukewarmail.com");
+        assert_eq!(RAW_JOI.to_lowercase(), String::from_utf8(joi.into_bytes()).unwrap().to_lowercase());
+    }
+
+    #[test]
+    fn joi_legacy_ser() {
+        let joi = JoiServer::try_from_raw(RawCommand::from_str(RAW_JOI).unwrap()).unwrap();
+
+        assert_eq!("JOI aeon@lukewarmail.com Aeon 2789003324\r\n", String::from_utf8(joi.into_versioned_bytes(MsnpVersion::MSNP15)).unwrap());
+    }
+
+    #[test]
+    fn joi_ser() {
+        let joi = JoiServer::try_from_raw(RawCommand::from_str(RAW_JOI).unwrap()).unwrap();
+
+        assert_eq!(RAW_JOI.to_lowercase(), String::from_utf8(joi.into_versioned_bytes(MsnpVersion::MSNP18)).unwrap().to_lowercase());
+    }
 }
\ No newline at end of file
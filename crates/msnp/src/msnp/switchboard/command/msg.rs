@@ -18,6 +18,7 @@ use crate::shared::models::email_address::EmailAddress;
 use crate::shared::payload::msg::chunked_msg_payload::ChunkedMsgPayload;
 use crate::shared::payload::msg::control_msg::ControlMessagePayload;
 use crate::shared::payload::msg::datacast_msg::DatacastMessagePayload;
+use crate::shared::payload::msg::emoticon::EmoticonMsgPayload;
 use crate::shared::payload::msg::gif_msg::GifMsgPayload;
 use crate::shared::payload::msg::ink_msg::InkMessagePayload;
 use crate::shared::payload::msg::p2p_msg_payload::P2PMessagePayload;
@@ -44,7 +45,7 @@ impl TryFromRawCommand for MsgClient {
         let raw_ack_type = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "ack_type".into(), 1))?;
         let ack_type = MsgAcknowledgment::from_str(&raw_ack_type)?;
 
-        let payload = MsgPayload::try_from_bytes(raw.payload)?;
+        let payload = MsgPayload::try_from_bytes(Vec::from(raw.payload))?;
 
         Ok(MsgClient{
             tr_id,
@@ -54,6 +55,16 @@ impl TryFromRawCommand for MsgClient {
     }
 }
 
+impl IntoBytes for MsgClient {
+    fn into_bytes(self) -> Vec<u8> {
+        let mut payload = self.payload.into_bytes();
+        let mut cmd = format!("MSG {} {} {}\r\n", self.tr_id, self.ack_type, payload.len()).into_bytes();
+        cmd.append(&mut payload);
+
+        cmd
+    }
+}
+
 #[derive(Display, EnumString)]
 pub enum MsgAcknowledgment {
     #[strum(serialize = "U")]
@@ -73,10 +84,25 @@ pub struct MsgServer {
 }
 
 impl TryFromRawCommand for MsgServer {
-    type Err = ();
+    type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_sender = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "sender".into(), 1))?;
+        let sender = EmailAddress::from_str(&raw_sender)?;
+
+        let raw_display_name = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "display_name".into(), 2))?;
+        let display_name = DisplayName::from_str(&raw_display_name)?;
+
+        let payload = MsgPayload::try_from_bytes(Vec::from(raw.payload))?;
+
+        Ok(MsgServer {
+            sender,
+            display_name,
+            payload,
+        })
     }
 }
 
@@ -98,7 +124,8 @@ pub enum MsgPayload {
     Control(ControlMessagePayload),
     P2P(P2PMessagePayload),
     Gif(GifMsgPayload),
-    Ink(InkMessagePayload)
+    Ink(InkMessagePayload),
+    Emoticon(EmoticonMsgPayload)
 }
 
 impl TryFromRawMsgPayload for MsgPayload {
@@ -128,6 +155,7 @@ impl TryFromRawMsgPayload for MsgPayload {
             MsgContentType::MailDataNotification => {Ok(MsgPayload::Raw(raw_msg_payload))}
             MsgContentType::Gif => { Ok(MsgPayload::Gif(GifMsgPayload::try_from_raw(raw_msg_payload)?)) }
             MsgContentType::Ink => { Ok(MsgPayload::Ink(InkMessagePayload::try_from_raw(raw_msg_payload)?)) }
+            MsgContentType::Emoticon => { Ok(MsgPayload::Emoticon(EmoticonMsgPayload::try_from_raw(raw_msg_payload)?)) }
         }
     }
 }
@@ -144,6 +172,7 @@ impl IntoBytes for MsgPayload {
             MsgPayload::Chunked(payload) => payload.into_bytes(),
             MsgPayload::Gif(payload) => { payload.into_bytes() }
             MsgPayload::Ink(payload) => { payload.into_bytes() }
+            MsgPayload::Emoticon(payload) => { payload.into_bytes() }
         }
     }
 }
@@ -49,6 +49,12 @@ impl TryFromRawCommand for UsrClient {
 
 }
 
+impl IntoBytes for UsrClient {
+
+    fn into_bytes(self) -> Vec<u8> {
+        format!("USR {} {} {}\r\n", self.tr_id, self.endpoint_id, self.token).into_bytes()
+    }
+}
 
 pub struct UsrServer {
     pub tr_id: u128,
@@ -59,8 +65,23 @@ pub struct UsrServer {
 impl TryFromRawCommand for UsrServer {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let _operand = split.pop_front();
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        let raw_email_addr = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "email_addr".into(), 2))?;
+        let email_addr = EmailAddress::from_str(&raw_email_addr)?;
+
+        let display_name = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "display_name".into(), 3))?;
+
+        Ok(UsrServer {
+            tr_id,
+            email_addr,
+            display_name,
+        })
     }
 
 }
@@ -1 +1,3 @@
+pub mod v1;
 pub mod v2;
+pub mod transport_packet;
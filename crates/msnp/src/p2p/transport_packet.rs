@@ -0,0 +1,111 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::p2p::v1::binary_header::BINARY_HEADER_LENGTH;
+use crate::p2p::v1::p2p_packet::P2PPacket;
+use crate::p2p::v2::p2p_transport_packet::P2PTransportPacket;
+use crate::shared::models::capabilities::{ClientCapabilities, ExtendedCapabilities};
+use crate::shared::traits::IntoBytes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum P2PVersion {
+    V1,
+    V2,
+}
+
+impl P2PVersion {
+    //The official client only switches to P2Pv2 headers when it advertises the P2PV2 extended capability
+    pub fn for_capabilities(capabilities: &ClientCapabilities) -> Self {
+        if capabilities.supports_ext(ExtendedCapabilities::SupportsP2PV2) {
+            P2PVersion::V2
+        } else {
+            P2PVersion::V1
+        }
+    }
+
+    //Incoming messages don't say which header they use, but a P2Pv1 body is exactly its 48 bytes header, the message and the footer
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.len() < BINARY_HEADER_LENGTH + 4 {
+            return P2PVersion::V2;
+        }
+
+        let message_size = LittleEndian::read_u32(&bytes[24..28]) as usize;
+        if BINARY_HEADER_LENGTH + message_size + 4 == bytes.len() {
+            P2PVersion::V1
+        } else {
+            P2PVersion::V2
+        }
+    }
+}
+
+/// A switchboard P2P message body, in whichever binary header the peers agreed on.
+#[derive(Debug, Clone)]
+pub enum TransportPacket {
+    V1(P2PPacket),
+    V2(P2PTransportPacket),
+}
+
+impl TransportPacket {
+    pub fn version(&self) -> P2PVersion {
+        match self {
+            TransportPacket::V1(_) => P2PVersion::V1,
+            TransportPacket::V2(_) => P2PVersion::V2,
+        }
+    }
+
+    pub fn try_from_bytes(bytes: &[u8], version: P2PVersion) -> Result<Self, crate::msnp::error::PayloadError> {
+        match version {
+            P2PVersion::V1 => Ok(TransportPacket::V1(P2PPacket::try_from(bytes)?)),
+            P2PVersion::V2 => Ok(TransportPacket::V2(P2PTransportPacket::try_from(bytes)?)),
+        }
+    }
+}
+
+impl From<P2PPacket> for TransportPacket {
+    fn from(value: P2PPacket) -> Self {
+        TransportPacket::V1(value)
+    }
+}
+
+impl From<P2PTransportPacket> for TransportPacket {
+    fn from(value: P2PTransportPacket) -> Self {
+        TransportPacket::V2(value)
+    }
+}
+
+impl IntoBytes for TransportPacket {
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            TransportPacket::V1(packet) => packet.into_bytes(),
+            TransportPacket::V2(packet) => packet.into_bytes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::p2p::transport_packet::P2PVersion;
+    use crate::p2p::v1::binary_header::BinaryHeaderFlag;
+    use crate::p2p::v1::p2p_packet::{P2PPacket, FOOTER_MSN_OBJECT};
+    use crate::p2p::v2::factories::P2PPayloadFactory;
+    use crate::p2p::v2::p2p_transport_packet::P2PTransportPacket;
+    use crate::shared::models::capabilities::{ClientCapabilities, ExtendedCapabilities};
+    use crate::shared::traits::IntoBytes;
+
+    #[test]
+    fn version_from_capabilities() {
+        assert_eq!(P2PVersion::V2, P2PVersion::for_capabilities(&ClientCapabilities::default()));
+        assert_eq!(P2PVersion::V1, P2PVersion::for_capabilities(&ClientCapabilities::new(2789003324, 0)));
+        assert_eq!(P2PVersion::V2, P2PVersion::for_capabilities(&ClientCapabilities::new(0, ExtendedCapabilities::SupportsP2PV2 as u32)));
+    }
+
+    #[test]
+    fn detect_version_from_framing() {
+        let v1 = P2PPacket::new(12, 100, BinaryHeaderFlag::Data as u32, vec![1; 30], FOOTER_MSN_OBJECT).into_bytes();
+        assert_eq!(P2PVersion::V1, P2PVersion::detect(&v1));
+
+        let mut msn_obj = P2PPayloadFactory::get_msn_obj(12);
+        msn_obj.payload = vec![1; 30];
+        let v2 = P2PTransportPacket::new(0, Some(msn_obj)).into_bytes();
+        assert_eq!(P2PVersion::V2, P2PVersion::detect(&v2));
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::anyhow;
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::msnp::error::PayloadError;
+use crate::shared::traits::IntoBytes;
+
+pub const BINARY_HEADER_LENGTH: usize = 48;
+
+/// Values of the P2Pv1 `flags` field.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryHeaderFlag {
+    Normal = 0x0,
+    NegativeAck = 0x1,
+    Ack = 0x2,
+    Waiting = 0x4,
+    Error = 0x8,
+    File = 0x10,
+    Data = 0x20,
+    CloseSession = 0x40,
+    TlpError = 0x80,
+    DirectHandshake = 0x100,
+    MsnObjectData = 0x01000000,
+    FileData = 0x01000030,
+}
+
+/// The 48 byte, little-endian P2Pv1 binary header.
+///
+/// | Offset | Size | Field            |
+/// |--------|------|------------------|
+/// | 0      | 4    | `session_id`     |
+/// | 4      | 4    | `identifier`     |
+/// | 8      | 8    | `offset`         |
+/// | 16     | 8    | `total_size`     |
+/// | 24     | 4    | `message_size`   |
+/// | 28     | 4    | `flags`          |
+/// | 32     | 4    | `ack_session_id` |
+/// | 36     | 4    | `ack_unique_id`  |
+/// | 40     | 8    | `ack_data_size`  |
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct P2PBinaryHeader {
+    pub session_id: u32,
+    pub identifier: u32,
+    pub offset: u64,
+    pub total_size: u64,
+    pub message_size: u32,
+    pub flags: u32,
+    pub ack_session_id: u32,
+    pub ack_unique_id: u32,
+    pub ack_data_size: u64,
+}
+
+impl P2PBinaryHeader {
+    pub fn has_flag(&self, flag: BinaryHeaderFlag) -> bool {
+        let flag = flag as u32;
+        if flag == 0 {
+            return self.flags == 0;
+        }
+        self.flags & flag == flag
+    }
+}
+
+impl TryFrom<&[u8]> for P2PBinaryHeader {
+    type Error = PayloadError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() < BINARY_HEADER_LENGTH {
+            return Err(PayloadError::BinaryPayloadParsingError {
+                payload: bytes.to_owned(),
+                source: anyhow!("P2Pv1 binary header must be {} bytes long, but was: {}", BINARY_HEADER_LENGTH, bytes.len()),
+            });
+        }
+
+        Ok(P2PBinaryHeader {
+            session_id: LittleEndian::read_u32(&bytes[0..4]),
+            identifier: LittleEndian::read_u32(&bytes[4..8]),
+            offset: LittleEndian::read_u64(&bytes[8..16]),
+            total_size: LittleEndian::read_u64(&bytes[16..24]),
+            message_size: LittleEndian::read_u32(&bytes[24..28]),
+            flags: LittleEndian::read_u32(&bytes[28..32]),
+            ack_session_id: LittleEndian::read_u32(&bytes[32..36]),
+            ack_unique_id: LittleEndian::read_u32(&bytes[36..40]),
+            ack_data_size: LittleEndian::read_u64(&bytes[40..48]),
+        })
+    }
+}
+
+impl IntoBytes for P2PBinaryHeader {
+    fn into_bytes(self) -> Vec<u8> {
+        let mut out = vec![0u8; BINARY_HEADER_LENGTH];
+        LittleEndian::write_u32(&mut out[0..4], self.session_id);
+        LittleEndian::write_u32(&mut out[4..8], self.identifier);
+        LittleEndian::write_u64(&mut out[8..16], self.offset);
+        LittleEndian::write_u64(&mut out[16..24], self.total_size);
+        LittleEndian::write_u32(&mut out[24..28], self.message_size);
+        LittleEndian::write_u32(&mut out[28..32], self.flags);
+        LittleEndian::write_u32(&mut out[32..36], self.ack_session_id);
+        LittleEndian::write_u32(&mut out[36..40], self.ack_unique_id);
+        LittleEndian::write_u64(&mut out[40..48], self.ack_data_size);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::p2p::v1::binary_header::{BinaryHeaderFlag, P2PBinaryHeader, BINARY_HEADER_LENGTH};
+    use crate::shared::traits::IntoBytes;
+
+    #[test]
+    fn binary_header_round_trip() {
+        let header = P2PBinaryHeader {
+            session_id: 0,
+            identifier: 0x09A2C4F1,
+            offset: 0,
+            total_size: 1337,
+            message_size: 1202,
+            flags: BinaryHeaderFlag::Normal as u32,
+            ack_session_id: 0x0012E3A4,
+            ack_unique_id: 0,
+            ack_data_size: 0,
+        };
+
+        let bytes = header.clone().into_bytes();
+        assert_eq!(BINARY_HEADER_LENGTH, bytes.len());
+        assert_eq!([0xF1, 0xC4, 0xA2, 0x09], bytes[4..8]);
+
+        let deser = P2PBinaryHeader::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(header, deser);
+        assert!(deser.has_flag(BinaryHeaderFlag::Normal));
+        assert!(!deser.has_flag(BinaryHeaderFlag::Ack));
+    }
+
+    #[test]
+    fn binary_header_too_short() {
+        assert!(P2PBinaryHeader::try_from([0u8; 47].as_slice()).is_err());
+    }
+
+    #[test]
+    fn binary_header_file_data_flag() {
+        let header = P2PBinaryHeader { flags: BinaryHeaderFlag::FileData as u32, ..Default::default() };
+        assert!(header.has_flag(BinaryHeaderFlag::File));
+        assert!(header.has_flag(BinaryHeaderFlag::Data));
+        assert!(!header.has_flag(BinaryHeaderFlag::Ack));
+    }
+}
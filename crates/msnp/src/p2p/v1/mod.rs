@@ -0,0 +1,32 @@
+pub mod binary_header;
+pub mod p2p_packet;
+
+pub mod factories {
+    use super::binary_header::{BinaryHeaderFlag, P2PBinaryHeader};
+    use super::p2p_packet::P2PPacket;
+
+    pub struct P2PPacketFactory;
+
+    impl P2PPacketFactory {
+        /// Acknowledges a fully received message, echoing its identifiers back to the sender.
+        pub fn get_ack(acked: &P2PPacket, identifier: u32) -> P2PPacket {
+            let header = P2PBinaryHeader {
+                session_id: acked.header.session_id,
+                identifier,
+                offset: 0,
+                total_size: acked.header.total_size,
+                message_size: 0,
+                flags: BinaryHeaderFlag::Ack as u32,
+                ack_session_id: acked.header.identifier,
+                ack_unique_id: acked.header.ack_session_id,
+                ack_data_size: acked.header.total_size,
+            };
+
+            P2PPacket {
+                header,
+                payload: Vec::new(),
+                footer: 0,
+            }
+        }
+    }
+}
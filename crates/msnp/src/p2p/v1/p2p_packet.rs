@@ -0,0 +1,278 @@
+use std::cmp::min;
+
+use anyhow::anyhow;
+use byteorder::{BigEndian, ByteOrder};
+
+use super::binary_header::{BinaryHeaderFlag, P2PBinaryHeader, BINARY_HEADER_LENGTH};
+use crate::msnp::error::PayloadError;
+use crate::p2p::v2::raw_p2p_payload::RawP2PPayload;
+use crate::shared::traits::IntoBytes;
+
+/// Application identifiers carried in the big-endian footer of a switchboard P2Pv1 message.
+pub const FOOTER_SLP: u32 = 0;
+pub const FOOTER_MSN_OBJECT: u32 = 1;
+pub const FOOTER_FILE_TRANSFER: u32 = 2;
+
+/* P2PHeaderV1 */
+/* Used by MSNP15 clients and by newer ones talking to a non MPOP endpoint. There is no transport handshake:
+each message is identified by its header identifier, chunked through offset/total_size and acknowledged once complete. */
+#[derive(Debug, Clone)]
+pub struct P2PPacket {
+    pub header: P2PBinaryHeader,
+    pub payload: Vec<u8>,
+    pub footer: u32,
+}
+
+impl P2PPacket {
+    pub fn new(session_id: u32, identifier: u32, flags: u32, payload: Vec<u8>, footer: u32) -> Self {
+        let header = P2PBinaryHeader {
+            session_id,
+            identifier,
+            offset: 0,
+            total_size: payload.len() as u64,
+            message_size: payload.len() as u32,
+            flags,
+            ack_session_id: rand::random(),
+            ack_unique_id: 0,
+            ack_data_size: 0,
+        };
+
+        Self { header, payload, footer }
+    }
+
+    pub fn is_ack(&self) -> bool {
+        self.header.has_flag(BinaryHeaderFlag::Ack)
+    }
+
+    pub fn is_slp_msg(&self) -> bool {
+        self.header.session_id == 0 && !self.is_ack()
+    }
+
+    pub fn is_chunked(&self) -> bool {
+        (self.header.message_size as u64) < self.header.total_size
+    }
+
+    pub fn is_last_chunk(&self) -> bool {
+        self.header.offset + self.header.message_size as u64 >= self.header.total_size
+    }
+
+    /// Splits the payload in messages of at most `chunk_size` bytes sharing the same identifier.
+    pub fn chunk(self, chunk_size: usize) -> Vec<P2PPacket> {
+        if self.payload.len() <= chunk_size {
+            return vec![self];
+        }
+
+        let total_size = self.payload.len() as u64;
+        self.payload.chunks(chunk_size).enumerate().map(|(index, chunk)| {
+            let mut header = self.header.clone();
+            header.offset = (index * chunk_size) as u64;
+            header.total_size = total_size;
+            header.message_size = chunk.len() as u32;
+
+            P2PPacket {
+                header,
+                payload: chunk.to_vec(),
+                footer: self.footer,
+            }
+        }).collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.header.offset == 0 && self.payload.len() as u64 >= self.header.total_size
+    }
+
+    /// Writes a chunk at its offset in the message being reassembled.
+    /// Bytes we already hold are skipped, a chunk starting past them means one went missing and is rejected.
+    pub fn append_chunk(&mut self, chunk: P2PPacket) -> Result<(), PayloadError> {
+        let received = self.header.offset + self.payload.len() as u64;
+        let start = chunk.header.offset;
+        let end = start + chunk.payload.len() as u64;
+
+        if start > received || end > self.header.total_size {
+            return Err(PayloadError::BinaryPayloadParsingError {
+                payload: chunk.payload,
+                source: anyhow!("P2Pv1 chunk {}..{} of message {} doesn't follow the {} bytes received out of {}", start, end, self.header.identifier, received, self.header.total_size),
+            });
+        }
+
+        if end > received {
+            self.payload.extend_from_slice(&chunk.payload[(received - start) as usize..]);
+            self.header.message_size = self.payload.len() as u32;
+        }
+
+        Ok(())
+    }
+
+    /// Wraps a data layer payload, the P2Pv1 header carries what the v2 data layer header would.
+    pub fn from_data_layer(payload: RawP2PPayload, identifier: u32) -> Self {
+        let (flags, footer) = if payload.session_id == 0 {
+            (BinaryHeaderFlag::Normal, FOOTER_SLP)
+        } else if payload.tf.is_file_transfer() {
+            (BinaryHeaderFlag::FileData, FOOTER_FILE_TRANSFER)
+        } else if payload.tf.is_msn_obj_transfer() {
+            (BinaryHeaderFlag::Data, FOOTER_MSN_OBJECT)
+        } else {
+            (BinaryHeaderFlag::Normal, FOOTER_MSN_OBJECT)
+        };
+
+        P2PPacket::new(payload.session_id, identifier, flags as u32, payload.payload, footer)
+    }
+
+    pub fn into_data_layer(self) -> RawP2PPayload {
+        let transfer_type = if self.header.session_id == 0 {
+            0
+        } else if self.header.has_flag(BinaryHeaderFlag::File) {
+            3
+        } else if self.header.has_flag(BinaryHeaderFlag::Data) || self.header.has_flag(BinaryHeaderFlag::MsnObjectData) {
+            2
+        } else {
+            0
+        };
+
+        let mut out = RawP2PPayload::new(transfer_type, 1, self.header.session_id);
+        out.set_payload(self.payload);
+        out
+    }
+}
+
+impl TryFrom<&[u8]> for P2PPacket {
+    type Error = PayloadError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let header = P2PBinaryHeader::try_from(bytes)?;
+        let payload_end = BINARY_HEADER_LENGTH + header.message_size as usize;
+
+        if payload_end > bytes.len() {
+            return Err(PayloadError::BinaryPayloadParsingError {
+                payload: bytes.to_owned(),
+                source: anyhow!("P2Pv1 message size is {} but only {} bytes are available", header.message_size, bytes.len() - BINARY_HEADER_LENGTH),
+            });
+        }
+
+        let payload = bytes[BINARY_HEADER_LENGTH..payload_end].to_vec();
+        let footer_bytes = &bytes[payload_end..min(payload_end + 4, bytes.len())];
+        let footer = if footer_bytes.len() == 4 { BigEndian::read_u32(footer_bytes) } else { 0 };
+
+        Ok(P2PPacket { header, payload, footer })
+    }
+}
+
+impl IntoBytes for P2PPacket {
+    fn into_bytes(self) -> Vec<u8> {
+        let mut header = self.header;
+        header.message_size = self.payload.len() as u32;
+
+        let mut out = header.into_bytes();
+        out.extend(self.payload);
+
+        let mut footer: [u8; 4] = [0, 0, 0, 0];
+        BigEndian::write_u32(&mut footer, self.footer);
+        out.extend_from_slice(&footer);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::p2p::v1::binary_header::BinaryHeaderFlag;
+    use crate::p2p::v1::factories::P2PPacketFactory;
+    use crate::p2p::v1::p2p_packet::{P2PPacket, FOOTER_MSN_OBJECT, FOOTER_SLP};
+    use crate::p2p::v2::factories::P2PPayloadFactory;
+    use crate::shared::traits::IntoBytes;
+
+    #[test]
+    fn packet_round_trip() {
+        let packet = P2PPacket::new(0, 42, BinaryHeaderFlag::Normal as u32, b"INVITE MSNMSGR:aeon@test.com MSNSLP/1.0\r\n".to_vec(), FOOTER_SLP);
+
+        let bytes = packet.clone().into_bytes();
+        assert_eq!(48 + packet.payload.len() + 4, bytes.len());
+
+        let deser = P2PPacket::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(packet.header, deser.header);
+        assert_eq!(packet.payload, deser.payload);
+        assert_eq!(FOOTER_SLP, deser.footer);
+        assert!(deser.is_slp_msg());
+    }
+
+    #[test]
+    fn packet_truncated_payload() {
+        let mut bytes = P2PPacket::new(0, 42, 0, vec![1; 20], FOOTER_SLP).into_bytes();
+        bytes.truncate(60);
+        assert!(P2PPacket::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn chunk_and_reassemble() {
+        let payload: Vec<u8> = (0..3000u32).map(|i| i as u8).collect();
+        let packet = P2PPacket::new(12, 100, BinaryHeaderFlag::Data as u32, payload.clone(), FOOTER_MSN_OBJECT);
+
+        let chunks = packet.chunk(1202);
+        assert_eq!(3, chunks.len());
+        assert!(chunks.iter().all(|c| c.header.identifier == 100 && c.header.total_size == 3000));
+        assert_eq!(1202, chunks[1].header.offset);
+        assert_eq!(596, chunks[2].header.message_size);
+        assert!(chunks[0].is_chunked());
+        assert!(!chunks[1].is_last_chunk());
+        assert!(chunks[2].is_last_chunk());
+
+        let mut chunks = chunks.into_iter();
+        let mut reformed = chunks.next().unwrap();
+        for chunk in chunks {
+            reformed.append_chunk(chunk).unwrap();
+        }
+
+        assert!(!reformed.is_chunked());
+        assert!(reformed.is_complete());
+        assert_eq!(payload, reformed.payload);
+    }
+
+    #[test]
+    fn reassemble_skips_overlaps_and_rejects_gaps() {
+        let payload: Vec<u8> = (0..3000u32).map(|i| i as u8).collect();
+        let chunks = P2PPacket::new(12, 100, BinaryHeaderFlag::Data as u32, payload.clone(), FOOTER_MSN_OBJECT).chunk(1202);
+
+        let mut reformed = chunks[0].clone();
+        assert!(reformed.append_chunk(chunks[2].clone()).is_err());
+        assert!(!reformed.is_complete());
+
+        reformed.append_chunk(chunks[0].clone()).unwrap();
+        reformed.append_chunk(chunks[1].clone()).unwrap();
+        reformed.append_chunk(chunks[1].clone()).unwrap();
+
+        let mut overlapping = chunks[2].clone();
+        overlapping.header.offset -= 2;
+        overlapping.payload = payload[overlapping.header.offset as usize..].to_vec();
+        reformed.append_chunk(overlapping).unwrap();
+
+        assert!(reformed.is_complete());
+        assert_eq!(payload, reformed.payload);
+    }
+
+    #[test]
+    fn ack_references_acked_message() {
+        let packet = P2PPacket::new(12, 100, BinaryHeaderFlag::Data as u32, vec![0; 10], FOOTER_MSN_OBJECT);
+        let ack = P2PPacketFactory::get_ack(&packet, 101);
+
+        assert!(ack.is_ack());
+        assert!(ack.payload.is_empty());
+        assert_eq!(12, ack.header.session_id);
+        assert_eq!(100, ack.header.ack_session_id);
+        assert_eq!(packet.header.ack_session_id, ack.header.ack_unique_id);
+        assert_eq!(10, ack.header.ack_data_size);
+    }
+
+    #[test]
+    fn data_layer_mapping() {
+        let mut msn_obj = P2PPayloadFactory::get_msn_obj(12);
+        msn_obj.payload = vec![1, 2, 3];
+
+        let packet = P2PPacket::from_data_layer(msn_obj, 7);
+        assert!(packet.header.has_flag(BinaryHeaderFlag::Data));
+        assert_eq!(FOOTER_MSN_OBJECT, packet.footer);
+
+        let back = packet.into_data_layer();
+        assert!(back.tf.is_msn_obj_transfer());
+        assert_eq!(12, back.session_id);
+        assert_eq!(vec![1, 2, 3], back.payload);
+    }
+}
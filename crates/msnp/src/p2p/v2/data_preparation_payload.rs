@@ -1,5 +1,20 @@
+use std::char::decode_utf16;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use strum_macros::{Display, EnumString};
+use yaserde::de::from_str;
+use yaserde::ser::to_string_with_config;
 use yaserde_derive::{YaDeserialize, YaSerialize};
+
+use crate::msnp::error::PayloadError;
 use crate::shared::models::msn_object::MsnObject;
+use crate::shared::models::uuid::Uuid;
+use crate::shared::traits::TryFromBytes;
+
+// Photo Sharing maps are sent as the payload of data preparation packets (TF 0x01) of a SharePhoto session.
+// They are UTF-16LE encoded, followed by CRLF and a null terminator.
+// Source: doc/Photosharing
 
 #[derive(Clone, Debug, YaDeserialize, YaSerialize, Default)]
 #[yaserde(rename = "map")]
@@ -17,8 +32,9 @@ pub struct Map {
 #[yaserde(rename = "h")]
 pub struct MapHeader {
 
+    //Always PS (Photo Sharing)
     #[yaserde(rename = "aid", attribute)]
-    pub aid: MapAid,
+    pub aid: String,
 
     #[yaserde(rename = "op", attribute)]
     pub op: String,
@@ -30,30 +46,215 @@ pub struct MapHeader {
 #[derive(Clone, Debug, YaDeserialize, YaSerialize, Default)]
 #[yaserde(rename = "m")]
 pub struct MapBody {
+    //Identifies a photo for the whole session
     #[yaserde(rename = "guid", attribute)]
     pub guid: String,
 
+    /* CHG */
+    #[yaserde(rename = "seq", attribute)]
+    pub seq: Option<u32>,
+
+    #[yaserde(rename = "ack", attribute)]
+    pub ack: Option<u32>,
+
+    /* ADDH */
     #[yaserde(rename = "hash", attribute)]
-    pub hash: String,
+    pub hash: Option<String>,
 
+    //Index of the photo in the session
     #[yaserde(rename = "si", attribute)]
-    pub si: u32,
+    pub si: Option<u32>,
 
-    //Thumbnail URLEncoded MSN Object
+    //Thumbnail MSN Object
     #[yaserde(rename = "tospath", attribute)]
-    pub tospath: Option<MsnObject>,
+    pub tospath: Option<String>,
 
-    //Main URLEncoded MSN Object
+    //Main MSN Object
     #[yaserde(rename = "mospath", attribute)]
-    pub mospath: Option<MsnObject>,
+    pub mospath: Option<String>,
 
     #[yaserde(rename = "dispn", attribute)]
-    pub display_name: String
+    pub display_name: Option<String>,
+
+    /* PROGRESS */
+    //Download progress, from 0 to 1
+    #[yaserde(rename = "dp", attribute)]
+    pub dp: Option<String>,
 }
 
+#[derive(Clone, Debug, Display, EnumString, PartialEq)]
+pub enum MapOperation {
+    //The user selected a photo in the Photo Sharing pane
+    #[strum(serialize = "CHG")]
+    Change,
+    //The user added a photo to the session
+    #[strum(serialize = "ADDH")]
+    PhotoAdded,
+    //Download progress of a photo, sent by the receiving side
+    #[strum(serialize = "PROGRESS")]
+    Progress,
+}
 
-#[derive(Clone, Debug, YaDeserialize, YaSerialize, Default)]
-pub enum MapAid {
-    #[default]
-    PS
-}
\ No newline at end of file
+impl Map {
+
+    fn new(op: MapOperation, body: MapBody) -> Self {
+        Self {
+            header: MapHeader {
+                aid: "PS".to_string(),
+                op: op.to_string(),
+                ver: "1".to_string(),
+            },
+            body,
+        }
+    }
+
+    pub fn new_change(guid: &Uuid, seq: u32) -> Self {
+        Self::new(MapOperation::Change, MapBody {
+            guid: format!("{{{}}}", guid),
+            seq: Some(seq),
+            ack: Some(0),
+            ..Default::default()
+        })
+    }
+
+    pub fn new_photo_added(guid: &Uuid, hash: String, si: u32, thumbnail: &MsnObject, photo: &MsnObject, display_name: String) -> Self {
+        Self::new(MapOperation::PhotoAdded, MapBody {
+            guid: format!("{{{}}}", guid),
+            hash: Some(hash),
+            si: Some(si),
+            tospath: Some(thumbnail.to_string_not_encoded()),
+            mospath: Some(photo.to_string_not_encoded()),
+            display_name: Some(display_name),
+            ..Default::default()
+        })
+    }
+
+    pub fn new_progress(guid: &str, progress: f32) -> Self {
+        Self::new(MapOperation::Progress, MapBody {
+            guid: guid.to_string(),
+            dp: Some(progress.clamp(0.0, 1.0).to_string()),
+            ..Default::default()
+        })
+    }
+
+    pub fn operation(&self) -> Result<MapOperation, PayloadError> {
+        MapOperation::from_str(&self.header.op).map_err(|e| PayloadError::EnumParsingError { payload: self.header.op.clone(), source: anyhow!(e) })
+    }
+
+    pub fn guid(&self) -> &str {
+        &self.body.guid
+    }
+
+    pub fn thumbnail(&self) -> Result<Option<MsnObject>, PayloadError> {
+        self.body.tospath.as_deref().map(MsnObject::from_str).transpose()
+    }
+
+    pub fn photo(&self) -> Result<Option<MsnObject>, PayloadError> {
+        self.body.mospath.as_deref().map(MsnObject::from_str).transpose()
+    }
+
+    pub fn progress(&self) -> Option<f32> {
+        self.body.dp.as_ref().and_then(|dp| dp.parse::<f32>().ok())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let yaserde_cfg = yaserde::ser::Config{
+            perform_indent: false,
+            write_document_declaration: false,
+            indent_string: None
+        };
+
+        let mut serialized = to_string_with_config(self, &yaserde_cfg).expect("Map to be serializable");
+        serialized.push_str("\r\n\0");
+
+        serialized.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+    }
+}
+
+impl TryFromBytes for Map {
+    type Err = PayloadError;
+
+    fn try_from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Err>
+    where
+        Self: Sized
+    {
+        let utf16: Vec<u16> = bytes.chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+
+        let decoded = decode_utf16(utf16.into_iter())
+            .collect::<Result<String, _>>()
+            .map_err(|e| PayloadError::BinaryPayloadParsingError { payload: bytes.clone(), source: anyhow!("Map was not valid UTF-16: {}", e) })?;
+
+        let trimmed = decoded.trim_end_matches('\0').trim();
+
+        from_str::<Map>(trimmed).map_err(|e| PayloadError::StringPayloadParsingError { payload: trimmed.to_string(), source: anyhow!("Could not parse Photo Sharing map: {}", e) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use crate::p2p::v2::data_preparation_payload::{Map, MapOperation};
+    use crate::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObjectType};
+    use crate::shared::models::uuid::Uuid;
+    use crate::shared::traits::TryFromBytes;
+
+    fn to_utf16_bytes(map: &str) -> Vec<u8> {
+        format!("{}\r\n\0", map).encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn deserialize_photo_added() {
+        let raw = r#"<map><h aid="PS" op="ADDH" ver="1"></h><m guid="{5D59B0A0-44B1-4899-95CC-E7142B1CD711}" hash="IaMcpG6dX3QhL4U1ovzETXlI9j0=" si="2" tospath="&lt;msnobj Creator=&quot;aeoncl@escargot.chat&quot; Type=&quot;15&quot; SHA1D=&quot;EQYEN68VmjEgdNKQobpoEYIVeIQ=&quot; Size=&quot;1063&quot; Location=&quot;0&quot; Friendly=&quot;cgBhAHcAAAA=&quot;/&gt;" mospath="&lt;msnobj Creator=&quot;aeoncl@escargot.chat&quot; Type=&quot;15&quot; SHA1D=&quot;oA6ToUkA+4u9wSMS6Z0Q+1KIejU=&quot; Size=&quot;7331&quot; Location=&quot;0&quot; Friendly=&quot;cgBhAHcAAAA=&quot;/&gt;" dispn="raw.png"/></map>"#;
+
+        let map = Map::try_from_bytes(to_utf16_bytes(raw)).unwrap();
+
+        assert_eq!(map.operation().unwrap(), MapOperation::PhotoAdded);
+        assert_eq!(map.guid(), "{5D59B0A0-44B1-4899-95CC-E7142B1CD711}");
+        assert_eq!(map.body.si, Some(2));
+        assert_eq!(map.body.display_name.as_deref(), Some("raw.png"));
+
+        let thumbnail = map.thumbnail().unwrap().unwrap();
+        assert_eq!(thumbnail.obj_type, MsnObjectType::SharedPhoto);
+        assert_eq!(thumbnail.size, 1063);
+        assert_eq!(thumbnail.sha1d, "EQYEN68VmjEgdNKQobpoEYIVeIQ=");
+
+        let photo = map.photo().unwrap().unwrap();
+        assert_eq!(photo.creator, "aeoncl@escargot.chat");
+        assert_eq!(photo.size, 7331);
+        assert_eq!(photo.sha1d, "oA6ToUkA+4u9wSMS6Z0Q+1KIejU=");
+    }
+
+    #[test]
+    fn deserialize_change_and_progress() {
+        let change = Map::try_from_bytes(to_utf16_bytes(r#"<map><h aid="PS" op="CHG" ver="1"></h><m guid="{5D59B0A0-44B1-4899-95CC-E7142B1CD711}" seq="2" ack="0"/></map>"#)).unwrap();
+        assert_eq!(change.operation().unwrap(), MapOperation::Change);
+        assert_eq!(change.body.seq, Some(2));
+        assert_eq!(change.body.ack, Some(0));
+
+        let progress = Map::try_from_bytes(to_utf16_bytes(r#"<map><h aid="PS" op="PROGRESS" ver="1"></h><m guid="{5D59B0A0-44B1-4899-95CC-E7142B1CD711}" dp="0.1871505"/></map>"#)).unwrap();
+        assert_eq!(progress.operation().unwrap(), MapOperation::Progress);
+        assert_eq!(progress.progress(), Some(0.1871505));
+    }
+
+    #[test]
+    fn serialize_deserialize_photo_added() {
+        let thumbnail = MSNObjectFactory::get_shared_photo(b"thumbnail", "aeon@test.com".to_string(), FriendlyName::new("raw"));
+        let photo = MSNObjectFactory::get_shared_photo(b"full size picture", "aeon@test.com".to_string(), FriendlyName::new("raw"));
+        let guid = Uuid::from_str("5D59B0A0-44B1-4899-95CC-E7142B1CD711").unwrap();
+
+        let map = Map::new_photo_added(&guid, photo.sha1d.clone(), 1, &thumbnail, &photo, "raw.png".to_string());
+        let bytes = map.to_bytes();
+
+        assert_eq!(&bytes[bytes.len() - 6..], &[b'\r', 0, b'\n', 0, 0, 0]);
+
+        let deserialized = Map::try_from_bytes(bytes).unwrap();
+        assert_eq!(deserialized.operation().unwrap(), MapOperation::PhotoAdded);
+        assert_eq!(deserialized.guid(), map.guid());
+        assert_eq!(deserialized.body.display_name.as_deref(), Some("raw.png"));
+        assert_eq!(deserialized.thumbnail().unwrap().unwrap().sha1d, thumbnail.sha1d);
+        assert_eq!(deserialized.photo().unwrap().unwrap().size, photo.size);
+    }
+
+}
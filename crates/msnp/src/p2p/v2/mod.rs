@@ -7,6 +7,7 @@ pub mod pending_packet;
 pub mod tlv;
 pub mod slp;
 pub mod data_preparation_payload;
+pub mod webcam;
 
 pub mod factories {
     use byteorder::{BigEndian, ByteOrder, LittleEndian};
@@ -1,4 +1,5 @@
 use num_derive::FromPrimitive;
+use crate::shared::models::msn_object::MsnObjectType;
 
 #[derive(Clone, Debug, FromPrimitive, Eq, PartialEq)]
 pub enum AppID {
@@ -6,6 +7,43 @@ pub enum AppID {
     CustomEmoticonTransfer = 11,
     DisplayPictureTransfer = 12,
     VoiceClipTransfer = 20,
+    SharedPhotoTransfer = 33,
     PhotoSharing = 35,
     Webcam = 4
-}
\ No newline at end of file
+}
+
+impl AppID {
+
+    //The AppID clients expect in an MSNObject INVITE, depending on the type of object requested.
+    pub fn for_msn_object_type(obj_type: &MsnObjectType) -> Self {
+        match obj_type {
+            MsnObjectType::CustomEmoticon => AppID::CustomEmoticonTransfer,
+            MsnObjectType::DisplayPicture | MsnObjectType::DynamicDisplayPicture | MsnObjectType::Scene => AppID::DisplayPictureTransfer,
+            MsnObjectType::VoiceClip | MsnObjectType::SignatureSound => AppID::VoiceClipTransfer,
+            MsnObjectType::SharedPhoto => AppID::SharedPhotoTransfer,
+            _ => AppID::DisplayPictureTransfer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shared::models::msn_object::MsnObjectType;
+
+    use super::AppID;
+
+    #[test]
+    fn msn_object_types_map_to_their_app_id() {
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::DisplayPicture), AppID::DisplayPictureTransfer);
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::CustomEmoticon), AppID::CustomEmoticonTransfer);
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::VoiceClip), AppID::VoiceClipTransfer);
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::SharedPhoto), AppID::SharedPhotoTransfer);
+    }
+
+    #[test]
+    fn profile_extras_map_to_their_app_id() {
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::DynamicDisplayPicture), AppID::DisplayPictureTransfer);
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::Scene), AppID::DisplayPictureTransfer);
+        assert_eq!(AppID::for_msn_object_type(&MsnObjectType::SignatureSound), AppID::VoiceClipTransfer);
+    }
+}
@@ -11,7 +11,9 @@ use std::{
     str::{from_utf8, FromStr},
 };
 
+use crate::p2p::v2::slp::app_id::AppID;
 use crate::p2p::v2::slp::session_slp_context::{PreviewData, SlpContext};
+use crate::p2p::v2::slp::SlpStatus;
 use crate::shared::models::endpoint_id::EndpointId;
 use crate::shared::traits::IntoBytes;
 use crate::{
@@ -367,6 +369,12 @@ impl SlpPayloadFactory {
         return Ok(out);
     }
 
+    pub fn get_603_decline_session(invite: &RawSlpPayload) -> Result<RawSlpPayload, PayloadError> {
+        let mut out = SlpPayloadFactory::get_200_ok_session(invite)?;
+        out.first_line = format!("MSNSLP/1.0 {}", SlpStatus::Decline);
+        return Ok(out);
+    }
+
     pub fn get_file_transfer_request(
         sender: &EndpointId,
         receiver: &EndpointId,
@@ -418,6 +426,26 @@ impl SlpPayloadFactory {
         receiver: &MsnUser,
         context: &MsnObject,
         session_id: u32,
+    ) -> Result<RawSlpPayload, PayloadError> {
+        SlpPayloadFactory::get_msn_object_request_for_app(sender, receiver, context, session_id, AppID::for_msn_object_type(&context.obj_type))
+    }
+
+    //Photos of a Photo Sharing session are fetched with regular MSNObject requests, under their own AppID.
+    pub fn get_shared_photo_request(
+        sender: &MsnUser,
+        receiver: &MsnUser,
+        context: &MsnObject,
+        session_id: u32,
+    ) -> Result<RawSlpPayload, PayloadError> {
+        SlpPayloadFactory::get_msn_object_request_for_app(sender, receiver, context, session_id, AppID::SharedPhotoTransfer)
+    }
+
+    fn get_msn_object_request_for_app(
+        sender: &MsnUser,
+        receiver: &MsnUser,
+        context: &MsnObject,
+        session_id: u32,
+        app_id: AppID,
     ) -> Result<RawSlpPayload, PayloadError> {
         let context_b64 = general_purpose::STANDARD.encode(context.to_string_not_encoded());
 
@@ -452,12 +480,55 @@ impl SlpPayloadFactory {
 
         out.add_body_property(String::from("EUF-GUID"), EufGUID::MSNObject.to_string());
         out.add_body_property(String::from("SessionID"), session_id.to_string());
-        out.add_body_property(String::from("AppID"), String::from("20"));
+        out.add_body_property(String::from("AppID"), format!("{}", app_id as u32));
         out.add_body_property(String::from("RequestFlags"), String::from("18"));
         out.add_body_property(String::from("Context"), context_b64);
         return Ok(out);
     }
 
+    pub fn get_photo_sharing_request(
+        sender: &EndpointId,
+        receiver: &EndpointId,
+        session_id: u32,
+        call_id: &Uuid
+    ) -> Result<RawSlpPayload, PayloadError> {
+        let mut out = RawSlpPayload::new();
+        out.first_line = format!("INVITE MSNMSGR:{} MSNSLP/1.0", receiver);
+        out.add_header(
+            String::from("To"),
+            format!("<msnmsgr:{mpop_id}>", mpop_id = receiver),
+        );
+        out.add_header(
+            String::from("From"),
+            format!("<msnmsgr:{mpop_id}>", mpop_id = sender),
+        );
+        out.add_header(
+            String::from("Via"),
+            format!(
+                "MSNSLP/1.0/TLP ;branch={{{branch_uuid}}}",
+                branch_uuid = Uuid::new().to_string()
+            ),
+        );
+
+        out.add_header(String::from("CSeq"), String::from("0"));
+        out.add_header(
+            String::from("Call-ID"),
+            format!("{{{call_id}}}", call_id = call_id.to_string()),
+        );
+        out.add_header(String::from("Max-Forwards"), String::from("0"));
+        out.add_header(
+            String::from("Content-Type"),
+            String::from("application/x-msnmsgr-sessionreqbody"),
+        );
+
+        out.add_body_property(String::from("EUF-GUID"), EufGUID::SharePhoto.to_string());
+        out.add_body_property(String::from("SessionID"), session_id.to_string());
+        out.add_body_property(String::from("AppID"), format!("{}", AppID::PhotoSharing as u32));
+        out.add_body_property(String::from("RequestFlags"), String::from("16"));
+
+        return Ok(out);
+    }
+
     pub fn get_200_ok_indirect_connect(
         invite: &RawSlpPayload,
     ) -> Result<RawSlpPayload, PayloadError> {
@@ -803,7 +874,11 @@ impl SlpPayloadFactory {
 mod tests {
     use std::str::FromStr;
 
-    use super::EufGUID;
+    use crate::shared::models::email_address::EmailAddress;
+    use crate::shared::models::msn_object::{FriendlyName, MSNObjectFactory};
+    use crate::shared::models::msn_user::MsnUser;
+
+    use super::{EufGUID, SlpPayloadFactory};
 
     #[test]
     fn test_euf_guid_try_from_str() {
@@ -816,4 +891,18 @@ mod tests {
         let test = EufGUID::MSNObject.to_string();
         assert_eq!("{A4268EEC-FEC5-49E5-95C3-F126696BDBF6}", test.as_str());
     }
+
+    #[test]
+    fn msn_object_request_uses_the_app_id_of_the_object() {
+        let sender = MsnUser::with_email_addr(EmailAddress::from_str("aeon@test.com").unwrap());
+        let receiver = MsnUser::with_email_addr(EmailAddress::from_str("bob@test.com").unwrap());
+
+        let display_picture = MSNObjectFactory::get_display_picture(&[1, 2, 3], &receiver.endpoint_id.email_addr, String::new(), FriendlyName::default());
+        let request = SlpPayloadFactory::get_msn_object_request(&sender, &receiver, &display_picture, 1).unwrap();
+        assert_eq!(request.get_body_property("AppID"), Some("12"));
+
+        let voice_clip = MSNObjectFactory::get_voice_message(&[1, 2, 3], "bob@test.com".into(), FriendlyName::default());
+        let request = SlpPayloadFactory::get_msn_object_request(&sender, &receiver, &voice_clip, 2).unwrap();
+        assert_eq!(request.get_body_property("AppID"), Some("20"));
+    }
 }
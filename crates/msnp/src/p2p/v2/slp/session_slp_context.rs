@@ -9,16 +9,28 @@ pub trait SlpContext {
     fn from_slp_context(bytes: &[u8]) -> Option<Self> where Self: Sized;
 }
 
+const PREVIEW_DATA_CONTEXT_SIZE: usize = 574;
+
 #[derive(Debug)]
 pub struct PreviewData {
     size: usize,
     filename: String,
+    //Thumbnail shown by the client in the file transfer invite, appended after the context.
+    preview: Option<Vec<u8>>,
 }
 
 impl PreviewData {
 
     pub fn new(size: usize, filename: String) -> PreviewData {
-        return PreviewData {size, filename};
+        return PreviewData {size, filename, preview: None};
+    }
+
+    pub fn new_with_preview(size: usize, filename: String, preview: Vec<u8>) -> PreviewData {
+        return PreviewData {size, filename, preview: Some(preview)};
+    }
+
+    pub fn get_preview(&self) -> Option<&[u8]> {
+        return self.preview.as_deref();
     }
 
     pub fn get_size(&self) -> usize {
@@ -31,10 +43,10 @@ impl PreviewData {
 
 
     fn to_slp_context(&self) -> Vec<u8> {
-        let mut result = vec![0; 574];
+        let mut result = vec![0; PREVIEW_DATA_CONTEXT_SIZE];
 
         //context_size
-        LittleEndian::write_u32(&mut result[0..4], 574);
+        LittleEndian::write_u32(&mut result[0..4], PREVIEW_DATA_CONTEXT_SIZE as u32);
 
         //tf_type
         LittleEndian::write_u32(&mut result[4..8], 2);
@@ -46,8 +58,8 @@ impl PreviewData {
         LittleEndian::write_u32(&mut result[12..16], 0);
 
 
-        //Preview
-        LittleEndian::write_u32(&mut result[16..20], 1);
+        //Preview: 0 when a preview follows the context, 1 otherwise
+        LittleEndian::write_u32(&mut result[16..20], if self.preview.is_some() { 0 } else { 1 });
 
         let mut file_name_bytes: Vec<u8> = Vec::new();
 
@@ -60,6 +72,10 @@ impl PreviewData {
         let slice = &mut result[20..file_name_bytes.len()+20];
         slice.clone_from_slice(file_name_bytes.as_slice());
 
+        if let Some(preview) = &self.preview {
+            result.extend_from_slice(preview);
+        }
+
         return result;
     }
 }
@@ -84,14 +100,13 @@ impl SlpContext for PreviewData {
         if bytes.len() >= 4 {
             let context_size = LittleEndian::read_u32(&bytes[0..4]) as usize;
 
-            if context_size == 574 && bytes.len() >= context_size as usize {
+            if context_size == PREVIEW_DATA_CONTEXT_SIZE && bytes.len() >= context_size as usize {
                 let _tf_type = LittleEndian::read_u32(&bytes[4..8]);
                 let file_size = LittleEndian::read_u32(&bytes[8..12]) as usize;
                 let zero_separator = LittleEndian::read_u32(&bytes[12..16]);
 
                 if zero_separator == 0 {
-                    let _has_preview = LittleEndian::read_u32(&bytes[16..20]) == 0;
-                    //handle preview later
+                    let has_preview = LittleEndian::read_u32(&bytes[16..20]) == 0;
                     let filename_chunks: Vec<u16> = bytes[20..context_size].to_vec()
                     .chunks_exact(2)
                     .into_iter()
@@ -99,7 +114,13 @@ impl SlpContext for PreviewData {
                     .collect();
 
                     let filename = decode_utf16(filename_chunks.into_iter()).map(|r| r.unwrap_or('�')).collect::<String>().trim_end_matches('\0').to_string();
-                    return Some(PreviewData {size: file_size, filename});
+                    let preview = if has_preview && bytes.len() > context_size {
+                        Some(bytes[context_size..].to_vec())
+                    } else {
+                        None
+                    };
+
+                    return Some(PreviewData {size: file_size, filename, preview});
                 }
             }
         }
@@ -148,5 +169,19 @@ mod tests {
 
     }
 
+    #[test]
+    fn preview_data_with_preview_test() {
+        let thumbnail = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        let preview_data = PreviewData::new_with_preview(1234, String::from("meme.png"), thumbnail.clone());
+
+        let result = preview_data.to_slp_context();
+        assert_eq!(result.len(), 574 + thumbnail.len());
+
+        let deserialized = PreviewData::from_slp_context(&result).unwrap();
+        assert_eq!(deserialized.get_filename(), String::from("meme.png"));
+        assert_eq!(deserialized.get_size(), 1234);
+        assert_eq!(deserialized.get_preview(), Some(thumbnail.as_slice()));
+    }
+
 
 }
\ No newline at end of file
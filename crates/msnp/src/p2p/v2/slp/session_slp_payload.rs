@@ -79,25 +79,29 @@ impl TryFromRawSlpPayload for SessionInviteRequestPayload {
         };
 
         let context = {
-            let raw_context = payload.body.remove("Context").ok_or(anyhow!("Missing slp `Context` body"))?;
+            //Photo Sharing invites are sent without any Context.
+            let raw_context = payload.body.remove("Context");
+            let mandatory_context = || raw_context.clone().ok_or(anyhow!("Missing slp `Context` body"));
 
             //Todo refactor this so that SessionReqInviteContext handles the conversion itself of each of it's branches.
             match euf_guid {
                 EufGUID::MSNObject => {
+                    let raw_context = mandatory_context()?;
                     let decoded = general_purpose::STANDARD.decode(raw_context).map_err(|e| anyhow!("Invalid base64 `MsnObject` context. error: {}", e))?;
                     let utf8_decoded = String::from_utf8(decoded).map_err(|e| anyhow!("Invalid UTF8 `MsnObject` context body. error: {}", e))?;
                     SessionReqInviteContext::MsnObject(MsnObject::from_str(&utf8_decoded)?)
                 }
                 EufGUID::FileTransfer => {
+                    let raw_context = mandatory_context()?;
                     let decoded = general_purpose::STANDARD.decode(raw_context).map_err(|e| anyhow!("Invalid base64 `FileTransfer` contex body. error: {}", e))?;
                     SessionReqInviteContext::FileTransfer(PreviewData::from_slp_context(&decoded)
                         .ok_or(anyhow!("Invalid `PreviewData` context body: {:?}", decoded))?)
                 }
                 EufGUID::MediaReceiveOnly => {
-                    SessionReqInviteContext::MediaReceiveOnly
+                    SessionReqInviteContext::MediaReceiveOnly(decode_media_context(raw_context)?)
                 }
                 EufGUID::MediaSession => {
-                    SessionReqInviteContext::MediaSession
+                    SessionReqInviteContext::MediaSession(decode_media_context(raw_context)?)
                 }
                 EufGUID::SharePhoto => {
                     SessionReqInviteContext::SharePhoto
@@ -127,7 +131,9 @@ impl IntoRawSlpPayload for SessionInviteRequestPayload {
         out.add_body_property(String::from("SessionID"), self.session_id.to_string());
         out.add_body_property(String::from("AppID"), format!("{}", self.app_id as u32));
         out.add_body_property(String::from("RequestFlags"), format!("{}", self.request_flags));
-        out.add_body_property(String::from("Context"), self.context.to_string());
+        if self.context.has_body() {
+            out.add_body_property(String::from("Context"), self.context.to_string());
+        }
 
         out.headers = self.headers.into();
         out.add_header("Content-Type".to_string(), "application/x-msnmsgr-sessionreqbody".to_string());
@@ -219,11 +225,15 @@ impl IntoRawSlpPayload for SessionClosePayload {
 }
 
 
+//The context a client puts in a media session invite to share its webcam, as UTF-16.
+const WEBCAM_CONTEXT_GUID: &str = "{B8BE70DE-E2CA-4400-AE03-88FF85B9F4E8}";
+
 pub enum SessionReqInviteContext {
     MsnObject(MsnObject),
     FileTransfer(PreviewData),
-    MediaReceiveOnly,
-    MediaSession,
+    //Media sessions carry the session description of the inviter, base64 decoded. It can be empty.
+    MediaReceiveOnly(String),
+    MediaSession(String),
     SharePhoto,
     Activity,
 }
@@ -233,12 +243,30 @@ impl SessionReqInviteContext {
         match self {
             SessionReqInviteContext::MsnObject(_) => { EufGUID::MSNObject }
             SessionReqInviteContext::FileTransfer(_) => { EufGUID::FileTransfer }
-            SessionReqInviteContext::MediaReceiveOnly => { EufGUID::MediaReceiveOnly }
-            SessionReqInviteContext::MediaSession => { EufGUID::MediaSession }
+            SessionReqInviteContext::MediaReceiveOnly(_) => { EufGUID::MediaReceiveOnly }
+            SessionReqInviteContext::MediaSession(_) => { EufGUID::MediaSession }
             SessionReqInviteContext::SharePhoto => { EufGUID::SharePhoto }
             SessionReqInviteContext::Activity => { EufGUID::Activity }
         }
     }
+
+    pub fn has_body(&self) -> bool {
+        match self {
+            SessionReqInviteContext::SharePhoto => false,
+            SessionReqInviteContext::MediaReceiveOnly(context) | SessionReqInviteContext::MediaSession(context) => !context.is_empty(),
+            _ => true
+        }
+    }
+
+    //Webcam invites are media sessions too, they're told apart from calls by their context.
+    pub fn is_webcam(&self) -> bool {
+        match self {
+            SessionReqInviteContext::MediaReceiveOnly(context) | SessionReqInviteContext::MediaSession(context) => {
+                context.replace('\0', "").eq_ignore_ascii_case(WEBCAM_CONTEXT_GUID)
+            }
+            _ => false
+        }
+    }
 }
 
 //TODO refactor this because right now some SLP handles base64 encoding, some don't.
@@ -255,14 +283,12 @@ impl Display for SessionReqInviteContext {
                 let base64 = preview_data.to_string();
                 write!(f, "{}", base64)
             }
-            SessionReqInviteContext::MediaReceiveOnly => {
-                todo!("MediaReceiveOnly serialization not yet implemented")
-            }
-            SessionReqInviteContext::MediaSession => {
-                todo!("MediaSession serialization not yet implemented")
+            SessionReqInviteContext::MediaReceiveOnly(context) | SessionReqInviteContext::MediaSession(context) => {
+                let base64 = general_purpose::STANDARD.encode(context);
+                write!(f, "{}", base64)
             }
             SessionReqInviteContext::SharePhoto => {
-                todo!("SharePhoto serialization not yet implemented")
+                Ok(())
             }
             SessionReqInviteContext::Activity => {
                 todo!("Activity serialization not yet implemented")
@@ -272,16 +298,27 @@ impl Display for SessionReqInviteContext {
     }
 }
 
+pub fn decode_media_context(raw_context: Option<String>) -> Result<String, PayloadError> {
+    let Some(raw_context) = raw_context.filter(|raw| !raw.is_empty()) else {
+        return Ok(String::new());
+    };
+
+    let decoded = general_purpose::STANDARD.decode(raw_context.trim_end_matches('\0')).map_err(|e| anyhow!("Invalid base64 media session context. error: {}", e))?;
+    Ok(String::from_utf8_lossy(&decoded).trim_end_matches('\0').to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::p2p::v2::slp::app_id::AppID;
     use crate::p2p::v2::slp::raw_slp_payload::{IntoRawSlpPayload, SlpPayloadFactory, TryFromRawSlpPayload};
     use crate::p2p::v2::slp::session_slp_context::PreviewData;
-    use crate::p2p::v2::slp::session_slp_payload::{SessionReqInviteContext, SessionInviteRequestPayload};
+    use crate::p2p::v2::slp::session_slp_payload::{decode_media_context, SessionReqInviteContext, SessionInviteRequestPayload};
     use crate::p2p::v2::slp::{SlpHeaders, ViaHeader};
     use crate::shared::models::email_address::EmailAddress;
     use crate::shared::models::endpoint_id::EndpointId;
     use crate::shared::models::uuid::Uuid;
+    use base64::engine::general_purpose;
+    use base64::Engine;
     use std::str::FromStr;
 
     #[test]
@@ -333,5 +370,53 @@ Context: PgIAAAIAAAB7AAAAAAAAAAEAAABiAGwAYQBiAGwAYQBiAGwAYQAuAGcAaQBmAAAAAAAAAAA
         assert_eq!(serialized, model_serialized)
     }
 
+    #[test]
+    fn photo_sharing_invite_without_context() {
+        let sender = EndpointId::from_email_addr(EmailAddress::from_str("aeon@test.com").unwrap());
+        let receiver = EndpointId::from_email_addr(EmailAddress::from_str("aeon1@test.com").unwrap());
+
+        let factory_raw = SlpPayloadFactory::get_photo_sharing_request(&sender, &receiver, 3600158560, &Uuid::new()).unwrap();
+        let serialized = factory_raw.to_string();
+        assert!(!serialized.contains("Context"));
+
+        let model = SessionInviteRequestPayload::try_from_raw_slp_payload(factory_raw).unwrap();
+        assert!(matches!(model.context(), SessionReqInviteContext::SharePhoto));
+        assert_eq!(model.app_id(), AppID::PhotoSharing);
+        assert_eq!(model.session_id(), 3600158560);
+
+        assert_eq!(serialized, model.into_raw_slp_payload().to_string())
+    }
+
+    #[test]
+    fn media_session_invite_round_trip() {
+        let sender = EndpointId::from_email_addr(EmailAddress::from_str("aeon@test.com").unwrap());
+        let receiver = EndpointId::from_email_addr(EmailAddress::from_str("aeon1@test.com").unwrap());
+        let sdp = "v=0\r\nc=IN IP4 127.0.0.1\r\nm=audio 5004 RTP/AVP 0\r\n";
+
+        let headers = SlpHeaders::new_minimal(receiver, sender, 0);
+        let raw = SessionInviteRequestPayload::new(headers, 42, AppID::Webcam, 16, SessionReqInviteContext::MediaSession(sdp.to_string())).into_raw_slp_payload();
+        let serialized = raw.to_string();
+
+        let model = SessionInviteRequestPayload::try_from_raw_slp_payload(raw).unwrap();
+        let SessionReqInviteContext::MediaSession(context) = model.context() else {
+            panic!("Expected a MediaSession context");
+        };
+        assert_eq!(context, sdp);
+
+        assert_eq!(serialized, model.into_raw_slp_payload().to_string())
+    }
+
+    #[test]
+    fn webcam_context_is_recognized() {
+        let utf16_guid: Vec<u8> = "{B8BE70DE-E2CA-4400-AE03-88FF85B9F4E8}\0".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        let raw_context = general_purpose::STANDARD.encode(utf16_guid);
+
+        let webcam = SessionReqInviteContext::MediaSession(decode_media_context(Some(raw_context)).unwrap());
+        assert!(webcam.is_webcam());
 
-}
\ No newline at end of file
+        let call = SessionReqInviteContext::MediaSession("v=0\r\n".to_string());
+        assert!(!call.is_webcam());
+        assert!(!SessionReqInviteContext::SharePhoto.is_webcam());
+    }
+
+}
@@ -0,0 +1,300 @@
+use std::char::decode_utf16;
+use std::net::IpAddr;
+
+use anyhow::anyhow;
+use byteorder::{ByteOrder, LittleEndian};
+use yaserde::de::from_str;
+use yaserde::ser::to_string_with_config;
+use yaserde_derive::{YaDeserialize, YaSerialize};
+
+use crate::msnp::error::PayloadError;
+use crate::shared::traits::TryFromBytes;
+
+// Webcam sessions are negotiated in the data packets of a MediaSession session.
+// Each message is UTF-16LE & null terminated, behind a 10 bytes header:
+// 0x80, a random u16 id, the step (1 for syn & ack, 3 for the XML messages), 0x08, 0x00 & the u32 length of the message.
+// Once both sides exchanged their <producer> & <viewer> XML, the viewer opens a TCP connection to the producer
+// and the video comes through it as Mimic (ML20) frames.
+
+const WEBCAM_MESSAGE_MAGIC: u8 = 0x80;
+const WEBCAM_MESSAGE_HEADER_SIZE: usize = 10;
+
+pub const WEBCAM_FRAME_HEADER_SIZE: usize = 24;
+pub const MIMIC_FOURCC: &[u8; 4] = b"ML20";
+
+//What the producer answers on the TCP connection once it recognized the viewer.
+pub const WEBCAM_CONNECTED: &[u8] = b"connected\r\n\r\n";
+
+#[derive(Clone, Debug)]
+pub enum WebcamMessage {
+    Syn,
+    Ack,
+    Producer(WebcamProducer),
+    Viewer(WebcamViewer),
+    ReceivedViewerData,
+    //Reflector & TURN data, we don't use them.
+    Other(String),
+}
+
+impl WebcamMessage {
+
+    pub fn to_bytes(&self, id: u16) -> Vec<u8> {
+        let (step, message) = match self {
+            WebcamMessage::Syn => (1, "syn".to_string()),
+            WebcamMessage::Ack => (1, "ack".to_string()),
+            WebcamMessage::Producer(producer) => (3, producer.to_xml()),
+            WebcamMessage::Viewer(viewer) => (3, viewer.to_xml()),
+            WebcamMessage::ReceivedViewerData => (3, "receivedViewerData".to_string()),
+            WebcamMessage::Other(other) => (3, other.clone()),
+        };
+
+        let body: Vec<u8> = format!("{}\0", message).encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+
+        let mut out = Vec::with_capacity(WEBCAM_MESSAGE_HEADER_SIZE + body.len());
+        out.push(WEBCAM_MESSAGE_MAGIC);
+        out.extend_from_slice(&id.to_le_bytes());
+        out.push(step);
+        out.push(0x08);
+        out.push(0x00);
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+}
+
+impl TryFromBytes for WebcamMessage {
+    type Err = PayloadError;
+
+    fn try_from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Err>
+    where
+        Self: Sized
+    {
+        if bytes.len() < WEBCAM_MESSAGE_HEADER_SIZE || bytes[0] != WEBCAM_MESSAGE_MAGIC {
+            return Err(PayloadError::BinaryPayloadParsingError { payload: bytes, source: anyhow!("Not a webcam message") });
+        }
+
+        let length = LittleEndian::read_u32(&bytes[6..10]) as usize;
+        let body = &bytes[WEBCAM_MESSAGE_HEADER_SIZE..];
+        if body.len() < length {
+            return Err(PayloadError::PayloadBytesMissing);
+        }
+
+        let utf16: Vec<u16> = body[..length].chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+
+        let decoded = decode_utf16(utf16.into_iter())
+            .collect::<Result<String, _>>()
+            .map_err(|e| PayloadError::BinaryPayloadParsingError { payload: bytes.clone(), source: anyhow!("Webcam message was not valid UTF-16: {}", e) })?;
+
+        let message = decoded.trim_end_matches('\0').trim();
+
+        let parsed = match message {
+            "syn" => WebcamMessage::Syn,
+            "ack" => WebcamMessage::Ack,
+            "receivedViewerData" => WebcamMessage::ReceivedViewerData,
+            producer if producer.starts_with("<producer>") => {
+                WebcamMessage::Producer(from_str::<WebcamProducer>(producer).map_err(|e| PayloadError::StringPayloadParsingError { payload: producer.to_string(), source: anyhow!("Could not parse webcam producer: {}", e) })?)
+            }
+            viewer if viewer.starts_with("<viewer>") => {
+                WebcamMessage::Viewer(from_str::<WebcamViewer>(viewer).map_err(|e| PayloadError::StringPayloadParsingError { payload: viewer.to_string(), source: anyhow!("Could not parse webcam viewer: {}", e) })?)
+            }
+            other => WebcamMessage::Other(other.to_string()),
+        };
+
+        Ok(parsed)
+    }
+}
+
+#[derive(Clone, Debug, YaDeserialize, YaSerialize, Default)]
+#[yaserde(rename = "tcp")]
+pub struct WebcamTcp {
+    #[yaserde(rename = "tcpport")]
+    pub port: u16,
+
+    #[yaserde(rename = "tcplocalport")]
+    pub local_port: u16,
+
+    #[yaserde(rename = "tcpexternalport")]
+    pub external_port: u16,
+
+    //Clients list every address they have, there's rarely more than a few.
+    #[yaserde(rename = "tcpipaddress1")]
+    pub ip_address_1: Option<String>,
+
+    #[yaserde(rename = "tcpipaddress2")]
+    pub ip_address_2: Option<String>,
+
+    #[yaserde(rename = "tcpipaddress3")]
+    pub ip_address_3: Option<String>,
+
+    #[yaserde(rename = "tcpipaddress4")]
+    pub ip_address_4: Option<String>,
+}
+
+impl WebcamTcp {
+    pub fn addresses(&self) -> Vec<IpAddr> {
+        [&self.ip_address_1, &self.ip_address_2, &self.ip_address_3, &self.ip_address_4]
+            .into_iter()
+            .flatten()
+            .filter_map(|address| address.trim().parse().ok())
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, YaDeserialize, YaSerialize, Default)]
+#[yaserde(rename = "producer")]
+pub struct WebcamProducer {
+    #[yaserde(rename = "version")]
+    pub version: String,
+
+    //Recipient id, the viewer hands it back when it connects
+    #[yaserde(rename = "rid")]
+    pub rid: u32,
+
+    #[yaserde(rename = "session")]
+    pub session: u32,
+
+    #[yaserde(rename = "tcp")]
+    pub tcp: WebcamTcp,
+}
+
+impl WebcamProducer {
+    fn to_xml(&self) -> String {
+        to_string_with_config(self, &yaserde::ser::Config { perform_indent: false, write_document_declaration: false, indent_string: None }).expect("producer to serialize")
+    }
+}
+
+#[derive(Clone, Debug, YaDeserialize, YaSerialize, Default)]
+#[yaserde(rename = "viewer")]
+pub struct WebcamViewer {
+    #[yaserde(rename = "version")]
+    pub version: String,
+
+    #[yaserde(rename = "rid")]
+    pub rid: u32,
+
+    #[yaserde(rename = "session")]
+    pub session: u32,
+
+    #[yaserde(rename = "tcp")]
+    pub tcp: WebcamTcp,
+}
+
+impl WebcamViewer {
+
+    //A viewer that doesn't listen: it always connects to the producer itself.
+    pub fn for_producer(producer: &WebcamProducer) -> Self {
+        Self {
+            version: "2.0".to_string(),
+            rid: producer.rid,
+            session: producer.session,
+            tcp: WebcamTcp::default(),
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        to_string_with_config(self, &yaserde::ser::Config { perform_indent: false, write_document_declaration: false, indent_string: None }).expect("viewer to serialize")
+    }
+}
+
+//First thing the viewer sends on the TCP connection.
+pub fn get_webcam_auth(producer: &WebcamProducer) -> String {
+    format!("recipientid={}&sessionid={}\r\n\r\n", producer.rid, producer.session)
+}
+
+//Every frame on the webcam TCP connection starts with this, all little endian.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebcamFrameHeader {
+    pub width: u16,
+    pub height: u16,
+    pub payload_size: u32,
+    //Milliseconds, from whenever the producer started its webcam
+    pub timestamp: u32,
+}
+
+impl WebcamFrameHeader {
+
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < WEBCAM_FRAME_HEADER_SIZE
+            || LittleEndian::read_u16(&bytes[0..2]) as usize != WEBCAM_FRAME_HEADER_SIZE
+            || &bytes[12..16] != MIMIC_FOURCC {
+            return None;
+        }
+
+        Some(Self {
+            width: LittleEndian::read_u16(&bytes[2..4]),
+            height: LittleEndian::read_u16(&bytes[4..6]),
+            payload_size: LittleEndian::read_u32(&bytes[8..12]),
+            timestamp: LittleEndian::read_u32(&bytes[20..24]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; WEBCAM_FRAME_HEADER_SIZE] {
+        let mut out = [0u8; WEBCAM_FRAME_HEADER_SIZE];
+        LittleEndian::write_u16(&mut out[0..2], WEBCAM_FRAME_HEADER_SIZE as u16);
+        LittleEndian::write_u16(&mut out[2..4], self.width);
+        LittleEndian::write_u16(&mut out[4..6], self.height);
+        LittleEndian::write_u32(&mut out[8..12], self.payload_size);
+        out[12..16].copy_from_slice(MIMIC_FOURCC);
+        LittleEndian::write_u32(&mut out[20..24], self.timestamp);
+        out
+    }
+
+    pub fn frame_size(&self) -> usize {
+        WEBCAM_FRAME_HEADER_SIZE + self.payload_size as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::p2p::v2::webcam::{WebcamFrameHeader, WebcamMessage, WebcamViewer};
+    use crate::shared::traits::TryFromBytes;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn to_webcam_message(message: &str) -> Vec<u8> {
+        let body: Vec<u8> = format!("{}\0", message).encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        let mut out = vec![0x80, 0x12, 0x34, 0x03, 0x08, 0x00];
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn syn_round_trip() {
+        let bytes = WebcamMessage::Syn.to_bytes(42);
+        assert_eq!(bytes.len(), 10 + 8);
+        assert!(matches!(WebcamMessage::try_from_bytes(bytes).unwrap(), WebcamMessage::Syn));
+    }
+
+    #[test]
+    fn deserialize_producer() {
+        let producer = "<producer><version>2.0</version><rid>54</rid><session>23422</session><ctypes>0</ctypes><cpu>2010</cpu><tcp><tcpport>6891</tcpport><tcplocalport>6891</tcplocalport><tcpexternalport>0</tcpexternalport><tcpipaddress1>192.168.0.2</tcpipaddress1><tcpipaddress2>127.0.0.1</tcpipaddress2></tcp><codec></codec><channelmode>1</channelmode></producer>";
+
+        let WebcamMessage::Producer(producer) = WebcamMessage::try_from_bytes(to_webcam_message(producer)).unwrap() else {
+            panic!("Expected a producer");
+        };
+
+        assert_eq!(producer.rid, 54);
+        assert_eq!(producer.session, 23422);
+        assert_eq!(producer.tcp.port, 6891);
+        assert_eq!(producer.tcp.addresses(), vec![IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2)), IpAddr::V4(Ipv4Addr::LOCALHOST)]);
+
+        let viewer = WebcamViewer::for_producer(&producer);
+        let WebcamMessage::Viewer(viewer) = WebcamMessage::try_from_bytes(WebcamMessage::Viewer(viewer).to_bytes(1)).unwrap() else {
+            panic!("Expected a viewer");
+        };
+        assert_eq!(viewer.rid, 54);
+        assert_eq!(viewer.session, 23422);
+    }
+
+    #[test]
+    fn frame_header_round_trip() {
+        let header = WebcamFrameHeader { width: 320, height: 240, payload_size: 1234, timestamp: 5000 };
+        let bytes = header.to_bytes();
+
+        assert_eq!(&bytes[12..16], b"ML20");
+        assert_eq!(WebcamFrameHeader::parse(&bytes), Some(header));
+        assert_eq!(WebcamFrameHeader::parse(b"connected\r\n\r\n"), None);
+    }
+}
@@ -2,6 +2,8 @@ use crate::msnp::error::CommandError;
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::traits::{IntoBytes, TryFromRawCommand};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use anyhow::anyhow;
 use num_derive::{FromPrimitive, ToPrimitive};
 use strum_macros::Display;
 
@@ -35,12 +37,21 @@ impl TryFromRawCommand for ErrCommand {
     type Err = CommandError;
 
     fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+        let mut split = raw.command_split;
+
+        let raw_error_code = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "error_code".into(), 0))?;
+        let msnp_error: MsnpError = num::FromPrimitive::from_u16(u16::from_str(&raw_error_code)?)
+                                        .ok_or(CommandError::ArgumentParseError { argument: raw_error_code.to_string(), command: raw.command.clone(), source: anyhow!("Couldn't parse int to MsnpError") })?;
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        Ok(Self { tr_id, msnp_error })
     }
 
 }
 #[repr(u16)]
-#[derive(Display, Clone, FromPrimitive, ToPrimitive)]
+#[derive(Display, Debug, Clone, PartialEq, FromPrimitive, ToPrimitive)]
 pub enum MsnpError {
     InvalidSyntax = 200,
     InvalidParameter = 201,
@@ -113,4 +124,26 @@ pub enum MsnpError {
     PassportAccountNotYetVerified = 924,
     BadTicket = 928,
     AccountNotOnThisServer = 931
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::shared::command::err::{ErrCommand, MsnpError};
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+
+    #[test]
+    fn err_des_round_trip() {
+        let err = ErrCommand::try_from_raw(RawCommand::from_str("911 3").unwrap()).unwrap();
+        assert_eq!(err.tr_id, 3);
+        assert_eq!(err.msnp_error, MsnpError::ServerIsBusy2);
+        assert_eq!("911 3\r\n", String::from_utf8(err.into_bytes()).unwrap());
+    }
+
+    #[test]
+    fn err_des_unknown_code() {
+        assert!(ErrCommand::try_from_raw(RawCommand::from_str("999 3").unwrap()).is_err());
+    }
+}
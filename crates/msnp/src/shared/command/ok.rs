@@ -1,4 +1,7 @@
 use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::anyhow;
 
 use crate::msnp::error::CommandError;
 use crate::msnp::raw_command_parser::RawCommand;
@@ -18,8 +21,18 @@ impl Display for OkCommand {
 impl TryFromRawCommand for OkCommand {
     type Err = CommandError;
 
-    fn try_from_raw(_raw: RawCommand) -> Result<Self, Self::Err> {
-        todo!()
+    fn try_from_raw(raw: RawCommand) -> Result<Self, Self::Err> {
+        let mut split = raw.command_split;
+        let operand = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "operand".into(), 0))?;
+
+        let raw_tr_id = split.pop_front().ok_or(CommandError::MissingArgument(raw.command.clone(), "tr_id".into(), 1))?;
+        let tr_id = u128::from_str(&raw_tr_id)?;
+
+        match split.pop_front() {
+            Some(ok) if ok == "OK" => Ok(Self { operand, tr_id }),
+            Some(other) => Err(CommandError::ArgumentParseError { argument: other, command: raw.command, source: anyhow!("Expected OK") }),
+            None => Err(CommandError::MissingArgument(raw.command.clone(), "OK".into(), 2))
+        }
     }
 
 }
@@ -29,4 +42,20 @@ impl IntoBytes for OkCommand {
     fn into_bytes(self) -> Vec<u8> {
         self.to_string().into_bytes()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::msnp::raw_command_parser::RawCommand;
+    use crate::shared::command::ok::OkCommand;
+    use crate::shared::traits::{IntoBytes, TryFromRawCommand};
+
+    #[test]
+    fn ok_des_round_trip() {
+        let ok = OkCommand::try_from_raw(RawCommand::from_str("ADL 4 OK").unwrap()).unwrap();
+        assert_eq!(ok.operand, "ADL");
+        assert_eq!(ok.tr_id, 4);
+        assert_eq!("ADL 4 OK\r\n", String::from_utf8(ok.into_bytes()).unwrap());
+    }
+}
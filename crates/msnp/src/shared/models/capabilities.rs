@@ -3,6 +3,7 @@ use std::num::ParseIntError;
 use anyhow::anyhow;
 
 use crate::msnp::error::PayloadError;
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
 
 
 /** source: https://wiki.nina.chat/wiki/Protocols/MSNP/MSNC/Client_Capabilities */
@@ -176,6 +177,19 @@ impl ClientCapabilities {
         return ClientCapabilities{ capabilities, extended_capabilities };
     }
 
+    pub fn get_capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    //MSNP15 only knows the base capabilities, the extended ones came with MSNP16
+    pub fn to_versioned_string(&self, version: MsnpVersion) -> String {
+        if version.supports_endpoints() {
+            self.to_string()
+        } else {
+            self.capabilities.to_string()
+        }
+    }
+
     pub fn supports(&self, capability: Capabilities) -> bool {
        let cap_as_int = capability as u32;
        let and = self.capabilities & cap_as_int;
@@ -246,7 +246,7 @@ impl MsnObject {
         return Self{ creator, size, obj_type, location, friendly, sha1d, contenttype, contentid: None, partnerid: None, stamp: None, avatarid: None, avatarcontentid: None, sha1c: String::default(), compute_sha1c };
     }
 
-    fn get_sha1c(&self) -> String {
+    pub fn get_sha1c(&self) -> String {
         if !self.sha1c.is_empty() {
             return self.sha1c.clone();
         }
@@ -438,7 +438,8 @@ pub enum MsnObjectType {
     PluginState=12,
     RoamingObject=13,
     SignatureSound=14,
-    UnknownYet=15,
+    //Photos shared in a Photo Sharing session, both the thumbnail and the full picture.
+    SharedPhoto=15,
     Scene=16,
     WebcamDynamicDisplayPicture=17
 }
@@ -478,7 +479,7 @@ impl TryFrom<i32> for MsnObjectType {
             x if x == MsnObjectType::PluginState as i32 => Ok(MsnObjectType::PluginState),
             x if x == MsnObjectType::RoamingObject as i32 => Ok(MsnObjectType::RoamingObject),
             x if x == MsnObjectType::SignatureSound as i32 => Ok(MsnObjectType::SignatureSound),
-            x if x == MsnObjectType::UnknownYet as i32 => Ok(MsnObjectType::UnknownYet),
+            x if x == MsnObjectType::SharedPhoto as i32 => Ok(MsnObjectType::SharedPhoto),
             x if x == MsnObjectType::Scene as i32 => Ok(MsnObjectType::Scene),
             x if x == MsnObjectType::WebcamDynamicDisplayPicture as i32 => Ok(MsnObjectType::WebcamDynamicDisplayPicture),
             _ => {
@@ -497,9 +498,13 @@ impl MSNObjectFactory {
         return MsnObject::new(creator_msn_addr.to_string(), MsnObjectType::DisplayPicture, location, sha1d, image.len(), friendly, Some(MsnObjectContentType::D), false);
     }
 
-    //FIXME: this is an experiment to see if SHA1 is required or not.
-    pub fn get_display_picture_no_bytes(length: usize, creator_msn_addr: &EmailAddress, location: String, friendly: FriendlyName) -> MsnObject {
-        return MsnObject::new(creator_msn_addr.to_string(), MsnObjectType::DisplayPicture, location, String::new(), length, friendly, Some(MsnObjectContentType::D), false);
+    //For display pictures whose SHA1D was computed earlier, so we don't need the bytes at hand.
+    pub fn get_display_picture_with_sha1d(sha1d: String, length: usize, creator_msn_addr: &EmailAddress, location: String, friendly: FriendlyName) -> MsnObject {
+        return MsnObject::new(creator_msn_addr.to_string(), MsnObjectType::DisplayPicture, location, sha1d, length, friendly, Some(MsnObjectContentType::D), false);
+    }
+
+    pub fn get_from_sha1d(obj_type: MsnObjectType, sha1d: String, length: usize, creator_msn_addr: String, location: String, friendly: FriendlyName) -> MsnObject {
+        return MsnObject::new(creator_msn_addr, obj_type, location, sha1d, length, friendly, None, false);
     }
 
     pub fn get_me_display_picture(image: &[u8], creator_msn_addr: String, friendly: FriendlyName) -> MsnObject {
@@ -512,6 +517,16 @@ impl MSNObjectFactory {
         return MsnObject::new(creator_msn_addr, MsnObjectType::VoiceClip,"0".into(), sha1d, data.len(),  friendly, None, false);
     }
 
+    pub fn get_shared_photo(image: &[u8], creator_msn_addr: String, friendly: FriendlyName) -> MsnObject {
+        let sha1d = compute_sha1(&image);
+        return MsnObject::new(creator_msn_addr, MsnObjectType::SharedPhoto, "0".into(), sha1d, image.len(), friendly, None, false);
+    }
+
+    pub fn get_custom_emoticon(image: &[u8], creator_msn_addr: String, friendly: FriendlyName) -> MsnObject {
+        let sha1d = compute_sha1(&image);
+        return MsnObject::new(creator_msn_addr, MsnObjectType::CustomEmoticon, "0".into(), sha1d, image.len(), friendly, None, false);
+    }
+
 }
 
 
@@ -1,3 +1,10 @@
+use std::str::FromStr;
+use anyhow::anyhow;
+use crate::msnp::error::PayloadError;
+use crate::shared::models::msn_object::MsnObject;
+use crate::shared::payload::msg::raw_msg_payload::{MsgContentType, RawMsgPayload};
+use crate::shared::traits::{IntoBytes, IntoRawMsgPayload, TryFromRawMsgPayload};
+
 /*
  SB << | MSG 130 N 355MIME-Version: 1.0
 Content-Type: text/x-mms-emoticon
@@ -5,3 +12,94 @@ Content-Type: text/x-mms-emoticon
 %anim0% <msnobj Creator="aeonshl@shlasouf.local" Type="2" SHA1D="DYdzOExnKssGVbtdeUblmmY5De8=" Size="763" Location="0" Friendly="YQBuAGkAbQAAAA=="/>    %anim1% <msnobj Creator="aeonshl@shlasouf.local" Type="2" SHA1D="gxg8johAlBZPTp4qHLz+eQua1wc=" Size="1781" Location="0" Friendly="YQBuAGkAbQAAAA=="/>
  */
 
+//Announces the custom emoticons used in the next text message: shortcut & msnobj, tab separated.
+//The client then fetches each one it doesn't have cached over P2P.
+pub struct EmoticonMsgPayload {
+    pub emoticons: Vec<(String, MsnObject)>
+}
+
+impl EmoticonMsgPayload {
+    pub fn new(shortcut: String, msn_object: MsnObject) -> Self {
+        Self {
+            emoticons: vec![(shortcut, msn_object)],
+        }
+    }
+}
+
+impl TryFromRawMsgPayload for EmoticonMsgPayload {
+    type Err = PayloadError;
+
+    fn try_from_raw(raw_msg_payload: RawMsgPayload) -> Result<Self, Self::Err>
+    where
+        Self: Sized
+    {
+        let body = raw_msg_payload.get_body_as_string()?;
+
+        let mut emoticons = Vec::new();
+        let mut parts = body.split('\t').map(|part| part.trim()).filter(|part| !part.is_empty());
+
+        while let Some(shortcut) = parts.next() {
+            let raw_msn_object = parts.next().ok_or(PayloadError::StringPayloadParsingError { payload: body.clone(), source: anyhow!("Emoticon shortcut without msnobj: {}", shortcut) })?;
+            emoticons.push((shortcut.to_string(), MsnObject::from_str(raw_msn_object)?));
+        }
+
+        Ok(Self {
+            emoticons,
+        })
+    }
+}
+
+impl IntoRawMsgPayload for EmoticonMsgPayload {
+    fn into_raw(self) -> RawMsgPayload {
+        let mut out = RawMsgPayload::new(MsgContentType::Emoticon, false);
+
+        let mut body = String::new();
+        for (shortcut, msn_object) in self.emoticons {
+            body.push_str(&format!("{}\t{}\t", shortcut, msn_object.to_string_not_encoded()));
+        }
+
+        out.set_body_string(body);
+        out
+    }
+}
+
+impl IntoBytes for EmoticonMsgPayload {
+    fn into_bytes(self) -> Vec<u8> {
+        self.into_raw().into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shared::models::msn_object::MsnObjectType;
+    use crate::shared::payload::msg::emoticon::EmoticonMsgPayload;
+    use crate::shared::payload::msg::raw_msg_payload::RawMsgPayload;
+    use crate::shared::traits::{IntoRawMsgPayload, TryFromBytes, TryFromRawMsgPayload};
+
+    #[test]
+    fn deserialize_emoticons() {
+        let raw = "MIME-Version: 1.0\r\nContent-Type: text/x-mms-emoticon\r\n\r\n%anim0%\t<msnobj Creator=\"aeonshl@shlasouf.local\" Type=\"2\" SHA1D=\"DYdzOExnKssGVbtdeUblmmY5De8=\" Size=\"763\" Location=\"0\" Friendly=\"YQBuAGkAbQAAAA==\"/>\t%anim1%\t<msnobj Creator=\"aeonshl@shlasouf.local\" Type=\"2\" SHA1D=\"gxg8johAlBZPTp4qHLz+eQua1wc=\" Size=\"1781\" Location=\"0\" Friendly=\"YQBuAGkAbQAAAA==\"/>\t";
+        let raw_payload = RawMsgPayload::try_from_bytes(raw.as_bytes().to_vec()).unwrap();
+
+        let payload = EmoticonMsgPayload::try_from_raw(raw_payload).unwrap();
+
+        assert_eq!(payload.emoticons.len(), 2);
+        assert_eq!(payload.emoticons[0].0, "%anim0%");
+        assert_eq!(payload.emoticons[0].1.obj_type, MsnObjectType::CustomEmoticon);
+        assert_eq!(payload.emoticons[1].0, "%anim1%");
+        assert_eq!(payload.emoticons[1].1.size, 1781);
+    }
+
+    #[test]
+    fn serialize_deserialize_emoticons() {
+        let raw = "MIME-Version: 1.0\r\nContent-Type: text/x-mms-emoticon\r\n\r\n%anim0%\t<msnobj Creator=\"aeonshl@shlasouf.local\" Type=\"2\" SHA1D=\"DYdzOExnKssGVbtdeUblmmY5De8=\" Size=\"763\" Location=\"0\" Friendly=\"YQBuAGkAbQAAAA==\"/>\t";
+        let payload = EmoticonMsgPayload::try_from_raw(RawMsgPayload::try_from_bytes(raw.as_bytes().to_vec()).unwrap()).unwrap();
+
+        let serialized = payload.into_raw();
+        let deserialized = EmoticonMsgPayload::try_from_raw(serialized).unwrap();
+
+        assert_eq!(deserialized.emoticons.len(), 1);
+        assert_eq!(deserialized.emoticons[0].0, "%anim0%");
+        assert_eq!(deserialized.emoticons[0].1.sha1d, "DYdzOExnKssGVbtdeUblmmY5De8=");
+    }
+}
@@ -7,4 +7,4 @@ pub mod chunked_msg_payload;
 pub mod control_msg;
 pub mod ink_msg;
 pub mod gif_msg;
-mod emoticon;
+pub mod emoticon;
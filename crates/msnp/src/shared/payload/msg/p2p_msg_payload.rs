@@ -1,5 +1,5 @@
 use crate::msnp::error::PayloadError;
-use crate::p2p::v2::p2p_transport_packet::P2PTransportPacket;
+use crate::p2p::transport_packet::{P2PVersion, TransportPacket};
 use crate::shared::models::endpoint_id::EndpointId;
 use crate::shared::payload::msg::raw_msg_payload::{MsgContentType, RawMsgPayload};
 use crate::shared::traits::{IntoBytes, IntoRawMsgPayload, TryFromRawMsgPayload};
@@ -9,7 +9,7 @@ use std::str::FromStr;
 pub struct P2PMessagePayload {
     pub sender: EndpointId,
     pub receiver: EndpointId,
-    pub payload: P2PTransportPacket,
+    pub payload: TransportPacket,
     pub sender_display_name: Option<String>,
 }
 
@@ -17,13 +17,13 @@ impl P2PMessagePayload {
     pub fn new(
         sender: EndpointId,
         receiver: EndpointId,
-        payload: P2PTransportPacket,
+        payload: impl Into<TransportPacket>,
         sender_display_name: Option<String>,
     ) -> Self {
         Self {
             sender,
             receiver,
-            payload,
+            payload: payload.into(),
             sender_display_name,
         }
     }
@@ -80,7 +80,7 @@ impl TryFromRawMsgPayload for P2PMessagePayload {
 
         let display_name = raw.headers.remove("P4-Context");
 
-        let payload = P2PTransportPacket::try_from(raw.body.iter().as_slice())?;
+        let payload = TransportPacket::try_from_bytes(raw.body.iter().as_slice(), P2PVersion::detect(&raw.body))?;
 
         Ok(P2PMessagePayload {
             sender: src,
@@ -61,6 +61,9 @@ pub enum MsgContentType {
     #[strum(serialize = "application/x-ms-ink", ascii_case_insensitive)]
     Ink,
 
+    #[strum(serialize = "text/x-mms-emoticon", ascii_case_insensitive)]
+    Emoticon,
+
     None,
 }
 
@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use crate::msnp::notification::models::msnp_version::MsnpVersion;
 use crate::msnp::raw_command_parser::RawCommand;
 use crate::shared::payload::msg::raw_msg_payload::RawMsgPayload;
 
@@ -37,3 +38,8 @@ pub trait TryFromSplit {
 pub trait IntoBytes {
     fn into_bytes(self) -> Vec<u8>;
 }
+
+//For server commands whose wire format depends on the negotiated protocol version
+pub trait IntoVersionedBytes {
+    fn into_versioned_bytes(self, version: MsnpVersion) -> Vec<u8>;
+}
@@ -4,6 +4,9 @@ pub mod request {
     mod tests {
         use yaserde::de::from_str;
 
+        use crate::soap::traits::xml::{ToXml, TryFromXml};
+
+        use super::factory::RST2RequestFactory;
         use super::RST2RequestMessageSoapEnvelope;
 
         #[test]
@@ -18,12 +21,63 @@ pub mod request {
             //TODO add assertions
             let test3 = 1;
         }  
+
+        #[test]
+        fn test_rst2_request_factory() {
+            let request = RST2RequestFactory::get_rst2_request("test@homeserver.org", "passwd");
+            let parsed = RST2RequestMessageSoapEnvelope::try_from_xml(&request.to_xml().unwrap()).unwrap();
+
+            let creds = parsed.header.security.username_token.unwrap();
+            assert_eq!(creds.username, "test@homeserver.org");
+            assert_eq!(creds.password, "passwd");
+            assert_eq!(parsed.body.request_multiple_security_tokens.request_security_tokens[1].applies_to.endpoint_reference.address, "messengerclear.live.com");
+        }
     }
 
 
+    pub mod factory {
+        use chrono::{Local, TimeDelta};
+
+        use crate::soap::passport::rst2::shared::{AppliesTo, EndpointReference, SecurityHeader, Timestamp, UsernameToken};
+
+        use super::{RST2RequestMessageBody, RST2RequestMessageHeader, RST2RequestMessageSoapEnvelope, RequestMultipleSecurityTokens, RequestSecurityToken};
+
+        pub struct RST2RequestFactory;
+
+        impl RST2RequestFactory {
+
+            //What a client sends to sign in, only the tokens we hand out are requested.
+            pub fn get_rst2_request(username: &str, password: &str) -> RST2RequestMessageSoapEnvelope {
+                let now = Local::now();
+                let expires = now + TimeDelta::minutes(5);
+
+                let security = SecurityHeader {
+                    must_understand: None,
+                    username_token: Some(UsernameToken { username: username.to_string(), password: password.to_string() }),
+                    timestamp: Timestamp::new(String::from("Timestamp"), now.format("%Y-%m-%dT%H:%M:%SZ").to_string(), expires.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+                };
+
+                let addresses = ["http://Passport.NET/tb", "messengerclear.live.com", "messenger.msn.com", "messengersecure.live.com", "contacts.msn.com", "storage.msn.com", "sup.live.com"];
+
+                let request_security_tokens = addresses.iter().enumerate().map(|(index, address)| RequestSecurityToken {
+                    id: format!("RST{}", index),
+                    request_type: String::from("http://schemas.xmlsoap.org/ws/2005/02/trust/Issue"),
+                    applies_to: AppliesTo { endpoint_reference: EndpointReference { address: address.to_string() } },
+                }).collect();
+
+                RST2RequestMessageSoapEnvelope {
+                    header: RST2RequestMessageHeader { security },
+                    body: RST2RequestMessageBody {
+                        request_multiple_security_tokens: RequestMultipleSecurityTokens { id: String::from("RSTS"), request_security_tokens },
+                    },
+                }
+            }
+        }
+    }
+
     use yaserde_derive::{YaDeserialize, YaSerialize};
 
-    use crate::soap::{error::SoapMarshallError, traits::xml::TryFromXml};
+    use crate::soap::{error::SoapMarshallError, traits::xml::{ToXml, TryFromXml}};
 
     use super::shared::{AppliesTo, SecurityHeader};
 
@@ -48,6 +102,14 @@ pub mod request {
         pub body: RST2RequestMessageBody,
     }
 
+    impl ToXml for RST2RequestMessageSoapEnvelope {
+        type Error = SoapMarshallError;
+
+        fn to_xml(&self) -> Result<String, Self::Error> {
+            yaserde::ser::to_string(self).map_err(|e| Self::Error::SerializationError { message: e})
+        }
+    }
+
     impl TryFromXml for RST2RequestMessageSoapEnvelope {
 
         type Error = SoapMarshallError;
@@ -60,7 +122,7 @@ pub mod request {
 
     #[derive(Debug, Default, YaSerialize, YaDeserialize)]
     #[yaserde(
-        namespace = "wsse: http://schemas.xmlsoap.org/ws/2004/09/policy",
+        namespace = "wsse: http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd",
         prefix = "wsse"
     )]
 
@@ -351,6 +413,17 @@ pub mod response {
                 OUT.replace("{{server_time}}", &server_time)
             }
 
+            //The client shows the fault text, hosted servers can't open a browser on the user's machine.
+            pub fn get_web_login_required_response(web_login_url: &str) -> String {
+                //TODO Use the ps-fault xsd
+                let now = Local::now();
+                let server_time = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+                const OUT: &str = "<?xml version=\"1.0\" encoding=\"utf-8\" ?><S:Envelope xmlns:S=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:wsse=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd\" xmlns:wsu=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd\" xmlns:wst=\"http://schemas.xmlsoap.org/ws/2005/02/trust\" xmlns:psf=\"http://schemas.microsoft.com/Passport/SoapServices/SOAPFault\"><S:Header><psf:pp xmlns:psf=\"http://schemas.microsoft.com/Passport/SoapServices/SOAPFault\"><psf:serverVersion>1</psf:serverVersion><psf:authstate>0x80048800</psf:authstate><psf:reqstatus>0x80048821</psf:reqstatus><psf:serverInfo Path=\"Live1\" RollingUpgradeState=\"ExclusiveNew\" LocVersion=\"0\" ServerTime=\"{{server_time}}\" BuildVersion=\"16.0.28426.6\">XYZPPLOGN1A23 2017.09.28.12.44.07</psf:serverInfo><psf:cookies/><psf:response/></psf:pp></S:Header><S:Body><S:Fault><S:Code><S:Value>S:Sender</S:Value><S:Subcode><S:Value>wst:FailedAuthentication</S:Value></S:Subcode></S:Code><S:Reason><S:Text xml:lang=\"en-US\">Web login required: {{web_login_url}}</S:Text></S:Reason><S:Detail><psf:error><psf:value>0x80048821</psf:value><psf:internalerror><psf:code>0x80041012</psf:code><psf:text>Complete the login at {{web_login_url}}&#x000D;&#x000A;</psf:text></psf:internalerror></psf:error></S:Detail></S:Fault></S:Body></S:Envelope>";
+                OUT.replace("{{server_time}}", &server_time)
+                    .replace("{{web_login_url}}", &web_login_url.replace('&', "&amp;"))
+            }
+
             pub fn get_bad_request() -> String {
                 //TODO Use the ps-fault xsd
                 const OUT: &str = "<?xml version=\"1.0\" encoding=\"utf-8\" ?><S:Envelope xmlns:S=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:wsse=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd\" xmlns:wsu=\"http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd\" xmlns:wst=\"http://schemas.xmlsoap.org/ws/2005/02/trust\" xmlns:psf=\"http://schemas.microsoft.com/Passport/SoapServices/SOAPFault\"><S:Body><S:Fault><S:Code><S:Value>S:Sender</S:Value><S:Subcode><S:Value>wst:InvalidRequest</S:Value></S:Subcode></S:Code><S:Reason><S:Text xml:lang=\"en-US\">Invalid Request</S:Text></S:Reason><S:Detail><psf:error><psf:value>0x80048820</psf:value><psf:internalerror><psf:code>0x80045c01</psf:code><psf:text>Invalid STS request.&#x000D;&#x000A;</psf:text></psf:internalerror></psf:error></S:Detail></S:Fault></S:Body></S:Envelope>";
@@ -375,6 +448,22 @@ pub mod response {
             println!("{}", to_string(&test).unwrap());
         }
 
+        #[test]
+        fn test_web_login_required_response() {
+            let response = RST2ResponseFactory::get_web_login_required_response("https://tachyon.example/tachyon/auth?username=aeon@test.com&x=1");
+            assert!(response.contains("Web login required: https://tachyon.example/tachyon/auth?username=aeon@test.com&amp;x=1"));
+            assert!(!response.contains("{{"));
+        }
+
+        #[test]
+        fn test_ticket_token_from_response() {
+            let response = RST2ResponseFactory::get_rst2_success_response(TicketToken("t0k3n".to_string()),"aeon@test.com".to_string(), Uuid::new());
+            let parsed = RST2ResponseMessageSoapEnvelope::try_from_xml(&response.to_xml().unwrap()).unwrap();
+
+            assert_eq!(parsed.get_ticket_token("messengerclear.live.com"), Some(TicketToken("t0k3n".to_string())));
+            assert_eq!(parsed.get_ticket_token("http://Passport.NET/tb"), None);
+        }
+
         #[test]
         fn test_rst2_response() {
             let action = ActionHeader::new(String::from("Action"), 1, String::from("http://schemas.xmlsoap.org/ws/2005/02/trust/RSTR/Issue"));
@@ -413,10 +502,13 @@ pub mod response {
 
     }
 
+    use std::str::FromStr;
+
     use yaserde::ser::to_string;
     use yaserde_derive::{YaDeserialize, YaSerialize};
+    use crate::shared::models::ticket_token::TicketToken;
     use crate::soap::error::SoapMarshallError;
-    use crate::soap::traits::xml::ToXml;
+    use crate::soap::traits::xml::{ToXml, TryFromXml};
 
     use super::shared::{AppliesTo, Reference, SecurityHeader};
 
@@ -446,6 +538,25 @@ pub mod response {
         }
     }
 
+    impl TryFromXml for RST2ResponseMessageSoapEnvelope {
+        type Error = SoapMarshallError;
+
+        fn try_from_xml(xml_str: &str) -> Result<Self, Self::Error> {
+            yaserde::de::from_str::<Self>(xml_str).map_err(|e| SoapMarshallError::DeserializationError { message: e})
+        }
+    }
+
+    impl RST2ResponseMessageSoapEnvelope {
+
+        //The compact ticket issued for a service, messengerclear.live.com is the one USR SSO S expects.
+        pub fn get_ticket_token(&self, address: &str) -> Option<TicketToken> {
+            self.body.request_security_token_response_collection.request_security_token_response.iter()
+                .find(|response| response.applies_to.endpoint_reference.address == address)
+                .and_then(|response| response.requested_security_token.binary_security_token.as_ref())
+                .and_then(|token| TicketToken::from_str(&token.token).ok())
+        }
+    }
+
 
     #[derive(Debug, Default, YaSerialize, YaDeserialize)]
     pub struct RST2ResponseMessageHeader {
@@ -790,7 +901,9 @@ pub mod response {
         request_security_token_response: Vec<RequestSecurityTokenResponse>,
     }
 
+    //Deserializing checks the namespace of text fields against the ones declared here.
     #[derive(Debug, Default, YaSerialize, YaDeserialize)]
+    #[yaserde(namespace = "wst: http://schemas.xmlsoap.org/ws/2005/02/trust")]
     pub struct RequestSecurityTokenResponse {
         #[yaserde(rename = "TokenType", prefix = "wst")]
         token_type: String,
@@ -845,12 +958,14 @@ pub mod response {
     }
 
     #[derive(Debug, Default, YaSerialize, YaDeserialize)]
+    #[yaserde(namespace = "wst: http://schemas.xmlsoap.org/ws/2005/02/trust")]
     pub struct RequestedProofToken {
         #[yaserde(rename = "BinarySecret", prefix = "wst")]
         binary_secret: String,
     }
 
     #[derive(Debug, Default, YaSerialize, YaDeserialize)]
+    #[yaserde(namespace = "wsu: http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd")]
     pub struct Lifetime {
         #[yaserde(rename = "Created", prefix = "wsu")]
         created: String,
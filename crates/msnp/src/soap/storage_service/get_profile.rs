@@ -140,6 +140,21 @@ pub mod response {
     use crate::soap::storage_service::msnstorage_datatypes::{DocumentBaseType, DocumentStream, DocumentStreams};
     use crate::soap::traits::xml::ToXml;
 
+    #[cfg(test)]
+    mod tests {
+        use crate::shared::models::uuid::Uuid;
+        use crate::soap::storage_service::get_profile::response::GetProfileResponseMessageSoapEnvelope;
+
+        #[test]
+        fn usertile_urls_use_the_given_base_url() {
+            let response = GetProfileResponseMessageSoapEnvelope::new(Uuid::new(), "key".into(), "aeon".into(), String::new(), Some("tile".into()), "https://10.0.0.2:8080/storage/usertile", Some("t=ticket".into()));
+
+            let profile = response.body.body.get_profile_result.expression_profile;
+            assert_eq!(profile.static_user_tile_public_url, "https://10.0.0.2:8080/storage/usertile/tile/static?t=ticket");
+            assert_eq!(profile.photo.document_streams.document_stream[1].pre_auth_url.as_deref(), Some("https://10.0.0.2:8080/storage/usertile/tile/small?t=ticket"));
+        }
+    }
+
     #[derive(Debug, Default, YaSerialize, YaDeserialize)]
     pub struct SoapGetProfileResponseMessage {
         #[yaserde(rename = "GetProfileResponse", default)]
@@ -247,7 +262,9 @@ pub mod response {
 
 
     impl GetProfileResponseMessageSoapEnvelope {
-        pub fn new(uuid: Uuid, cache_key: String, display_name: String, psm: String, image_name: Option<String>) -> GetProfileResponseMessageSoapEnvelope {
+        //usertile_url is the scheme, host and port the tiles are served from, up to the /storage/usertile route.
+        //pre_auth_query is appended to the user tile urls, the client fetches them without any other credentials.
+        pub fn new(uuid: Uuid, cache_key: String, display_name: String, psm: String, image_name: Option<String>, usertile_url: &str, pre_auth_query: Option<String>) -> GetProfileResponseMessageSoapEnvelope {
 
 
             let now = Local::now();
@@ -260,13 +277,14 @@ pub mod response {
 
             let mut static_user_tile_public_url = String::new();
             if let Some(found_image_id) = image_name {
-                let user_tile_static = DocumentStream{ document_stream_name: Some(String::from("UserTileStatic")), mime_type: Some(String::from("image/jpeg")), data: None, data_size: 0, pre_auth_url: Some(format!("http://127.0.0.1/storage/usertile/{}/static", &found_image_id)), pre_auth_url_partner: None, document_stream_type: String::from("UserTileStatic"), write_mode: Some(String::from("Overwrite")), stream_version: Some(0), sha1_hash: None, genie: Some(false), stream_data_status: Some(String::from("None")), stream_status: Some(String::from("None")), is_alias_for_default:Some(false), expiration_date_time: Some(String::from("0001-01-01T00:00:00")) };
+                let query = pre_auth_query.map(|query| format!("?{}", query)).unwrap_or_default();
+                let user_tile_static = DocumentStream{ document_stream_name: Some(String::from("UserTileStatic")), mime_type: Some(String::from("image/jpeg")), data: None, data_size: 0, pre_auth_url: Some(format!("{}/{}/static{}", usertile_url, &found_image_id, &query)), pre_auth_url_partner: None, document_stream_type: String::from("UserTileStatic"), write_mode: Some(String::from("Overwrite")), stream_version: Some(0), sha1_hash: None, genie: Some(false), stream_data_status: Some(String::from("None")), stream_status: Some(String::from("None")), is_alias_for_default:Some(false), expiration_date_time: Some(String::from("0001-01-01T00:00:00")) };
                 document_stream_array.push(user_tile_static);
 
-                let user_tile_small = DocumentStream{ document_stream_name: Some(String::from("UserTileSmall")), mime_type: None, data: None, data_size: 0, pre_auth_url: Some(format!("http://127.0.0.1/storage/usertile/{}/small", &found_image_id)), pre_auth_url_partner: None, document_stream_type: String::from("Named"), write_mode: Some(String::from("Overwrite")), stream_version: Some(0), sha1_hash: None, genie: Some(false), stream_data_status: Some(String::from("None")), stream_status: Some(String::from("None")), is_alias_for_default:Some(false), expiration_date_time: Some(String::from("0001-01-01T00:00:00")) };
+                let user_tile_small = DocumentStream{ document_stream_name: Some(String::from("UserTileSmall")), mime_type: None, data: None, data_size: 0, pre_auth_url: Some(format!("{}/{}/small{}", usertile_url, &found_image_id, &query)), pre_auth_url_partner: None, document_stream_type: String::from("Named"), write_mode: Some(String::from("Overwrite")), stream_version: Some(0), sha1_hash: None, genie: Some(false), stream_data_status: Some(String::from("None")), stream_status: Some(String::from("None")), is_alias_for_default:Some(false), expiration_date_time: Some(String::from("0001-01-01T00:00:00")) };
                 document_stream_array.push(user_tile_small);
 
-                static_user_tile_public_url = format!("http://127.0.0.1/storage/usertile/{}/static", &found_image_id);
+                static_user_tile_public_url = format!("{}/{}/static{}", usertile_url, &found_image_id, &query);
             }
 
 
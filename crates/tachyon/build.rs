@@ -5,6 +5,7 @@ fn main() {
         "rmlt.c",
         "dct4.c",
         "encoder.c",
+        "decoder.c",
         "huffman.c",
     ];
 
@@ -1,8 +1,28 @@
+//A fallback for audio the in-process pipeline can't handle, and what turns webcam recordings into videos.
+//ffmpeg doesn't have to be installed, webcam invites are declined without it.
+
 use crate::audio::AudioConversionError;
 use std::process::Stdio;
 use std::str::from_utf8;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+static FFMPEG_AVAILABLE: OnceCell<bool> = OnceCell::const_new();
+
+/// Whether ffmpeg can be spawned, only checked once per run.
+pub async fn is_available() -> bool {
+    *FFMPEG_AVAILABLE.get_or_init(|| async {
+        Command::new("ffmpeg")
+            .arg("-version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await
+            .is_ok_and(|status| status.success())
+    }).await
+}
 
 /// Decodes arbitrary audio into the mono 16kHz PCM s16le libsiren expects.
 pub async fn decode_to_siren_pcm(audio: Vec<u8>) -> Result<Vec<u8>, AudioConversionError> {
@@ -20,8 +40,6 @@ pub async fn decode_to_siren_pcm(audio: Vec<u8>) -> Result<Vec<u8>, AudioConvers
     .await
 }
 
-//TODO replace this with compiled FFMPEG lib to remove dependency from the PATH
-
 /// Decodes a Siren7 WAV voice clip and re-encodes it as Ogg Opus.
 pub async fn encode_siren_to_opus(voice_clip: Vec<u8>) -> Result<Vec<u8>, AudioConversionError> {
     run(
@@ -40,6 +58,24 @@ pub async fn encode_siren_to_opus(voice_clip: Vec<u8>) -> Result<Vec<u8>, AudioC
     .await
 }
 
+/// Transcodes the Mimic frames of a webcam session, as they came on the wire, into an MP4 Matrix clients can play.
+pub async fn transcode_webcam_to_mp4(recording: Vec<u8>) -> Result<Vec<u8>, AudioConversionError> {
+    run(
+        &[
+            "-f", "msnwc_tcp",
+            "-i", "pipe:0",
+            "-c:v", "libx264",
+            "-pix_fmt", "yuv420p",
+            //The MP4 can't be seeked back into when written to a pipe, so the index has to come first.
+            "-movflags", "frag_keyframe+empty_moov",
+            "-f", "mp4",
+            "pipe:1",
+        ],
+        recording,
+    )
+    .await
+}
+
 pub(super) async fn run(args: &[&str], input: Vec<u8>) -> Result<Vec<u8>, AudioConversionError> {
     let mut child = Command::new("ffmpeg")
         .stdin(Stdio::piped())
@@ -1,7 +1,12 @@
 pub mod ffmpeg;
+pub mod ogg_opus;
+pub mod resampler;
 pub mod siren;
+pub mod wav;
 
 use crate::audio::siren::{SIREN_ENCODED_FRAME_SIZE, SIREN_WAV_HEADER_SIZE};
+use crate::audio::wav::WAVE_FORMAT_PCM;
+use log::warn;
 use std::time::Duration;
 use thiserror::Error;
 
@@ -11,6 +16,8 @@ pub const MAX_VOICE_CLIP_SIZE: usize = 30_000;
 /// Every Siren7 frame carries 320 samples at 16kHz.
 const SIREN_FRAME_DURATION_MS: u64 = 20;
 
+const SIREN_SAMPLE_RATE: u32 = 16000;
+
 #[derive(Error, Debug)]
 pub enum AudioConversionError {
     #[error("Could not spawn ffmpeg, is it installed and on the PATH?")]
@@ -19,20 +26,77 @@ pub enum AudioConversionError {
     FfmpegFailed { message: String },
     #[error("libsiren could not encode a frame, error code: {code}")]
     SirenEncodingFailed { code: i32 },
+    #[error("libsiren could not decode a frame, error code: {code}")]
+    SirenDecodingFailed { code: i32 },
     #[error("The Siren encoding task did not complete")]
     SirenTaskFailed(#[from] tokio::task::JoinError),
+    #[error("Unsupported audio: {reason}")]
+    UnsupportedFormat { reason: String },
+    #[error("Malformed WAV file: {reason}")]
+    InvalidWav { reason: String },
+    #[error(transparent)]
+    Opus(#[from] opus::Error),
+    #[error(transparent)]
+    Ogg(#[from] ogg::OggReadError),
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
 
+/// Converts a Matrix voice message into a WLM voice clip.
+///
+/// Ogg Opus and PCM WAV are handled in-process. Anything else goes through ffmpeg when it is on the PATH.
 pub async fn to_siren_wav(audio: Vec<u8>) -> Result<Vec<u8>, AudioConversionError> {
-    let pcm = ffmpeg::decode_to_siren_pcm(audio).await?;
-    //siren::encode blocks and serializes with every other encode, so it stays off the runtime's workers.
-    tokio::task::spawn_blocking(move || siren::encode(&pcm)).await?
+    //Decoding, resampling and siren::encode all block, and the Siren codec serializes with every other call,
+    //so it all stays off the runtime's workers.
+    let in_process = tokio::task::spawn_blocking(move || match decode_to_siren_pcm(&audio) {
+        Ok(pcm) => siren::encode(&pcm).map(Ok),
+        Err(err) => Ok(Err((err, audio))),
+    }).await??;
+
+    match in_process {
+        Ok(voice_clip) => Ok(voice_clip),
+        Err((err, audio)) => {
+            warn!("Could not decode voice message in-process, falling back to ffmpeg: {}", err);
+            let pcm = ffmpeg::decode_to_siren_pcm(audio).await?;
+            tokio::task::spawn_blocking(move || siren::encode(&pcm)).await?
+        }
+    }
 }
 
+/// Converts a WLM voice clip into an Ogg Opus voice message.
 pub async fn from_siren_wav_to_opus_ogg(voice_clip: Vec<u8>) -> Result<Vec<u8>, AudioConversionError> {
-    ffmpeg::encode_siren_to_opus(voice_clip).await
+    let in_process = tokio::task::spawn_blocking(move || {
+        let result = siren::decode(&voice_clip)
+            .and_then(|pcm| ogg_opus::encode(&resampler::from_s16le(&pcm)));
+        (result, voice_clip)
+    }).await?;
+
+    match in_process {
+        (Ok(opus), _) => Ok(opus),
+        (Err(err), voice_clip) => {
+            warn!("Could not transcode voice clip in-process, falling back to ffmpeg: {}", err);
+            ffmpeg::encode_siren_to_opus(voice_clip).await
+        }
+    }
+}
+
+/// Decodes the formats we can handle without ffmpeg into the mono 16kHz PCM s16le libsiren expects.
+fn decode_to_siren_pcm(audio: &[u8]) -> Result<Vec<u8>, AudioConversionError> {
+    if ogg_opus::is_ogg(audio) {
+        return Ok(resampler::to_s16le(&ogg_opus::decode(audio)?));
+    }
+
+    if wav::is_wav(audio) {
+        let wav = wav::parse(audio)?;
+        if wav.format != WAVE_FORMAT_PCM || wav.bits_per_sample != 16 || wav.channels == 0 {
+            return Err(AudioConversionError::UnsupportedFormat { reason: format!("WAV format tag {:#06x}, {} bits", wav.format, wav.bits_per_sample) });
+        }
+
+        let mono = resampler::to_mono(&resampler::from_s16le(wav.data), wav.channels);
+        return Ok(resampler::to_s16le(&resampler::resample(&mono, wav.sample_rate, SIREN_SAMPLE_RATE)));
+    }
+
+    Err(AudioConversionError::UnsupportedFormat { reason: "neither Ogg nor WAV".into() })
 }
 
 pub const fn siren_wav_duration(voice_clip_len: usize) -> Duration {
@@ -44,6 +108,24 @@ pub const fn siren_wav_duration(voice_clip_len: usize) -> Duration {
 mod tests {
     use super::*;
 
+    /// Same round trip as below, with no ffmpeg involved.
+    #[tokio::test]
+    async fn voice_clip_round_trip_in_process() {
+        let tone: Vec<i16> = (0..48000 * 3)
+            .map(|index| ((index as f32 * 440.0 * std::f32::consts::TAU / 48000.0).sin() * 8000.0) as i16)
+            .collect();
+        let opus = ogg_opus::encode(&resampler::resample(&tone, 48000, SIREN_SAMPLE_RATE)).unwrap();
+
+        let clip = to_siren_wav(opus).await.unwrap();
+
+        assert_eq!(siren_wav_duration(clip.len()), Duration::from_secs(3));
+        assert!(clip.len() < MAX_VOICE_CLIP_SIZE, "clip was {} bytes", clip.len());
+
+        let opus = from_siren_wav_to_opus_ogg(clip).await.unwrap();
+        let decoded = ogg_opus::decode(&opus).unwrap();
+        assert!(decoded.len().abs_diff(SIREN_SAMPLE_RATE as usize * 3) <= 320, "decoded {} samples", decoded.len());
+    }
+
     /// Round trips a tone through the whole voice clip pipeline. Ignored by default: it needs
     /// ffmpeg on the PATH, built with the msnsiren decoder and libopus.
     /// Run with `cargo test -p tachyon --bin tachyon audio:: -- --ignored`.
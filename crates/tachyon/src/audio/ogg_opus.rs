@@ -0,0 +1,154 @@
+use crate::audio::resampler;
+use crate::audio::AudioConversionError;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use ogg::PacketReader;
+use opus::{Application, Bitrate, Channels};
+use std::io::Cursor;
+
+/// Opus decodes and encodes natively at the Siren rate, libopus does the resampling for us.
+const SAMPLE_RATE: u32 = 16000;
+
+/// 20ms frames, the same duration as a Siren7 frame.
+const FRAME_SAMPLES: usize = 320;
+
+/// The longest frame Opus allows is 120ms, at 48kHz and stereo for safety.
+const MAX_DECODED_SAMPLES: usize = 5760 * 2;
+
+const MAX_PACKET_SIZE: usize = 4000;
+
+const BITRATE: i32 = 16000;
+
+/// Ogg granule positions are always counted at 48kHz, whatever the encoder ran at.
+const GRANULE_RATE_FACTOR: u64 = 48000 / SAMPLE_RATE as u64;
+
+/// Arbitrary, we only ever write a single logical stream.
+const STREAM_SERIAL: u32 = 0x7461_6368;
+
+pub fn is_ogg(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"OggS")
+}
+
+/// Decodes an Ogg Opus file (what Matrix clients record voice messages as) into mono 16kHz PCM.
+pub fn decode(ogg_opus: &[u8]) -> Result<Vec<i16>, AudioConversionError> {
+    let mut reader = PacketReader::new(Cursor::new(ogg_opus));
+
+    let head = reader.read_packet()?.ok_or(AudioConversionError::UnsupportedFormat { reason: "empty Ogg stream".into() })?;
+    if !head.data.starts_with(b"OpusHead") || head.data.len() < 19 {
+        return Err(AudioConversionError::UnsupportedFormat { reason: "Ogg stream is not Opus".into() });
+    }
+
+    let channel_count = head.data[9];
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as usize / GRANULE_RATE_FACTOR as usize;
+    let channels = match channel_count {
+        1 => Channels::Mono,
+        2 => Channels::Stereo,
+        _ => return Err(AudioConversionError::UnsupportedFormat { reason: format!("Opus with {} channels", channel_count) }),
+    };
+
+    let mut decoder = opus::Decoder::new(SAMPLE_RATE, channels)?;
+    let mut frame = vec![0i16; MAX_DECODED_SAMPLES];
+    let mut pcm = Vec::new();
+
+    while let Some(packet) = reader.read_packet()? {
+        //OpusTags, and any other header a muxer felt like adding.
+        if packet.data.starts_with(b"OpusTags") || packet.data.is_empty() {
+            continue;
+        }
+
+        let decoded = decoder.decode(&packet.data, &mut frame, false)?;
+        pcm.extend(resampler::to_mono(&frame[..decoded * channel_count as usize], channel_count as u16));
+    }
+
+    //The first pre_skip samples are encoder priming, not audio.
+    pcm.drain(..pre_skip.min(pcm.len()));
+    Ok(pcm)
+}
+
+/// Encodes mono 16kHz PCM into an Ogg Opus file Matrix clients can play as a voice message.
+pub fn encode(pcm: &[i16]) -> Result<Vec<u8>, AudioConversionError> {
+    let mut encoder = opus::Encoder::new(SAMPLE_RATE, Channels::Mono, Application::Voip)?;
+    encoder.set_bitrate(Bitrate::Bits(BITRATE))?;
+    let pre_skip = encoder.get_lookahead()? as u64 * GRANULE_RATE_FACTOR;
+
+    let mut writer = PacketWriter::new(Vec::new());
+    writer.write_packet(opus_head(pre_skip as u16), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+    writer.write_packet(opus_tags(), STREAM_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    //An empty clip still needs one packet to carry the end of stream.
+    let chunks: Vec<&[i16]> = if pcm.is_empty() { vec![pcm] } else { pcm.chunks(FRAME_SAMPLES).collect() };
+    let frame_count = chunks.len();
+    let mut frame_in = [0i16; FRAME_SAMPLES];
+    let mut packet = [0u8; MAX_PACKET_SIZE];
+
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        frame_in[..chunk.len()].copy_from_slice(chunk);
+        frame_in[chunk.len()..].fill(0);
+
+        let size = encoder.encode(&frame_in, &mut packet)?;
+
+        let is_last = index + 1 == frame_count;
+        let end_info = if is_last { PacketWriteEndInfo::EndStream } else { PacketWriteEndInfo::NormalPacket };
+        //The last granule position trims the padding we added to the trailing frame.
+        let granule = if is_last {
+            pre_skip + pcm.len() as u64 * GRANULE_RATE_FACTOR
+        } else {
+            pre_skip + ((index + 1) * FRAME_SAMPLES) as u64 * GRANULE_RATE_FACTOR
+        };
+
+        writer.write_packet(packet[..size].to_vec(), STREAM_SERIAL, end_info, granule)?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+//RFC 7845 section 5.1
+fn opus_head(pre_skip: u16) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1);
+    head.push(1);
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    head
+}
+
+//RFC 7845 section 5.2
+fn opus_tags() -> Vec<u8> {
+    let vendor = b"Tachyon";
+    let mut tags = Vec::with_capacity(8 + 4 + vendor.len() + 4);
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_a_tone() {
+        //One second of a 440Hz tone.
+        let tone: Vec<i16> = (0..16000)
+            .map(|index| ((index as f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin() * 8000.0) as i16)
+            .collect();
+
+        let encoded = encode(&tone).unwrap();
+        assert!(is_ogg(&encoded));
+
+        let decoded = decode(&encoded).unwrap();
+        assert!((decoded.len() as i64 - tone.len() as i64).abs() <= FRAME_SAMPLES as i64, "decoded {} samples", decoded.len());
+        assert!(decoded.iter().any(|&sample| sample.abs() > 1000));
+    }
+
+    #[test]
+    fn rejects_ogg_that_is_not_opus() {
+        let mut writer = PacketWriter::new(Vec::new());
+        writer.write_packet(b"\x01vorbis".to_vec(), 1, PacketWriteEndInfo::EndStream, 0).unwrap();
+
+        assert!(matches!(decode(&writer.into_inner()), Err(AudioConversionError::UnsupportedFormat { .. })));
+    }
+}
@@ -0,0 +1,101 @@
+/// Averages interleaved channels down to mono.
+pub fn to_mono(samples: &[i16], channels: u16) -> Vec<i16> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+
+    samples
+        .chunks(channels as usize)
+        .map(|frame| (frame.iter().map(|&sample| sample as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect()
+}
+
+/// Resamples mono PCM with linear interpolation.
+///
+/// When downsampling, each output sample first averages the input samples it covers, which is a
+/// crude low-pass but keeps aliasing out of voice clips without pulling in a DSP crate.
+pub fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = ((samples.len() as f64) / ratio).round() as usize;
+
+    let filtered;
+    let source = if ratio > 1.0 {
+        filtered = box_filter(samples, ratio.ceil() as usize);
+        &filtered
+    } else {
+        samples
+    };
+
+    let last = source.len() - 1;
+    (0..out_len)
+        .map(|index| {
+            let position = index as f64 * ratio;
+            let left = (position.floor() as usize).min(last);
+            let right = (left + 1).min(last);
+            let fraction = position - left as f64;
+
+            let value = source[left] as f64 * (1.0 - fraction) + source[right] as f64 * fraction;
+            value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+        })
+        .collect()
+}
+
+fn box_filter(samples: &[i16], width: usize) -> Vec<i16> {
+    if width <= 1 {
+        return samples.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(samples.len());
+    let mut sum: i64 = 0;
+
+    for (index, &sample) in samples.iter().enumerate() {
+        sum += sample as i64;
+        if index >= width {
+            sum -= samples[index - width] as i64;
+        }
+        let count = (index + 1).min(width) as i64;
+        out.push((sum / count) as i16);
+    }
+
+    out
+}
+
+pub fn from_s16le(bytes: &[u8]) -> Vec<i16> {
+    bytes.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect()
+}
+
+pub fn to_s16le(samples: &[i16]) -> Vec<u8> {
+    samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downmixes_stereo() {
+        assert_eq!(to_mono(&[100, 300, -50, 50], 2), vec![200, 0]);
+    }
+
+    #[test]
+    fn keeps_the_duration_when_resampling() {
+        let one_second_at_48k = vec![0i16; 48000];
+        assert_eq!(resample(&one_second_at_48k, 48000, 16000).len(), 16000);
+
+        let one_second_at_8k = vec![0i16; 8000];
+        assert_eq!(resample(&one_second_at_8k, 8000, 16000).len(), 16000);
+
+        let one_second_at_44k = vec![0i16; 44100];
+        assert_eq!(resample(&one_second_at_44k, 44100, 16000).len(), 16000);
+    }
+
+    #[test]
+    fn keeps_a_constant_signal_constant() {
+        let constant = vec![1000i16; 4410];
+        assert!(resample(&constant, 44100, 16000).iter().all(|&sample| sample == 1000));
+    }
+}
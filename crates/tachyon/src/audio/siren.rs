@@ -1,3 +1,4 @@
+use crate::audio::wav::{self, WAVE_FORMAT_SIREN};
 use crate::audio::AudioConversionError;
 use std::os::raw::{c_int, c_uchar};
 use std::sync::Mutex;
@@ -51,6 +52,18 @@ pub struct SirenWavHeader {
     data_size: u32,
 }
 
+/// The plain PCM header the decoder keeps up to date, we don't use it but it sizes the struct.
+#[repr(C)]
+struct PcmWavHeader {
+    riff: RiffHeader,
+    wave_id: u32,
+    fmt_id: u32,
+    fmt_size: u32,
+    fmt: FmtChunk,
+    data_id: u32,
+    data_size: u32,
+}
+
 #[repr(C)]
 struct StSirenEncoder {
     sample_rate: c_int,
@@ -58,6 +71,18 @@ struct StSirenEncoder {
     context: [f32; 320],
 }
 
+#[repr(C)]
+struct StSirenDecoder {
+    sample_rate: c_int,
+    wav_header: PcmWavHeader,
+    context: [f32; 320],
+    backup_frame: [f32; 320],
+    dw1: c_int,
+    dw2: c_int,
+    dw3: c_int,
+    dw4: c_int,
+}
+
 extern "C" {
     fn Siren7_NewEncoder(sample_rate: c_int) -> *mut StSirenEncoder;
     fn Siren7_CloseEncoder(encoder: *mut StSirenEncoder);
@@ -66,18 +91,27 @@ extern "C" {
         data_in: *mut c_uchar,
         data_out: *mut c_uchar,
     ) -> c_int;
+
+    fn Siren7_NewDecoder(sample_rate: c_int) -> *mut StSirenDecoder;
+    fn Siren7_CloseDecoder(decoder: *mut StSirenDecoder);
+    fn Siren7_DecodeFrame(
+        decoder: *mut StSirenDecoder,
+        data_in: *mut c_uchar,
+        data_out: *mut c_uchar,
+    ) -> c_int;
 }
 
-/// libsiren keeps its lookup tables and per-frame scratch buffers in file scope statics, so no two
-/// encoders may run at the same time, not even from different encoder handles.
-static ENCODER_LOCK: Mutex<()> = Mutex::new(());
+/// libsiren keeps its lookup tables and per-frame scratch buffers in file scope statics shared by
+/// the encoder and the decoder, so no two codec calls may run at the same time, not even from
+/// different handles.
+static CODEC_LOCK: Mutex<()> = Mutex::new(());
 
 /// Encodes mono 16kHz PCM s16le into a Siren7 WAV voice clip.
 ///
 /// This blocks the calling thread and serializes with every other caller: run it off the async
 /// runtime's worker threads.
 pub fn encode(pcm_s16le: &[u8]) -> Result<Vec<u8>, AudioConversionError> {
-    let _guard = ENCODER_LOCK.lock().unwrap_or_else(|poison| poison.into_inner());
+    let _guard = CODEC_LOCK.lock().unwrap_or_else(|poison| poison.into_inner());
 
     let encoder = unsafe { Siren7_NewEncoder(SIREN_SAMPLE_RATE) };
     let result = encode_with(encoder, pcm_s16le);
@@ -117,6 +151,72 @@ fn encode_with(
     Ok(out)
 }
 
+/// Decodes a Siren7 WAV voice clip into mono 16kHz PCM s16le.
+///
+/// Same threading caveats as [`encode`].
+pub fn decode(voice_clip: &[u8]) -> Result<Vec<u8>, AudioConversionError> {
+    let wav = wav::parse(voice_clip)?;
+    if wav.format != WAVE_FORMAT_SIREN {
+        return Err(AudioConversionError::UnsupportedFormat { reason: format!("WAV format tag {:#06x} is not Siren7", wav.format) });
+    }
+
+    let _guard = CODEC_LOCK.lock().unwrap_or_else(|poison| poison.into_inner());
+
+    let decoder = unsafe { Siren7_NewDecoder(SIREN_SAMPLE_RATE) };
+    let result = decode_with(decoder, wav.data);
+    unsafe { Siren7_CloseDecoder(decoder) };
+    result
+}
+
+fn decode_with(
+    decoder: *mut StSirenDecoder,
+    siren_data: &[u8],
+) -> Result<Vec<u8>, AudioConversionError> {
+    let mut frame_in = [0u8; SIREN_ENCODED_FRAME_SIZE];
+    let mut frame_out = [0u8; SIREN_FRAME_SIZE];
+    let mut pcm = Vec::with_capacity(siren_data.len() / SIREN_ENCODED_FRAME_SIZE * SIREN_FRAME_SIZE);
+
+    //A truncated trailing frame can't be decoded, drop it rather than feed the decoder garbage.
+    for chunk in siren_data.chunks_exact(SIREN_ENCODED_FRAME_SIZE) {
+        frame_in.copy_from_slice(chunk);
+
+        let code = unsafe { Siren7_DecodeFrame(decoder, frame_in.as_mut_ptr(), frame_out.as_mut_ptr()) };
+        if code != 0 {
+            return Err(AudioConversionError::SirenDecodingFailed { code });
+        }
+
+        pcm.extend_from_slice(&frame_out);
+    }
+
+    Ok(pcm)
+}
+
+/// Splits a voice clip produced by [`encode`] into clips of at most `max_size` bytes each.
+///
+/// Frames are copied as is, only the sizes in each part's header are rewritten.
+pub fn split(voice_clip: &[u8], max_size: usize) -> Vec<Vec<u8>> {
+    let (header, data) = voice_clip.split_at(SIREN_WAV_HEADER_SIZE.min(voice_clip.len()));
+    let frames_per_clip = (max_size.saturating_sub(SIREN_WAV_HEADER_SIZE) / SIREN_ENCODED_FRAME_SIZE).max(1);
+
+    if data.len() <= frames_per_clip * SIREN_ENCODED_FRAME_SIZE {
+        return vec![voice_clip.to_vec()];
+    }
+
+    data.chunks(frames_per_clip * SIREN_ENCODED_FRAME_SIZE)
+        .map(|part| {
+            let mut clip = Vec::with_capacity(SIREN_WAV_HEADER_SIZE + part.len());
+            clip.extend_from_slice(header);
+            clip.extend_from_slice(part);
+
+            let frames = part.len() / SIREN_ENCODED_FRAME_SIZE;
+            clip[4..8].copy_from_slice(&((clip.len() - 8) as u32).to_le_bytes());
+            clip[48..52].copy_from_slice(&((frames * SIREN_FRAME_SIZE / 2) as u32).to_le_bytes());
+            clip[56..60].copy_from_slice(&(part.len() as u32).to_le_bytes());
+            clip
+        })
+        .collect()
+}
+
 unsafe fn header_as_bytes<'a>(encoder: *mut StSirenEncoder) -> &'a [u8] {
     std::slice::from_raw_parts(
         std::ptr::addr_of!((*encoder).wav_header) as *const u8,
@@ -169,4 +269,42 @@ mod tests {
         assert_eq!(riff_size, encoded.len() - 8);
         assert_eq!(samples, 320 * 3);
     }
+
+    #[test]
+    fn splits_into_well_formed_clips() {
+        let clip = encode(&vec![0u8; SIREN_FRAME_SIZE * 5]).unwrap();
+
+        let parts = split(&clip, SIREN_WAV_HEADER_SIZE + SIREN_ENCODED_FRAME_SIZE * 2);
+
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[2].len(), SIREN_WAV_HEADER_SIZE + SIREN_ENCODED_FRAME_SIZE);
+        for part in &parts {
+            let expected = encode(&vec![0u8; SIREN_FRAME_SIZE * ((part.len() - SIREN_WAV_HEADER_SIZE) / SIREN_ENCODED_FRAME_SIZE)]).unwrap();
+            assert_eq!(&part[..SIREN_WAV_HEADER_SIZE], &expected[..SIREN_WAV_HEADER_SIZE]);
+        }
+    }
+
+    #[test]
+    fn does_not_split_a_clip_that_fits() {
+        let clip = encode(&vec![0u8; SIREN_FRAME_SIZE * 5]).unwrap();
+
+        assert_eq!(split(&clip, clip.len()), vec![clip]);
+    }
+
+    #[test]
+    fn decodes_what_it_encoded() {
+        let pcm = vec![0u8; SIREN_FRAME_SIZE * 10];
+
+        let decoded = decode(&encode(&pcm).unwrap()).unwrap();
+
+        assert_eq!(decoded.len(), SIREN_FRAME_SIZE * 10);
+    }
+
+    #[test]
+    fn refuses_to_decode_plain_pcm() {
+        let mut not_siren = encode(&[0u8; SIREN_FRAME_SIZE]).unwrap();
+        not_siren[20..22].copy_from_slice(&wav::WAVE_FORMAT_PCM.to_le_bytes());
+
+        assert!(matches!(decode(&not_siren), Err(AudioConversionError::UnsupportedFormat { .. })));
+    }
 }
@@ -0,0 +1,121 @@
+use crate::audio::AudioConversionError;
+
+pub const WAVE_FORMAT_PCM: u16 = 0x0001;
+
+/// Format tag WLM writes in Siren7 voice clips.
+pub const WAVE_FORMAT_SIREN: u16 = 0x028E;
+
+/// The parts of a RIFF WAVE file we care about. `data` borrows from the parsed buffer.
+pub struct Wav<'a> {
+    pub format: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub data: &'a [u8],
+}
+
+pub fn is_wav(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE"
+}
+
+/// Walks the RIFF chunks instead of assuming a header size: voice clips from other clients
+/// sometimes carry extra chunks before `data`.
+pub fn parse(bytes: &[u8]) -> Result<Wav<'_>, AudioConversionError> {
+    if !is_wav(bytes) {
+        return Err(AudioConversionError::InvalidWav { reason: "missing RIFF/WAVE header".into() });
+    }
+
+    let mut fmt = None;
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().expect("4 bytes")) as usize;
+        let body_start = offset + 8;
+        //Some encoders leave the data size at 0 or too big when streaming, take what's there.
+        let body_end = body_start.saturating_add(size).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        match id {
+            b"fmt " => {
+                if body.len() < 16 {
+                    return Err(AudioConversionError::InvalidWav { reason: "fmt chunk is too short".into() });
+                }
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let sample_rate = u32::from_le_bytes(body[4..8].try_into().expect("4 bytes"));
+                //Both end up as divisors when resampling.
+                if channels == 0 || sample_rate == 0 {
+                    return Err(AudioConversionError::InvalidWav { reason: format!("{} channels at {}Hz", channels, sample_rate) });
+                }
+                fmt = Some((
+                    u16::from_le_bytes([body[0], body[1]]),
+                    channels,
+                    sample_rate,
+                    u16::from_le_bytes([body[14], body[15]]),
+                ));
+            }
+            b"data" => {
+                let (format, channels, sample_rate, bits_per_sample) = fmt.ok_or(AudioConversionError::InvalidWav { reason: "data chunk before fmt chunk".into() })?;
+                let data = if size == 0 { &bytes[body_start..] } else { body };
+                return Ok(Wav { format, channels, sample_rate, bits_per_sample, data });
+            }
+            _ => {}
+        }
+
+        //Chunks are word aligned.
+        offset = body_start.saturating_add(size + (size & 1));
+    }
+
+    Err(AudioConversionError::InvalidWav { reason: "no data chunk".into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pcm_wav(sample_rate: u32, channels: u16, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+        out.extend_from_slice(b"fmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        out.extend_from_slice(&channels.to_le_bytes());
+        out.extend_from_slice(&sample_rate.to_le_bytes());
+        out.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        out.extend_from_slice(&(channels * 2).to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"LIST");
+        out.extend_from_slice(&3u32.to_le_bytes());
+        out.extend_from_slice(&[1, 2, 3, 0]);
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    #[test]
+    fn parses_pcm_with_extra_chunks() {
+        let wav = pcm_wav(44100, 2, &[1, 0, 2, 0, 3, 0, 4, 0]);
+
+        let parsed = parse(&wav).unwrap();
+
+        assert_eq!(parsed.format, WAVE_FORMAT_PCM);
+        assert_eq!(parsed.channels, 2);
+        assert_eq!(parsed.sample_rate, 44100);
+        assert_eq!(parsed.bits_per_sample, 16);
+        assert_eq!(parsed.data, &[1, 0, 2, 0, 3, 0, 4, 0]);
+    }
+
+    #[test]
+    fn rejects_zero_sample_rate_or_channels() {
+        assert!(matches!(parse(&pcm_wav(0, 1, &[1, 0])), Err(AudioConversionError::InvalidWav { .. })));
+        assert!(matches!(parse(&pcm_wav(16000, 0, &[1, 0])), Err(AudioConversionError::InvalidWav { .. })));
+    }
+
+    #[test]
+    fn rejects_non_wav() {
+        assert!(parse(b"OggS\0\0\0\0\0\0\0\0").is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use chrono::Local;
 use env_logger::Builder;
-use log::{error, info, warn, LevelFilter};
+use log::{error, info, warn};
 use std::fs;
 use std::fs::File;
 use tokio::{join, signal, sync::broadcast::{self, Sender}};
@@ -8,18 +8,23 @@ use tokio::{join, signal, sync::broadcast::{self, Sender}};
 use crate::notification::notification_server::NotificationServer;
 use crate::switchboard::switchboard_server::SwitchboardServer;
 use crate::tachyon::global_state::GlobalState;
+use crate::tachyon::sweeper::run_sweeper;
 use self::tachyon::config::secret_encryptor::SecretEncryptor;
 use crate::web::web_server::WebServer;
 use anyhow::anyhow;
 use directories::ProjectDirs;
 use rand::{random, Rng};
 use std::io::Write;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::io::ErrorKind;
+use std::process::ExitCode;
+use clap::Parser;
 use crate::matrix::services::login::MatrixLoginServiceImpl;
+use self::tachyon::config::cli::CliArgs;
 use self::tachyon::config::paths;
 use self::tachyon::config::paths::create_dirs;
-use self::tachyon::config::tachyon_config::TachyonConfig;
+use self::tachyon::config::tachyon_config::{ConfigOverride, TachyonConfig};
 
 mod notification;
 mod web;
@@ -30,27 +35,43 @@ mod p2p;
 mod audio;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     //console_subscriber::init();
 
+    let args = CliArgs::parse();
+
     let tachyon_path = paths::get_tachyon_path();
     create_dirs(&tachyon_path);
 
-    let config = setup_config(tachyon_path.config_dir().to_path_buf());
+    let config_path = args.config.clone().unwrap_or_else(|| tachyon_path.config_dir().join("config.ini"));
+    let config = match setup_config(&config_path, &args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Couldn't load Tachyon config from {}: {:#}", config_path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
     setup_logs(tachyon_path.data_dir().to_path_buf(), &config);
     let secret = setup_key(tachyon_path.data_local_dir().to_path_buf()).expect("secret key is mandatory");
 
     let (global_shutdown_signal_snd, global_shutdown_signal_rcv) = broadcast::channel::<()>(1);
 
-    let global_state = GlobalState::new(config.clone(), SecretEncryptor::new(&secret).expect("secret key to be valid"), Box::new(MatrixLoginServiceImpl::new()));
+    let global_state = GlobalState::new(config.clone(), SecretEncryptor::new(&secret).expect("secret key to be valid"), Box::new(MatrixLoginServiceImpl::with_homeserver_url(config.homeserver_url.clone())));
 
-    let notification_server = NotificationServer::listen("127.0.0.1", config.notification_port, global_shutdown_signal_rcv.resubscribe(), global_state.clone());
-    let switchboard_server = SwitchboardServer::listen("127.0.0.1", config.switchboard_port, global_shutdown_signal_rcv.resubscribe(), global_state.clone());
-    let web_server = WebServer::listen("127.0.0.1", config.http_port, global_shutdown_signal_rcv, global_state);
+    //The servers append their port, IPv6 addresses need brackets for that
+    let bind_address = match config.bind_address {
+        IpAddr::V6(ip) => format!("[{}]", ip),
+        ip => ip.to_string(),
+    };
+    let notification_server = NotificationServer::listen(&bind_address, config.notification_port, global_shutdown_signal_rcv.resubscribe(), global_state.clone());
+    let switchboard_server = SwitchboardServer::listen(&bind_address, config.switchboard_port, global_shutdown_signal_rcv.resubscribe(), global_state.clone());
+    let web_server = WebServer::listen(&bind_address, config.http_port, global_shutdown_signal_rcv.resubscribe(), global_state.clone());
+    let sweeper = run_sweeper(global_state, global_shutdown_signal_rcv);
 
-    let _result = join!(notification_server, switchboard_server, web_server, listen_for_stop_signal(global_shutdown_signal_snd));
+    let _result = join!(notification_server, switchboard_server, web_server, sweeper, listen_for_stop_signal(global_shutdown_signal_snd));
 
     info!("Byebye, world!");
+    ExitCode::SUCCESS
 }
 
 fn setup_key(config_folder_path: PathBuf) -> Result<Vec<u8>, anyhow::Error> {
@@ -68,30 +89,30 @@ fn setup_key(config_folder_path: PathBuf) -> Result<Vec<u8>, anyhow::Error> {
     }
 }
 
-fn setup_config(config_dir: PathBuf) -> TachyonConfig {
-
-    let settings_path = config_dir.join("config.ini");
+//config.ini, then TACHYON_* environment variables, then command line flags.
+fn setup_config(settings_path: &Path, args: &CliArgs) -> Result<TachyonConfig, anyhow::Error> {
 
-    let result : Result<TachyonConfig, anyhow::Error> = match fs::read_to_string(&settings_path) {
-        Ok(content) => {
-            TachyonConfig::from_str(&content).map_err(|e| anyhow!(e))
-        }
-        Err(err) => {
-            println!("Couldn't load Tachyon config: {:?}", err);
-            let default = TachyonConfig::default();
-            let default_str = default.to_string();
-            if let Err(e) = fs::write(&settings_path, default_str) {
+    let content = match fs::read_to_string(settings_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            println!("No Tachyon config at {}, writing the defaults", settings_path.display());
+            let default_str = TachyonConfig::default().to_string();
+            if let Err(e) = fs::write(settings_path, &default_str) {
                 println!("Couldn't write default config to disk: {:?}", e);
             }
-            Ok(default)
+            default_str
         }
+        Err(err) => return Err(anyhow!("Couldn't read config file: {}", err)),
     };
 
-    result.expect("default config to be here")
+    let mut overrides = ConfigOverride::from_env(std::env::vars())?;
+    overrides.extend(args.overrides()?);
+
+    TachyonConfig::load(&content, &overrides)
 }
 
 fn setup_logs(path: PathBuf, config: &TachyonConfig) {
-    if !config.logs_enabled {
+    if !config.logging.enabled {
         return;
     }
 
@@ -100,7 +121,8 @@ fn setup_logs(path: PathBuf, config: &TachyonConfig) {
     let log_path = path.join("logs.txt");
     let target = Box::new(File::create(log_path).expect("Can't create file"));
     log_print_panics::init();
-    Builder::new()
+    let mut builder = Builder::new();
+    builder
         .format(|buf, record| {
             writeln!(
                 buf,
@@ -113,13 +135,13 @@ fn setup_logs(path: PathBuf, config: &TachyonConfig) {
             )
         })
         .target(env_logger::Target::Pipe(target))
-        .filter(Some("v2") , LevelFilter::Debug)
-        .filter(Some("tachyon") , LevelFilter::Debug)
-        .filter(Some("msnp") , LevelFilter::Debug)
-        .filter(Some("matrix-sdk"), LevelFilter::Off)
-        .filter(Some("yaserde"), LevelFilter::Warn)
-        .filter(None, LevelFilter::Warn)
-        .init();
+        .filter(None, config.logging.level);
+
+    for (log_target, level) in &config.logging.targets {
+        builder.filter(Some(log_target), *level);
+    }
+
+    builder.init();
 
     //Some("wlmatrix_rust")
     info!("=========NEW LOG SESSION (✿◡‿◡)  - {}=========", Local::now().format("%d-%m-%YT%H:%M:%S%.3f"));
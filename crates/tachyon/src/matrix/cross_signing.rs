@@ -3,15 +3,20 @@ use log::{info, warn};
 use matrix_sdk::encryption::recovery::RecoveryState;
 use matrix_sdk::encryption::verification::{SasState, SasVerification, Verification, VerificationRequest, VerificationRequestState};
 use matrix_sdk::encryption::CrossSigningResetAuthType;
+use matrix_sdk::ruma::api::client::filter::FilterDefinition;
+use matrix_sdk::ruma::api::client::sync::sync_events::v3::Filter;
 use matrix_sdk::ruma::api::client::sync::sync_events::v5::request::{ListFilters, ToDevice, E2EE};
 use matrix_sdk::ruma::api::client::uiaa;
 use matrix_sdk::ruma::directory::RoomTypeFilter;
 use matrix_sdk::ruma::events::key::verification::request::ToDeviceKeyVerificationRequestEvent;
+use matrix_sdk::config::SyncSettings;
 use matrix_sdk::sliding_sync::Range;
 use matrix_sdk::{sliding_sync, Client, SlidingSync, SlidingSyncList, SlidingSyncListBuilder, SlidingSyncMode};
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::sleep;
+use crate::matrix::sync::use_sliding_sync;
+use crate::tachyon::config::tachyon_config::SyncMode;
 
 pub async fn check_device_is_crossed_signed(client: &matrix_sdk::Client) -> Result<bool, anyhow::Error> {
 
@@ -184,30 +189,50 @@ pub async fn build_to_device_only_sliding_sync(matrix_client: &Client) -> Result
 }
 
 
+//Classic /sync still stores the next_batch of the verification loop, the main sync has to resume from the returned token
+//or it would skip every room event received in the meantime.
 pub async fn cross_sign_sync_task(
     client: &matrix_sdk::Client,
+    sync_mode: &SyncMode,
     mut client_shutdown_recv: tokio::sync::broadcast::Receiver<()>,
 
-) -> Result<mpsc::Sender<()>, anyhow::Error> {
+) -> Result<(mpsc::Sender<()>, Option<String>), anyhow::Error> {
 
     let (sign_sync_kill_signal_snd, mut sign_sync_kill_signal_rcv) = tokio::sync::mpsc::channel::<()>(1);
 
+    let sliding = use_sliding_sync(client, sync_mode).await;
+    let resume_token = if sliding { None } else { client.sync_token().await };
     let client = client.clone();
     tokio::spawn(async move {
-        let sliding_sync = build_to_device_only_sliding_sync(&client).await.unwrap();
-
-        sliding_sync.add_list(no_room_data_list()).await.unwrap();
+        let sliding_sync = if sliding {
+            let sliding_sync = build_to_device_only_sliding_sync(&client).await.unwrap();
+            sliding_sync.add_list(no_room_data_list()).await.unwrap();
+            Some(sliding_sync)
+        } else {
+            None
+        };
 
         let mut sync_handle = tokio::spawn({
             let sliding_sync = sliding_sync.clone();
+            let client = client.clone();
             async move {
-                let mut sync_stream = Box::pin(sliding_sync.sync());
-                let _result = sync_stream.next().await;
-                loop {
-                    match sync_stream.next().await {
-                        Some(Ok(_update)) => {}
-                        Some(Err(_err)) => {}
-                        None => break,
+                match sliding_sync {
+                    Some(sliding_sync) => {
+                        let mut sync_stream = Box::pin(sliding_sync.sync());
+                        let _result = sync_stream.next().await;
+                        loop {
+                            match sync_stream.next().await {
+                                Some(Ok(_update)) => {}
+                                Some(Err(_err)) => {}
+                                None => break,
+                            }
+                        }
+                    }
+                    None => {
+                        //Only to-device events and device lists are needed to verify, rooms are left out of the responses.
+                        let sync_settings = SyncSettings::default().filter(Filter::FilterDefinition(FilterDefinition::ignore_all()));
+                        let mut sync_stream = Box::pin(client.sync_stream(sync_settings).await);
+                        while let Some(_response) = sync_stream.next().await {}
                     }
                 }
             }
@@ -216,11 +241,11 @@ pub async fn cross_sign_sync_task(
 
         tokio::select! {
             _ = sign_sync_kill_signal_rcv.recv() => {
-                sliding_sync.stop_sync().unwrap();
+                stop_sliding_sync(&sliding_sync);
                 sync_handle.abort();
             },
             _ = client_shutdown_recv.recv() => {
-                sliding_sync.stop_sync().unwrap();
+                stop_sliding_sync(&sliding_sync);
                 sync_handle.abort();
             },
             _ = &mut sync_handle => {},
@@ -229,5 +254,11 @@ pub async fn cross_sign_sync_task(
         info!("Gracefully exit cross_sign sync loop...");
     });
 
-    Ok(sign_sync_kill_signal_snd)
-}
\ No newline at end of file
+    Ok((sign_sync_kill_signal_snd, resume_token))
+}
+
+fn stop_sliding_sync(sliding_sync: &Option<SlidingSync>) {
+    if let Some(sliding_sync) = sliding_sync {
+        sliding_sync.stop_sync().unwrap();
+    }
+}
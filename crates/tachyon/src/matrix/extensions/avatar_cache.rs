@@ -0,0 +1,250 @@
+use crate::tachyon::client::task_supervisor::TaskSupervisor;
+use anyhow::anyhow;
+use base64::engine::general_purpose;
+use base64::Engine;
+use dashmap::DashMap;
+use log::{debug, warn};
+use matrix_sdk::media::{MediaFormat, MediaRequestParameters, MediaThumbnailSettings};
+use matrix_sdk::ruma::events::room::MediaSource;
+use matrix_sdk::ruma::media::Method;
+use matrix_sdk::ruma::{MxcUri, OwnedMxcUri, UInt};
+use matrix_sdk::Client;
+use msnp::shared::models::email_address::EmailAddress;
+use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+//WLM shows display pictures at 96x96, it downscales anything bigger.
+const AVATAR_THUMBNAIL_SIZE: u64 = 96;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AvatarCacheEntry {
+    pub sha1d: String,
+    pub size: usize,
+    //SHA1C depends on the creator, so it's only reused for the one it was computed for.
+    pub creator: String,
+    pub sha1c: String,
+}
+
+//Avatar thumbnails keyed by mxc URI, persisted in the user data dir.
+//Keeps MSNObjects stable across sessions so WLM's own display picture cache hits.
+pub struct AvatarCache {
+    dir: PathBuf,
+    entries: DashMap<OwnedMxcUri, AvatarCacheEntry>,
+    tasks: Arc<TaskSupervisor>,
+}
+
+impl AvatarCache {
+
+    pub fn new(dir: PathBuf, tasks: Arc<TaskSupervisor>) -> Self {
+        Self {
+            dir,
+            entries: DashMap::new(),
+            tasks,
+        }
+    }
+
+    //Fetches a missing avatar in the background so the next lookup hits, the task stops with the client.
+    pub fn warm(self: &Arc<Self>, client: &Client, mxc: &MxcUri, creator: &EmailAddress) {
+        let cache = self.clone();
+        let client = client.clone();
+        let mxc = mxc.to_owned();
+        let creator = creator.clone();
+
+        self.tasks.spawn("avatar cache warming", async move {
+            if let Err(e) = cache.get_msn_object(&client, &mxc, &creator).await {
+                warn!("Could not warm avatar cache for {}: {}", mxc, e);
+            }
+        });
+    }
+
+    //Only looks at what we already have, never hits the homeserver.
+    pub async fn get_cached_msn_object(&self, mxc: &MxcUri, creator: &EmailAddress) -> Option<MsnObject> {
+        let entry = self.get_entry(mxc).await?;
+        Some(to_msn_object(&entry, mxc, creator))
+    }
+
+    pub async fn get_msn_object(&self, client: &Client, mxc: &MxcUri, creator: &EmailAddress) -> Result<MsnObject, anyhow::Error> {
+        let entry = match self.get_entry(mxc).await {
+            Some(entry) => entry,
+            None => {
+                let thumbnail = fetch_thumbnail(client, mxc).await?;
+                self.store(mxc, creator, &thumbnail).await?
+            }
+        };
+
+        Ok(to_msn_object(&entry, mxc, creator))
+    }
+
+    pub async fn get_thumbnail(&self, client: &Client, mxc: &MxcUri) -> Result<Vec<u8>, anyhow::Error> {
+        if self.get_entry(mxc).await.is_some() {
+            match tokio::fs::read(self.thumbnail_path(mxc)).await {
+                Ok(thumbnail) => return Ok(thumbnail),
+                Err(e) => warn!("Avatar cache entry for {} has no thumbnail on disk, fetching it again: {}", mxc, e),
+            }
+        }
+
+        //We don't know who the MSNObject is for yet, SHA1C will be computed when it's first needed.
+        let thumbnail = fetch_thumbnail(client, mxc).await?;
+        self.store(mxc, &EmailAddress::default(), &thumbnail).await?;
+        Ok(thumbnail)
+    }
+
+    pub async fn store(&self, mxc: &MxcUri, creator: &EmailAddress, thumbnail: &[u8]) -> Result<AvatarCacheEntry, anyhow::Error> {
+        let msn_object = MSNObjectFactory::get_display_picture(thumbnail, creator, location(mxc), FriendlyName::default());
+
+        let entry = AvatarCacheEntry {
+            sha1d: msn_object.sha1d.clone(),
+            size: msn_object.size,
+            creator: creator.to_string(),
+            sha1c: msn_object.get_sha1c(),
+        };
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.thumbnail_path(mxc), thumbnail).await?;
+        tokio::fs::write(self.entry_path(mxc), serde_json::to_vec(&entry)?).await?;
+
+        debug!("Cached avatar {}: {} bytes, sha1d {}", mxc, entry.size, &entry.sha1d);
+        self.entries.insert(mxc.to_owned(), entry.clone());
+        Ok(entry)
+    }
+
+    //The SHA1D of the full file rather than the thumbnail, for our own avatar so logins don't download it to compare.
+    pub async fn store_file_sha1d(&self, mxc: &MxcUri, sha1d: &str) -> Result<(), anyhow::Error> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        tokio::fs::write(self.file_sha1d_path(mxc), sha1d).await?;
+        Ok(())
+    }
+
+    pub async fn get_file_sha1d(&self, mxc: &MxcUri) -> Option<String> {
+        tokio::fs::read_to_string(self.file_sha1d_path(mxc)).await.ok()
+    }
+
+    async fn get_entry(&self, mxc: &MxcUri) -> Option<AvatarCacheEntry> {
+        if let Some(entry) = self.entries.get(mxc) {
+            return Some(entry.value().clone());
+        }
+
+        let raw = tokio::fs::read(self.entry_path(mxc)).await.ok()?;
+        match serde_json::from_slice::<AvatarCacheEntry>(&raw) {
+            Ok(entry) => {
+                self.entries.insert(mxc.to_owned(), entry.clone());
+                Some(entry)
+            }
+            Err(e) => {
+                warn!("Ignoring corrupted avatar cache entry for {}: {}", mxc, e);
+                None
+            }
+        }
+    }
+
+    fn entry_path(&self, mxc: &MxcUri) -> PathBuf {
+        self.dir.join(format!("{}.json", cache_key(mxc)))
+    }
+
+    fn thumbnail_path(&self, mxc: &MxcUri) -> PathBuf {
+        self.dir.join(format!("{}.bin", cache_key(mxc)))
+    }
+
+    fn file_sha1d_path(&self, mxc: &MxcUri) -> PathBuf {
+        self.dir.join(format!("{}.sha1d", cache_key(mxc)))
+    }
+}
+
+fn to_msn_object(entry: &AvatarCacheEntry, mxc: &MxcUri, creator: &EmailAddress) -> MsnObject {
+    let mut msn_object = MSNObjectFactory::get_display_picture_with_sha1d(entry.sha1d.clone(), entry.size, creator, location(mxc), FriendlyName::default());
+    if entry.creator == creator.to_string() {
+        msn_object.sha1c = entry.sha1c.clone();
+    }
+    msn_object
+}
+
+fn location(mxc: &MxcUri) -> String {
+    format!("{}.tmp", general_purpose::STANDARD.encode(mxc.to_string()))
+}
+
+fn cache_key(mxc: &MxcUri) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(mxc.as_str().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+async fn fetch_thumbnail(client: &Client, mxc: &MxcUri) -> Result<Vec<u8>, anyhow::Error> {
+    let format = MediaFormat::Thumbnail(MediaThumbnailSettings {
+        method: Method::Crop,
+        width: UInt::new_saturating(AVATAR_THUMBNAIL_SIZE),
+        height: UInt::new_saturating(AVATAR_THUMBNAIL_SIZE),
+        animated: true,
+    });
+
+    let request = MediaRequestParameters {
+        source: MediaSource::Plain(mxc.to_owned()),
+        format,
+    };
+
+    client.media().get_media_content(&request, true).await.map_err(|e| anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matrix::extensions::avatar_cache::AvatarCache;
+    use crate::tachyon::client::task_supervisor::TaskSupervisor;
+    use matrix_sdk::ruma::OwnedMxcUri;
+    use msnp::shared::models::email_address::EmailAddress;
+    use msnp::shared::models::uuid::Uuid;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use tokio::sync::broadcast;
+
+    fn tasks() -> Arc<TaskSupervisor> {
+        Arc::new(TaskSupervisor::new(broadcast::channel(1).1))
+    }
+
+    #[tokio::test]
+    async fn entries_survive_a_restart() {
+        let dir = std::env::temp_dir().join(format!("tachyon-avatar-cache-{}", Uuid::new()));
+        let mxc = OwnedMxcUri::from("mxc://example.org/avatar");
+        let creator = EmailAddress::from_str("aeon@example.org").unwrap();
+
+        let stored = AvatarCache::new(dir.clone(), tasks()).store(&mxc, &creator, b"thumbnail bytes").await.unwrap();
+
+        let reloaded = AvatarCache::new(dir.clone(), tasks());
+        let msn_object = reloaded.get_cached_msn_object(&mxc, &creator).await.unwrap();
+
+        assert_eq!(msn_object.sha1d, stored.sha1d);
+        assert_eq!(msn_object.size, "thumbnail bytes".len());
+        assert_eq!(msn_object.sha1c, stored.sha1c);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn file_sha1d_is_kept_apart_from_the_thumbnail() {
+        let dir = std::env::temp_dir().join(format!("tachyon-avatar-cache-{}", Uuid::new()));
+        let mxc = OwnedMxcUri::from("mxc://example.org/own");
+        let creator = EmailAddress::from_str("aeon@example.org").unwrap();
+
+        let cache = AvatarCache::new(dir.clone(), tasks());
+        assert!(cache.get_file_sha1d(&mxc).await.is_none());
+
+        let stored = cache.store(&mxc, &creator, b"thumbnail bytes").await.unwrap();
+        cache.store_file_sha1d(&mxc, "full file sha1d").await.unwrap();
+
+        let reloaded = AvatarCache::new(dir.clone(), tasks());
+        assert_eq!(reloaded.get_file_sha1d(&mxc).await.as_deref(), Some("full file sha1d"));
+        assert_eq!(reloaded.get_cached_msn_object(&mxc, &creator).await.unwrap().sha1d, stored.sha1d);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn unknown_mxc_is_a_miss() {
+        let dir = std::env::temp_dir().join(format!("tachyon-avatar-cache-{}", Uuid::new()));
+        let cache = AvatarCache::new(dir, tasks());
+        let creator = EmailAddress::from_str("aeon@example.org").unwrap();
+
+        assert!(cache.get_cached_msn_object(&OwnedMxcUri::from("mxc://example.org/missing"), &creator).await.is_none());
+    }
+}
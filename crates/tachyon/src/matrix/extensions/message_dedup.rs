@@ -1,17 +1,11 @@
-
 use dashmap::DashMap;
-use lazy_static::lazy_static;
 use matrix_sdk::{Error, Room};
-use matrix_sdk::room::futures::{SendMessageLikeEvent, SendMessageLikeEventResult};
+use matrix_sdk::room::futures::SendMessageLikeEventResult;
 use matrix_sdk::ruma::events::MessageLikeEventContent;
 use matrix_sdk::ruma::{EventId, OwnedEventId, RoomId, OwnedRoomId};
 use tokio::sync::Mutex;
 use std::sync::Arc;
 
-lazy_static! {
-    static ref DEDUP_STATE: DashMap<OwnedRoomId, RoomDedupState> = DashMap::new();
-}
-
 #[derive(Clone)]
 struct RoomDedupState {
     send_lock: Arc<Mutex<()>>,
@@ -27,44 +21,62 @@ impl RoomDedupState {
     }
 }
 
-pub trait SendWithDedup {
-    async fn send_with_dedup(&self, content: impl MessageLikeEventContent) -> Result<SendMessageLikeEventResult, Error>;
-
-    async fn is_event_deduped(&self, event_id: &EventId) -> bool;
+//Events we sent ourselves come back through sync, they are skipped so the client doesn't see them twice.
+//Owned by each TachyonClient: two accounts in the same room must not swallow each other's messages.
+#[derive(Default)]
+pub struct MessageDedup {
+    rooms: DashMap<OwnedRoomId, RoomDedupState>,
 }
 
-impl SendWithDedup for Room {
-    async fn send_with_dedup(&self, content: impl MessageLikeEventContent) -> Result<SendMessageLikeEventResult, Error> {
-        let room_id = self.room_id().to_owned();
+impl MessageDedup {
 
-        let state = DEDUP_STATE
-            .entry(room_id.clone())
-            .or_insert_with(RoomDedupState::new)
-            .clone();
+    pub async fn send_with_dedup(&self, room: &Room, content: impl MessageLikeEventContent) -> Result<SendMessageLikeEventResult, Error> {
+        let state = self.room_state(room.room_id());
 
         let _guard = state.send_lock.lock().await;
 
-        let result = self.send(content).await?;
-        let event_id = result.response.event_id.to_owned();
-
-        state.deduped_events.insert(event_id, ());
+        let result = room.send(content).await?;
+        state.deduped_events.insert(result.response.event_id.to_owned(), ());
 
         Ok(result)
     }
 
-    async fn is_event_deduped(&self, event_id: &EventId) -> bool {
-        let room_id = self.room_id();
+    pub async fn is_event_deduped(&self, room_id: &RoomId, event_id: &EventId) -> bool {
+        let state = match self.rooms.get(room_id) {
+            Some(state) => state.clone(),
+            None => return false
+        };
 
-        if let Some(state) = DEDUP_STATE.get(room_id) {
-            let _guard = state.send_lock.lock().await;
+        let _guard = state.send_lock.lock().await;
+        state.deduped_events.remove(event_id).is_some()
+    }
 
-            let deduped = state.deduped_events.contains_key(event_id);
-            if deduped {
-                state.deduped_events.remove(event_id);
-            }
-            deduped
-        } else {
-            false
-        }
+    fn room_state(&self, room_id: &RoomId) -> RoomDedupState {
+        self.rooms
+            .entry(room_id.to_owned())
+            .or_insert_with(RoomDedupState::new)
+            .clone()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use matrix_sdk::ruma::{owned_event_id, room_id};
+
+    use super::MessageDedup;
+
+    #[tokio::test]
+    async fn dedup_is_scoped_to_its_client() {
+        let room_id = room_id!("!shared:localhost");
+        let event_id = owned_event_id!("$sent_by_a:localhost");
+
+        let account_a = MessageDedup::default();
+        let account_b = MessageDedup::default();
+
+        account_a.room_state(room_id).deduped_events.insert(event_id.clone(), ());
+
+        assert!(!account_b.is_event_deduped(room_id, &event_id).await);
+        assert!(account_a.is_event_deduped(room_id, &event_id).await);
+        assert!(!account_a.is_event_deduped(room_id, &event_id).await);
+    }
+}
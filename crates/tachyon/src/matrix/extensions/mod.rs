@@ -1,3 +1,4 @@
 pub mod msn_user_resolver;
 pub mod direct;
-pub mod message_dedup;
\ No newline at end of file
+pub mod message_dedup;
+pub mod avatar_cache;
@@ -1,29 +1,54 @@
+use crate::matrix::extensions::avatar_cache::AvatarCache;
 use crate::matrix::extensions::direct::DirectRoom;
 use anyhow::Error;
-use base64::engine::general_purpose;
-use base64::Engine;
 use dashmap::DashMap;
-use lazy_static::lazy_static;
+use log::warn;
 use matrix_sdk::room::RoomMember;
-use matrix_sdk::ruma::{OwnedRoomId, RoomId, UserId};
+use matrix_sdk::ruma::{MxcUri, OwnedRoomId, RoomId, UserId};
 use matrix_sdk::{Client, Room};
-use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory};
+use msnp::shared::models::msn_object::MsnObject;
 use msnp::shared::models::{email_address::EmailAddress, msn_user::MsnUser};
 use sha1::digest::DynDigest;
 use sha1::{Digest, Sha1};
 use std::str::FromStr;
-use dashmap::mapref::one::Ref;
+use std::sync::Arc;
 use crate::tachyon::mappers::user_id::MatrixIdCompatible;
 
-lazy_static! {
-    static ref ROOM_HASH_TABLE: DashMap<String, OwnedRoomId> = DashMap::new();
-    static ref ROOM_HASH_CACHE: DashMap<OwnedRoomId, String> = DashMap::new();
+//What resolving a room needs, each TachyonClient owns its own so accounts never see each other's entries.
+#[derive(Clone)]
+pub struct ResolverCaches {
+    pub room_hashes: Arc<RoomHashCache>,
+    pub avatars: Arc<AvatarCache>,
+}
 
+#[derive(Default)]
+pub struct RoomHashCache {
+    room_ids: DashMap<String, OwnedRoomId>,
+    hashes: DashMap<OwnedRoomId, String>,
+}
+
+impl RoomHashCache {
+
+    fn hash(&self, room_id: &RoomId) -> String {
+        match self.hashes.get(room_id) {
+            None => {
+                let hash = hash_room_id(room_id);
+                self.room_ids.insert(hash.clone(), room_id.to_owned());
+                self.hashes.insert(room_id.to_owned(), hash.clone());
+                hash
+            }
+            Some(hash) => { hash.value().clone() }
+        }
+    }
+
+    fn room_id(&self, room_id_hashed: &str) -> Option<OwnedRoomId> {
+        self.room_ids.get(room_id_hashed).map(|entry| entry.value().clone())
+    }
 }
 
 pub trait ToMsnUser {
-    async fn to_msn_user(&self) -> Result<MsnUser, anyhow::Error>;
-    async fn to_msn_user_lazy(&self)  -> Result<MsnUser, anyhow::Error>;
+    async fn to_msn_user(&self, caches: &ResolverCaches) -> Result<MsnUser, anyhow::Error>;
+    async fn to_msn_user_lazy(&self, caches: &ResolverCaches)  -> Result<MsnUser, anyhow::Error>;
 }
 
 pub trait RoomMsnUserResolver {
@@ -33,18 +58,18 @@ pub trait RoomMsnUserResolver {
 
 
 impl ToMsnUser for Room {
-    async fn to_msn_user(&self) -> Result<MsnUser, anyhow::Error> {
-        to_msn_user_internal(self, false).await
+    async fn to_msn_user(&self, caches: &ResolverCaches) -> Result<MsnUser, anyhow::Error> {
+        to_msn_user_internal(self, caches, false).await
     }
 
-    async fn to_msn_user_lazy(&self) -> Result<MsnUser, Error> {
-        to_msn_user_internal(self, true).await
+    async fn to_msn_user_lazy(&self, caches: &ResolverCaches) -> Result<MsnUser, Error> {
+        to_msn_user_internal(self, caches, true).await
     }
 
 }
 
-async fn to_msn_user_internal(room: &Room, lazy_resolve: bool) -> Result<MsnUser, Error> {
-    let email = room.to_email_address()?;
+async fn to_msn_user_internal(room: &Room, caches: &ResolverCaches, lazy_resolve: bool) -> Result<MsnUser, Error> {
+    let email = room.to_email_address(&caches.room_hashes)?;
     let mut user = MsnUser::with_email_addr(email);
 
     let maybe_direct_target = if room.is_valid_one_to_one_direct() {
@@ -67,23 +92,29 @@ async fn to_msn_user_internal(room: &Room, lazy_resolve: bool) -> Result<MsnUser
 
 
     //Todo chek if direct_target for avatar.
-    // Check if we call call endpoint with OPTION or HEAD request to fetch image size and avoid downloading the image at this time
-
+    if let Some(avatar_mxc) = room.avatar_url() {
+        user.display_picture = get_display_picture(room, &caches.avatars, &avatar_mxc, user.get_email_address(), lazy_resolve).await;
+    }
 
-    if let Some(avatar_info) = room.avatar_info() {
-        //FIXME: Avatar MSNObject requires to compute the SHA1 of the bytes, let's see if we can avoid that.
-        // What happens if no SHA1 is set in the MSNObject?
+    Ok(user)
+}
 
-        let avatar_mxc = room.avatar_url().unwrap();
-        let base64_mxc =  general_purpose::STANDARD.encode(avatar_mxc.to_string());
+//Lazy resolution never waits on the homeserver: a cache miss is fetched in the background for next time.
+async fn get_display_picture(room: &Room, cache: &Arc<AvatarCache>, avatar_mxc: &MxcUri, email: &EmailAddress, lazy_resolve: bool) -> Option<MsnObject> {
+    let client = room.client();
 
+    if !lazy_resolve {
+        return cache.get_msn_object(&client, avatar_mxc, email).await
+            .inspect_err(|e| warn!("Could not get avatar {} for {}: {}", avatar_mxc, email, e))
+            .ok();
+    }
 
-        let size = usize::try_from(avatar_info.size.unwrap()).unwrap();
-        let display_picture = MSNObjectFactory::get_display_picture_no_bytes(size, user.get_email_address(), format!("{}.tmp", base64_mxc).to_string(), FriendlyName::default());
-        user.display_picture = Some(display_picture)
+    let cached = cache.get_cached_msn_object(avatar_mxc, email).await;
+    if cached.is_none() {
+        cache.warm(&client, avatar_mxc, email);
     }
 
-    Ok(user)
+    cached
 }
 
 pub trait ToRoomId {
@@ -91,19 +122,19 @@ pub trait ToRoomId {
 }
 
 pub trait ToEmailAddress {
-    fn to_email_address(&self) -> Result<EmailAddress, anyhow::Error>;
+    fn to_email_address(&self, room_hashes: &RoomHashCache) -> Result<EmailAddress, anyhow::Error>;
 }
 
 
 impl ToEmailAddress for Room {
-    fn to_email_address(&self) -> Result<EmailAddress, anyhow::Error> {
+    fn to_email_address(&self, room_hashes: &RoomHashCache) -> Result<EmailAddress, anyhow::Error> {
         let room_info = self.clone_info();
 
         let room_id_format = room_info.room_version_rules_or_default().room_id_format;
 
         let room_id = self.room_id();
 
-        let room_id_hashed = hash_room_id_cached(room_id);
+        let room_id_hashed = room_hashes.hash(room_id);
 
         match room_id_format {
             matrix_sdk::ruma::room_version_rules::RoomIdFormatVersion::V1 => {
@@ -145,24 +176,23 @@ impl ToEmailAddress for Room {
 }
 
 pub trait FindRoomFromEmail {
-    fn find_room_from_email(&self, email: &EmailAddress) -> Result<Option<Room>, anyhow::Error>;
+    fn find_room_from_email(&self, email: &EmailAddress, room_hashes: &RoomHashCache) -> Result<Option<Room>, anyhow::Error>;
 }
 
 impl FindRoomFromEmail for Client {
     
-    fn find_room_from_email(&self, email: &EmailAddress) -> Result<Option<Room>, Error> {
+    fn find_room_from_email(&self, email: &EmailAddress, room_hashes: &RoomHashCache) -> Result<Option<Room>, Error> {
 
         let (room_id_hashed, server_name) = email.crack();
 
-        let out = if let Some(entry) = ROOM_HASH_TABLE.get(room_id_hashed) {
-            let room_id = entry.value();
+        let out = if let Some(room_id) = room_hashes.room_id(room_id_hashed) {
             self.get_room(room_id.as_ref())
         } else {
             
             let mut found = None;
             
             for curent_room in self.rooms() {
-                let current_room_hash = hash_room_id_cached(curent_room.room_id());
+                let current_room_hash = room_hashes.hash(curent_room.room_id());
                 if current_room_hash == room_id_hashed {
                     found = Some(curent_room);
                     break;
@@ -176,17 +206,6 @@ impl FindRoomFromEmail for Client {
 }
 
 
-fn hash_room_id_cached(room_id: &RoomId) -> String {
-    match ROOM_HASH_CACHE.get(room_id) {
-        None => {
-            let hash = hash_room_id(room_id);
-            ROOM_HASH_TABLE.insert(hash.clone(), room_id.to_owned());
-            ROOM_HASH_CACHE.insert(room_id.to_owned(), hash.clone());
-            hash
-        }
-        Some(hash) => { hash.value().clone() }
-    }
-}
 fn hash_room_id(room_id: &RoomId) -> String {
     let mut hasher = Sha1::new();
     Digest::update(&mut hasher, room_id.as_bytes());
@@ -195,7 +214,7 @@ fn hash_room_id(room_id: &RoomId) -> String {
 }
 
 impl ToMsnUser for RoomMember {
-    async fn to_msn_user(&self) -> Result<MsnUser, Error> {
+    async fn to_msn_user(&self, _caches: &ResolverCaches) -> Result<MsnUser, Error> {
         let mut msn_user = MsnUser::from_user_id(self.user_id());
         msn_user.display_name = self.display_name().map(|name| name.to_string());
 
@@ -204,7 +223,7 @@ impl ToMsnUser for RoomMember {
         Ok(msn_user)
     }
 
-    async fn to_msn_user_lazy(&self) -> Result<MsnUser, Error> {
+    async fn to_msn_user_lazy(&self, _caches: &ResolverCaches) -> Result<MsnUser, Error> {
         let mut msn_user = MsnUser::from_user_id(self.user_id());
         msn_user.display_name = self.display_name().map(|name| name.to_string());
 
@@ -214,7 +233,27 @@ impl ToMsnUser for RoomMember {
 }
 
 impl ToEmailAddress for RoomMember {
-    fn to_email_address(&self) -> Result<EmailAddress, Error> {
+    fn to_email_address(&self, _room_hashes: &RoomHashCache) -> Result<EmailAddress, Error> {
         Ok(EmailAddress::from_user_id(self.user_id()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use matrix_sdk::ruma::room_id;
+
+    use super::{hash_room_id, RoomHashCache};
+
+    #[test]
+    fn room_hash_cache_is_owned_by_its_client() {
+        let account_a = RoomHashCache::default();
+        let account_b = RoomHashCache::default();
+
+        let room_id = room_id!("!shared:localhost");
+        let hash = account_a.hash(room_id);
+
+        assert_eq!(hash, hash_room_id(room_id));
+        assert_eq!(account_a.room_id(&hash).as_deref(), Some(room_id));
+        assert!(account_b.room_id(&hash).is_none());
+    }
+}
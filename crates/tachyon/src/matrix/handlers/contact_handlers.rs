@@ -1,4 +1,4 @@
-use crate::matrix::extensions::msn_user_resolver::ToMsnUser;
+use crate::matrix::extensions::msn_user_resolver::{ResolverCaches, ToMsnUser};
 use crate::notification::models::soap_holder::AddressBookContact;
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use matrix_sdk::deserialized_responses::RawSyncOrStrippedState;
@@ -22,7 +22,7 @@ pub(super) async fn handle_contacts(
 
     println!("Handling contact event for room: {}", room.room_id().to_string());
 
-    let mut contacts = compute_contacts(&event, &room).await.unwrap();
+    let mut contacts = compute_contacts(&event, &room, tachyon_client.resolver_caches()).await.unwrap();
 
     if !contacts.is_empty() {
         let mut contact_holder = tachyon_client.soap_holder().contacts.lock().unwrap();
@@ -31,13 +31,13 @@ pub(super) async fn handle_contacts(
 
 }
 
-async fn compute_contacts(event: &SyncRoomMemberEvent, room: &Room) -> Result<Vec<AddressBookContact>, anyhow::Error> {
+async fn compute_contacts(event: &SyncRoomMemberEvent, room: &Room, caches: &ResolverCaches) -> Result<Vec<AddressBookContact>, anyhow::Error> {
     let mut out = Vec::new();
     let event_is_about_me = event.state_key() == room.own_user_id();
 
     if event_is_about_me {
 
-        let room_msn_user = room.to_msn_user().await?;
+        let room_msn_user = room.to_msn_user(caches).await?;
 
         match event.membership() {
             MembershipState::Ban => {
@@ -78,7 +78,7 @@ pub(super) async fn handle_contacts_stripped(
     }
 
 
-    let mut contacts = compute_contacts_from_stripped_event(&event, &room).await.unwrap();
+    let mut contacts = compute_contacts_from_stripped_event(&event, &room, tachyon_client.resolver_caches()).await.unwrap();
 
     if !contacts.is_empty() {
         let mut contact_holder = tachyon_client.soap_holder().contacts.lock().unwrap();
@@ -87,14 +87,14 @@ pub(super) async fn handle_contacts_stripped(
 
 }
 
-async fn compute_contacts_from_stripped_event(event: &StrippedRoomMemberEvent, room: &Room) ->  Result<Vec<AddressBookContact>, anyhow::Error> {
+async fn compute_contacts_from_stripped_event(event: &StrippedRoomMemberEvent, room: &Room, caches: &ResolverCaches) ->  Result<Vec<AddressBookContact>, anyhow::Error> {
     let mut out = Vec::new();
 
     let event_is_about_me = event.state_key == room.own_user_id();
 
     if event_is_about_me {
 
-        let msn_user = room.to_msn_user().await?;
+        let msn_user = room.to_msn_user(caches).await?;
 
         match room.state() {
             RoomState::Invited => {
@@ -116,7 +116,7 @@ async fn compute_contacts_from_stripped_event(event: &StrippedRoomMemberEvent, r
 
 }
 
-pub async fn compute_all_contacts(client: Client) -> Vec<AddressBookContact> {
+pub async fn compute_all_contacts(client: Client, caches: &ResolverCaches) -> Vec<AddressBookContact> {
     let mut out = Vec::new();
 
     for room in client.rooms() {
@@ -124,13 +124,13 @@ pub async fn compute_all_contacts(client: Client) -> Vec<AddressBookContact> {
             match event {
                 RawSyncOrStrippedState::Sync(sync) => {
                     let deserialized = sync.deserialize().unwrap();
-                    let mut memberships = compute_contacts(&deserialized, &room).await.unwrap();
+                    let mut memberships = compute_contacts(&deserialized, &room, caches).await.unwrap();
                     out.append(&mut memberships);
 
                 }
                 RawSyncOrStrippedState::Stripped(stripped) => {
                     let deserialized = stripped.deserialize().unwrap();
-                    let mut stripped_memberships = compute_contacts_from_stripped_event(&deserialized, &room).await.unwrap();
+                    let mut stripped_memberships = compute_contacts_from_stripped_event(&deserialized, &room, caches).await.unwrap();
                     out.append(&mut stripped_memberships);
                 }
             }
@@ -145,7 +145,7 @@ pub(super) async fn handle_tombstone(    event: OriginalSyncRoomTombstoneEvent,
                                tachyon_client: TachyonClient,
                                client: Client) {
 
-    let room_msn_user = room.to_msn_user().await.unwrap();
+    let room_msn_user = room.to_msn_user(tachyon_client.resolver_caches()).await.unwrap();
 
     let contact = ContactType::new(&room_msn_user, ContactTypeEnum::Live, true);
 
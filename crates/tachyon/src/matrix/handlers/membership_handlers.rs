@@ -1,4 +1,4 @@
-use crate::matrix::extensions::msn_user_resolver::ToMsnUser;
+use crate::matrix::extensions::msn_user_resolver::{ResolverCaches, ToMsnUser};
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use matrix_sdk::deserialized_responses::RawSyncOrStrippedState;
 use matrix_sdk::ruma::events::room::member::{MembershipState, RoomMemberEventContent};
@@ -20,7 +20,7 @@ pub(super) async fn handle_memberships(
         return;
     }
 
-    let mut members = compute_memberships(&event, &room).await.unwrap();
+    let mut members = compute_memberships(&event, &room, tachyon_client.resolver_caches()).await.unwrap();
 
     if !members.is_empty() {
         let mut member_holder = tachyon_client.soap_holder().memberships.lock().unwrap();
@@ -29,14 +29,14 @@ pub(super) async fn handle_memberships(
 
 }
 
-async fn compute_memberships(event: &SyncRoomMemberEvent, room: &Room) -> Result<Vec<BaseMember>, anyhow::Error> {
+async fn compute_memberships(event: &SyncRoomMemberEvent, room: &Room, caches: &ResolverCaches) -> Result<Vec<BaseMember>, anyhow::Error> {
     let mut out = Vec::new();
 
     let event_is_about_me = event.state_key() == room.own_user_id();
 
     if event_is_about_me {
 
-        let msn_user = room.to_msn_user().await?;
+        let msn_user = room.to_msn_user(caches).await?;
 
         match event.membership() {
             MembershipState::Ban => {
@@ -83,7 +83,7 @@ pub(super) async fn handle_memberships_stripped(
         return;
     }
     
-    let mut members = compute_memberships_from_stripped_event(&event, &room).await.unwrap();
+    let mut members = compute_memberships_from_stripped_event(&event, &room, tachyon_client.resolver_caches()).await.unwrap();
 
     if !members.is_empty() {
         let mut member_holder = tachyon_client.soap_holder().memberships.lock().unwrap();
@@ -96,6 +96,7 @@ pub(super) async fn handle_memberships_stripped(
 async fn compute_memberships_from_stripped_event(
     event: &StrippedRoomMemberEvent,
     room: &Room,
+    caches: &ResolverCaches,
 ) -> Result<Vec<BaseMember>, anyhow::Error> {
 
     let mut out = Vec::new();
@@ -104,7 +105,7 @@ async fn compute_memberships_from_stripped_event(
 
     if event_is_about_me {
 
-        let msn_user = room.to_msn_user().await?;
+        let msn_user = room.to_msn_user(caches).await?;
 
         match room.state() {
             RoomState::Invited => {
@@ -128,7 +129,7 @@ async fn compute_memberships_from_stripped_event(
     Ok(out)
 }
 
-pub async fn compute_all_memberships(client: Client) -> Vec<BaseMember> {
+pub async fn compute_all_memberships(client: Client, caches: &ResolverCaches) -> Vec<BaseMember> {
     let mut out = Vec::new();
 
     for room in client.rooms() {
@@ -136,13 +137,13 @@ pub async fn compute_all_memberships(client: Client) -> Vec<BaseMember> {
             match event {
                 RawSyncOrStrippedState::Sync(sync) => {
                     let deserialized = sync.deserialize().unwrap();
-                    let mut memberships = compute_memberships(&deserialized, &room).await.unwrap();
+                    let mut memberships = compute_memberships(&deserialized, &room, caches).await.unwrap();
                     out.append(&mut memberships);
 
                 }
                 RawSyncOrStrippedState::Stripped(stripped) => {
                     let deserialized = stripped.deserialize().unwrap();
-                    let mut stripped_memberships = compute_memberships_from_stripped_event(&deserialized, &room).await.unwrap();
+                    let mut stripped_memberships = compute_memberships_from_stripped_event(&deserialized, &room, caches).await.unwrap();
                     out.append(&mut stripped_memberships);
                 }
             }
@@ -157,7 +158,7 @@ pub(super) async fn handle_tombstone(event: OriginalSyncRoomTombstoneEvent,
                                tachyon_client: TachyonClient,
                                client: Client) {
 
-    let room_msn_user = room.to_msn_user().await.unwrap();
+    let room_msn_user = room.to_msn_user(tachyon_client.resolver_caches()).await.unwrap();
 
 
     let delete_allow_member = BaseMember::new_passport_member(&room_msn_user, MemberState::Accepted, RoleList::Allow, true);
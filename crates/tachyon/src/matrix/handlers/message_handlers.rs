@@ -1,5 +1,4 @@
 use crate::matrix::extensions::direct::DirectRoom;
-use crate::matrix::extensions::message_dedup::SendWithDedup;
 use crate::matrix::extensions::msn_user_resolver::ToMsnUser;
 use crate::switchboard::extensions::CustomStyles;
 use crate::tachyon::client::tachyon_client::TachyonClient;
@@ -24,11 +23,11 @@ pub async fn handle_message(
     client: Client,
 ) {
 
-    if room.is_event_deduped(event.event_id.as_ref()).await {
+    if tachyon_client.message_dedup().is_event_deduped(room.room_id(), event.event_id.as_ref()).await {
         return;
     }
 
-    let room_user = room.to_msn_user_lazy().await.unwrap();
+    let room_user = room.to_msn_user_lazy(tachyon_client.resolver_caches()).await.unwrap();
     let switchboard = tachyon_client.switchboards().get_or_initialize(room.room_id(), &room_user);
 
 
@@ -36,7 +35,7 @@ pub async fn handle_message(
 
         match room.get_single_direct_target() {
             None => {
-                room.get_member_no_sync(event.sender.as_ref()).await.unwrap().unwrap().to_msn_user_lazy().await.unwrap()
+                room.get_member_no_sync(event.sender.as_ref()).await.unwrap().unwrap().to_msn_user_lazy(tachyon_client.resolver_caches()).await.unwrap()
             }
             Some(direct_target) => {
                 room_user.clone()
@@ -52,30 +51,39 @@ pub async fn handle_message(
 
     match &event.content.msgtype {
         MessageType::Audio(audio) => {
-            //Audio goes out as a WLM voice clip when it fits in one, and as a plain file otherwise.
-            match tachyon_client.prepare_voice_clip(&room_user, audio).await {
-                Ok(msn_object) => {
-                    let voice_clip = SwitchboardServerCommand::MSG(MsgServer {
-                        sender: room_user.get_email_address().clone(),
-                        display_name: DisplayName::new_from_ref(message_sender.compute_display_name()),
-                        payload: MsgPayload::Datacast(DatacastMessagePayload::new_msn_object(msn_object)),
-                    });
-                    
-                    let notice = SwitchboardServerCommand::MSG(MsgServer {
-                       sender: message_sender.get_email_address().clone(), 
-                        display_name: DisplayName::new_from_ref(message_sender.compute_display_name()),
-                        payload: MsgPayload::TextPlain(TextPlainMessagePayload {
-                            font_family: Default::default(),
-                            right_to_left: false,
-                            font_styles: Default::default(),
-                            font_color: Default::default(),
-                            body: "has sent you an audio message".to_string(),
-                        })
-
-                    });
-                    
-                    switchboard.receive_command(notice).await.unwrap();
-                    switchboard.receive_command(voice_clip).await.unwrap();
+            //Audio goes out as WLM voice clips, split in parts when it's long, and as a plain file when it's way too long.
+            match tachyon_client.prepare_voice_clips(&room_user, audio).await {
+                Ok(msn_objects) => {
+                    let part_count = msn_objects.len();
+
+                    for (index, msn_object) in msn_objects.into_iter().enumerate() {
+                        let voice_clip = SwitchboardServerCommand::MSG(MsgServer {
+                            sender: room_user.get_email_address().clone(),
+                            display_name: DisplayName::new_from_ref(message_sender.compute_display_name()),
+                            payload: MsgPayload::Datacast(DatacastMessagePayload::new_msn_object(msn_object)),
+                        });
+
+                        let body = if part_count > 1 {
+                            format!("has sent you an audio message (part {}/{})", index + 1, part_count)
+                        } else {
+                            "has sent you an audio message".to_string()
+                        };
+
+                        let notice = SwitchboardServerCommand::MSG(MsgServer {
+                            sender: message_sender.get_email_address().clone(),
+                            display_name: DisplayName::new_from_ref(message_sender.compute_display_name()),
+                            payload: MsgPayload::TextPlain(TextPlainMessagePayload {
+                                font_family: Default::default(),
+                                right_to_left: false,
+                                font_styles: Default::default(),
+                                font_color: Default::default(),
+                                body,
+                            })
+                        });
+
+                        switchboard.receive_command(notice).await.unwrap();
+                        switchboard.receive_command(voice_clip).await.unwrap();
+                    }
                 }
                 Err(e) => {
                     info!("Could not send audio message as a voice clip, falling back to a file transfer: {}", e);
@@ -97,7 +105,7 @@ pub async fn handle_message(
                     let size = audio.info.as_ref().map( |i| i.size.map(|u| usize::try_from(u).unwrap_or(0))).flatten().unwrap_or(0);
                     let filename = audio.filename.as_ref().unwrap_or(&audio.body).to_owned();
                     //TODO fix filename
-                    tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, audio.source.clone()).await;
+                    tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, audio.source.clone(), None).await;
                 }
             }
         }
@@ -108,14 +116,10 @@ pub async fn handle_message(
             let size = file.info.as_ref().map( |i| i.size.map(|u| usize::try_from(u).unwrap_or(0))).flatten().unwrap_or(0);
             let filename = file.filename.as_ref().unwrap_or(&file.body).to_owned();
             //TODO fix filename
-            tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, file.source.clone()).await;
+            tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, file.source.clone(), None).await;
         }
         MessageType::Image(image) => {
-
-            let size = image.info.as_ref().map( |i| i.size.map(|u| usize::try_from(u).unwrap_or(0))).flatten().unwrap_or(0);
-            let filename = image.filename.as_ref().unwrap_or(&image.body).to_owned();
-            //TODO fix filename
-            tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, image.source.clone()).await;
+            tachyon_client.receive_image(room.room_id(), &room_user, &message_sender, image).await;
         }
         MessageType::Location(_) => {}
         MessageType::Notice(message) => {
@@ -148,7 +152,7 @@ pub async fn handle_message(
             let size = video.info.as_ref().map( |i| i.size.map(|u| usize::try_from(u).unwrap_or(0))).flatten().unwrap_or(0);
             let filename = video.filename.as_ref().unwrap_or(&video.body).to_owned();
             //TODO fix filename
-            tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, video.source.clone()).await;
+            tachyon_client.receive_file(room.room_id(), &room_user, &message_sender, size, filename, video.source.clone(), None).await;
         }
         MessageType::VerificationRequest(_) => {}
         MessageType::_Custom(_) => {
@@ -181,7 +185,7 @@ pub(crate) async fn handle_typing_notice(event: SyncTypingEvent, room: Room, tac
                 let sender = {
                     let member = room.get_member_no_sync(&user_id).await;
                     if let Ok(Some(member)) = member {
-                        if let Ok(member) = member.to_msn_user_lazy().await {
+                        if let Ok(member) = member.to_msn_user_lazy(tachyon_client.resolver_caches()).await {
                             member
                         } else {
                             MsnUser::from_user_id(&user_id)
@@ -9,6 +9,7 @@ use matrix_sdk::ruma::UserId;
 use matrix_sdk::ruma::{device_id, DeviceId, OwnedDeviceId, OwnedUserId};
 use matrix_sdk::{async_trait, AuthSession, Client, ClientBuilder, ServerName, SessionTokens};
 use matrix_sdk_ui::sync_service::SyncService;
+use reqwest::Url;
 use tokio::fs::create_dir_all;
 use msnp::shared::models::ticket_token::TicketToken;
 
@@ -26,11 +27,18 @@ pub trait MatrixLoginService: Send + Sync {
 }
 
 #[derive(Clone)]
-pub struct MatrixLoginServiceImpl {}
+pub struct MatrixLoginServiceImpl {
+    //None resolves the homeserver from the server name of the user id.
+    homeserver_url: Option<Url>,
+}
 
 impl MatrixLoginServiceImpl {
     pub fn new() -> Self {
-        Self {}
+        Self { homeserver_url: None }
+    }
+
+    pub fn with_homeserver_url(homeserver_url: Option<Url>) -> Self {
+        Self { homeserver_url }
     }
 }
 
@@ -40,7 +48,7 @@ impl MatrixLoginService for MatrixLoginServiceImpl {
         let device_id = get_device_id(user_id)?;
         let store_path = get_store_path(user_id);
 
-        let client = get_matrix_client_builder(user_id.server_name(), None, disable_ssl)
+        let client = get_matrix_client_builder(user_id.server_name(), self.homeserver_url.as_ref().map(Url::to_string), disable_ssl)
             .sqlite_store(store_path, None)
             .build()
             .await?;
@@ -58,7 +66,7 @@ impl MatrixLoginService for MatrixLoginServiceImpl {
     async fn login_with_password(&self, matrix_id: &UserId, password: &str, disable_ssl: bool) -> Result<(AccessToken, Client), TachyonError> {
         create_dir(get_user_data(matrix_id).as_path());
 
-        let client = get_matrix_client_builder(matrix_id.server_name(), None, disable_ssl).build().await?;
+        let client = get_matrix_client_builder(matrix_id.server_name(), self.homeserver_url.as_ref().map(Url::to_string), disable_ssl).build().await?;
         let device_id = get_device_id(matrix_id).ok();
 
 
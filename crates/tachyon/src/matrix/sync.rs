@@ -1,8 +1,11 @@
 use crate::matrix::handlers::context::TachyonContext;
 use crate::matrix::handlers::{self, register_event_handlers};
 use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::config::tachyon_config::SyncMode;
+use crate::notification::presence::{send_contacts_offline, send_contacts_online};
+use anyhow::anyhow;
 use futures::StreamExt;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use matrix_sdk::deserialized_responses::RawAnySyncOrStrippedState;
 use matrix_sdk::event_handler::Ctx;
 use matrix_sdk::ruma::api::client::error::ErrorKind;
@@ -14,6 +17,8 @@ use matrix_sdk::ruma::events::direct::{DirectEvent, DirectEventContent};
 use matrix_sdk::ruma::events::room::member::{StrippedRoomMemberEvent, SyncRoomMemberEvent};
 use matrix_sdk::ruma::events::{GlobalAccountDataEventType, StateEventType};
 use matrix_sdk::ruma::{assign, OwnedRoomId, RoomId, UInt, UserId};
+use matrix_sdk::config::SyncSettings;
+use matrix_sdk::sliding_sync::Version as SlidingSyncVersion;
 use matrix_sdk::sync::RoomUpdates;
 use matrix_sdk::{
     Client, Error, Room, SlidingSync, SlidingSyncList, SlidingSyncListBuilder, SlidingSyncMode,
@@ -24,7 +29,6 @@ use msnp::msnp::notification::command::msg::{MsgPayload, MsgServer};
 use msnp::msnp::raw_command_parser::RawCommand;
 use msnp::shared::payload::msg::raw_msg_payload::factories::RawMsgPayloadFactory;
 use tokio::sync::broadcast::{Receiver, Sender};
-use tokio::sync::mpsc;
 use tokio::sync::mpsc::error::SendTimeoutError;
 use tokio::task::JoinHandle;
 use msnp::msnp::notification::command::nln::NlnServer;
@@ -32,6 +36,11 @@ use msnp::msnp::notification::command::not::factories::NotificationFactory;
 use msnp::msnp::notification::command::not::{NotServer, NotificationPayloadType};
 use msnp::shared::models::display_name::DisplayName;
 use msnp::shared::models::presence_status::PresenceStatus;
+use std::time::Duration;
+use tokio_retry2::strategy::{jitter, ExponentialBackoff};
+use tokio_retry2::{Retry, RetryError};
+
+const CLASSIC_SYNC_TIMEOUT: Duration = Duration::from_secs(30);
 
 const REQUIRED_STATE: &[(StateEventType, &str)] = &[
     (StateEventType::RoomName, ""),
@@ -67,35 +76,37 @@ pub async fn build_sliding_sync(matrix_client: &Client) -> Result<SlidingSync, a
     Ok(sliding_sync_builder.build().await?)
 }
 
-pub async fn sync(tachyon_client: TachyonClient, matrix_client: Client, kill_signal_snd: Sender<()>, kill_signal_rcv: Receiver<()>) -> JoinHandle<()> {
-    let sliding_sync = build_sliding_sync(&matrix_client).await.unwrap();
-    let updates_recv = matrix_client.subscribe_to_all_room_updates();
+pub async fn use_sliding_sync(matrix_client: &Client, sync_mode: &SyncMode) -> bool {
+    match sync_mode {
+        SyncMode::SlidingSync => true,
+        SyncMode::Classic => false,
+        SyncMode::Auto => {
+            let supported = matrix_client.available_sliding_sync_versions().await
+                .iter()
+                .any(|version| matches!(version, SlidingSyncVersion::Native));
 
-    spawn_sync_task(tachyon_client, matrix_client, sliding_sync, updates_recv, kill_signal_snd, kill_signal_rcv)
+            if !supported {
+                info!("Homeserver doesn't advertise Simplified Sliding Sync, falling back to classic /sync");
+            }
+            supported
+        }
+    }
 }
 
+//Both backends feed the same event handlers and room update stream, only the request loop differs.
+#[derive(Clone)]
+enum SyncBackend {
+    Sliding(SlidingSync),
+    Classic,
+}
 
-fn spawn_sync_task(
-    tachyon_client: TachyonClient,
-    matrix_client: Client,
-    sliding_sync: SlidingSync,
-    mut updates_recv: Receiver<RoomUpdates>,
-    client_shutdown_snd: Sender<()>,
-    mut client_shutdown_rcv: Receiver<()>,
-) -> JoinHandle<()> {
-    tokio::spawn(async move {
-        info!("Initializing Sliding Sync...");
-        let (handler_drop_guards, context_drop_guard) =
-            register_event_handlers(&matrix_client, tachyon_client.clone());
-
-        info!("Starting Sliding Sync...");
-        let (error_tx, mut error_rx) =
-            mpsc::channel::<matrix_sdk::ruma::api::client::error::ErrorKind>(1);
-
-        let mut sync_handle = tokio::spawn({
-            let sliding_sync = sliding_sync.clone();
+impl SyncBackend {
 
-            async move {
+    //resume_token only applies to classic /sync, when the stored token can't be trusted.
+    //It's handed back until a response went through, so a restart still resumes from it.
+    async fn run(self, matrix_client: Client, mut resume_token: Option<String>) -> (SyncInterruption, Option<String>) {
+        match self {
+            SyncBackend::Sliding(sliding_sync) => {
                 let mut sync_stream = Box::pin(sliding_sync.sync());
                 loop {
                     match sync_stream.next().await {
@@ -106,31 +117,105 @@ fn spawn_sync_task(
                             );
                         }
                         Some(Err(err)) => {
-                            if let Some(error_kind) = err.client_api_error_kind() {
-                                error!("Error in sync stream: {:?}", error_kind);
-                                let _ = error_tx.send(error_kind.clone()).await;
-                            } else {
-                                error!("Error in sync stream: {:?}", err);
-                            }
-                            break;
+                            error!("Error in sync stream: {:?}", err);
+                            return (SyncInterruption::from_error(&err), resume_token);
                         }
                         None => {
                             error!("Sync stream ended unexpectedly");
-                            break;
+                            return (SyncInterruption::ConnectionLost, resume_token);
                         }
                     }
                 }
             }
-        });
+            SyncBackend::Classic => {
+                //The sync token is persisted in the store, restarts resume where the last response left off.
+                let sync_settings = match resume_token.clone() {
+                    Some(token) => SyncSettings::default().timeout(CLASSIC_SYNC_TIMEOUT).token(token),
+                    None => SyncSettings::default().timeout(CLASSIC_SYNC_TIMEOUT),
+                };
+                let mut sync_stream = Box::pin(matrix_client.sync_stream(sync_settings).await);
+                loop {
+                    match sync_stream.next().await {
+                        Some(Ok(response)) => {
+                            info!("Received /sync response with next_batch: {}", &response.next_batch);
+                            resume_token = None;
+                        }
+                        Some(Err(err)) => {
+                            error!("Error in sync stream: {:?}", err);
+                            return (SyncInterruption::from_error(&err), resume_token);
+                        }
+                        None => {
+                            error!("Sync stream ended unexpectedly");
+                            return (SyncInterruption::ConnectionLost, resume_token);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop(&self) {
+        if let SyncBackend::Sliding(sliding_sync) = self {
+            if let Err(err) = sliding_sync.stop_sync() {
+                error!("Error stopping sync loop: {:?}", err);
+            }
+        }
+    }
+}
+
+pub async fn sync(tachyon_client: TachyonClient, matrix_client: Client, resume_token: Option<String>, kill_signal_snd: Sender<()>, kill_signal_rcv: Receiver<()>) -> JoinHandle<()> {
+    let sync_backend = if use_sliding_sync(&matrix_client, &tachyon_client.config().sync_mode).await {
+        SyncBackend::Sliding(build_sliding_sync(&matrix_client).await.unwrap())
+    } else {
+        SyncBackend::Classic
+    };
+    let updates_recv = matrix_client.subscribe_to_all_room_updates();
+
+    spawn_sync_task(tachyon_client, matrix_client, sync_backend, resume_token, updates_recv, kill_signal_snd, kill_signal_rcv)
+}
+
+
+//Why the sync stream stopped, decides between restarting, waiting for the homeserver or logging out.
+#[derive(Debug)]
+enum SyncInterruption {
+    UnknownPos,
+    UnknownToken,
+    ConnectionLost,
+}
+
+impl SyncInterruption {
+    fn from_error(err: &Error) -> Self {
+        match err.client_api_error_kind() {
+            Some(ErrorKind::UnknownPos) => SyncInterruption::UnknownPos,
+            Some(ErrorKind::UnknownToken { .. }) => SyncInterruption::UnknownToken,
+            _ => SyncInterruption::ConnectionLost
+        }
+    }
+}
+
+fn spawn_sync_task(
+    tachyon_client: TachyonClient,
+    matrix_client: Client,
+    sync_backend: SyncBackend,
+    mut resume_token: Option<String>,
+    mut updates_recv: Receiver<RoomUpdates>,
+    client_shutdown_snd: Sender<()>,
+    mut client_shutdown_rcv: Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Initializing sync...");
+        let (handler_drop_guards, context_drop_guard) =
+            register_event_handlers(&matrix_client, tachyon_client.clone());
+
+        info!("Starting sync...");
+        let mut sync_handle = tokio::spawn(sync_backend.clone().run(matrix_client.clone(), resume_token.clone()));
 
         let mut restart_sync = false;
         loop {
             tokio::select! {
                 _ = client_shutdown_rcv.recv() => {
                     info!("Gracefully exit sync loop...");
-                    if let Err(err) = sliding_sync.stop_sync() {
-                        error!("Error stopping sync loop: {:?}", err);
-                    }
+                    sync_backend.stop();
                     sync_handle.abort();
                     break;
                 }
@@ -148,21 +233,42 @@ fn spawn_sync_task(
                         }
                     }
                 }
-                error_kind = error_rx.recv() => {
-                    if let Some(matrix_sdk::ruma::api::client::error::ErrorKind::UnknownPos) = error_kind {
-                        info!("Unknown pos detected, re-syncing...");
-                        sync_handle.abort();
-                        restart_sync = true;
-                        break;
-                    } else {
-                        error!("Unrecoverable sync error: {:?}", error_kind);
-                        let _ = client_shutdown_snd.send(());
-                        sync_handle.abort();
-                        break;
+                result = &mut sync_handle => {
+                    let (interruption, unused_resume_token) = result.unwrap_or((SyncInterruption::ConnectionLost, resume_token.clone()));
+                    resume_token = unused_resume_token;
+                    match interruption {
+                        SyncInterruption::UnknownPos => {
+                            info!("Unknown pos detected, re-syncing...");
+                            restart_sync = true;
+                        }
+                        SyncInterruption::UnknownToken => {
+                            error!("Access token is not valid anymore, logging out...");
+                            let _ = client_shutdown_snd.send(());
+                        }
+                        SyncInterruption::ConnectionLost => {
+                            warn!("Lost connection to the homeserver, waiting for it to come back...");
+                            set_disconnected(&tachyon_client).await;
+
+                            tokio::select! {
+                                _ = client_shutdown_rcv.recv() => {
+                                    info!("Gracefully exit sync loop while disconnected...");
+                                }
+                                reconnected = wait_for_homeserver(&matrix_client) => {
+                                    match reconnected {
+                                        Ok(()) => {
+                                            info!("Homeserver is reachable again, re-syncing...");
+                                            set_reconnected(&tachyon_client, &matrix_client).await;
+                                            restart_sync = true;
+                                        }
+                                        Err(err) => {
+                                            error!("Unrecoverable sync error: {:?}", err);
+                                            let _ = client_shutdown_snd.send(());
+                                        }
+                                    }
+                                }
+                            }
+                        }
                     }
-                }
-                _ = &mut sync_handle => {
-                    info!("Sync handle completed");
                     break;
                 }
             }
@@ -174,7 +280,8 @@ fn spawn_sync_task(
             spawn_sync_task(
                 tachyon_client,
                 matrix_client,
-                sliding_sync.clone(),
+                sync_backend,
+                resume_token,
                 updates_recv,
                 client_shutdown_snd,
                 client_shutdown_rcv,
@@ -185,6 +292,57 @@ fn spawn_sync_task(
     })
 }
 
+fn reconnect_strategy() -> impl Iterator<Item = Duration> {
+    //1s, 2s, 4s... capped at a minute, jittered so a homeserver restart isn't hammered by every client at once.
+    ExponentialBackoff::from_millis(2)
+        .factor(500)
+        .max_delay(Duration::from_secs(60))
+        .map(jitter)
+}
+
+//Only a revoked token is permanent, anything else (timeouts, 5xx, rate limits) is worth retrying.
+async fn wait_for_homeserver(matrix_client: &Client) -> Result<(), anyhow::Error> {
+    Retry::spawn(reconnect_strategy(), || async {
+        match matrix_client.whoami().await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                if let Some(ErrorKind::UnknownToken { .. }) = err.client_api_error_kind() {
+                    RetryError::to_permanent(anyhow!("Access token is not valid anymore: {}", err))
+                } else {
+                    debug!("Homeserver still unreachable: {}", err);
+                    RetryError::to_transient(anyhow!("Homeserver unreachable: {}", err))
+                }
+            }
+        }
+    }).await
+}
+
+async fn set_disconnected(tachyon_client: &TachyonClient) {
+    tachyon_client.set_homeserver_reachable(false);
+
+    let contacts = tachyon_client.get_contact_list().lock().unwrap().get_forward_list();
+    send_contacts_offline(contacts, tachyon_client).await;
+
+    send_connection_alert(tachyon_client, "Tachyon lost the connection to your Matrix homeserver. Your contacts will appear offline until it reconnects.").await;
+}
+
+async fn set_reconnected(tachyon_client: &TachyonClient, matrix_client: &Client) {
+    tachyon_client.set_homeserver_reachable(true);
+
+    let contacts = tachyon_client.get_contact_list().lock().unwrap().get_forward_list();
+    //Contacts coming back online is notice enough, no need for a second alert.
+    send_contacts_online(contacts, tachyon_client, matrix_client).await;
+}
+
+async fn send_connection_alert(tachyon_client: &TachyonClient, msg: &str) {
+    let user = tachyon_client.own_user();
+    let tachyon_url = tachyon_client.config().web_url("/tachyon");
+
+    let _ = tachyon_client.notification_handle().send(NotificationServerCommand::NOT(NotServer {
+        payload: NotificationPayloadType::Normal(NotificationFactory::alert(&user.uuid, user.get_email_address(), msg, &tachyon_url, &tachyon_url, &tachyon_url, None, rand::random::<i32>())),
+    })).await;
+}
+
 async fn handle_addressbook_notifications(client_data: &TachyonClient) -> Result<(), SendTimeoutError<NotificationServerCommand>> {
 
     let update_required = {
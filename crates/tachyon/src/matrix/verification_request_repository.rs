@@ -1,23 +1,23 @@
+use std::time::{Duration, Instant};
 use dashmap::DashMap;
-use dashmap::mapref::one::Ref;
+use dashmap::mapref::one::MappedRef;
 use matrix_sdk::encryption::verification::VerificationRequest;
 use matrix_sdk::ruma::UserId;
-use crate::tachyon::repository::RepositoryStr;
 
 pub struct VerificationRequestRepository {
 
-    requests: DashMap<String, VerificationRequest>,
+    requests: DashMap<String, (VerificationRequest, Instant)>,
 
 }
 
 impl VerificationRequestRepository {
 
-    pub fn get(&self, key: &str) -> Option<Ref<'_, String, VerificationRequest>> {
-        self.requests.get(key)
+    pub fn get(&self, key: &str) -> Option<MappedRef<'_, String, (VerificationRequest, Instant), VerificationRequest>> {
+        self.requests.get(key).map(|entry| entry.map(|(request, _)| request))
     }
 
     pub fn insert(&self, key: String, value: VerificationRequest) {
-        self.requests.insert(key, value);
+        self.requests.insert(key, (value, Instant::now()));
     }
 
     pub fn remove(&self, key: &str) {
@@ -25,7 +25,17 @@ impl VerificationRequestRepository {
     }
 
     pub fn remove_for(&self, user_id: &UserId) {
-        self.requests.retain(|_, v| v.own_user_id() != user_id);
+        self.requests.retain(|_, (request, _)| request.own_user_id() != user_id);
+    }
+
+    pub fn remove_expired(&self, now: Instant, ttl: Duration) -> usize {
+        let before = self.requests.len();
+        self.requests.retain(|_, (_, inserted_at)| now < *inserted_at + ttl);
+        before - self.requests.len()
+    }
+
+    pub fn count(&self) -> usize {
+        self.requests.len()
     }
 }
 
@@ -37,4 +47,4 @@ impl Default for VerificationRequestRepository {
             requests: DashMap::new(),
         }
     }
-}
\ No newline at end of file
+}
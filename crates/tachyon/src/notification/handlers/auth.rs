@@ -10,7 +10,7 @@ use crate::tachyon::global_state::GlobalState;
 use crate::tachyon::mappers::user_id::MatrixIdCompatible;
 use crate::tachyon::repository::RepositoryStr;
 use anyhow::Error;
-use log::debug;
+use log::{debug, warn};
 use matrix_sdk::Client;
 use msnp::msnp::notification::command::command::{NotificationClientCommand, NotificationServerCommand};
 use msnp::msnp::notification::command::msg::{MsgPayload, MsgServer};
@@ -18,16 +18,18 @@ use msnp::msnp::notification::command::not::factories::NotificationFactory;
 use msnp::msnp::notification::command::not::{NotServer, NotificationPayloadType};
 use msnp::msnp::notification::command::usr::{AuthOperationTypeClient, AuthPolicy, OperationTypeServer, SsoPhaseClient, SsoPhaseServer, UsrServer};
 use msnp::msnp::raw_command_parser::RawCommand;
+use msnp::shared::command::err::{ErrCommand, MsnpError};
 use msnp::shared::models::display_name::DisplayName;
 use msnp::shared::models::endpoint_id::EndpointId;
 use msnp::shared::models::msn_user::MsnUser;
 use msnp::shared::models::ticket_token::TicketToken;
 use msnp::shared::payload::msg::raw_msg_payload::factories::RawMsgPayloadFactory;
 use tokio::sync::mpsc::Sender;
-use tokio::{select, task};
+use tokio::select;
 use crate::matrix::cross_signing;
 use crate::matrix::services::login::MatrixLoginService;
 use crate::tachyon::config::tachyon_config::TachyonConfig;
+use crate::tachyon::error::TachyonError;
 
 const SHIELDS_PAYLOAD: &str = "<Policies><Policy type= \"SHIELDS\"><config><shield><cli maj= \"7\" min= \"0\" minbld= \"0\" maxbld= \"1000\" deny= \" \" /></shield><block></block></config></Policy><Policy type= \"ABCH\"><policy><set id= \"push\" service= \"ABCH\" priority= \"100\"><r id= \"pushstorage\" threshold= \"0\" /></set><set id= \"using_notifications\" service= \"ABCH\" priority= \"100\"><r id= \"pullab\" threshold= \"0\" timer= \"1800000\" trigger= \"Timer\" /><r id= \"pullmembership\" threshold= \"0\" timer= \"1800000\" trigger= \"Timer\" /></set><set id= \"delaysup\" service= \"ABCH\" priority= \"150\"><r id= \"whatsnew\" threshold= \"0\" /><r id= \"whatsnew_storage_ABCH_delay\" timer= \"1800000\" /><r id= \"whatsnewt_link\" threshold= \"0\" trigger= \"QueryActivities\" /></set><c id= \"PROFILE_Rampup\">100</c></policy></Policy><Policy type= \"ERRORRESPONSETABLE\"><Policy><Feature type= \"3\" name= \"P2P\"><Entry hr= \"0x81000398\" action= \"3\" /><Entry hr= \"0x82000020\" action= \"3\" /></Feature><Feature type= \"4\"><Entry hr= \"0x81000440\" /></Feature><Feature type= \"6\" name= \"TURN\"><Entry hr= \"0x8007274C\" action= \"3\" /><Entry hr= \"0x82000020\" action= \"3\" /><Entry hr= \"0x8007274A\" action= \"3\" /></Feature></Policy></Policy><Policy type= \"P2P\"><ObjStr SndDly= \"1\" /></Policy></Policies>";
 
@@ -50,15 +52,38 @@ pub(crate) async fn handle_auth(command: NotificationClientCommand, notif_sender
 
                             let user_id = local_store.email_addr.to_owned_user_id();
 
-                            let matrix_token = tachyon_state.secret_encryptor().decrypt(ticket_token.as_str())?;
-
-                            let matrix_client = matrix_login_service.login_with_token(&user_id, &matrix_token, !config.strict_ssl).await?;
-
-                            let endpoint_id = EndpointId::new(local_store.email_addr.clone(), Some(endpoint_guid));
+                            let matrix_token = match tachyon_state.secret_encryptor().decrypt(ticket_token.as_str()) {
+                                Ok(matrix_token) => matrix_token,
+                                Err(e) => {
+                                    warn!("NS|AUTH: Rejecting ticket token for {}: {}", &local_store.email_addr, e);
+                                    reject_authentication(command.tr_id, &notif_sender).await?;
+                                    return Ok(());
+                                }
+                            };
+
+                            let client_slot = match tachyon_state.reserve_client_slot() {
+                                Ok(client_slot) => client_slot,
+                                Err(e) => {
+                                    warn!("NS|AUTH: Refusing {}: {}", &local_store.email_addr, e);
+                                    return Err(e.into());
+                                }
+                            };
+
+                            let matrix_client = match matrix_login_service.login_with_token(&user_id, &matrix_token, !config.strict_ssl).await {
+                                Ok(matrix_client) => matrix_client,
+                                Err(e) if e.is_authentication_failure() => {
+                                    warn!("NS|AUTH: Homeserver refused the token of {}: {}", &local_store.email_addr, e);
+                                    reject_authentication(command.tr_id, &notif_sender).await?;
+                                    return Ok(());
+                                }
+                                Err(e) => return Err(e.into())
+                            };
+
+                            let endpoint_id = EndpointId::new(local_store.email_addr.clone(), endpoint_guid);
                             let msn_user = MsnUser::new(endpoint_id);
 
-                            let tachyon_client = TachyonClient::new(matrix_client.clone(), config.clone(), msn_user.clone(), ticket_token.clone(), notif_sender.clone(), local_store.client_shutdown_snd.clone(), local_store.client_shutdown_recv.resubscribe());
-                            let drop_guard = tachyon_state.insert_clients(ticket_token.as_str().to_owned(), tachyon_client.clone());
+                            let tachyon_client = TachyonClient::new(matrix_client.clone(), config.clone(), *local_store.msnp_version.borrow(), msn_user.clone(), ticket_token.clone(), notif_sender.clone(), local_store.client_shutdown_snd.clone(), local_store.client_shutdown_recv.resubscribe());
+                            let drop_guard = tachyon_state.insert_clients(ticket_token.as_str().to_owned(), tachyon_client.clone(), client_slot);
 
                             local_store.client_drop_guard = Some(drop_guard);
                             local_store.token = ticket_token.clone();
@@ -87,20 +112,27 @@ pub(crate) async fn handle_auth(command: NotificationClientCommand, notif_sender
                         }
                     }
                 },
-                _ => {
-                    //return unauth error
-                    todo!()
+                AuthOperationTypeClient::Sha(_) => {
+                    //SHA is only used to join circles once signed in
+                    reject_authentication(command.tr_id, &notif_sender).await?;
                 }
 
             }
             Ok(())
         },
 
-        _ => {todo!()}
+        _ => Err(TachyonError::NotLoggedIn.into())
     }
 
 }
 
+//The client shows its "wrong sign-in name or password" dialog on 911 and expects to be disconnected.
+async fn reject_authentication(tr_id: u128, notif_sender: &Sender<NotificationServerCommand>) -> Result<(), anyhow::Error> {
+    notif_sender.send(NotificationServerCommand::ERR(ErrCommand::new(tr_id, MsnpError::ServerIsBusy2))).await?;
+    notif_sender.send(NotificationServerCommand::OUT).await?;
+    Ok(())
+}
+
 fn sync_with_server_task(notif_sender: &Sender<NotificationServerCommand>, local_store: &LocalClientData, ticket_token: &TicketToken, matrix_client: &Client, msn_user: &MsnUser, tachyon_client: TachyonClient, config: &TachyonConfig) -> Result<(), Error> {
     let msn_user_clone = msn_user.clone();
     let matrix_client_clone = matrix_client.clone();
@@ -109,19 +141,23 @@ fn sync_with_server_task(notif_sender: &Sender<NotificationServerCommand>, local
     let config_clone = config.clone();
     let client_shutdown_snd = local_store.client_shutdown_snd.clone();
     let mut client_shutdown_recv = local_store.client_shutdown_recv.resubscribe();
+    let msnp_version = *local_store.msnp_version.borrow();
 
 
-    task::spawn(async move {
+    let client = tachyon_client.clone();
+    client.spawn("sync with server", async move {
         let cross_signed = check_device_is_crossed_signed(&matrix_client_clone).await.unwrap();
+        let mut resume_token = None;
 
         if !cross_signed {
 
-            let sign_sync_loop_kill_snd = cross_signing::cross_sign_sync_task(&matrix_client_clone, client_shutdown_recv.resubscribe()).await.unwrap();
+            let (sign_sync_loop_kill_snd, pre_verification_token) = cross_signing::cross_sign_sync_task(&matrix_client_clone, &config_clone.sync_mode, client_shutdown_recv.resubscribe()).await.unwrap();
+            resume_token = pre_verification_token;
 
             let notification_id = rand::random::<i32>();
 
             let verif_not = NotificationServerCommand::NOT(NotServer {
-                payload: NotificationPayloadType::Normal(NotificationFactory::alert(&msn_user_clone.uuid, msn_user_clone.get_email_address(), "Oops ! Your device is not verified yet ! Click here to verify.", format!("http://127.0.0.1:{}/tachyon", config_clone.http_port).as_str(), format!("http://127.0.0.1:{}/tachyon/confirm_device?t={}", config_clone.http_port, &ticket_token_clone.as_str()).as_str(), format!("http://127.0.0.1:{}/tachyon/confirm_device?t={}", config_clone.http_port, &ticket_token_clone.as_str()).as_str(), Some("shield_verify.png"), notification_id)),
+                payload: NotificationPayloadType::Normal(NotificationFactory::alert(&msn_user_clone.uuid, msn_user_clone.get_email_address(), "Oops ! Your device is not verified yet ! Click here to verify.", config_clone.web_url("/tachyon").as_str(), config_clone.web_url(&format!("/tachyon/confirm_device?t={}", &ticket_token_clone.as_str())).as_str(), config_clone.web_url(&format!("/tachyon/confirm_device?t={}", &ticket_token_clone.as_str())).as_str(), Some("shield_verify.png"), notification_id)),
             });
 
             let (alert, receiver) = Alert::new_confirm_device(Duration::from_mins(5));
@@ -165,7 +201,7 @@ fn sync_with_server_task(notif_sender: &Sender<NotificationServerCommand>, local
 
 
             let backup_not = NotificationServerCommand::NOT(NotServer {
-                payload: NotificationPayloadType::Normal(NotificationFactory::alert(&msn_user_clone.uuid, msn_user_clone.get_email_address(), "Account backup is disabled. Click here to set it up !", format!("http://127.0.0.1:{}/tachyon", config_clone.http_port).as_str(), format!("http://127.0.0.1:{}/tachyon/backup?t={}", config_clone.http_port, &ticket_token_clone.as_str()).as_str(), format!("http://127.0.0.1:{}/tachyon/backup?t={}", config_clone.http_port, &ticket_token_clone.as_str()).as_str(), None, notification_id)),
+                payload: NotificationPayloadType::Normal(NotificationFactory::alert(&msn_user_clone.uuid, msn_user_clone.get_email_address(), "Account backup is disabled. Click here to set it up !", config_clone.web_url("/tachyon").as_str(), config_clone.web_url(&format!("/tachyon/backup?t={}", &ticket_token_clone.as_str())).as_str(), config_clone.web_url(&format!("/tachyon/backup?t={}", &ticket_token_clone.as_str())).as_str(), None, notification_id)),
             });
 
             notif_sender_clone.send(backup_not).await;
@@ -190,16 +226,21 @@ fn sync_with_server_task(notif_sender: &Sender<NotificationServerCommand>, local
 
         //Todo fetch endpoint data
         let endpoint_data = b"<Data></Data>";
+        let self_ubx = if msnp_version.supports_circles() {
+            format!("UBX 1:{}", &msn_user_clone.get_email_address().as_str())
+        } else {
+            format!("UBX {} 1", &msn_user_clone.get_email_address().as_str())
+        };
         notif_sender_clone
             .send(NotificationServerCommand::RAW(RawCommand::with_payload(
-                &format!("UBX 1:{}", &msn_user_clone.get_email_address().as_str()),
+                &self_ubx,
                 endpoint_data.to_vec(),
             )))
             .await;
 
         //Todo check the device state before we sync
 
-        let sync_join_handle = sync(tachyon_client, matrix_client_clone, client_shutdown_snd, client_shutdown_recv).await;
+        let sync_join_handle = sync(tachyon_client, matrix_client_clone, resume_token, client_shutdown_snd, client_shutdown_recv).await;
 
         let initial_mail_data = NotificationServerCommand::MSG(MsgServer {
             sender: "Hotmail".to_string(),
@@ -10,7 +10,7 @@ use tokio::sync::mpsc::Sender;
 
 pub(crate) async fn handle_command(command: NotificationClientCommand, command_sender: Sender<NotificationServerCommand>, global_state: &GlobalState, local_client_data: &mut LocalClientData, config: &TachyonConfig) -> Result<(), anyhow::Error> {
 
-    let _command_result = match &local_client_data.phase {
+    match &local_client_data.phase {
         ConnectionPhase::Negotiating => {
             negotiation::handle_negotiation(command, command_sender, local_client_data).await
         },
@@ -22,9 +22,7 @@ pub(crate) async fn handle_command(command: NotificationClientCommand, command_s
             let tachyon_client = local_client_data.tachyon_client.as_ref().ok_or(anyhow!("Tachyon Client should be here by now"))?.clone();
             handle_ready(command, command_sender, tachyon_client, matrix_client, local_client_data, config).await
         }
-    };
-
-    Ok(())
+    }
 
 }
 
@@ -39,7 +37,7 @@ mod tests {
     use msnp::msnp::notification::command::cvr::CvrClient;
     use msnp::msnp::notification::command::usr::{AuthOperationTypeClient, AuthPolicy, OperationTypeServer, SsoPhaseClient, SsoPhaseServer, UsrClient};
     use msnp::msnp::notification::command::ver::VerClient;
-    use msnp::msnp::notification::models::msnp_version::MsnpVersion::{MSNP17, MSNP18};
+    use msnp::msnp::notification::models::msnp_version::MsnpVersion::{MSNP13, MSNP14, MSNP15, MSNP17, MSNP18};
     use msnp::msnp::raw_command_parser::RawCommand;
     use msnp::shared::models::email_address::EmailAddress;
     use msnp::shared::traits::TryFromRawCommand;
@@ -48,7 +46,9 @@ mod tests {
     use matrix_sdk::ruma::UserId;
     use matrix_sdk::test_utils::mocks::{MatrixMock, MatrixMockServer};
     use crate::matrix::services::login::{AccessToken, MatrixLoginService, MatrixLoginServiceImpl};
-    use crate::tachyon::error::TachyonError;
+    use crate::tachyon::error::{to_msnp_error, TachyonError};
+    use msnp::shared::command::err::MsnpError;
+    use msnp::shared::models::ticket_token::TicketToken;
 
     const TEST_SECRET: [u8; 32] = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32];
 
@@ -111,6 +111,28 @@ mod tests {
         assert!(matches!(local_client_data.phase, ConnectionPhase::Authenticating));
     }
 
+    #[tokio::test]
+    async fn handshake_legacy_version_test() {
+
+        let (snd, mut rcv) = tokio::sync::mpsc::channel::<NotificationServerCommand>(10);
+
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(MatrixLoginServiceImpl::new()));
+
+        let (kill_snd, kill_recv) = tokio::sync::broadcast::channel::<()>(1);
+        let mut local_client_data = LocalClientData::new(kill_snd.clone(), kill_recv.resubscribe());
+
+        let mut ver_client = VerClient::new(1, MSNP15, MSNP14);
+        ver_client.additional_candidates.push(MSNP13);
+        handle_command(NotificationClientCommand::VER(ver_client), snd, &state, &mut local_client_data, state.get_config()).await.unwrap();
+
+        let NotificationServerCommand::VER(ver_resp) = rcv.recv().await.unwrap() else {
+            panic!("Expected VER response");
+        };
+
+        assert_eq!(ver_resp.agreed_version, MSNP15);
+        assert_eq!(*local_client_data.msnp_version.borrow(), MSNP15);
+    }
+
 
     #[tokio::test]
     async fn auth_i_test() {
@@ -161,5 +183,90 @@ mod tests {
         assert_eq!(raw_resp.get_operand(), "GCF");
     }
 
+    struct RejectingMatrixLoginService;
+
+    #[async_trait]
+    impl MatrixLoginService for RejectingMatrixLoginService {
+        async fn login_with_token(&self, user_id: &UserId, token: &str, disable_ssl: bool) -> Result<Client, TachyonError> {
+            Err(TachyonError::AuthenticationFailed)
+        }
+
+        async fn login_with_password(&self, matrix_id: &UserId, password: &str, disable_ssl: bool) -> Result<(AccessToken, Client), TachyonError> {
+            Err(TachyonError::AuthenticationFailed)
+        }
+    }
+
+    async fn send_usr_s(ticket_token: String, state: &GlobalState) -> (Vec<NotificationServerCommand>, Result<(), anyhow::Error>) {
+        let (snd, mut rcv) = tokio::sync::mpsc::channel::<NotificationServerCommand>(10);
+
+        let (kill_snd, kill_recv) = tokio::sync::broadcast::channel::<()>(1);
+        let mut local_client_data = LocalClientData::new(kill_snd.clone(), kill_recv.resubscribe());
+        local_client_data.phase = ConnectionPhase::Authenticating;
+        local_client_data.email_addr = EmailAddress::from_str("aeon@test.com").unwrap();
+
+        let usr_s = NotificationClientCommand::USR(UsrClient {
+            tr_id: 2,
+            auth_type: AuthOperationTypeClient::Sso(SsoPhaseClient::S { ticket_token: TicketToken(ticket_token), challenge: "challenge".into(), endpoint_guid: None }),
+        });
+
+        let result = handle_command(usr_s, snd, state, &mut local_client_data, state.get_config()).await;
+
+        let mut responses = Vec::new();
+        while let Ok(response) = rcv.try_recv() {
+            responses.push(response);
+        }
+
+        (responses, result)
+    }
+
+    fn assert_rejected(responses: &[NotificationServerCommand]) {
+        assert_eq!(responses.len(), 2);
+        assert!(matches!(&responses[0], NotificationServerCommand::ERR(err) if err.tr_id == 2 && err.msnp_error == MsnpError::ServerIsBusy2));
+        assert!(matches!(&responses[1], NotificationServerCommand::OUT));
+    }
+
+    #[tokio::test]
+    async fn auth_s_tampered_ticket_test() {
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(RejectingMatrixLoginService));
+
+        let mut bytes = hex::decode(state.secret_encryptor().encrypt("syt_matrix_token").unwrap()).unwrap();
+        bytes[12] ^= 0x01;
+
+        let (responses, result) = send_usr_s(hex::encode(bytes), &state).await;
+        assert!(result.is_ok());
+        assert_rejected(&responses);
+    }
+
+    #[tokio::test]
+    async fn auth_s_foreign_ticket_test() {
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(RejectingMatrixLoginService));
+
+        let (responses, result) = send_usr_s("t=not_a_tachyon_ticket".into(), &state).await;
+        assert!(result.is_ok());
+        assert_rejected(&responses);
+    }
+
+    #[tokio::test]
+    async fn auth_s_expired_token_test() {
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(RejectingMatrixLoginService));
+        let ticket_token = state.secret_encryptor().encrypt("syt_expired_token").unwrap();
+
+        let (responses, result) = send_usr_s(ticket_token, &state).await;
+        assert!(result.is_ok());
+        assert_rejected(&responses);
+    }
+
+    #[tokio::test]
+    async fn command_before_auth_test() {
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(RejectingMatrixLoginService));
+        let (snd, _rcv) = tokio::sync::mpsc::channel::<NotificationServerCommand>(10);
+
+        let (kill_snd, kill_recv) = tokio::sync::broadcast::channel::<()>(1);
+        let mut local_client_data = LocalClientData::new(kill_snd.clone(), kill_recv.resubscribe());
+        local_client_data.phase = ConnectionPhase::Authenticating;
+
+        let result = handle_command(NotificationClientCommand::PNG, snd, &state, &mut local_client_data, state.get_config()).await;
+        assert_eq!(MsnpError::NotLoggedIn, to_msnp_error(&result.unwrap_err()));
+    }
 
 }
\ No newline at end of file
@@ -2,21 +2,27 @@ use msnp::msnp::notification::command::command::{NotificationClientCommand, Noti
 use tokio::sync::mpsc::Sender;
 use anyhow::anyhow;
 use msnp::msnp::notification::command::cvr::CvrServer;
-use msnp::msnp::notification::models::msnp_version::MsnpVersion::MSNP18;
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
 use crate::notification::models::connection_phase::ConnectionPhase;
 use crate::notification::models::local_client_data::LocalClientData;
 
+const SUPPORTED_VERSIONS: [MsnpVersion; 4] = [MsnpVersion::MSNP15, MsnpVersion::MSNP16, MsnpVersion::MSNP17, MsnpVersion::MSNP18];
+
 pub(crate) async fn handle_negotiation(raw_command: NotificationClientCommand, notif_sender: Sender<NotificationServerCommand>, local_client_data: &mut LocalClientData) -> Result<(), anyhow::Error> {
     match raw_command {
         NotificationClientCommand::VER(command) => {
-            if command.first_candidate != MSNP18 && command.second_candidate != MSNP18 {
-                //Unsupported protocol version
-                //TODO add error code
-                notif_sender.send(NotificationServerCommand::OUT).await?;
-                return Ok(());
-            }
+            let agreed_version = match command.candidates().filter(|candidate| SUPPORTED_VERSIONS.contains(candidate)).max() {
+                None => {
+                    //Unsupported protocol version
+                    //TODO add error code
+                    notif_sender.send(NotificationServerCommand::OUT).await?;
+                    return Ok(());
+                }
+                Some(version) => *version
+            };
 
-            notif_sender.send(NotificationServerCommand::VER(command.get_response_for(MSNP18))).await?;
+            local_client_data.msnp_version.send_replace(agreed_version);
+            notif_sender.send(NotificationServerCommand::VER(command.get_response_for(agreed_version))).await?;
             Ok(())
         },
         NotificationClientCommand::CVR(command) => {
@@ -1,23 +1,12 @@
-use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, ToMsnUser};
+use crate::notification::presence::send_contacts_online;
 use crate::tachyon::client::tachyon_client::TachyonClient;
-use crate::tachyon::identifiers::is_sha1::IsSha1;
 use log::debug;
 use matrix_sdk::Client;
 use msnp::msnp::notification::command::adl::AdlClient;
 use msnp::msnp::notification::command::command::NotificationServerCommand;
-use msnp::msnp::notification::command::iln::IlnServer;
-use msnp::msnp::notification::command::nln::NlnServer;
-use msnp::msnp::notification::command::ubx::{ExtendedPresenceContent, UbxPayload, UbxServer};
-use msnp::shared::models::display_name::DisplayName;
-use msnp::shared::models::network_id_email::NetworkIdEmail;
-use msnp::shared::models::presence_status::PresenceStatus;
 use std::time::Duration;
 use tokio::sync::mpsc::Sender;
 use tokio::time::sleep;
-use msnp::msnp::notification::models::endpoint_data::EndpointData;
-use msnp::msnp::notification::models::endpoint_guid::EndpointGuid;
-use msnp::shared::models::capabilities::ClientCapabilities;
-use msnp::shared::models::endpoint_id::EndpointId;
 
 pub async fn handle_adl(command: AdlClient, tachyon_client: TachyonClient, matrix_client: Client, command_sender: Sender<NotificationServerCommand>) -> Result<(), anyhow::Error>  {
     debug!("ADL: {:?}", &command);
@@ -31,64 +20,12 @@ pub async fn handle_adl(command: AdlClient, tachyon_client: TachyonClient, matri
 
     command_sender.send(NotificationServerCommand::OK(command.get_ok_response("ADL"))).await?;
 
-    tokio::spawn( async move {
-
+    let client = tachyon_client.clone();
+    client.spawn("adl presence", async move {
         sleep(Duration::from_millis(1000)).await;
-        //Hardcoded presence to Online
-        //TODO: implement real presence
-        for contact in contacts {
-
-            if !contact.email_address.is_sha1_imprecise() {
-                continue;
-            }
-
-            let found_room = matrix_client.find_room_from_email(&contact.email_address);
-
-            let display_name = if let Ok(Some(room)) = &found_room {
-                if let Ok(msn_user) = room.to_msn_user_lazy().await {
-                    msn_user.display_name
-                } else {
-                    None
-                }
-
-            } else {
-                None
-            };
-
-            let avatar = if let Ok(Some(room)) = &found_room {
-                tachyon_client.get_avatar_as_msn_object(room.room_id()).await.unwrap()
-            } else { None };
-
-
-            let network_id_email = NetworkIdEmail {
-                network_id: contact.network_id.clone(),
-                email: contact.email_address.clone(),
-            };
-
-            let endpoint_id = EndpointId::from_email_addr(network_id_email.email.clone());
-            let endpoint_guid = endpoint_id.endpoint_guid.expect("to be here");
-
-            let _ = command_sender.send(NotificationServerCommand::NLN(NlnServer{
-                presence_status: PresenceStatus::NLN,
-                target_user: network_id_email.clone(),
-                via: None,
-                display_name: display_name.map(|name| DisplayName::new(name) ).unwrap_or_default(),
-                client_capabilities: Default::default(),
-                avatar,
-                badge_url: None,
-            })).await;
-
-            //If we don't set the EndpointData here, we don't get P2P Transport Requests because the client has no information about the presence of this Endpoint.
-            let _ = command_sender.send(NotificationServerCommand::UBX(UbxServer {
-                target_user: network_id_email,
-                via: None,
-                payload: UbxPayload::ExtendedPresence(ExtendedPresenceContent {
-                    psm: "".to_string(),
-                    current_media: "".to_string(),
-                    endpoint_data: EndpointData::new(Some(endpoint_guid), ClientCapabilities::default()),
-                    private_endpoint_data: None,
-                }),
-            })).await;
+        //Contacts stay offline until the sync loop reconnects, it sends the whole list again then.
+        if tachyon_client.is_homeserver_reachable() {
+            send_contacts_online(contacts, &tachyon_client, &matrix_client).await;
         }
     });
     Ok(())
@@ -2,6 +2,7 @@ use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, ToMsnUser}
 use crate::notification::models::local_client_data::LocalClientData;
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use crate::tachyon::identifiers::is_sha1::IsSha1;
+use log::warn;
 use matrix_sdk::Client;
 use msnp::msnp::notification::command::chg::ChgClient;
 use msnp::msnp::notification::command::command::NotificationServerCommand;
@@ -20,11 +21,29 @@ pub async fn handle_chg(command: ChgClient, local_store: &mut LocalClientData, c
 
     let tachyon_client = client_data.clone();
 
+    // The P2P transports pick their header version from what the client advertises here
+    client_data.own_user_mut().capabilities = command.client_capabilities.clone();
+
+    if let Some(avatar) = &command.avatar {
+        let changed = client_data.own_user().display_picture.as_ref().map(|current| current.sha1d != avatar.sha1d).unwrap_or(true);
+
+        if changed {
+            client_data.own_user_mut().display_picture = Some(avatar.clone());
+
+            let client = client_data.clone();
+            let avatar = avatar.clone();
+            client_data.spawn("display picture sync", async move {
+                if let Err(e) = client.sync_own_display_picture(avatar).await {
+                    warn!("Could not sync our display picture to Matrix: {:?}", e);
+                }
+            });
+        }
+    }
 
     if local_store.needs_initial_presence {
         local_store.needs_initial_presence = false;
 
-        tokio::spawn( async move {
+        client_data.spawn("initial presence", async move {
 
             let contacts = {
                 tachyon_client.get_contact_list().lock().unwrap().get_forward_list()
@@ -37,10 +56,10 @@ pub async fn handle_chg(command: ChgClient, local_store: &mut LocalClientData, c
                 }
 
 
-               let found_room =  matrix_client.find_room_from_email(&contact.email_address);
+               let found_room =  matrix_client.find_room_from_email(&contact.email_address, tachyon_client.room_hashes());
 
                let display_name = if let Ok(Some(room)) = &found_room {
-                   if let Ok(msn_user) = room.to_msn_user_lazy().await {
+                   if let Ok(msn_user) = room.to_msn_user_lazy(tachyon_client.resolver_caches()).await {
                        msn_user.display_name
                    } else {
                        None
@@ -54,6 +73,10 @@ pub async fn handle_chg(command: ChgClient, local_store: &mut LocalClientData, c
                     tachyon_client.get_avatar_as_msn_object(room.room_id()).await.unwrap()
                 } else { None };
 
+                let profile_extras = if let Ok(Some(room)) = &found_room {
+                    tachyon_client.get_contact_profile_extras(room.room_id()).await.unwrap_or_default()
+                } else { None };
+
                 let network_id_email = NetworkIdEmail {
                     network_id: contact.network_id.clone(),
                     email: contact.email_address.clone(),
@@ -73,16 +96,26 @@ pub async fn handle_chg(command: ChgClient, local_store: &mut LocalClientData, c
                     badge_url: None,
                 })).await;
 
+                let mut extended_presence = ExtendedPresenceContent {
+                    psm: "".to_string(),
+                    current_media: "".to_string(),
+                    ddp: None,
+                    signature_sound: None,
+                    scene: None,
+                    color_scheme: None,
+                    endpoint_data: EndpointData::new(Some(endpoint_guid), ClientCapabilities::default()),
+                    private_endpoint_data: None,
+                };
+
+                if let Some(profile_extras) = &profile_extras {
+                    extended_presence.set_profile_extras(profile_extras);
+                }
+
                 //If we don't set the EndpointData here, we don't get P2P Transport Requests because the client has no information about the presence of this Endpoint.
                 let _ = command_sender.send(NotificationServerCommand::UBX(UbxServer {
                     target_user: network_id_email,
                     via: None,
-                    payload: UbxPayload::ExtendedPresence(ExtendedPresenceContent {
-                        psm: "".to_string(),
-                        current_media: "".to_string(),
-                        endpoint_data: EndpointData::new(Some(endpoint_guid), ClientCapabilities::default()),
-                        private_endpoint_data: None,
-                    }),
+                    payload: UbxPayload::ExtendedPresence(extended_presence),
                 })).await;
 
             }
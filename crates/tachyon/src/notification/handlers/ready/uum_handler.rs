@@ -19,7 +19,7 @@ pub async fn handle_uum(command: UumClient, client_data: TachyonClient, matrix_c
             let dest_email = EmailAddress::from_str(&command.destination);
             if let Ok(dest_email) = dest_email {
 
-                let room = matrix_client.find_room_from_email(&dest_email)?;
+                let room = matrix_client.find_room_from_email(&dest_email, client_data.room_hashes())?;
                 match room {
                     None => {
                         //NO DM ROOM FOUND
@@ -68,7 +68,16 @@ pub async fn handle_uum(command: UumClient, client_data: TachyonClient, matrix_c
             Ok(())
         },
         UumPayload::TypingUser(_) => {
-            todo!()
+            let dest_email = EmailAddress::from_str(&command.destination)?;
+
+            if client_data.config().send_typing_notifications {
+                if let Some(room) = matrix_client.find_room_from_email(&dest_email, client_data.room_hashes())? {
+                    room.typing_notice(true).await?;
+                }
+            }
+
+            command_sender.send(NotificationServerCommand::OK(ok_response)).await?;
+            Ok(())
         }
         UumPayload::Nudge(_) => {
             todo!()
@@ -2,8 +2,10 @@ use tokio::sync::mpsc::Sender;
 use msnp::msnp::notification::command::command::NotificationServerCommand;
 use msnp::msnp::notification::command::uux::{UuxClient, UuxPayload};
 use crate::notification::models::local_client_data::LocalClientData;
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use log::warn;
 
-pub async fn handle_uux(command: UuxClient, local_store: &mut LocalClientData, command_sender: Sender<NotificationServerCommand>) -> Result<(), anyhow::Error>  {
+pub async fn handle_uux(command: UuxClient, local_store: &mut LocalClientData, tachyon_client: TachyonClient, command_sender: Sender<NotificationServerCommand>) -> Result<(), anyhow::Error>  {
     let ok_resp = command.get_ok_response();
 
     match command.payload {
@@ -14,6 +16,15 @@ pub async fn handle_uux(command: UuxClient, local_store: &mut LocalClientData, c
                     local_store.private_endpoint_data = private_endpoint_data;
                     //TODO
                 }
+                UuxPayload::PersonalMessage(personal_message) => {
+                    let extras = personal_message.profile_extras()?;
+                    let client = tachyon_client.clone();
+                    client.spawn("profile extras sync", async move {
+                        if let Err(e) = tachyon_client.sync_own_profile_extras(extras).await {
+                            warn!("Could not sync our profile extras to Matrix: {:?}", e);
+                        }
+                    });
+                }
                 UuxPayload::Unknown(_) => {}
             }
         }
@@ -3,14 +3,20 @@ use crate::notification::models::local_client_data::LocalClientData;
 use msnp::msnp::notification::command::command::NotificationServerCommand;
 use msnp::msnp::notification::command::xfr::XfrClient;
 use msnp::msnp::notification::models::ip_address::IpAddress;
-use std::str::FromStr;
 use tokio::sync::mpsc::Sender;
 use crate::tachyon::config::tachyon_config::TachyonConfig;
+use crate::tachyon::error::TachyonError;
 
 pub async fn handle_xfr(command: XfrClient, local_store: &mut LocalClientData, command_sender: Sender<NotificationServerCommand>, config: &TachyonConfig) -> Result<(), anyhow::Error>  {
 
+    //Only switchboards the client asks for are capped, ones opened for incoming Matrix messages would otherwise drop them.
+    if let (Some(max), Some(tachyon_client)) = (config.hosted.switchboard_limit(), local_store.tachyon_client.as_ref()) {
+        if tachyon_client.inner.switchboards.len() >= max {
+            return Err(TachyonError::TooManySessions { max }.into());
+        }
+    }
 
-    let xfr_response = command.get_response_for(IpAddress::from_str(format!("127.0.0.1:{}", config.switchboard_port).as_str())?, local_store.token.to_string());
+    let xfr_response = command.get_response_for(IpAddress::new(config.public_host, config.switchboard_port), local_store.token.to_string());
     command_sender.send(NotificationServerCommand::XFR(xfr_response)).await?;
     Ok(())
 }
@@ -3,3 +3,4 @@ pub mod notification_server;
 
 pub mod circle_store;
 pub(crate) mod models;
+pub(crate) mod presence;
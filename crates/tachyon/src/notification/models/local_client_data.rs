@@ -1,4 +1,6 @@
 use tokio::sync::broadcast::{Receiver, Sender};
+use tokio::sync::watch;
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
 use msnp::msnp::notification::models::endpoint_data::PrivateEndpointData;
 use msnp::shared::models::email_address::EmailAddress;
 use msnp::shared::models::ticket_token::TicketToken;
@@ -8,6 +10,8 @@ use crate::tachyon::global_state::ClientDropGuard;
 
 pub(crate) struct LocalClientData {
     pub(crate) phase: ConnectionPhase,
+    //Watched by the write task, which serializes commands for the negotiated version
+    pub(crate) msnp_version: watch::Sender<MsnpVersion>,
     pub(crate) email_addr: EmailAddress,
     pub(crate) token: TicketToken,
     pub(crate) tachyon_client: Option<TachyonClient>,
@@ -23,6 +27,7 @@ impl LocalClientData {
     pub(crate) fn new(client_shutdown_snd: Sender<()>, client_shutdown_recv: Receiver<()>) -> Self {
         Self {
             phase: ConnectionPhase::default(),
+            msnp_version: watch::Sender::new(MsnpVersion::default()),
             email_addr: EmailAddress::default(),
             token: TicketToken::default(),
             tachyon_client: None,
@@ -1,20 +1,30 @@
 use std::str::from_utf8_unchecked;
+use std::time::Duration;
 
 use anyhow::anyhow;
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
 use log::{debug, error, info, warn};
+use msnp::msnp::codec::MsnpCodec;
 use msnp::msnp::notification::command::command::NotificationClientCommand;
-use msnp::msnp::{notification::command::command::NotificationServerCommand, raw_command_parser::RawCommandParser};
-use msnp::shared::traits::{IntoBytes, TryFromRawCommand};
-use tokio::{io::{AsyncReadExt, AsyncWriteExt, BufReader}, net::{tcp::OwnedWriteHalf, TcpListener, TcpStream}, sync::{broadcast::{self, Receiver}, mpsc::{self, Sender}}};
+use msnp::msnp::notification::command::command::NotificationServerCommand;
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
+use msnp::shared::traits::{IntoVersionedBytes, TryFromRawCommand};
+use tokio::{net::{tcp::OwnedWriteHalf, TcpListener, TcpStream}, sync::{broadcast::{self, Receiver}, mpsc::{self, Sender}, watch}};
+use tokio_util::codec::{FramedRead, FramedWrite};
 use msnp::msnp::raw_command_parser::RawCommand;
+use msnp::shared::command::err::{ErrCommand, MsnpError};
 use crate::notification::handlers::command_handler::handle_command;
 use crate::notification::models::local_client_data::LocalClientData;
 use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::error::{command_error_to_msnp, to_msnp_error_for_command};
 use crate::tachyon::global_state::GlobalState;
 use crate::tachyon::repository::RepositoryStr;
 
 pub struct NotificationServer;
 
+const CLIENT_TASKS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 
 impl NotificationServer {
 
@@ -57,39 +67,22 @@ async fn handle_client(socket: TcpStream, mut global_shutdown_recv: broadcast::R
 
     let (read, write) = socket.into_split();
     let (client_shutdown_snd, client_shutdown_recv) = broadcast::channel::<()>(1);
-    let command_sender = start_write_task(write, client_shutdown_recv.resubscribe());
-
-    let mut local_client_data = LocalClientData::new(client_shutdown_snd.clone(), client_shutdown_recv);
+    let mut local_client_data = LocalClientData::new(client_shutdown_snd.clone(), client_shutdown_recv.resubscribe());
+    let command_sender = start_write_task(write, local_client_data.msnp_version.subscribe(), client_shutdown_recv);
 
-    let mut parser = RawCommandParser::new();
-    let mut reader = BufReader::new(read);
-    let mut buffer= [0u8; 2048];
+    let mut reader = FramedRead::new(read, MsnpCodec::default());
 
     loop {
         tokio::select! {
-            bytes_read = reader.read(&mut buffer) => {
-                match bytes_read {
-                    Err(e) => {
-                        error!("MSNP|NOT: Socket Read Error: {}", e);
+            command = reader.next() => {
+                match command {
+                    None => break,
+                    Some(Err(e)) => {
+                        error!("MSNP|NOT: Unable to read command from socket: {}", e);
                         break;
                     },
-                    Ok(bytes_read) => {
-
-                        if bytes_read == 0 {
-                            break;
-                        }
-
-                        let data = &buffer[..bytes_read];
-
-                        let commands = parser.parse_message(data);
-
-                        match commands {
-                            Err(e) => error!("MSNP|NOT: Unable to parse message into commands: {}", e),
-                            Ok(commands) => {
-                                handle_commands(commands, &command_sender, &global_state, &mut local_client_data).await;
-                            }
-                        }
-
+                    Some(Ok(command)) => {
+                        handle_raw_command(command, &command_sender, &global_state, &mut local_client_data).await;
                     }
                 }
             },
@@ -102,36 +95,51 @@ async fn handle_client(socket: TcpStream, mut global_shutdown_recv: broadcast::R
         }
     }
 
+    if let Some(tachyon_client) = local_client_data.tachyon_client.take() {
+        tachyon_client.shutdown();
+        tachyon_client.shutdown_tasks(CLIENT_TASKS_SHUTDOWN_TIMEOUT).await;
+        tachyon_client.spill_voice_clips().await;
+    }
+
     info!("Client gracefully shutdown...");
     Ok(())
 
 }
 
-async fn handle_commands(commands: Vec<RawCommand>, command_sender: &Sender<NotificationServerCommand>, global_state: &GlobalState, local_client_data: &mut LocalClientData) {
-    for command in commands {
-        debug!("NS << | {}", command.get_command());
-
-        let notification_command = NotificationClientCommand::try_from_raw(command);
-        match notification_command {
-            Err(e) => {
-                error!("MSNP|NOT: Unable to parse command: {}", e);
-                debug!("{:?}", e);
-            },
-            Ok(notification_command) => {
-                let command_result = handle_command(notification_command, command_sender.clone(), &global_state, local_client_data, &global_state.get_config()).await;
-
-                if let Err(error) = command_result {
-                    error!("MSNP|NS: An error has occured handling a notification command: {}", &error);
-                    debug!("MSNP|NS: {:?}", &error);
-                    //TODO SEND ERROR BACK TO Client
-                }
+async fn handle_raw_command(command: RawCommand, command_sender: &Sender<NotificationServerCommand>, global_state: &GlobalState, local_client_data: &mut LocalClientData) {
+    debug!("NS << | {}", command.get_command());
+    let tr_id = command.get_tr_id();
+    let operand = command.get_operand().to_string();
+
+    let notification_command = NotificationClientCommand::try_from_raw(command);
+    match notification_command {
+        Err(e) => {
+            error!("MSNP|NOT: Unable to parse command: {}", e);
+            debug!("{:?}", e);
+            send_error(tr_id, command_error_to_msnp(&e), command_sender).await;
+        },
+        Ok(notification_command) => {
+            let command_result = handle_command(notification_command, command_sender.clone(), &global_state, local_client_data, &global_state.get_config()).await;
+
+            if let Err(error) = command_result {
+                error!("MSNP|NS: An error has occured handling a notification command: {}", &error);
+                debug!("MSNP|NS: {:?}", &error);
+                send_error(tr_id, to_msnp_error_for_command(&error, Some(&operand)), command_sender).await;
             }
         }
     }
 }
 
-fn start_write_task(mut write: OwnedWriteHalf, mut kill_recv: Receiver<()>) -> Sender<NotificationServerCommand> {
+//Without a reply to its transaction the client just hangs there
+async fn send_error(tr_id: Option<u128>, msnp_error: MsnpError, command_sender: &Sender<NotificationServerCommand>) {
+    if let Some(tr_id) = tr_id {
+        let _ = command_sender.send(NotificationServerCommand::ERR(ErrCommand::new(tr_id, msnp_error))).await;
+    }
+}
+
+fn start_write_task(write: OwnedWriteHalf, msnp_version: watch::Receiver<MsnpVersion>, mut kill_recv: Receiver<()>) -> Sender<NotificationServerCommand> {
     println!("Socket write task started...");
+    let mut write = FramedWrite::new(write, MsnpCodec::default());
     let (sender, mut receiver) = mpsc::channel::<NotificationServerCommand>(300);
 
     let _result = tokio::spawn(async move {
@@ -140,13 +148,13 @@ fn start_write_task(mut write: OwnedWriteHalf, mut kill_recv: Receiver<()>) -> S
                 command = receiver.recv() => {
                     if let Some(command) = command {
 
-                        let bytes = command.into_bytes();
+                        let bytes = command.into_versioned_bytes(*msnp_version.borrow());
 
                         unsafe {
                             debug!("NS >> | {}", from_utf8_unchecked(&bytes));
                         }
 
-                        let _result = write.write_all(&bytes).await;
+                        let _result = write.send(Bytes::from(bytes)).await;
                     }
                 },
                 _kill_signal = kill_recv.recv() => {
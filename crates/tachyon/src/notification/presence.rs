@@ -0,0 +1,103 @@
+use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, ToMsnUser};
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::identifiers::is_sha1::IsSha1;
+use matrix_sdk::Client;
+use msnp::msnp::models::contact::Contact;
+use msnp::msnp::notification::command::command::NotificationServerCommand;
+use msnp::msnp::notification::command::fln::FlnServer;
+use msnp::msnp::notification::command::nln::NlnServer;
+use msnp::msnp::notification::command::ubx::{ExtendedPresenceContent, UbxPayload, UbxServer};
+use msnp::msnp::notification::models::endpoint_data::EndpointData;
+use msnp::shared::models::capabilities::ClientCapabilities;
+use msnp::shared::models::display_name::DisplayName;
+use msnp::shared::models::endpoint_id::EndpointId;
+use msnp::shared::models::network_id_email::NetworkIdEmail;
+use msnp::shared::models::presence_status::PresenceStatus;
+
+pub(crate) async fn send_contacts_online(contacts: Vec<Contact>, tachyon_client: &TachyonClient, matrix_client: &Client) {
+    let notification_handle = tachyon_client.notification_handle();
+
+    //Hardcoded presence to Online
+    //TODO: implement real presence
+    for contact in contacts {
+
+        if !contact.email_address.is_sha1_imprecise() {
+            continue;
+        }
+
+        let found_room = matrix_client.find_room_from_email(&contact.email_address, tachyon_client.room_hashes());
+
+        let display_name = if let Ok(Some(room)) = &found_room {
+            if let Ok(msn_user) = room.to_msn_user_lazy(tachyon_client.resolver_caches()).await {
+                msn_user.display_name
+            } else {
+                None
+            }
+
+        } else {
+            None
+        };
+
+        let avatar = if let Ok(Some(room)) = &found_room {
+            tachyon_client.get_avatar_as_msn_object(room.room_id()).await.unwrap()
+        } else { None };
+
+        let profile_extras = if let Ok(Some(room)) = &found_room {
+            tachyon_client.get_contact_profile_extras(room.room_id()).await.unwrap_or_default()
+        } else { None };
+
+
+        let network_id_email = NetworkIdEmail {
+            network_id: contact.network_id.clone(),
+            email: contact.email_address.clone(),
+        };
+
+        let endpoint_id = EndpointId::from_email_addr(network_id_email.email.clone());
+        let endpoint_guid = endpoint_id.endpoint_guid.expect("to be here");
+
+        let _ = notification_handle.send(NotificationServerCommand::NLN(NlnServer{
+            presence_status: PresenceStatus::NLN,
+            target_user: network_id_email.clone(),
+            via: None,
+            display_name: display_name.map(|name| DisplayName::new(name) ).unwrap_or_default(),
+            client_capabilities: Default::default(),
+            avatar,
+            badge_url: None,
+        })).await;
+
+        let mut extended_presence = ExtendedPresenceContent {
+            psm: "".to_string(),
+            current_media: "".to_string(),
+            ddp: None,
+            signature_sound: None,
+            scene: None,
+            color_scheme: None,
+            endpoint_data: EndpointData::new(Some(endpoint_guid), ClientCapabilities::default()),
+            private_endpoint_data: None,
+        };
+
+        if let Some(profile_extras) = &profile_extras {
+            extended_presence.set_profile_extras(profile_extras);
+        }
+
+        //If we don't set the EndpointData here, we don't get P2P Transport Requests because the client has no information about the presence of this Endpoint.
+        let _ = notification_handle.send(NotificationServerCommand::UBX(UbxServer {
+            target_user: network_id_email,
+            via: None,
+            payload: UbxPayload::ExtendedPresence(extended_presence),
+        })).await;
+    }
+}
+
+//Mirrors send_contacts_online, contacts that never got an NLN don't need an FLN.
+pub(crate) async fn send_contacts_offline(contacts: Vec<Contact>, tachyon_client: &TachyonClient) {
+    let notification_handle = tachyon_client.notification_handle();
+
+    for contact in contacts {
+        if !contact.email_address.is_sha1_imprecise() {
+            continue;
+        }
+
+        let _ = notification_handle.send(NotificationServerCommand::FLN(FlnServer::new(contact.get_network_id_email()))).await;
+    }
+}
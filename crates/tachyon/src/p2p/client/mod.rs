@@ -1,4 +1,6 @@
 pub mod session;
 pub mod transport;
 mod send_file;
-mod send_msn_object;
\ No newline at end of file
+mod send_msn_object;
+mod photo_sharing;
+mod webcam;
\ No newline at end of file
@@ -0,0 +1,257 @@
+use crate::matrix::extensions::msn_user_resolver::ToMsnUser;
+use crate::p2p::client::send_file::attachment_info;
+use crate::p2p::client::session::{P2PSession, SendPhotoContent, SessionId, SessionType, SharePhotoContent, SharedPhoto};
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use anyhow::anyhow;
+use log::{debug, info, warn};
+use matrix_sdk::attachment::AttachmentConfig;
+use matrix_sdk::media::{MediaFormat, MediaRequestParameters};
+use matrix_sdk::ruma::events::room::message::ImageMessageEventContent;
+use msnp::p2p::v2::data_preparation_payload::{Map, MapOperation};
+use msnp::p2p::v2::factories::P2PPayloadFactory;
+use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
+use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject};
+use msnp::shared::models::msn_user::MsnUser;
+use msnp::shared::models::uuid::Uuid;
+use msnp::shared::traits::TryFromBytes;
+use ruma::RoomId;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+//WLM shows photo thumbnails at 96x96 in the Photo Sharing pane.
+const PHOTO_THUMBNAIL_SIZE: u32 = 96;
+
+//How many PROGRESS maps we send back while downloading a photo from the client.
+const PROGRESS_STEPS: usize = 5;
+
+impl TachyonClient {
+
+    pub async fn share_photo(&self, room_id: &RoomId, inviter: &MsnUser, sender: &MsnUser, content: &ImageMessageEventContent) -> Result<(), anyhow::Error> {
+        let media = self.matrix_client().media();
+
+        let photo = media.get_media_content(
+            &MediaRequestParameters { source: content.source.clone(), format: MediaFormat::File },
+            false,
+        ).await?;
+
+        let thumbnail = match self.get_media_thumbnail(&content.source, PHOTO_THUMBNAIL_SIZE).await {
+            Ok(thumbnail) => thumbnail,
+            Err(e) => {
+                warn!("Could not get a thumbnail for shared photo, using the full picture instead: {}", e);
+                photo.clone()
+            }
+        };
+
+        let filename = content.filename.as_ref().unwrap_or(&content.body).to_owned();
+        let friendly = FriendlyName::new(Path::new(&filename).file_stem().and_then(|s| s.to_str()).unwrap_or_default());
+        let creator = inviter.get_email_address().to_string();
+
+        let thumbnail_object = MSNObjectFactory::get_shared_photo(&thumbnail, creator.clone(), friendly.clone());
+        let photo_object = MSNObjectFactory::get_shared_photo(&photo, creator, friendly);
+
+        let shared_photo = SharedPhoto {
+            guid: Uuid::new(),
+            thumbnail: thumbnail_object,
+            photo: photo_object,
+            filename,
+        };
+
+        let existing_session = self.find_photo_sharing_session(room_id);
+
+        match existing_session {
+            Some(session) => {
+                let SessionType::SharePhoto(photo_sharing) = session.session_type() else {
+                    unreachable!("find_photo_sharing_session only returns SharePhoto sessions");
+                };
+
+                photo_sharing.photos.insert(shared_photo.thumbnail.sha1d.clone(), thumbnail);
+                photo_sharing.photos.insert(shared_photo.photo.sha1d.clone(), photo);
+                photo_sharing.pending_photos.lock().expect("Not to be poisonned").push(shared_photo);
+
+                if session.is_established() {
+                    self.send_pending_photos(&session).await;
+                }
+            }
+            None => {
+                let transport = self.get_or_create_transport(room_id, inviter);
+                let photo_sharing = SharePhotoContent::new(room_id.to_owned(), inviter.endpoint_id.clone(), sender.compute_display_name().to_string(), self.own_user().endpoint_id);
+
+                photo_sharing.photos.insert(shared_photo.thumbnail.sha1d.clone(), thumbnail);
+                photo_sharing.photos.insert(shared_photo.photo.sha1d.clone(), photo);
+                photo_sharing.pending_photos.lock().expect("Not to be poisonned").push(shared_photo);
+
+                let (session_id, session) = self.create_session_with_random_id(transport, SessionType::SharePhoto(photo_sharing));
+                info!("Inviting the client to a Photo Sharing session {}", session_id);
+                session.receive_invite().await;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn find_photo_sharing_session(&self, room_id: &RoomId) -> Option<P2PSession> {
+        self.inner.sessions.iter().find_map(|entry| {
+            match entry.value().session_type() {
+                SessionType::SharePhoto(content) if content.room_id == room_id => Some(entry.value().clone()),
+                _ => None
+            }
+        })
+    }
+
+    pub fn get_shared_photo(&self, room_id: &RoomId, sha1d: &str) -> Option<Vec<u8>> {
+        let session = self.find_photo_sharing_session(room_id)?;
+        let SessionType::SharePhoto(content) = session.session_type() else {
+            return None;
+        };
+
+        content.photos.get(sha1d).map(|photo| photo.value().clone())
+    }
+
+    //Announces the photos that were queued before the client accepted the session.
+    pub(crate) async fn send_pending_photos(&self, session: &P2PSession) {
+        let SessionType::SharePhoto(content) = session.session_type() else {
+            return;
+        };
+
+        let pending: Vec<SharedPhoto> = content.pending_photos.lock().expect("Not to be poisonned").drain(..).collect();
+
+        for shared_photo in pending {
+            let index = content.next_index.fetch_add(1, Ordering::Relaxed);
+            debug!("Announcing shared photo {} ({}) on session {}", &shared_photo.filename, &shared_photo.guid, session.session_id());
+
+            let added = Map::new_photo_added(&shared_photo.guid, shared_photo.photo.sha1d.clone(), index, &shared_photo.thumbnail, &shared_photo.photo, shared_photo.filename);
+            send_map(session, content, added).await;
+
+            //Select the photo we just added so it shows up right away.
+            let selected = Map::new_change(&shared_photo.guid, index);
+            send_map(session, content, selected).await;
+        }
+    }
+
+    pub(crate) async fn handle_photo_sharing_packet(&self, session: &P2PSession, p2p_payload: RawP2PPayload) -> Result<(), anyhow::Error> {
+        let SessionType::SharePhoto(content) = session.session_type() else {
+            return Err(anyhow!("Session {} is not a Photo Sharing session", session.session_id()));
+        };
+
+        let session_id = p2p_payload.session_id;
+
+        //Plain data preparation packets don't carry any map.
+        if p2p_payload.payload.iter().all(|byte| *byte == 0) {
+            return Ok(());
+        }
+
+        let missing_bytes = p2p_payload.get_missing_bytes_count();
+        self.inner.chunked_uploads.entry(session_id).or_default().push(p2p_payload);
+
+        if missing_bytes > 0 {
+            return Ok(());
+        }
+
+        let (_, chunks) = self.inner.chunked_uploads.remove(&session_id).ok_or(anyhow!("Missing chunks in map. SessionId: {}", session_id))?;
+
+        let mut map_bytes = Vec::new();
+        for mut chunk in chunks {
+            map_bytes.append(&mut chunk.payload);
+        }
+
+        let map = Map::try_from_bytes(map_bytes)?;
+
+        match map.operation()? {
+            MapOperation::PhotoAdded => {
+                let photo = map.photo()?.ok_or(anyhow!("Photo Sharing ADDH map without a photo: {:?}", &map))?;
+                let filename = map.body.display_name.clone().unwrap_or_else(|| "Shared Photo.jpg".to_string());
+                self.request_shared_photo(session, content, map.guid().to_string(), photo, filename).await
+            }
+            MapOperation::Change | MapOperation::Progress => {
+                debug!("Ignoring Photo Sharing map on session {}: {:?}", session_id, &map);
+                Ok(())
+            }
+        }
+    }
+
+    async fn request_shared_photo(&self, session: &P2PSession, content: &SharePhotoContent, guid: String, photo: MsnObject, filename: String) -> Result<(), anyhow::Error> {
+        let room = self.matrix_client().get_room(&content.room_id).ok_or(anyhow!("Could not find room for Photo Sharing session. RoomId: {}", &content.room_id))?;
+        let requester = room.to_msn_user_lazy(self.resolver_caches()).await?;
+        let owner = self.own_user();
+
+        let (session_id, photo_session) = self.create_session_with_random_id(session.transport(), SessionType::SendPhoto(SendPhotoContent {
+            room_id: content.room_id.clone(),
+            requester,
+            owner,
+            msn_object: photo,
+            guid,
+            filename,
+            photo_sharing_session_id: session.session_id(),
+        }));
+
+        info!("Requesting shared photo from the client on session {}", session_id);
+        photo_session.receive_invite().await;
+        Ok(())
+    }
+
+    pub(crate) async fn send_photo_buffered(&self, session_id: SessionId, p2p_payload: RawP2PPayload, content: &SendPhotoContent) -> Result<(), anyhow::Error> {
+        if p2p_payload.tf.is_metadata() {
+            return Ok(());
+        }
+
+        let expected_size = content.msn_object.size.max(1);
+        let chunk_len = p2p_payload.payload.len();
+
+        let received_len: usize = {
+            let mut chunks = self.inner.chunked_uploads.entry(session_id).or_default();
+            chunks.push(p2p_payload);
+            chunks.iter().map(|chunk| chunk.payload.len()).sum()
+        };
+
+        if received_len < expected_size {
+            let previous_step = (received_len - chunk_len) * PROGRESS_STEPS / expected_size;
+            let current_step = received_len * PROGRESS_STEPS / expected_size;
+            if current_step > previous_step {
+                self.send_photo_progress(content, received_len as f32 / expected_size as f32).await;
+            }
+            return Ok(());
+        }
+
+        let (_, chunks) = self.inner.chunked_uploads.remove(&session_id).ok_or(anyhow!("Missing chunks in map. SessionId: {}", session_id))?;
+
+        let mut photo = Vec::with_capacity(received_len);
+        for mut chunk in chunks {
+            photo.append(&mut chunk.payload);
+        }
+
+        if photo.len() != content.msn_object.size {
+            warn!("Shared photo session {} received {} bytes but the map announced {}", session_id, photo.len(), content.msn_object.size);
+        }
+
+        self.send_photo_progress(content, 1.0).await;
+
+        let room = self.matrix_client().get_room(&content.room_id).ok_or(anyhow!("Could not find room to send photo to. RoomId: {} SessionId: {}", &content.room_id, session_id))?;
+
+        let mime = mime_guess::from_path(&content.filename).first().unwrap_or(mime::IMAGE_JPEG);
+        let config = AttachmentConfig::new().info(attachment_info(&mime, photo.len()));
+
+        let len = photo.len();
+        let resp = room.send_attachment(&content.filename, &mime, photo, config).await.map_err(|e| anyhow!(e))?;
+        info!("Uploaded shared photo session {}: {} bytes ({}) -> event {}", session_id, len, mime, resp.event_id);
+
+        Ok(())
+    }
+
+    async fn send_photo_progress(&self, content: &SendPhotoContent, progress: f32) {
+        let Some(session) = self.get_session(content.photo_sharing_session_id) else {
+            return;
+        };
+
+        let SessionType::SharePhoto(photo_sharing) = session.session_type() else {
+            return;
+        };
+
+        send_map(&session, photo_sharing, Map::new_progress(&content.guid, progress)).await;
+    }
+}
+
+async fn send_map(session: &P2PSession, content: &SharePhotoContent, map: Map) {
+    let mut packet = P2PPayloadFactory::get_data_preparation_message(session.session_id());
+    packet.set_payload(map.to_bytes());
+    session.receive_packet(&content.sender, &content.sender_display_name, &content.receiver, packet).await;
+}
@@ -50,7 +50,7 @@ impl TachyonClient {
 
 /// The sdk only keeps `info` fields whose `AttachmentInfo` variant matches the
 /// event type it derives from the mime, so build the matching variant.
-fn attachment_info(mime: &Mime, size: usize) -> AttachmentInfo {
+pub(super) fn attachment_info(mime: &Mime, size: usize) -> AttachmentInfo {
     let size = UInt::new(size as u64);
     match mime.type_() {
         mime::IMAGE => AttachmentInfo::Image(BaseImageInfo { size, ..Default::default() }),
@@ -15,7 +15,7 @@ use std::str::FromStr;
 impl TachyonClient {
 
     pub async fn request_msn_object(&self, room: &Room, msn_object: MsnObject) -> Result<(), anyhow::Error> {
-        let requester = room.to_msn_user_lazy().await?;
+        let requester = room.to_msn_user_lazy(self.resolver_caches()).await?;
         let owner = self.own_user();
 
         let transport = self.get_or_create_transport(room.room_id(), &requester);
@@ -51,17 +51,19 @@ impl TachyonClient {
 
         let (_, chunks) = self.inner.chunked_uploads.remove(&session_id).ok_or(anyhow!("Missing chunks in map. SessionId: {}", session_id))?;
 
-        let mut voice_clip = Vec::with_capacity(received_len);
+        let mut object_bytes = Vec::with_capacity(received_len);
         for mut chunk in chunks {
-            voice_clip.append(&mut chunk.payload);
+            object_bytes.append(&mut chunk.payload);
         }
 
-        if voice_clip.len() != expected_size {
-            warn!("MSNObject session {} received {} bytes but the invite announced {}", session_id, voice_clip.len(), expected_size);
+        if object_bytes.len() != expected_size {
+            warn!("MSNObject session {} received {} bytes but the invite announced {}", session_id, object_bytes.len(), expected_size);
         }
 
         match content.msn_object.obj_type {
-            MsnObjectType::VoiceClip => self.send_voice_clip_to_matrix(session_id, &content.room_id, voice_clip).await,
+            MsnObjectType::VoiceClip => self.send_voice_clip_to_matrix(session_id, &content.room_id, object_bytes).await,
+            MsnObjectType::DisplayPicture => self.set_own_avatar(object_bytes).await,
+            MsnObjectType::DynamicDisplayPicture | MsnObjectType::SignatureSound | MsnObjectType::Scene => self.store_own_profile_extra(&content.msn_object, object_bytes).await,
             ref obj_type => Err(anyhow!("Received an MSNObject we don't know how to forward to Matrix: {:?}", obj_type)),
         }
     }
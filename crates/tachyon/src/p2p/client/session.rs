@@ -1,12 +1,17 @@
 use crate::p2p::client::transport::Transport;
+use crate::p2p::client::webcam::WebcamRecorder;
 use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::error::TachyonError;
 use matrix_sdk::ruma::events::room::MediaSource;
 use msnp::p2p::v2::factories::P2PPayloadFactory;
 use msnp::p2p::v2::slp::raw_slp_payload::SlpPayloadFactory;
 use msnp::p2p::v2::slp::session_slp_context::PreviewData;
 use msnp::shared::models::endpoint_id::EndpointId;
 use msnp::shared::traits::IntoBytes;
+use std::sync::atomic::AtomicU32;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
 use anyhow::anyhow;
 use ruma::{OwnedRoomId, RoomId};
 use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
@@ -38,6 +43,14 @@ impl TachyonClient {
         self.inner.chunked_uploads.remove(&session_id);
     }
 
+    //Hosted servers cap the sessions a client can open, the ones Matrix starts are never refused.
+    pub fn check_session_limit(&self) -> Result<(), TachyonError> {
+        match self.config().hosted.p2p_session_limit() {
+            Some(max) if self.inner.sessions.len() >= max => Err(TachyonError::TooManySessions { max }),
+            _ => Ok(())
+        }
+    }
+
 }
 
 pub struct P2PSessionInner {
@@ -46,6 +59,7 @@ pub struct P2PSessionInner {
     session_type: SessionType,
     session_status: Mutex<SessionStatus>,
     call_id: Uuid,
+    last_activity: Mutex<Instant>,
 }
 
 #[derive(Clone)]
@@ -65,7 +79,12 @@ impl P2PSession {
     pub async fn receive_invite(&self) {
         match &self.inner.session_type {
             SessionType::ReceiveFile(content) => {
-                let slp_payload = SlpPayloadFactory::get_file_transfer_request(&content.sender, &content.receiver,  &PreviewData::new(content.file_size, content.filename.clone()), self.inner.session_id, &self.inner.call_id).unwrap();
+                let preview_data = match &content.preview {
+                    Some(preview) => PreviewData::new_with_preview(content.file_size, content.filename.clone(), preview.clone()),
+                    None => PreviewData::new(content.file_size, content.filename.clone()),
+                };
+
+                let slp_payload = SlpPayloadFactory::get_file_transfer_request(&content.sender, &content.receiver, &preview_data, self.inner.session_id, &self.inner.call_id).unwrap();
                 let mut packet = P2PPayloadFactory::get_sip_text_message();
                 packet.set_payload(slp_payload.into_bytes());
                 self.inner.transport.receive_data_packet(&content.sender, &content.sender_display_name, &content.receiver, packet).await;
@@ -80,13 +99,32 @@ impl P2PSession {
                 packet.set_payload(slp_payload.into_bytes());
                 self.inner.transport.receive_data_packet(&content.requester.endpoint_id, content.requester.compute_display_name(), &content.owner.endpoint_id, packet).await;
             }
+            SessionType::SharePhoto(content) => {
+                let slp_payload = SlpPayloadFactory::get_photo_sharing_request(&content.sender, &content.receiver, self.inner.session_id, &self.inner.call_id).unwrap();
+                let mut packet = P2PPayloadFactory::get_sip_text_message();
+                packet.set_payload(slp_payload.into_bytes());
+                self.inner.transport.receive_data_packet(&content.sender, &content.sender_display_name, &content.receiver, packet).await;
+            }
+            //Only the client shares its webcam, we never invite it to one.
+            SessionType::WebcamRecording(_) => {}
+            SessionType::SendPhoto(content) => {
+                let slp_payload = SlpPayloadFactory::get_shared_photo_request(&content.requester, &content.owner, &content.msn_object, self.inner.session_id).unwrap();
+                let mut packet = P2PPayloadFactory::get_sip_text_message();
+                packet.set_payload(slp_payload.into_bytes());
+                self.inner.transport.receive_data_packet(&content.requester.endpoint_id, content.requester.compute_display_name(), &content.owner.endpoint_id, packet).await;
+            }
         }
     }
 
     pub async fn receive_packet(&self, sender: &EndpointId, sender_display_name: &str, receiver: &EndpointId, packet: RawP2PPayload){
+        self.touch();
         self.transport().receive_data_packet(sender, sender_display_name, receiver, packet).await;
     }
     
+    pub fn session_id(&self) -> SessionId {
+        self.inner.session_id
+    }
+
     pub fn session_type(&self) -> &SessionType {
         &self.inner.session_type
     }
@@ -95,10 +133,28 @@ impl P2PSession {
         &self.inner.call_id
     }
 
+    pub fn is_established(&self) -> bool {
+        matches!(*self.inner.session_status.lock().expect("Not to be poisonned"), SessionStatus::Established)
+    }
+
+    pub fn touch(&self) {
+        *self.inner.last_activity.lock().expect("Not to be poisonned") = Instant::now();
+    }
+
+    //Sessions expire once idle for the ttl, a long transfer keeps its session alive.
+    //Webcams can legitimately go quiet once they're up, they're cleared when they end.
+    pub fn is_expired(&self, now: Instant, ttl: Duration) -> bool {
+        let is_live_media = self.is_established() && matches!(self.inner.session_type, SessionType::WebcamRecording(_));
+        let last_activity = *self.inner.last_activity.lock().expect("Not to be poisonned");
+        !is_live_media && now >= last_activity + ttl
+    }
+
     pub(crate) fn accept(&self) -> Result<(), anyhow::Error> {
         let mut lock = self.inner.session_status.lock().expect("Not to be poisonned");
         if matches!(*lock, SessionStatus::Invite) {
             *lock = SessionStatus::Established;
+            drop(lock);
+            self.touch();
             Ok(())
         } else {
             Err(anyhow!("Invalid state transition: trying to go from {:?} to {:?}", *lock, SessionStatus::Established))
@@ -115,6 +171,7 @@ impl P2PSession {
                 session_type,
                 session_status: Mutex::new(SessionStatus::Invite),
                 call_id: Uuid::from_seed(&session_id.to_string()),
+                last_activity: Mutex::new(Instant::now()),
             }),
         }
     }
@@ -129,6 +186,9 @@ pub enum SessionType {
     ReceiveMsnObject(ReceiveMsnObject),
     SendFile(SendFileContent),
     SendMsnObject(SendMsnObjectContent),
+    SharePhoto(SharePhotoContent),
+    SendPhoto(SendPhotoContent),
+    WebcamRecording(WebcamRecordingContent),
 }
 
 pub struct ReceiveFileContent {
@@ -137,7 +197,9 @@ pub struct ReceiveFileContent {
     pub receiver: EndpointId,
     pub media_source: MediaSource,
     pub file_size: usize,
-    pub filename: String
+    pub filename: String,
+    //Thumbnail shown in the file transfer invite, WLM expects a 96x96 PNG
+    pub preview: Option<Vec<u8>>
 }
 
 pub struct ReceiveMsnObject {
@@ -156,4 +218,59 @@ pub struct SendMsnObjectContent {
     pub requester: MsnUser,
     pub owner: MsnUser,
     pub msn_object: MsnObject,
-}
\ No newline at end of file
+}
+
+//A Photo Sharing session goes both ways: photos from Matrix are offered through it,
+//and photos added by the client are fetched over separate SendPhoto sessions.
+pub struct SharePhotoContent {
+    pub room_id: OwnedRoomId,
+    pub sender: EndpointId,
+    pub sender_display_name: String,
+    pub receiver: EndpointId,
+    //Photos waiting for the session to be accepted before being announced
+    pub pending_photos: Mutex<Vec<SharedPhoto>>,
+    //Thumbnails & photos we announced, by SHA1D
+    pub photos: DashMap<String, Vec<u8>>,
+    pub next_index: AtomicU32,
+}
+
+impl SharePhotoContent {
+    pub fn new(room_id: OwnedRoomId, sender: EndpointId, sender_display_name: String, receiver: EndpointId) -> Self {
+        Self {
+            room_id,
+            sender,
+            sender_display_name,
+            receiver,
+            pending_photos: Default::default(),
+            photos: Default::default(),
+            next_index: AtomicU32::new(1),
+        }
+    }
+}
+
+pub struct SharedPhoto {
+    pub guid: Uuid,
+    pub thumbnail: MsnObject,
+    pub photo: MsnObject,
+    pub filename: String,
+}
+
+pub struct SendPhotoContent {
+    pub room_id: OwnedRoomId,
+    pub requester: MsnUser,
+    pub owner: MsnUser,
+    pub msn_object: MsnObject,
+    pub guid: String,
+    pub filename: String,
+    pub photo_sharing_session_id: SessionId,
+}
+
+//A webcam the client shows to the contact of a room, sent to Matrix as a video once it stops.
+pub struct WebcamRecordingContent {
+    pub room_id: OwnedRoomId,
+    //The contact, as seen by the client
+    pub sender: EndpointId,
+    //The client
+    pub receiver: EndpointId,
+    pub recorder: WebcamRecorder,
+}
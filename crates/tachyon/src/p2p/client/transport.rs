@@ -2,6 +2,9 @@ use std::slice::Chunks;
 use crate::switchboard::models::switchboard_handle::SwitchboardHandle;
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use matrix_sdk::ruma::RoomId;
+use msnp::p2p::transport_packet::{P2PVersion, TransportPacket};
+use msnp::p2p::v1::factories::P2PPacketFactory;
+use msnp::p2p::v1::p2p_packet::P2PPacket;
 use msnp::p2p::v2::p2p_transport_packet::{P2PTransportPacket, TransportOperationCode};
 use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
 use msnp::shared::models::endpoint_id::EndpointId;
@@ -28,7 +31,10 @@ impl TachyonClient {
         self.inner.transports.entry(room_id.to_owned())
             .or_insert_with(|| {
                 let switchboard_handle = self.switchboards().get_or_initialize(room_id, inviter);
-                Transport::new(TransportSender::SBBridge(switchboard_handle), inviter.endpoint_id.clone(), self.own_user().endpoint_id)
+                let own_user = self.own_user();
+                let version = P2PVersion::for_capabilities(&own_user.capabilities);
+                let own_endpoint_id = own_user.endpoint_id;
+                Transport::new(TransportSender::SBBridge(switchboard_handle), inviter.endpoint_id.clone(), own_endpoint_id, version)
             })
             .clone()
     }
@@ -45,7 +51,7 @@ pub enum TransportSender {
 }
 
 impl TransportSender {
-    pub async fn send_packet(&self, sender: &EndpointId, sender_display_name: &str, receiver: &EndpointId, packet: P2PTransportPacket) {
+    pub async fn send_packet(&self, sender: &EndpointId, sender_display_name: &str, receiver: &EndpointId, packet: TransportPacket) {
         match self {
             TransportSender::SBBridge(handle) => {
                 let msg = P2PMessagePayload::new(sender.to_owned(), receiver.clone(), packet, Some(sender_display_name.to_string()));
@@ -57,7 +63,7 @@ impl TransportSender {
         }
     }
 
-    pub async fn send_chunks(&self, sender: &EndpointId, sender_display_name: &str, receiver: &EndpointId, packets: Vec<P2PTransportPacket>) {
+    pub async fn send_chunks(&self, sender: &EndpointId, sender_display_name: &str, receiver: &EndpointId, packets: Vec<TransportPacket>) {
         match self {
             TransportSender::SBBridge(handle) => {
                 let msgs: Vec<P2PMessagePayload> = packets.into_iter().map( |tp| P2PMessagePayload::new(sender.to_owned(), receiver.clone(), tp, Some(sender_display_name.to_string()))).collect();
@@ -83,10 +89,13 @@ type PackageNumber = u16;
 
 struct TransportInner {
     transport_id: u32,
+    version: P2PVersion,
+    //P2Pv1 has no sequence numbers, this is the identifier of the last message we sent instead
     sequence_number: tokio::sync::Mutex<u32>,
     status: tokio::sync::RwLock<TransportStatus>,
     transport_sender: tokio::sync::Mutex<TransportSender>,
     chunks_unwraped: DashMap<PackageNumber, Vec<P2PTransportPacket>>,
+    v1_chunks_unwraped: DashMap<u32, P2PPacket>,
     receiver: EndpointId,
     sender: EndpointId
 }
@@ -97,18 +106,31 @@ pub struct Transport {
 }
 
 const PAYLOAD_MAX_LEN: usize = 2048;
+const V1_PAYLOAD_MAX_LEN: usize = 1202;
 
 impl Transport {
-    pub fn new(initial_transport: TransportSender, sender: EndpointId, receiver: EndpointId) -> Transport {
-        let sequence_number: u32 = 0;
+    pub fn new(initial_transport: TransportSender, sender: EndpointId, receiver: EndpointId, version: P2PVersion) -> Transport {
+        let sequence_number: u32 = match version {
+            P2PVersion::V1 => rand::random::<u32>() >> 8,
+            P2PVersion::V2 => 0
+        };
         let transport_id: u32 = rand::random();
+
+        //P2Pv1 peers are addressed by their bare email in P2P-Src / P2P-Dest
+        let (sender, receiver) = match version {
+            P2PVersion::V1 => (sender.strip_endpoint_guid(), receiver.strip_endpoint_guid()),
+            P2PVersion::V2 => (sender, receiver)
+        };
+
         Transport {
             inner: Arc::new(TransportInner {
                 transport_id,
+                version,
                 sequence_number: tokio::sync::Mutex::new(sequence_number),
                 status: tokio::sync::RwLock::new(TransportStatus::Initial),
                 transport_sender: tokio::sync::Mutex::new(initial_transport),
                 chunks_unwraped: Default::default(),
+                v1_chunks_unwraped: Default::default(),
                 receiver,
                 sender,
             }),
@@ -118,6 +140,12 @@ impl Transport {
 
     pub async fn receive_data_packet(&self, sender: &EndpointId, sender_display_name: &str, receiver: &EndpointId, packet: RawP2PPayload) {
 
+        if self.inner.version == P2PVersion::V1 {
+            //No transport handshake to wait for in P2Pv1
+            self.receive_v1_payload(packet).await;
+            return;
+        }
+
         if packet.session_id != 0 {
             if let Err(e) = self.wait_for_transport_ready(Duration::from_secs(20)).await {
                 error!("Dropping outgoing data packet for session {}: {}", packet.session_id, e);
@@ -175,8 +203,11 @@ impl Transport {
 
             let mut slp_transport_err_packet = P2PPayloadFactory::get_sip_text_message();
             slp_transport_err_packet.set_payload(slp_transport_req_error_response.into_bytes());
-            let transport_packet = P2PTransportPacket::new(0, Some(slp_transport_err_packet));
-            self.receive_single_packet(transport_packet).await;
+
+            match self.inner.version {
+                P2PVersion::V1 => self.receive_v1_payload(slp_transport_err_packet).await,
+                P2PVersion::V2 => self.receive_single_packet(P2PTransportPacket::new(0, Some(slp_transport_err_packet))).await
+            }
 
             let mut write_lock = self.inner.status.write().await;
             *write_lock = TransportStatus::Ready;
@@ -195,7 +226,7 @@ impl Transport {
             current_sequence_number = current_sequence_number + transport_packet.get_payload_length();
         }
 
-        transport_sender_lock.send_chunks(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, transport_packets).await;
+        transport_sender_lock.send_chunks(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, transport_packets.into_iter().map(TransportPacket::from).collect()).await;
         *sequence_lock = current_sequence_number;
     }
 
@@ -212,12 +243,44 @@ impl Transport {
         let next_sequence_number = current_sequence_number + transport_packet.get_payload_length();;
 
         debug!("Client<-Transport: {:?}", &transport_packet);
-        transport_sender_lock.send_packet(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, transport_packet).await;
+        transport_sender_lock.send_packet(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, transport_packet.into()).await;
 
         *sequence_lock = next_sequence_number
     }
 
+    async fn receive_v1_payload(&self, payload: RawP2PPayload) {
+        let mut identifier_lock = self.inner.sequence_number.lock().await;
+        let transport_sender_lock = self.inner.transport_sender.lock().await;
+
+        *identifier_lock = identifier_lock.wrapping_add(1);
+        let mut packets: Vec<TransportPacket> = P2PPacket::from_data_layer(payload, *identifier_lock).chunk(V1_PAYLOAD_MAX_LEN).into_iter().map(TransportPacket::from).collect();
+
+        if packets.len() == 1 {
+            let packet = packets.remove(0);
+            debug!("Client<-Transport: {:?}", &packet);
+            transport_sender_lock.send_packet(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, packet).await;
+        } else {
+            let batches: Vec<Vec<TransportPacket>> = packets.into_iter().chunks(100).into_iter().map(|c| c.collect()).collect();
+            for batch in batches {
+                transport_sender_lock.send_chunks(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, batch).await;
+            }
+        }
+    }
+
+    async fn send_v1_ack(&self, acked: &P2PPacket) {
+        let mut identifier_lock = self.inner.sequence_number.lock().await;
+        let transport_sender_lock = self.inner.transport_sender.lock().await;
+
+        *identifier_lock = identifier_lock.wrapping_add(1);
+        let ack = P2PPacketFactory::get_ack(acked, *identifier_lock);
+        transport_sender_lock.send_packet(&self.inner.sender, self.inner.sender.email_addr.as_str(), &self.inner.receiver, ack.into()).await;
+    }
+
     pub async fn request_for_ack(&self) {
+        if self.inner.version == P2PVersion::V1 {
+            //P2Pv1 acknowledges every complete message on its own
+            return;
+        }
         self.receive_single_packet(P2PTransportPacketFactory::get_rak()).await;
     }
 
@@ -279,7 +342,50 @@ impl Transport {
     // Handles SYN handshake
     // handles RAK
 
-    pub async fn unwrap_packet(&self, packet: P2PTransportPacket) -> Result<(Option<UnwrappedP2PPacket>) , anyhow::Error> {
+    pub async fn unwrap_packet(&self, packet: TransportPacket) -> Result<Option<UnwrappedP2PPacket>, anyhow::Error> {
+        match packet {
+            TransportPacket::V1(packet) => self.unwrap_v1_packet(packet).await,
+            TransportPacket::V2(packet) => self.unwrap_v2_packet(packet).await
+        }
+    }
+
+    // Reassembles chunked messages and acknowledges them once complete.
+    async fn unwrap_v1_packet(&self, packet: P2PPacket) -> Result<Option<UnwrappedP2PPacket>, anyhow::Error> {
+        debug!("Client->Transport: {:?}", &packet);
+
+        if packet.is_ack() {
+            return Ok(None);
+        }
+
+        let identifier = packet.header.identifier;
+
+        let complete = match self.inner.v1_chunks_unwraped.remove(&identifier) {
+            None if packet.header.offset != 0 => {
+                return Err(anyhow!("P2Pv1 message {} starts at offset {}, its first chunk went missing", identifier, packet.header.offset));
+            }
+            None => packet,
+            Some((_, mut reformed)) => {
+                reformed.append_chunk(packet)?;
+                reformed
+            }
+        };
+
+        if !complete.is_complete() {
+            self.inner.v1_chunks_unwraped.insert(identifier, complete);
+            return Ok(None);
+        }
+
+        self.send_v1_ack(&complete).await;
+
+        let payload = complete.into_data_layer();
+        if payload.session_id == 0 {
+            Ok(Some(UnwrappedP2PPacket::Slp(payload.get_payload_as_slp()?, TransportOperationCode::default())))
+        } else {
+            Ok(Some(UnwrappedP2PPacket::DataPacket(payload, TransportOperationCode::default())))
+        }
+    }
+
+    async fn unwrap_v2_packet(&self, packet: P2PTransportPacket) -> Result<Option<UnwrappedP2PPacket>, anyhow::Error> {
         debug!("Client->Transport: {:?}", &packet);
 
         self.unwrap_handshake(&packet).await;
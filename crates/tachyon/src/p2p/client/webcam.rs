@@ -0,0 +1,302 @@
+use crate::audio::ffmpeg;
+use crate::p2p::client::session::{P2PSession, SessionId, SessionType, WebcamRecordingContent};
+use crate::p2p::client::transport::Transport;
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use anyhow::anyhow;
+use log::{debug, info, warn};
+use matrix_sdk::attachment::{AttachmentConfig, AttachmentInfo, BaseVideoInfo};
+use matrix_sdk::ruma::UInt;
+use mime::Mime;
+use msnp::p2p::v2::factories::P2PPayloadFactory;
+use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
+use msnp::p2p::v2::slp::raw_slp_payload::{RawSlpPayload, SlpPayloadFactory};
+use msnp::p2p::v2::slp::session_slp_payload::SessionInviteRequestPayload;
+use msnp::p2p::v2::webcam::{get_webcam_auth, WebcamFrameHeader, WebcamMessage, WebcamProducer, WebcamViewer, WEBCAM_CONNECTED};
+use msnp::shared::traits::{IntoBytes, TryFromBytes};
+use ruma::{OwnedRoomId, RoomId};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+
+//Mimic frames are small, this is a few minutes of webcam. Past it we stop recording, the session stays up.
+const MAX_WEBCAM_RECORDING_SIZE: usize = 16 * 1024 * 1024;
+
+const WEBCAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+//Records the Mimic frames a client sends over the webcam TCP connection.
+#[derive(Default)]
+pub struct WebcamRecorder {
+    recording: Arc<Mutex<Vec<u8>>>,
+    //Set once recording started, the task itself belongs to the client's supervisor.
+    cancellation: Mutex<Option<CancellationToken>>,
+}
+
+impl WebcamRecorder {
+
+    fn start(&self, tachyon_client: &TachyonClient, producer: WebcamProducer) {
+        let mut cancellation = self.cancellation.lock().expect("Not to be poisonned");
+        if cancellation.is_some() {
+            return;
+        }
+
+        let token = CancellationToken::new();
+        *cancellation = Some(token.clone());
+
+        let recording = self.recording.clone();
+        tachyon_client.spawn("webcam recording", async move {
+            tokio::select! {
+                result = record(producer, recording) => {
+                    if let Err(e) = result {
+                        warn!("Webcam recording stopped: {:?}", e);
+                    }
+                }
+                _ = token.cancelled() => {}
+            }
+        });
+    }
+
+    //Stops recording and hands back what was received so far, the last frame may be cut short.
+    fn stop(&self) -> Vec<u8> {
+        if let Some(cancellation) = self.cancellation.lock().expect("Not to be poisonned").take() {
+            cancellation.cancel();
+        }
+
+        std::mem::take(&mut *self.recording.lock().expect("Not to be poisonned"))
+    }
+}
+
+impl Drop for WebcamRecorder {
+    fn drop(&mut self) {
+        if let Some(cancellation) = self.cancellation.get_mut().expect("Not to be poisonned").take() {
+            cancellation.cancel();
+        }
+    }
+}
+
+async fn record(producer: WebcamProducer, recording: Arc<Mutex<Vec<u8>>>) -> Result<(), anyhow::Error> {
+    let mut stream = connect_to_producer(&producer).await?;
+
+    stream.write_all(get_webcam_auth(&producer).as_bytes()).await?;
+
+    let mut buffer = vec![0u8; 8192];
+    let mut received = Vec::new();
+
+    //The producer acknowledges the viewer before sending any frame.
+    let frames_start = loop {
+        let size = stream.read(&mut buffer).await?;
+        if size == 0 {
+            return Err(anyhow!("Producer closed the webcam connection during the handshake"));
+        }
+        received.extend_from_slice(&buffer[..size]);
+
+        if let Some(position) = received.windows(WEBCAM_CONNECTED.len()).position(|window| window == WEBCAM_CONNECTED) {
+            break position + WEBCAM_CONNECTED.len();
+        }
+    };
+
+    stream.write_all(WEBCAM_CONNECTED).await?;
+    recording.lock().expect("Not to be poisonned").extend_from_slice(&received[frames_start..]);
+    debug!("Recording webcam session {}", producer.session);
+
+    loop {
+        let size = stream.read(&mut buffer).await?;
+        if size == 0 {
+            return Ok(());
+        }
+
+        let mut recorded = recording.lock().expect("Not to be poisonned");
+        if recorded.len() + size > MAX_WEBCAM_RECORDING_SIZE {
+            info!("Webcam session {} hit the recording size limit", producer.session);
+            return Ok(());
+        }
+        recorded.extend_from_slice(&buffer[..size]);
+    }
+}
+
+async fn connect_to_producer(producer: &WebcamProducer) -> Result<TcpStream, anyhow::Error> {
+    let port = if producer.tcp.port != 0 { producer.tcp.port } else { producer.tcp.local_port };
+
+    for address in producer.tcp.addresses() {
+        let address = SocketAddr::new(address, port);
+        match timeout(WEBCAM_CONNECT_TIMEOUT, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => debug!("Could not reach webcam producer on {}: {}", address, e),
+            Err(_) => debug!("Timed out reaching webcam producer on {}", address),
+        }
+    }
+
+    Err(anyhow!("Could not reach webcam producer on any of its addresses: {:?}", producer.tcp))
+}
+
+pub(crate) struct RecordingSummary {
+    pub len: usize,
+    pub width: u16,
+    pub height: u16,
+    pub duration: Duration,
+}
+
+//Walks the frames of a recording, a frame cut short by the end of the session doesn't count.
+pub(crate) fn summarize_recording(recording: &[u8]) -> Option<RecordingSummary> {
+    let mut offset = 0;
+    let mut first: Option<WebcamFrameHeader> = None;
+    let mut last: Option<WebcamFrameHeader> = None;
+
+    while let Some(header) = WebcamFrameHeader::parse(&recording[offset..]) {
+        if offset + header.frame_size() > recording.len() {
+            break;
+        }
+        offset += header.frame_size();
+        first.get_or_insert_with(|| header.clone());
+        last = Some(header);
+    }
+
+    let (first, last) = (first?, last?);
+    Some(RecordingSummary {
+        len: offset,
+        width: first.width,
+        height: first.height,
+        duration: Duration::from_millis(last.timestamp.saturating_sub(first.timestamp) as u64),
+    })
+}
+
+impl TachyonClient {
+
+    //The client wants to show its webcam: accept, and record it until the session ends.
+    pub(crate) async fn accept_webcam(&self, room_id: &RoomId, transport: Transport, invite: &SessionInviteRequestPayload, raw_invite: &RawSlpPayload) -> Result<(), anyhow::Error> {
+        let sender = invite.headers().receiver().clone();
+        let receiver = invite.headers().sender().clone();
+
+        let response = SlpPayloadFactory::get_200_ok_session(raw_invite)?;
+
+        let (session_id, session) = self.create_session(transport, SessionType::WebcamRecording(WebcamRecordingContent {
+            room_id: room_id.to_owned(),
+            sender,
+            receiver,
+            recorder: WebcamRecorder::default(),
+        }), invite.session_id());
+
+        let SessionType::WebcamRecording(content) = session.session_type() else {
+            unreachable!("We just created a WebcamRecording session");
+        };
+
+        let mut packet = P2PPayloadFactory::get_sip_text_message();
+        packet.set_payload(response.into_bytes());
+        session.receive_packet(&content.sender, "", &content.receiver, packet).await;
+        session.accept()?;
+
+        info!("Accepted webcam from the client on session {}", session_id);
+        Ok(())
+    }
+
+    pub(crate) async fn handle_webcam_packet(&self, session: &P2PSession, p2p_payload: RawP2PPayload) -> Result<(), anyhow::Error> {
+        let SessionType::WebcamRecording(content) = session.session_type() else {
+            return Err(anyhow!("Session {} is not a webcam session", session.session_id()));
+        };
+
+        //Plain data preparation packets don't carry any message.
+        if p2p_payload.payload.iter().all(|byte| *byte == 0) {
+            return Ok(());
+        }
+
+        match WebcamMessage::try_from_bytes(p2p_payload.payload)? {
+            WebcamMessage::Syn => {
+                send_webcam_message(session, content, WebcamMessage::Syn).await;
+                send_webcam_message(session, content, WebcamMessage::Ack).await;
+            }
+            WebcamMessage::Producer(producer) => {
+                send_webcam_message(session, content, WebcamMessage::Viewer(WebcamViewer::for_producer(&producer))).await;
+                content.recorder.start(self, producer);
+            }
+            message => {
+                debug!("Ignoring webcam message on session {}: {:?}", session.session_id(), message);
+            }
+        }
+
+        Ok(())
+    }
+
+    //The client stopped its webcam. Does nothing for sessions that aren't webcams.
+    pub(crate) fn stop_webcam_recording(&self, session_id: SessionId) -> Option<(OwnedRoomId, Vec<u8>)> {
+        let session = self.get_session(session_id)?;
+        let SessionType::WebcamRecording(content) = session.session_type() else {
+            return None;
+        };
+
+        let recording = content.recorder.stop();
+        Some((content.room_id.clone(), recording))
+    }
+
+    pub(crate) async fn send_webcam_recording(&self, session_id: SessionId, room_id: &RoomId, mut recording: Vec<u8>) -> Result<(), anyhow::Error> {
+        let Some(summary) = summarize_recording(&recording) else {
+            info!("Webcam session {} ended without a single frame", session_id);
+            return Ok(());
+        };
+        recording.truncate(summary.len);
+
+        let video = ffmpeg::transcode_webcam_to_mp4(recording).await?;
+
+        let room = self.matrix_client().get_room(room_id).ok_or(anyhow!("Could not find room to send webcam recording to. RoomId: {} SessionId: {}", room_id, session_id))?;
+
+        let info = AttachmentInfo::Video(BaseVideoInfo {
+            duration: Some(summary.duration),
+            width: Some(UInt::from(summary.width)),
+            height: Some(UInt::from(summary.height)),
+            size: UInt::new(video.len() as u64),
+            ..Default::default()
+        });
+
+        let mime = Mime::from_str("video/mp4")?;
+
+        let len = video.len();
+        let resp = room.send_attachment("Webcam.mp4", &mime, video, AttachmentConfig::new().info(info)).await.map_err(|e| anyhow!(e))?;
+        info!("Uploaded webcam session {}: {} bytes ({:?}) -> event {}", session_id, len, summary.duration, resp.event_id);
+
+        Ok(())
+    }
+}
+
+async fn send_webcam_message(session: &P2PSession, content: &WebcamRecordingContent, message: WebcamMessage) {
+    let mut packet = P2PPayloadFactory::get_data_preparation_message(session.session_id());
+    packet.set_payload(message.to_bytes(rand::random()));
+    session.receive_packet(&content.sender, "", &content.receiver, packet).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::p2p::client::webcam::summarize_recording;
+    use msnp::p2p::v2::webcam::WebcamFrameHeader;
+    use std::time::Duration;
+
+    fn frame(timestamp: u32, payload_size: u32) -> Vec<u8> {
+        let header = WebcamFrameHeader { width: 320, height: 240, payload_size, timestamp };
+        let mut frame = header.to_bytes().to_vec();
+        frame.resize(header.frame_size(), 0xAB);
+        frame
+    }
+
+    #[test]
+    fn summary_skips_the_truncated_last_frame() {
+        let mut recording = Vec::new();
+        recording.extend(frame(1000, 100));
+        recording.extend(frame(1500, 80));
+        recording.extend(frame(3000, 120));
+        let complete = recording.len();
+        recording.extend(&frame(3500, 200)[..50]);
+
+        let summary = summarize_recording(&recording).unwrap();
+        assert_eq!(summary.len, complete);
+        assert_eq!((summary.width, summary.height), (320, 240));
+        assert_eq!(summary.duration, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn no_frames_no_summary() {
+        assert!(summarize_recording(&[]).is_none());
+        assert!(summarize_recording(b"connected\r\n\r\n").is_none());
+    }
+}
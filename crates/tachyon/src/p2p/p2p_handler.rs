@@ -1,12 +1,13 @@
+use crate::audio::ffmpeg;
 use crate::matrix::extensions::msn_user_resolver::FindRoomFromEmail;
-use crate::p2p::client::session::{ReceiveMsnObject, SendFileContent, SessionType};
+use crate::p2p::client::session::{P2PSession, ReceiveMsnObject, SendFileContent, SessionType, SharePhotoContent};
 use crate::p2p::client::transport::{Transport, UnwrappedP2PPacket};
 use crate::tachyon::client::tachyon_client::TachyonClient;
-use log::{debug, info};
+use log::{debug, info, warn};
 use matrix_sdk::media::{MediaFormat, MediaRequestParameters};
 use msnp::msnp::error::PayloadError;
 use msnp::p2p::v2::factories::{P2PPayloadFactory, P2PTransportPacketFactory};
-use msnp::p2p::v2::p2p_transport_packet::P2PTransportPacket;
+use msnp::p2p::transport_packet::TransportPacket;
 use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
 use msnp::p2p::v2::slp::raw_slp_payload::{RawSlpPayload, SlpPayloadFactory, TryFromRawSlpPayload};
 use msnp::p2p::v2::slp::session_slp_payload::{SessionInviteRequestPayload, SessionReqInviteContext};
@@ -21,7 +22,7 @@ use std::str::FromStr;
 use std::time::Duration;
 use tokio::time::sleep;
 
-pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packet: P2PTransportPacket, tachyon_client: TachyonClient) {
+pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packet: TransportPacket, tachyon_client: TachyonClient) {
 
     let sorted_packet = match transport.unwrap_packet(p2p_packet).await {
         Ok(packet) => packet,
@@ -49,6 +50,16 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                         .ok_or(PayloadError::MandatoryPartNotFound { name: "SessionID".to_string(), payload: slp_payload.to_string() }).unwrap()
                         .parse::<u32>().unwrap();
 
+                    //Transcoding takes a while, the session is gone by the time the video is sent.
+                    if let Some((webcam_room_id, recording)) = tachyon_client.stop_webcam_recording(session_id) {
+                        let client = tachyon_client.clone();
+                        tachyon_client.spawn("webcam recording upload", async move {
+                            if let Err(e) = client.send_webcam_recording(session_id, &webcam_room_id, recording).await {
+                                log::error!("Could not send webcam recording to Matrix: {:?}", e);
+                            }
+                        });
+                    }
+
                     tachyon_client.clear_session(session_id);
                 }
 
@@ -59,11 +70,20 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                         .ok_or(PayloadError::MandatoryPartNotFound { name: "SessionID".to_string(), payload: slp_payload.to_string() }).unwrap()
                         .parse::<u32>().unwrap();
 
-                    let session = tachyon_client.get_session(session_id).unwrap();
-                    session.accept().unwrap();
+                    //The 200 OK can come in after the session was swept or cleared.
+                    let Some(session) = tachyon_client.get_session(session_id) else {
+                        warn!("Received 200 OK for unknown P2P session {}, ignoring", session_id);
+                        return;
+                    };
+
+                    if let Err(e) = session.accept() {
+                        warn!("Could not accept P2P session {}: {:?}", session_id, e);
+                        return;
+                    }
 
                     let matrix_client = tachyon_client.matrix_client();
-                    tokio::spawn(async move {
+                    let client = tachyon_client.clone();
+                    tachyon_client.spawn("p2p session accepted", async move {
 
                         let session_type = session.session_type();
                         match session_type {
@@ -88,6 +108,9 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                                 }
 
                             }
+                            SessionType::SharePhoto(_) => {
+                                client.send_pending_photos(&session).await;
+                            }
                             _ => {}
                         }
 
@@ -99,6 +122,12 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
 
                     let invite = SessionInviteRequestPayload::try_from_raw_slp_payload(slp_payload.clone()).unwrap();
 
+                    if let Err(e) = tachyon_client.check_session_limit() {
+                        warn!("Declining P2P invite {}: {}", invite.session_id(), e);
+                        decline_session(&transport, &invite, &slp_payload).await;
+                        return;
+                    }
+
                     match invite.context() {
                         SessionReqInviteContext::MsnObject(obj) => {
 
@@ -108,33 +137,46 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
 
                             match obj.obj_type {
                                 MsnObjectType::Avatar => {}
-                                MsnObjectType::CustomEmoticon => {}
-                                MsnObjectType::DisplayPicture => {
+                                MsnObjectType::CustomEmoticon => {
+
+                                    if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                        warn!("Could not accept P2P session {}: {:?}", session_id, e);
+                                        return;
+                                    }
 
-                                    let sender =  invite.headers().sender().clone();
+                                    let sender = invite.headers().sender().clone();
                                     let receiver = invite.headers().receiver().clone();
 
-                                    let response = SlpPayloadFactory::get_200_ok_session(&slp_payload).unwrap();
+                                    let client = tachyon_client.clone();
+                                    let sha1d = obj.sha1d.clone();
+                                    tachyon_client.spawn("custom emoticon transfer", async move {
+                                        let Some(emoticon) = client.get_custom_emoticon(&sha1d) else {
+                                            log::error!("Client requested a custom emoticon we don't hold: {}", sha1d);
+                                            //TODO send err 500
+                                            return;
+                                        };
+
+                                        send_msn_object(&session, &sender, &receiver, emoticon).await;
+                                    });
+
+                                }
+                                MsnObjectType::DisplayPicture => {
 
-                                    let mut packet = P2PPayloadFactory::get_sip_text_message();
-                                    packet.set_payload(response.into_bytes());
+                                    if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                        warn!("Could not accept P2P session {}: {:?}", session_id, e);
+                                        return;
+                                    }
 
-                                    session.receive_packet(&receiver, "", &sender, packet).await;
-                                    session.accept();
+                                    let sender = invite.headers().sender().clone();
+                                    let receiver = invite.headers().receiver().clone();
 
                                     let client = tachyon_client.clone();
                                     let proxy_room_email =  EmailAddress::from_str(&obj.creator).unwrap();
-                                    let room = client.matrix_client().find_room_from_email(&proxy_room_email).unwrap().unwrap();
-                                    tokio::spawn(async move {
+                                    let room = client.matrix_client().find_room_from_email(&proxy_room_email, client.room_hashes()).unwrap().unwrap();
+                                    tachyon_client.spawn("display picture transfer", async move {
                                         let (_, bytes) = client.get_avatar_thumbnail(&room).await.unwrap().unwrap();
 
-                                        //The client expects a data preparation packet before the first data packet of the session.
-                                        let data_preparation = P2PPayloadFactory::get_data_preparation_message(session_id);
-                                        session.receive_packet(&receiver, "blablabla", &sender, data_preparation).await;
-
-                                        let mut p2p_payload = P2PPayloadFactory::get_msn_obj(session_id);
-                                        p2p_payload.payload = bytes;
-                                        session.receive_packet(&receiver, "blablabla", &sender, p2p_payload).await;
+                                        send_msn_object(&session, &sender, &receiver, bytes).await;
                                     });
 
 
@@ -142,51 +184,91 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                                 MsnObjectType::SharedFile => {}
                                 MsnObjectType::Background => {}
                                 MsnObjectType::History => {}
-                                MsnObjectType::DynamicDisplayPicture => {}
                                 MsnObjectType::Wink => {}
                                 MsnObjectType::MapFile => {}
                                 MsnObjectType::DynamicBackground => {}
                                 MsnObjectType::VoiceClip => {
 
+                                    if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                        warn!("Could not accept P2P session {}: {:?}", session_id, e);
+                                        return;
+                                    }
+
                                     let sender = invite.headers().sender().clone();
                                     let receiver = invite.headers().receiver().clone();
 
-                                    let response = SlpPayloadFactory::get_200_ok_session(&slp_payload).unwrap();
+                                    let client = tachyon_client.clone();
+                                    let obj = obj.clone();
+                                    tachyon_client.spawn("voice clip transfer", async move {
+                                        let Some(voice_clip) = client.get_voice_clip(&obj).await else {
+                                            log::error!("Client requested a voice clip we no longer hold: {}", obj.sha1d);
+                                            //TODO send err 500
+                                            return;
+                                        };
+
+                                        send_msn_object(&session, &sender, &receiver, voice_clip).await;
+                                    });
 
-                                    let mut packet = P2PPayloadFactory::get_sip_text_message();
-                                    packet.set_payload(response.into_bytes());
+                                }
+                                MsnObjectType::PluginState => {}
+                                MsnObjectType::RoamingObject => {}
+                                MsnObjectType::SharedPhoto => {
 
-                                    session.receive_packet(&receiver, "", &sender, packet).await;
-                                    session.accept();
+                                    if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                        warn!("Could not accept P2P session {}: {:?}", session_id, e);
+                                        return;
+                                    }
+
+                                    let sender = invite.headers().sender().clone();
+                                    let receiver = invite.headers().receiver().clone();
 
                                     let client = tachyon_client.clone();
+                                    let room_id = room_id.to_owned();
                                     let sha1d = obj.sha1d.clone();
-                                    tokio::spawn(async move {
-                                        let Some(voice_clip) = client.take_voice_clip(&sha1d) else {
-                                            log::error!("Client requested a voice clip we no longer hold: {}", sha1d);
+                                    tachyon_client.spawn("shared photo transfer", async move {
+                                        let Some(photo) = client.get_shared_photo(&room_id, &sha1d) else {
+                                            log::error!("Client requested a shared photo we don't hold: {}", sha1d);
                                             //TODO send err 500
                                             return;
                                         };
 
-                                        //The client expects a data preparation packet before the first data packet of the session.
-                                        let data_preparation = P2PPayloadFactory::get_data_preparation_message(session_id);
-                                        session.receive_packet(&receiver, "", &sender, data_preparation).await;
+                                        send_msn_object(&session, &sender, &receiver, photo).await;
+                                    });
+
+                                }
+                                MsnObjectType::DynamicDisplayPicture | MsnObjectType::SignatureSound | MsnObjectType::Scene => {
+
+                                    if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                        warn!("Could not accept P2P session {}: {:?}", session_id, e);
+                                        return;
+                                    }
+
+                                    let sender = invite.headers().sender().clone();
+                                    let receiver = invite.headers().receiver().clone();
 
-                                        let mut p2p_payload = P2PPayloadFactory::get_msn_obj(session_id);
-                                        p2p_payload.payload = voice_clip;
-                                        session.receive_packet(&receiver, "", &sender, p2p_payload).await;
+                                    let client = tachyon_client.clone();
+                                    let obj = obj.clone();
+                                    tachyon_client.spawn("profile extra transfer", async move {
+                                        let profile_extra = match client.get_profile_extra_bytes(&obj).await {
+                                            Ok(profile_extra) => profile_extra,
+                                            Err(e) => {
+                                                log::error!("Could not get profile extra {:?} {} from Matrix: {:?}", &obj.obj_type, &obj.sha1d, e);
+                                                //TODO send err 500
+                                                return;
+                                            }
+                                        };
+
+                                        send_msn_object(&session, &sender, &receiver, profile_extra).await;
                                     });
 
                                 }
-                                MsnObjectType::PluginState => {}
-                                MsnObjectType::RoamingObject => {}
-                                MsnObjectType::SignatureSound => {}
-                                MsnObjectType::UnknownYet => {}
-                                MsnObjectType::Scene => {}
                                 MsnObjectType::WebcamDynamicDisplayPicture => {}
                             }
 
                         }
+                        SessionReqInviteContext::FileTransfer(_) if !tachyon_client.config().features.file_transfers => {
+                            decline_session(&transport, &invite, &slp_payload).await;
+                        }
                         SessionReqInviteContext::FileTransfer(transfer) => {
 
                             let (_, session) = tachyon_client.create_session(transport.clone(), SessionType::SendFile(SendFileContent {
@@ -198,17 +280,52 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                             let sender =  invite.headers().sender();
                             let receiver = invite.headers().receiver();
 
-                            let response = SlpPayloadFactory::get_200_ok_session(&slp_payload).unwrap();
+                            if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                warn!("Could not accept P2P session {}: {:?}", invite.session_id(), e);
+                            }
+                        }
+                        SessionReqInviteContext::MediaReceiveOnly(_) => {
+                            //The client asks to see the contact's webcam, Matrix has nothing like it.
+                            decline_session(&transport, &invite, &slp_payload).await;
+                        }
+                        SessionReqInviteContext::MediaSession(_) if invite.context().is_webcam() && !tachyon_client.config().features.webcam => {
+                            decline_session(&transport, &invite, &slp_payload).await;
+                        }
+                        SessionReqInviteContext::MediaSession(_) if invite.context().is_webcam() => {
+
+                            //The recording is turned into a video by ffmpeg, there's nothing to send to Matrix without it.
+                            if !ffmpeg::is_available().await {
+                                info!("Declining webcam {}, ffmpeg is needed to record it", invite.session_id());
+                                decline_session(&transport, &invite, &slp_payload).await;
+                                return;
+                            }
+
+                            if let Err(e) = tachyon_client.accept_webcam(room_id, transport.clone(), &invite, &slp_payload).await {
+                                log::error!("Could not accept webcam: {:?}", e);
+                                decline_session(&transport, &invite, &slp_payload).await;
+                            }
+                        }
+                        SessionReqInviteContext::MediaSession(_) => {
+                            //Matrix calls need ICE & DTLS-SRTP, which WLM's plain RTP can't talk to.
+                            info!("Declining audio/video call {}, calls can't be bridged to Matrix", invite.session_id());
+                            decline_session(&transport, &invite, &slp_payload).await;
+                        }
+                        SessionReqInviteContext::SharePhoto => {
+
+                            let sender = invite.headers().sender();
+                            let receiver = invite.headers().receiver();
 
-                            let mut packet = P2PPayloadFactory::get_sip_text_message();
-                            packet.set_payload(response.into_bytes());
+                            let (_, session) = tachyon_client.create_session(transport.clone(), SessionType::SharePhoto(SharePhotoContent::new(
+                                room_id.to_owned(),
+                                receiver.clone(),
+                                String::new(),
+                                sender.clone(),
+                            )), invite.session_id());
 
-                            session.receive_packet(receiver, "", sender, packet).await;
-                            session.accept();
+                            if let Err(e) = accept_session(&session, &invite, &slp_payload).await {
+                                warn!("Could not accept P2P session {}: {:?}", invite.session_id(), e);
+                            }
                         }
-                        SessionReqInviteContext::MediaReceiveOnly => {}
-                        SessionReqInviteContext::MediaSession => {}
-                        SessionReqInviteContext::SharePhoto => {}
                         SessionReqInviteContext::Activity => {}
                     }
 
@@ -221,7 +338,11 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                 }
             }
             UnwrappedP2PPacket::DataPacket(packet, transport_op) => {
-                let session = tachyon_client.get_session(packet.session_id).unwrap();
+                let Some(session) = tachyon_client.get_session(packet.session_id) else {
+                    warn!("Received data for unknown P2P session {}, ignoring", packet.session_id);
+                    return;
+                };
+                session.touch();
 
                 match session.session_type() {
                     SessionType::ReceiveFile(_) => {}
@@ -234,6 +355,21 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
                             log::error!("Could not forward the MSNObject sent by the client: {:?}", e);
                         }
                     }
+                    SessionType::SharePhoto(_) => {
+                        if let Err(e) = tachyon_client.handle_photo_sharing_packet(&session, packet).await {
+                            log::error!("Could not handle Photo Sharing map sent by the client: {:?}", e);
+                        }
+                    }
+                    SessionType::SendPhoto(content) => {
+                        if let Err(e) = tachyon_client.send_photo_buffered(packet.session_id, packet, content).await {
+                            log::error!("Could not forward the photo shared by the client: {:?}", e);
+                        }
+                    }
+                    SessionType::WebcamRecording(_) => {
+                        if let Err(e) = tachyon_client.handle_webcam_packet(&session, packet).await {
+                            log::error!("Could not handle webcam message sent by the client: {:?}", e);
+                        }
+                    }
                 }
             }
         }
@@ -242,6 +378,40 @@ pub async fn handle_p2p_packet(room_id: &RoomId, transport: Transport, p2p_packe
 
 }
 
+async fn accept_session(session: &P2PSession, invite: &SessionInviteRequestPayload, slp_payload: &RawSlpPayload) -> Result<(), anyhow::Error> {
+    let sender = invite.headers().sender();
+    let receiver = invite.headers().receiver();
+
+    let response = SlpPayloadFactory::get_200_ok_session(slp_payload)?;
+
+    let mut packet = P2PPayloadFactory::get_sip_text_message();
+    packet.set_payload(response.into_bytes());
+
+    session.receive_packet(receiver, "", sender, packet).await;
+    session.accept()
+}
+
+//The client expects a data preparation packet before the first data packet of the session.
+async fn send_msn_object(session: &P2PSession, sender: &EndpointId, receiver: &EndpointId, data: Vec<u8>) {
+    let data_preparation = P2PPayloadFactory::get_data_preparation_message(session.session_id());
+    session.receive_packet(receiver, "", sender, data_preparation).await;
+
+    let mut p2p_payload = P2PPayloadFactory::get_msn_obj(session.session_id());
+    p2p_payload.payload = data;
+    session.receive_packet(receiver, "", sender, p2p_payload).await;
+}
+
+async fn decline_session(transport: &Transport, invite: &SessionInviteRequestPayload, slp_payload: &RawSlpPayload) {
+    let sender = invite.headers().sender();
+    let receiver = invite.headers().receiver();
+
+    let response = SlpPayloadFactory::get_603_decline_session(slp_payload).unwrap();
+
+    let mut packet = P2PPayloadFactory::get_sip_text_message();
+    packet.set_payload(response.into_bytes());
+
+    transport.receive_data_packet(receiver, "", sender, packet).await;
+}
 
 fn handle_slp_payload(
     slp_payload: &RawSlpPayload,
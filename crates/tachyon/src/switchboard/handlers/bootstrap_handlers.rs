@@ -1,11 +1,12 @@
 use crate::matrix::extensions::direct::DirectRoom;
-use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, ToMsnUser};
+use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, ResolverCaches, ToMsnUser};
 use crate::switchboard::models::connection_phase::ConnectionPhase;
 use crate::switchboard::models::local_switchboard_data::LocalSwitchboardData;
 use crate::switchboard::models::switchboard_handle::{SwitchboardHandle, SwitchboardState};
 use crate::switchboard::models::switchboard_token::SwitchboardToken;
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use crate::tachyon::global_state::GlobalState;
+use crate::tachyon::error::TachyonError;
 use crate::tachyon::mappers::user_id::MatrixIdCompatible;
 use crate::tachyon::repository::RepositoryStr;
 use matrix_sdk::{Client, Room, RoomMemberships};
@@ -37,11 +38,12 @@ pub(crate) async fn handle_auth(command: SwitchboardClientCommand, command_sende
                     match matrix_client.get_room(token.room_id.as_ref()) {
                         None => {}
                         Some(room) => {
-                            let room_msn_user = room.to_msn_user_lazy().await?;
+                            let room_msn_user = room.to_msn_user_lazy(tachyon_client.resolver_caches()).await?;
                             local_switchboard_data.token = TicketToken(token.matrix_token);
                             local_switchboard_data.session_id = SessionId::random();
                             local_switchboard_data.email_addr = room_msn_user.get_email_address().clone();
                             local_switchboard_data.endpoint_guid = room_msn_user.endpoint_id.endpoint_guid.clone();
+                            local_switchboard_data.msnp_version.send_replace(tachyon_client.msnp_version());
                             local_switchboard_data.tachyon_client = Some(tachyon_client.clone());
                             local_switchboard_data.matrix_client = Some(matrix_client.clone());
                             local_switchboard_data.room_id = Some(room.room_id().to_owned());
@@ -53,7 +55,7 @@ pub(crate) async fn handle_auth(command: SwitchboardClientCommand, command_sende
                             let mut initial_roster = if ROOM_USER_PORTAL_MODE {
                                 vec![room_msn_user.clone()]
                             } else {
-                                get_initial_roster_with_room_user(&room, room_msn_user.clone()).await?
+                                get_initial_roster_with_room_user(&room, tachyon_client.resolver_caches(), room_msn_user.clone()).await?
                             };
 
                             let count = initial_roster.len() as u32;
@@ -104,6 +106,7 @@ pub(crate) async fn handle_auth(command: SwitchboardClientCommand, command_sende
                     local_switchboard_data.email_addr = usr_command.endpoint_id.email_addr.clone();
                     local_switchboard_data.endpoint_guid = usr_command.endpoint_id.endpoint_guid.clone();
                     local_switchboard_data.token = token;
+                    local_switchboard_data.msnp_version.send_replace(client_data.msnp_version());
                     local_switchboard_data.tachyon_client = Some(client_data);
                     local_switchboard_data.matrix_client = Some(matrix_client);
                     local_switchboard_data.session_id = SessionId::random();
@@ -132,6 +135,11 @@ pub(crate) async fn handle_init(command: SwitchboardClientCommand, command_sende
             let email = cal_client.email_addr;
             let user_id = email.to_owned_user_id();
 
+            let is_me = email == local_switchboard_data.email_addr;
+            let maybe_found = if is_me { None } else { matrix_client.find_room_from_email(&email, tachyon_client.room_hashes())? };
+            if !is_me && maybe_found.is_none() {
+                return Err(TachyonError::PrincipalNotOnList { email: email.to_string() }.into());
+            }
 
             let _ = command_sender.send(SwitchboardSenderMsg::Single(SwitchboardServerCommand::CAL(CalServer {
                 tr_id: cal_client.tr_id,
@@ -140,14 +148,13 @@ pub(crate) async fn handle_init(command: SwitchboardClientCommand, command_sende
             }))).await;
 
 
-            if email == local_switchboard_data.email_addr {
+            if is_me {
                 // It's me !
                 let me = tachyon_client.own_user();
                 send_initial_joined_member(me, &command_sender).await?;
             } else {
-                let maybe_found = matrix_client.find_room_from_email(&email).unwrap();
                 if let Some(room) = maybe_found {
-                    let target_room_user = room.to_msn_user_lazy().await?;
+                    let target_room_user = room.to_msn_user_lazy(tachyon_client.resolver_caches()).await?;
 
                     local_switchboard_data.room_id = Some(room.room_id().to_owned());
                     local_switchboard_data.room = Some(room.clone());
@@ -160,7 +167,6 @@ pub(crate) async fn handle_init(command: SwitchboardClientCommand, command_sende
 
                     let command_sender_clone = command_sender.clone();
                 }
-                //TODO: handle room not found
             }
 
 
@@ -173,7 +179,7 @@ pub(crate) async fn handle_init(command: SwitchboardClientCommand, command_sende
     Ok(())
 }
 
-async fn get_initial_roster(room: &Room) -> Result<Vec<MsnUser>, anyhow::Error> {
+async fn get_initial_roster(room: &Room, caches: &ResolverCaches) -> Result<Vec<MsnUser>, anyhow::Error> {
     let mut out = Vec::new();
 
     let direct_target = room.get_single_direct_target();
@@ -186,14 +192,14 @@ async fn get_initial_roster(room: &Room) -> Result<Vec<MsnUser>, anyhow::Error>
             }
         }
 
-        out.push(member.to_msn_user_lazy().await?);
+        out.push(member.to_msn_user_lazy(caches).await?);
     }
 
     Ok(out)
 }
 
-async fn get_initial_roster_with_room_user(room: &Room, room_msn_user: MsnUser) -> Result<Vec<MsnUser>, anyhow::Error> {
-    let mut out = get_initial_roster(room).await?;
+async fn get_initial_roster_with_room_user(room: &Room, caches: &ResolverCaches, room_msn_user: MsnUser) -> Result<Vec<MsnUser>, anyhow::Error> {
+    let mut out = get_initial_roster(room, caches).await?;
     out.push(room_msn_user);
     Ok(out)
 }
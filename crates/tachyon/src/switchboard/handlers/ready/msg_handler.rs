@@ -1,6 +1,5 @@
 use futures_util::FutureExt;
 use log::{debug, error};
-use crate::matrix::extensions::message_dedup::SendWithDedup;
 use crate::switchboard::models::local_switchboard_data::LocalSwitchboardData;
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use matrix_sdk::ruma::events::room::message::{ImageMessageEventContent, RoomMessageEventContent};
@@ -65,7 +64,8 @@ pub async fn dispatch_msg_payload(tr_id: u128, ack_type: MsgAcknowledgment, payl
         //chunk reassembly and the transport handshake break if a later packet overtakes an earlier one.
         handle_msg_payload(tr_id, ack_type, payload, room_clone, command_sender_clone, tachyon_client).await;
     } else {
-        tokio::spawn(handle_msg_payload(tr_id, ack_type, payload, room_clone, command_sender_clone, tachyon_client));
+        let client = tachyon_client.clone();
+        client.spawn("msg payload", handle_msg_payload(tr_id, ack_type, payload, room_clone, command_sender_clone, tachyon_client));
     }
 }
 
@@ -77,7 +77,7 @@ async fn handle_msg_payload(tr_id: u128, ack_type: MsgAcknowledgment, payload: M
             MsgPayload::TextPlain(text_plain) => {
 
                 let message = RoomMessageEventContent::text_plain(text_plain.body);
-                room_clone.send_with_dedup(message).await.map(|r| ())
+                tachyon_client.message_dedup().send_with_dedup(&room_clone, message).await.map(|r| ())
             }
             MsgPayload::Datacast(datacast) => {
                 debug!("received DATACAST {:?}", &datacast.get_type() );
@@ -112,14 +112,14 @@ async fn handle_msg_payload(tr_id: u128, ack_type: MsgAcknowledgment, payload: M
 
                 let typing_user_id = control.typing_user.to_owned_user_id();
 
-                if &typing_user_id == room_clone.own_user_id() {
+                if &typing_user_id == room_clone.own_user_id() && tachyon_client.config().send_typing_notifications {
                     room_clone.typing_notice(true).await
                 } else {
                     Ok(())
                 }
             }
             MsgPayload::P2P(p2p) => {
-                let transport = tachyon_client.get_or_create_transport(room_clone.room_id(), &room_clone.to_msn_user_lazy().await.unwrap());
+                let transport = tachyon_client.get_or_create_transport(room_clone.room_id(), &room_clone.to_msn_user_lazy(tachyon_client.resolver_caches()).await.unwrap());
 
                 handle_p2p_packet(room_clone.room_id(), transport, p2p.payload, tachyon_client).await;
                 Ok(())
@@ -133,6 +133,9 @@ async fn handle_msg_payload(tr_id: u128, ack_type: MsgAcknowledgment, payload: M
             MsgPayload::Ink(_) => {
                 Ok(())
             }
+            MsgPayload::Emoticon(_) => {
+                Ok(())
+            }
         };
 
         if let Err(e) = result {
@@ -8,10 +8,14 @@ use msnp::msnp::switchboard::models::session_id::SessionId;
 use msnp::shared::models::email_address::EmailAddress;
 use msnp::shared::models::ticket_token::TicketToken;
 use tokio::sync::broadcast::Receiver;
+use tokio::sync::watch;
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
 use msnp::shared::payload::msg::chunked_msg_payload::{ChunkedMsgPayload, MsgChunks};
 
 pub struct LocalSwitchboardData {
     pub(crate) phase: ConnectionPhase,
+    //Set from the TachyonClient once the connection is authenticated, watched by the write task.
+    pub(crate) msnp_version: watch::Sender<MsnpVersion>,
     pub(crate) email_addr: EmailAddress,
     pub(crate) endpoint_guid: Option<EndpointGuid>,
     pub(crate) token: TicketToken,
@@ -28,6 +32,7 @@ impl LocalSwitchboardData {
     pub fn new(client_kill_recv: Receiver<()>) -> Self {
         Self {
             phase: ConnectionPhase::default(),
+            msnp_version: watch::Sender::new(MsnpVersion::default()),
             email_addr: EmailAddress::default(),
             endpoint_guid: None,
             token: TicketToken::default(),
@@ -2,24 +2,29 @@ use crate::switchboard::handlers::handle_command;
 use crate::switchboard::models::local_switchboard_data::LocalSwitchboardData;
 use crate::switchboard::models::switchboard_handle::SwitchboardHandle;
 use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::error::{command_error_to_msnp, to_msnp_error_for_command};
 use crate::tachyon::global_state::GlobalState;
 use anyhow::anyhow;
 use log::{debug, error, info, logger};
 use msnp::msnp::notification::command::command::NotificationServerCommand;
-use msnp::msnp::raw_command_parser::RawCommandParser;
+use msnp::msnp::codec::MsnpCodec;
 use msnp::msnp::switchboard::command::command::{
     SwitchboardClientCommand, SwitchboardServerCommand,
 };
-use msnp::shared::traits::{IntoBytes, TryFromRawCommand};
+use msnp::shared::command::err::{ErrCommand, MsnpError};
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
+use msnp::shared::traits::{IntoVersionedBytes, TryFromRawCommand};
 use rayon::iter::ParallelIterator;
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator};
 use std::str::{from_utf8, from_utf8_unchecked};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
 use tokio::net::tcp::OwnedWriteHalf;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::broadcast::Receiver;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_util::codec::{FramedRead, FramedWrite};
 
 pub struct SwitchboardServer;
 
@@ -70,62 +75,44 @@ async fn handle_client(
 
     let (read, write) = socket.into_split();
     let (client_kill_snd, client_kill_recv) = broadcast::channel::<()>(1);
-    let command_sender = start_write_task(write, client_kill_recv.resubscribe());
+    let mut local_switchboard_data = LocalSwitchboardData::new(client_kill_recv.resubscribe());
+    let command_sender = start_write_task(write, local_switchboard_data.msnp_version.subscribe(), client_kill_recv);
 
-    let mut local_switchboard_data = LocalSwitchboardData::new(client_kill_recv);
-
-    let mut parser = RawCommandParser::new();
-    let mut reader = BufReader::new(read);
-    let mut buffer = [0u8; 2048];
+    let mut reader = FramedRead::new(read, MsnpCodec::default());
 
     loop {
         tokio::select! {
-            bytes_read = reader.read(&mut buffer) => {
-                match bytes_read {
-                    Err(e) => {
-                        error!("MSNP|SB: Socket Read Error: {}", e);
+            command = reader.next() => {
+                match command {
+                    None => break,
+                    Some(Err(e)) => {
+                        error!("MSNP|SB: Unable to read command from socket: {}", e);
                         break;
                     },
-                    Ok(bytes_read) => {
-
-                        if bytes_read == 0 {
-                            break;
+                    Some(Ok(command)) => {
+                        unsafe {
+                            debug!("SB << | {}{}", command.get_command(), from_utf8_unchecked(command.get_payload()));
                         }
 
-                        let data = &buffer[..bytes_read];
-
-                        let commands = parser.parse_message(data);
-
-                        match commands {
-                            Err(e) => error!("MSNP|SB: Unable to parse message into commands: {}", e),
-                            Ok(commands) => {
-
-                                for command in commands {
-                                    unsafe {
-                                        debug!("SB << | {}{}", command.get_command(), from_utf8_unchecked(command.get_payload()));
-                                    }
-
-                                    let notification_command = SwitchboardClientCommand::try_from_raw(command);
-                                    match notification_command {
-                                        Err(e) => {
-                                            error!("MSNP|SB: Unable to parse command: {}", e);
-                                            debug!("{:?}", e);
-                                        },
-                                        Ok(notification_command) => {
-                                            let command_result = handle_command(notification_command, command_sender.clone(), &tachyon_state, &mut local_switchboard_data).await;
-
-                                            if let Err(error) = command_result {
-                                                error!("MSNP|SB: An error has occured handling a notification command: {}", &error);
-                                                debug!("MSNP|SB: {:?}", &error);
-                                                //TODO SEND ERROR BACK TO Client
-                                            }
-                                        }
-                                    }
+                        let tr_id = command.get_tr_id();
+                        let operand = command.get_operand().to_string();
+                        let notification_command = SwitchboardClientCommand::try_from_raw(command);
+                        match notification_command {
+                            Err(e) => {
+                                error!("MSNP|SB: Unable to parse command: {}", e);
+                                debug!("{:?}", e);
+                                send_error(tr_id, command_error_to_msnp(&e), &command_sender).await;
+                            },
+                            Ok(notification_command) => {
+                                let command_result = handle_command(notification_command, command_sender.clone(), &tachyon_state, &mut local_switchboard_data).await;
+
+                                if let Err(error) = command_result {
+                                    error!("MSNP|SB: An error has occured handling a notification command: {}", &error);
+                                    debug!("MSNP|SB: {:?}", &error);
+                                    send_error(tr_id, to_msnp_error_for_command(&error, Some(&operand)), &command_sender).await;
                                 }
                             }
-
                         }
-
                     }
                 }
             },
@@ -151,16 +138,24 @@ async fn handle_client(
     Ok(())
 }
 
+async fn send_error(tr_id: Option<u128>, msnp_error: MsnpError, command_sender: &Sender<SwitchboardSenderMsg>) {
+    if let Some(tr_id) = tr_id {
+        let _ = command_sender.send(SwitchboardSenderMsg::Single(SwitchboardServerCommand::ERR(ErrCommand::new(tr_id, msnp_error)))).await;
+    }
+}
+
 pub enum SwitchboardSenderMsg {
     Single(SwitchboardServerCommand),
     Chunks(Vec<SwitchboardServerCommand>),
 }
 
 fn start_write_task(
-    mut write: OwnedWriteHalf,
+    write: OwnedWriteHalf,
+    msnp_version: watch::Receiver<MsnpVersion>,
     mut kill_recv: Receiver<()>,
 ) -> Sender<SwitchboardSenderMsg> {
     println!("Socket write task started...");
+    let mut write = FramedWrite::new(write, MsnpCodec::default());
     let (sender, mut receiver) = mpsc::channel::<SwitchboardSenderMsg>(10000000);
 
     let _result = tokio::spawn(async move {
@@ -173,7 +168,7 @@ fn start_write_task(
                             SwitchboardSenderMsg::Single(command) => {
 
 
-                                let bytes = command.into_bytes();
+                                let bytes = command.into_versioned_bytes(*msnp_version.borrow());
 
                                 // make this optional when debug logs are enabled
                                 match from_utf8(&bytes) {
@@ -186,12 +181,12 @@ fn start_write_task(
                                     }
                                 }
 
-                                write.write_all(&bytes).await.unwrap();
+                                write.send(Bytes::from(bytes)).await.unwrap();
                             }
                             SwitchboardSenderMsg::Chunks(chunks) => {
                                 for chunk in chunks {
-                                    let bytes = chunk.into_bytes();
-                                    write.write_all(&bytes).await.unwrap();
+                                    let bytes = chunk.into_versioned_bytes(*msnp_version.borrow());
+                                    write.send(Bytes::from(bytes)).await.unwrap();
                                 }
 
                             }
@@ -42,6 +42,7 @@ pub struct ConfirmDeviceAlertContent {
 
 pub struct WebLoginAlertContent {
     sender: oneshot::Sender<Result<TicketToken, AlertError>>,
+    creation_time: std::time::Instant,
 }
 
 pub enum AlertReceiver {
@@ -141,11 +142,19 @@ impl Alert {
     pub fn new_weblogin() -> (Self, AlertReceiver) {
         let (sender, receiver) = oneshot::channel();
         (
-            Alert::WebLogin(WebLoginAlertContent { sender }),
+            Alert::WebLogin(WebLoginAlertContent { sender, creation_time: std::time::Instant::now() }),
             AlertReceiver::TicketToken(receiver),
         )
     }
 
+    //Web logins don't carry an expiration, they use the ttl from the config.
+    pub fn is_expired(&self, now: std::time::Instant, ttl: std::time::Duration) -> bool {
+        match self {
+            Alert::ConfirmDevice(content) => now >= content.expiration_time,
+            Alert::WebLogin(content) => now >= content.creation_time + ttl,
+        }
+    }
+
     pub fn new_confirm_device(expiration_duration: std::time::Duration) -> (Self, AlertReceiver) {
         let creation_time = std::time::Instant::now();
         let expiration_time = creation_time + expiration_duration;
@@ -156,4 +165,38 @@ impl Alert {
             AlertReceiver::Unit(receiver),
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::tachyon::alert::{Alert, AlertNotify};
+
+    #[test]
+    fn confirm_device_expires_at_its_expiration_time() {
+        let (alert, _receiver) = Alert::new_confirm_device(Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(!alert.is_expired(now, Duration::ZERO));
+        assert!(alert.is_expired(now + Duration::from_secs(61), Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn web_login_expires_after_ttl() {
+        let (alert, _receiver) = Alert::new_weblogin();
+        let now = Instant::now();
+
+        assert!(!alert.is_expired(now, Duration::from_secs(60)));
+        assert!(alert.is_expired(now + Duration::from_secs(61), Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn receiver_sees_timeout_failure() {
+        let (alert, receiver) = Alert::new_confirm_device(Duration::ZERO);
+        alert.notify_failure(anyhow::anyhow!("Alert timed out")).expect("receiver to be alive");
+
+        let result = receiver.recv().await;
+        assert!(result.is_err_and(|e| e.to_string() == "Alert timed out"));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use dashmap::DashMap;
+use log::debug;
+use matrix_sdk::ruma::events::room::message::ImageMessageEventContent;
+use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject};
+use msnp::shared::models::msn_user::MsnUser;
+use std::time::{Duration, Instant};
+
+//WLM scales custom emoticons down anyway, no need to send the full picture.
+const EMOTICON_MAX_SIZE: u32 = 256;
+
+struct StoredEmoticon {
+    inserted_at: Instant,
+    image: Vec<u8>,
+}
+
+//Emoticon pictures waiting for WLM to request them over P2P, keyed by sha1d.
+//The sweeper evicts expired emoticons, inserts also enforce the size bound.
+pub struct CustomEmoticonStore {
+    emoticons: DashMap<String, StoredEmoticon>,
+    max_size: usize,
+    ttl: Duration,
+}
+
+impl CustomEmoticonStore {
+
+    pub fn new(max_size: usize, ttl: Duration) -> Self {
+        Self {
+            emoticons: DashMap::new(),
+            max_size,
+            ttl,
+        }
+    }
+
+    fn insert(&self, sha1d: String, image: Vec<u8>) {
+        let now = Instant::now();
+        self.emoticons.insert(sha1d, StoredEmoticon { inserted_at: now, image });
+        self.evict(now);
+    }
+
+    fn get(&self, sha1d: &str) -> Option<Vec<u8>> {
+        self.emoticons.get(sha1d).map(|stored| stored.image.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.emoticons.len()
+    }
+
+    //Expired emoticons first, then the oldest ones until we're back under the size bound. Returns how many were dropped.
+    pub fn evict(&self, now: Instant) -> usize {
+        let before = self.emoticons.len();
+        self.emoticons.retain(|_, stored| now.saturating_duration_since(stored.inserted_at) < self.ttl);
+
+        let mut by_age: Vec<(Instant, String, usize)> = self.emoticons.iter()
+            .map(|entry| (entry.inserted_at, entry.key().clone(), entry.image.len()))
+            .collect();
+        by_age.sort();
+
+        let mut size: usize = by_age.iter().map(|(_, _, len)| len).sum();
+        for (_, sha1d, len) in by_age {
+            if size <= self.max_size {
+                break;
+            }
+
+            self.emoticons.remove(&sha1d);
+            size -= len;
+        }
+
+        before - self.emoticons.len()
+    }
+}
+
+impl TachyonClient {
+
+    //Turns a Matrix image into a custom emoticon, the returned shortcut is what goes in the text message.
+    pub async fn prepare_custom_emoticon(&self, creator: &MsnUser, content: &ImageMessageEventContent) -> Result<(String, MsnObject), anyhow::Error> {
+        let image = self.get_media_thumbnail(&content.source, EMOTICON_MAX_SIZE).await?;
+
+        let msn_object = MSNObjectFactory::get_custom_emoticon(&image, creator.get_email_address().to_string(), FriendlyName::default());
+
+        //Shortcuts are limited to 7 characters, the hash keeps them unique per picture.
+        let shortcut = format!("[{}]", msn_object.sha1d.chars().take(5).collect::<String>());
+
+        debug!("Prepared custom emoticon {}: {} bytes, sha1d {}", &shortcut, image.len(), &msn_object.sha1d);
+        self.inner.custom_emoticons.insert(msn_object.sha1d.clone(), image);
+
+        Ok((shortcut, msn_object))
+    }
+
+    pub fn get_custom_emoticon(&self, sha1d: &str) -> Option<Vec<u8>> {
+        self.inner.custom_emoticons.get(sha1d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tachyon::client::custom_emoticon::CustomEmoticonStore;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn oldest_emoticons_are_dropped_over_the_size_bound() {
+        let store = CustomEmoticonStore::new(10, Duration::from_secs(60));
+        store.insert("first".into(), vec![0; 6]);
+        store.insert("second".into(), vec![0; 6]);
+
+        assert!(store.get("first").is_none());
+        assert!(store.get("second").is_some());
+    }
+
+    #[test]
+    fn expired_emoticons_are_swept() {
+        let store = CustomEmoticonStore::new(1_000, Duration::from_secs(60));
+        store.insert("sha1d".into(), vec![1, 2, 3]);
+
+        assert_eq!(store.evict(Instant::now()), 0);
+        assert_eq!(store.get("sha1d"), Some(vec![1, 2, 3]));
+
+        assert_eq!(store.evict(Instant::now() + Duration::from_secs(60)), 1);
+        assert_eq!(store.len(), 0);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use anyhow::anyhow;
+use log::{debug, info};
+use matrix_sdk::media::MediaFormat;
+use matrix_sdk::Room;
+use mime::Mime;
+use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject};
+
+impl TachyonClient {
+
+    //Called when the client announces a new display picture in CHG.
+    //The storage service usually already pushed it to Matrix through UpdateDocument, so we only fetch it over P2P when the Matrix avatar doesn't match.
+    pub async fn sync_own_display_picture(&self, msn_object: MsnObject) -> Result<(), anyhow::Error> {
+        if let Some(avatar_url) = self.matrix_client().account().get_avatar_url().await? {
+            let current_sha1d = match self.avatar_cache().get_file_sha1d(&avatar_url).await {
+                Some(sha1d) => Some(sha1d),
+                //Set from another Matrix client, only downloaded once.
+                None => match self.matrix_client().account().get_avatar(MediaFormat::File).await? {
+                    Some(avatar) => {
+                        let sha1d = self.own_display_picture_sha1d(&avatar);
+                        self.avatar_cache().store_file_sha1d(&avatar_url, &sha1d).await?;
+                        Some(sha1d)
+                    }
+                    None => None,
+                },
+            };
+
+            if current_sha1d.as_deref() == Some(msn_object.sha1d.as_str()) {
+                debug!("Matrix avatar already matches our display picture {}", &msn_object.sha1d);
+                return Ok(());
+            }
+        }
+
+        let room = self.find_room_for_own_msn_object_request().ok_or(anyhow!("No room to request our own display picture through"))?;
+
+        info!("Display picture {} is not on Matrix yet, requesting it from the client", &msn_object.sha1d);
+        self.request_msn_object(&room, msn_object).await
+    }
+
+    pub(crate) async fn set_own_avatar(&self, display_picture: Vec<u8>) -> Result<(), anyhow::Error> {
+        let mime = get_mime_type(&display_picture);
+        let sha1d = self.own_display_picture_sha1d(&display_picture);
+
+        let upload_response = self.matrix_client().account().upload_avatar(&mime, display_picture).await?;
+        self.matrix_client().account().set_avatar_url(Some(upload_response.as_ref())).await?;
+        self.avatar_cache().store_file_sha1d(&upload_response, &sha1d).await?;
+
+        info!("Matrix avatar updated from our display picture: {}", upload_response);
+        Ok(())
+    }
+
+    //Any contact can fetch our MSNObjects, but only through a room that already has a switchboard, we don't ring the client for it.
+    pub(crate) fn find_room_for_own_msn_object_request(&self) -> Option<Room> {
+        let matrix_client = self.matrix_client();
+        self.inner.switchboards.iter().find_map(|entry| matrix_client.get_room(entry.key()))
+    }
+
+    fn own_display_picture_sha1d(&self, display_picture: &[u8]) -> String {
+        MSNObjectFactory::get_display_picture(display_picture, self.own_user().get_email_address(), String::new(), FriendlyName::default()).sha1d
+    }
+}
+
+//We need to figure out the filetype from the content, because msn always sends png.
+pub(crate) fn get_mime_type(data_vector: &[u8]) -> Mime {
+    if data_vector.starts_with(b"GIF") {
+        return mime::IMAGE_GIF
+    } else if data_vector.starts_with(b"\xff\xd8") {
+        return mime::IMAGE_JPEG
+    } else if data_vector.starts_with(b"\x89PNG\x0d\x0a\x1a\x0a") {
+        return mime::IMAGE_PNG;
+    }
+    return mime::IMAGE_BMP;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_mime_type;
+
+    #[test]
+    fn mime_type_is_sniffed_from_the_content() {
+        assert_eq!(get_mime_type(b"GIF89a\x01\x00"), mime::IMAGE_GIF);
+        assert_eq!(get_mime_type(b"\xff\xd8\xff\xe0"), mime::IMAGE_JPEG);
+        assert_eq!(get_mime_type(b"\x89PNG\x0d\x0a\x1a\x0a\x00"), mime::IMAGE_PNG);
+    }
+
+    #[test]
+    fn unknown_content_falls_back_to_bmp() {
+        assert_eq!(get_mime_type(b"BM\x00\x00"), mime::IMAGE_BMP);
+        assert_eq!(get_mime_type(&[]), mime::IMAGE_BMP);
+    }
+}
@@ -1,17 +1,25 @@
 use crate::p2p::client::session::{ReceiveFileContent, SessionType};
 use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::config::tachyon_config::ImageStrategy;
+use log::{info, warn};
+use matrix_sdk::media::{MediaFormat, MediaRequestParameters, MediaThumbnailSettings};
+use matrix_sdk::ruma::events::room::message::ImageMessageEventContent;
 use matrix_sdk::ruma::events::room::MediaSource;
 use matrix_sdk::ruma::RoomId;
 use msnp::msnp::switchboard::command::command::SwitchboardServerCommand;
 use msnp::msnp::switchboard::command::msg::{MsgPayload, MsgServer};
 use msnp::shared::models::display_name::DisplayName;
 use msnp::shared::models::msn_user::MsnUser;
+use msnp::shared::payload::msg::emoticon::EmoticonMsgPayload;
 use msnp::shared::payload::msg::text_plain_msg::TextPlainMessagePayload;
+use ruma::media::Method;
+use ruma::UInt;
 
-impl TachyonClient {
-    pub async fn receive_file(&self, room_id: &RoomId, inviter: &MsnUser, sender: &MsnUser, file_size: usize, filename: String, media_source: MediaSource) {
-
+//Size of the preview shown in file transfer invites.
+const FILE_PREVIEW_SIZE: u32 = 96;
 
+impl TachyonClient {
+    pub async fn receive_file(&self, room_id: &RoomId, inviter: &MsnUser, sender: &MsnUser, file_size: usize, filename: String, media_source: MediaSource, preview: Option<Vec<u8>>) {
 
         let transport = self.get_or_create_transport(room_id, inviter);
         let (session_id, session) = self.create_session_with_random_id(transport, SessionType::ReceiveFile(ReceiveFileContent {
@@ -21,9 +29,86 @@ impl TachyonClient {
             media_source,
             file_size,
             filename,
+            preview,
         }));
 
         session.receive_invite().await;
 
     }
+
+    //Small images are delivered inline following the configured strategy, anything else (or anything that failed) becomes a file transfer with a preview.
+    pub async fn receive_image(&self, room_id: &RoomId, inviter: &MsnUser, sender: &MsnUser, image: &ImageMessageEventContent) {
+        let size = match image.info.as_ref().and_then(|i| i.size) {
+            Some(size) => Some(usize::try_from(size).unwrap_or(usize::MAX)),
+            //Not every Matrix client fills in the size, go by the file itself rather than never inlining.
+            None => match self.matrix_client().media().get_media_content(&MediaRequestParameters { source: image.source.clone(), format: MediaFormat::File }, true).await {
+                Ok(file) => Some(file.len()),
+                Err(e) => {
+                    warn!("Could not download image to find out its size: {}", e);
+                    None
+                }
+            },
+        };
+        let fits_inline = size.is_some_and(|size| size <= self.config().inline_image_max_size);
+
+        if fits_inline {
+            let delivered = match self.config().image_strategy {
+                ImageStrategy::PhotoSharing => Some(self.share_photo(room_id, inviter, sender, image).await),
+                ImageStrategy::Emoticon => Some(self.send_image_as_emoticon(room_id, inviter, sender, image).await),
+                ImageStrategy::FileTransfer => None,
+            };
+
+            match delivered {
+                Some(Ok(())) => return,
+                Some(Err(e)) => info!("Could not deliver image inline ({}), falling back to a file transfer: {}", self.config().image_strategy, e),
+                None => {}
+            }
+        }
+
+        let preview = match self.get_media_thumbnail(&image.source, FILE_PREVIEW_SIZE).await {
+            Ok(preview) => Some(preview),
+            Err(e) => {
+                warn!("Could not get a preview for image file transfer: {}", e);
+                None
+            }
+        };
+
+        let filename = image.filename.as_ref().unwrap_or(&image.body).to_owned();
+        self.receive_file(room_id, inviter, sender, size.unwrap_or(0), filename, image.source.clone(), preview).await;
+    }
+
+    async fn send_image_as_emoticon(&self, room_id: &RoomId, inviter: &MsnUser, sender: &MsnUser, image: &ImageMessageEventContent) -> Result<(), anyhow::Error> {
+        let (shortcut, msn_object) = self.prepare_custom_emoticon(inviter, image).await?;
+
+        //The emoticon declaration has to come right before the text message that uses it.
+        let emoticon = SwitchboardServerCommand::MSG(MsgServer {
+            sender: sender.get_email_address().clone(),
+            display_name: DisplayName::new_from_ref(sender.compute_display_name()),
+            payload: MsgPayload::Emoticon(EmoticonMsgPayload::new(shortcut.clone(), msn_object)),
+        });
+
+        let text = SwitchboardServerCommand::MSG(MsgServer {
+            sender: sender.get_email_address().clone(),
+            display_name: DisplayName::new_from_ref(sender.compute_display_name()),
+            payload: MsgPayload::TextPlain(TextPlainMessagePayload::new_with_default_style(&shortcut)),
+        });
+
+        let switchboard = self.switchboards().get_or_initialize(room_id, inviter);
+        switchboard.receive_command(emoticon).await?;
+        switchboard.receive_command(text).await?;
+
+        Ok(())
+    }
+
+    //Asks the homeserver for a scaled down version of the media.
+    pub(crate) async fn get_media_thumbnail(&self, source: &MediaSource, size: u32) -> Result<Vec<u8>, matrix_sdk::Error> {
+        let format = MediaFormat::Thumbnail(MediaThumbnailSettings {
+            method: Method::Scale,
+            width: UInt::new_saturating(size as u64),
+            height: UInt::new_saturating(size as u64),
+            animated: false,
+        });
+
+        self.matrix_client().media().get_media_content(&MediaRequestParameters { source: source.clone(), format }, true).await
+    }
 }
@@ -2,4 +2,8 @@ pub mod tachyon_client;
 pub mod tachyon_client_repository;
 pub mod messaging;
 pub mod voice_clip;
+pub mod custom_emoticon;
+pub mod display_picture;
+pub mod profile_extras;
 mod presence;
+pub mod task_supervisor;
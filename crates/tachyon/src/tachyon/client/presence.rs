@@ -1,14 +1,9 @@
 use crate::matrix::extensions::direct::DirectRoom;
 use crate::matrix::extensions::msn_user_resolver::ToEmailAddress;
 use crate::tachyon::client::tachyon_client::TachyonClient;
-use base64::engine::general_purpose;
-use base64::Engine;
-use matrix_sdk::media::{MediaFormat, MediaRequestParameters, MediaThumbnailSettings};
 use matrix_sdk::Room;
-use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject};
-use ruma::events::room::MediaSource;
-use ruma::media::Method;
-use ruma::{OwnedMxcUri, RoomId, UInt, UserId};
+use msnp::shared::models::msn_object::MsnObject;
+use ruma::{OwnedMxcUri, RoomId, UserId};
 
 impl TachyonClient {
     pub async fn get_avatar_as_msn_object(
@@ -37,25 +32,14 @@ impl TachyonClient {
         let out = match matrix_client.get_room(room_id) {
             None => None,
             Some(room) => {
-                let avatar_bytes = self.get_user_avatar_thumbnail(user_id, &room).await?;
-                let room_email_address = room.to_email_address()?;
-                 match avatar_bytes {
+                let room_email_address = room.to_email_address(self.room_hashes())?;
+                match room.get_member(user_id).await?.and_then(|member| member.avatar_url().map(|url| url.to_owned())) {
                     None => None,
-                    Some((uri, avatar_bytes)) => {
-                        let base64_mxc = general_purpose::STANDARD.encode(uri.to_string());
-                        Some(MSNObjectFactory::get_display_picture(
-                            avatar_bytes.as_slice(),
-                            &room_email_address,
-                            format!("{}.tmp", base64_mxc),
-                            FriendlyName::default(),
-                        ))
-                    }
+                    Some(avatar_url) => Some(self.avatar_cache().get_msn_object(&matrix_client, &avatar_url, &room_email_address).await?),
                 }
             },
         };
 
-
-
         Ok(out)
     }
 
@@ -67,18 +51,10 @@ impl TachyonClient {
         let out = match matrix_client.get_room(room_id) {
             None => None,
             Some(room) => {
-                let room_email_address = room.to_email_address()?;
-                match self.get_room_avatar_thumbnail(&room).await? {
+                let room_email_address = room.to_email_address(self.room_hashes())?;
+                match room.avatar_url() {
                     None => None,
-                    Some((uri, avatar_bytes)) => {
-                        let base64_mxc = general_purpose::STANDARD.encode(uri.to_string());
-                        Some(MSNObjectFactory::get_display_picture(
-                            avatar_bytes.as_slice(),
-                            &room_email_address,
-                            format!("{}.tmp", base64_mxc),
-                            FriendlyName::default(),
-                        ))
-                    }
+                    Some(avatar_url) => Some(self.avatar_cache().get_msn_object(&matrix_client, &avatar_url, &room_email_address).await?),
                 }
             }
         };
@@ -104,21 +80,7 @@ impl TachyonClient {
         let out = match avatar_url {
             None => None,
             Some(avatar_url) => {
-                let format = MediaFormat::Thumbnail(MediaThumbnailSettings {
-                    method: Method::Crop,
-                    width: UInt::new_saturating(120),
-                    height: UInt::new_saturating(120),
-                    animated: true,
-                });
-                let request = MediaRequestParameters {
-                    source: MediaSource::Plain(avatar_url.to_owned()),
-                    format,
-                };
-                let avatar_bytes = matrix_client
-                    .media()
-                    .get_media_content(&request, true)
-                    .await?;
-
+                let avatar_bytes = self.avatar_cache().get_thumbnail(&matrix_client, &avatar_url).await?;
                 Some((avatar_url.to_owned(), avatar_bytes))
             }
         };
@@ -141,21 +103,7 @@ impl TachyonClient {
                 match avatar_url {
                     None => None,
                     Some(avatar_url) => {
-                        let format = MediaFormat::Thumbnail(MediaThumbnailSettings {
-                            method: Method::Crop,
-                            width: UInt::new_saturating(120),
-                            height: UInt::new_saturating(120),
-                            animated: true,
-                        });
-                        let request = MediaRequestParameters {
-                            source: MediaSource::Plain(avatar_url.to_owned()),
-                            format,
-                        };
-                        let avatar_bytes = matrix_client
-                            .media()
-                            .get_media_content(&request, true)
-                            .await?;
-
+                        let avatar_bytes = self.avatar_cache().get_thumbnail(&matrix_client, avatar_url).await?;
                         Some((avatar_url.to_owned(), avatar_bytes))
                     }
                 }
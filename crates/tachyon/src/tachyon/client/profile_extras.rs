@@ -0,0 +1,174 @@
+use crate::matrix::extensions::direct::DirectRoom;
+use crate::matrix::extensions::msn_user_resolver::ToEmailAddress;
+use crate::tachyon::client::display_picture::get_mime_type;
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use anyhow::anyhow;
+use base64::engine::general_purpose;
+use base64::Engine;
+use log::{debug, info};
+use matrix_sdk::media::{MediaFormat, MediaRequestParameters};
+use matrix_sdk::ruma::api::client::profile::{ProfileFieldName, ProfileFieldValue};
+use matrix_sdk::ruma::events::room::MediaSource;
+use matrix_sdk::ruma::{OwnedMxcUri, RoomId, UserId};
+use msnp::msnp::notification::models::profile_extras::ProfileExtras;
+use msnp::shared::models::email_address::EmailAddress;
+use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject, MsnObjectType};
+use serde::{Deserialize, Serialize};
+
+//Extended profile field, so contacts who also use Tachyon can read it.
+const PROFILE_EXTRAS_FIELD: &str = "chat.tachyon.profile_extras";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct StoredProfileExtras {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ddp: Option<StoredMsnObject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature_sound: Option<StoredMsnObject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scene: Option<StoredMsnObject>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_scheme: Option<String>,
+}
+
+//Enough to rebuild the MSNObject without downloading the media.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct StoredMsnObject {
+    mxc: OwnedMxcUri,
+    sha1d: String,
+    size: usize,
+}
+
+impl StoredProfileExtras {
+    fn slot(&mut self, obj_type: &MsnObjectType) -> Option<&mut Option<StoredMsnObject>> {
+        match obj_type {
+            MsnObjectType::DynamicDisplayPicture => Some(&mut self.ddp),
+            MsnObjectType::SignatureSound => Some(&mut self.signature_sound),
+            MsnObjectType::Scene => Some(&mut self.scene),
+            _ => None
+        }
+    }
+
+    fn to_profile_extras(&self, creator: &EmailAddress) -> ProfileExtras {
+        ProfileExtras {
+            ddp: self.ddp.as_ref().map(|stored| stored.to_msn_object(MsnObjectType::DynamicDisplayPicture, creator)),
+            signature_sound: self.signature_sound.as_ref().map(|stored| stored.to_msn_object(MsnObjectType::SignatureSound, creator)),
+            scene: self.scene.as_ref().map(|stored| stored.to_msn_object(MsnObjectType::Scene, creator)),
+            color_scheme: self.color_scheme.clone(),
+        }
+    }
+}
+
+impl StoredMsnObject {
+    //The mxc goes in the Location so we know what to serve when a contact requests it.
+    fn to_msn_object(&self, obj_type: MsnObjectType, creator: &EmailAddress) -> MsnObject {
+        let location = general_purpose::STANDARD.encode(self.mxc.to_string());
+        MSNObjectFactory::get_from_sha1d(obj_type, self.sha1d.clone(), self.size, creator.to_string(), location, FriendlyName::default())
+    }
+}
+
+impl TachyonClient {
+
+    //Called when the client sends its scene, colour scheme, signature sound or dynamic display picture in UUX.
+    pub async fn sync_own_profile_extras(&self, extras: ProfileExtras) -> Result<(), anyhow::Error> {
+        let _guard = self.inner.profile_extras_lock.lock().await;
+
+        let mut stored = self.get_own_stored_profile_extras().await?;
+        let mut to_request = Vec::new();
+
+        for (current, announced) in [(&mut stored.ddp, extras.ddp), (&mut stored.signature_sound, extras.signature_sound), (&mut stored.scene, extras.scene)] {
+            match announced {
+                None => *current = None,
+                Some(msn_object) => {
+                    if current.as_ref().map(|stored| stored.sha1d != msn_object.sha1d).unwrap_or(true) {
+                        to_request.push(msn_object);
+                    }
+                }
+            }
+        }
+
+        stored.color_scheme = extras.color_scheme;
+        self.publish_profile_extras(&stored).await?;
+
+        if to_request.is_empty() {
+            return Ok(());
+        }
+
+        let room = self.find_room_for_own_msn_object_request().ok_or(anyhow!("No room to request our own profile extras through"))?;
+        for msn_object in to_request {
+            info!("Profile extra {:?} {} is not on Matrix yet, requesting it from the client", &msn_object.obj_type, &msn_object.sha1d);
+            self.request_msn_object(&room, msn_object).await?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn store_own_profile_extra(&self, msn_object: &MsnObject, data: Vec<u8>) -> Result<(), anyhow::Error> {
+        let _guard = self.inner.profile_extras_lock.lock().await;
+
+        let mime = match msn_object.obj_type {
+            MsnObjectType::SignatureSound => mime::APPLICATION_OCTET_STREAM,
+            _ => get_mime_type(&data),
+        };
+
+        let size = data.len();
+        let upload = self.matrix_client().media().upload(&mime, data, None).await?;
+
+        let mut stored = self.get_own_stored_profile_extras().await?;
+        let slot = stored.slot(&msn_object.obj_type).ok_or(anyhow!("{:?} is not a profile extra", &msn_object.obj_type))?;
+        *slot = Some(StoredMsnObject {
+            mxc: upload.content_uri,
+            sha1d: msn_object.sha1d.clone(),
+            size,
+        });
+
+        self.publish_profile_extras(&stored).await?;
+        info!("Stored profile extra {:?} {} on Matrix", &msn_object.obj_type, &msn_object.sha1d);
+        Ok(())
+    }
+
+    //Only direct rooms have someone to read profile extras from.
+    pub async fn get_contact_profile_extras(&self, room_id: &RoomId) -> Result<Option<ProfileExtras>, anyhow::Error> {
+        let Some(room) = self.matrix_client().get_room(room_id) else {
+            return Ok(None);
+        };
+
+        let Some(direct_target) = room.get_single_direct_target() else {
+            return Ok(None);
+        };
+
+        let Some(stored) = self.get_stored_profile_extras_of(&direct_target).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(stored.to_profile_extras(&room.to_email_address(self.room_hashes())?)))
+    }
+
+    pub async fn get_profile_extra_bytes(&self, msn_object: &MsnObject) -> Result<Vec<u8>, anyhow::Error> {
+        let raw_mxc = general_purpose::STANDARD.decode(&msn_object.location).map_err(|e| anyhow!("Profile extra location is not a mxc: {}", e))?;
+        let mxc = OwnedMxcUri::from(String::from_utf8(raw_mxc)?);
+
+        let request = MediaRequestParameters { source: MediaSource::Plain(mxc), format: MediaFormat::File };
+        Ok(self.matrix_client().media().get_media_content(&request, true).await?)
+    }
+
+    async fn get_own_stored_profile_extras(&self) -> Result<StoredProfileExtras, anyhow::Error> {
+        let own_user_id = self.matrix_client().user_id().ok_or(anyhow!("Client is not logged in"))?.to_owned();
+        Ok(self.get_stored_profile_extras_of(&own_user_id).await?.unwrap_or_default())
+    }
+
+    async fn get_stored_profile_extras_of(&self, user_id: &UserId) -> Result<Option<StoredProfileExtras>, anyhow::Error> {
+        let value = self.matrix_client().account().fetch_profile_field_of(user_id.to_owned(), ProfileFieldName::from(PROFILE_EXTRAS_FIELD)).await?;
+
+        match value {
+            None => Ok(None),
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        }
+    }
+
+    async fn publish_profile_extras(&self, stored: &StoredProfileExtras) -> Result<(), anyhow::Error> {
+        debug!("Publishing profile extras: {:?}", stored);
+        let value = ProfileFieldValue::new(PROFILE_EXTRAS_FIELD, serde_json::to_value(stored)?)?;
+        self.matrix_client().account().set_profile_field(value).await?;
+        Ok(())
+    }
+}
@@ -1,23 +1,33 @@
+use crate::matrix::extensions::avatar_cache::AvatarCache;
+use crate::matrix::extensions::message_dedup::MessageDedup;
+use crate::matrix::extensions::msn_user_resolver::{ResolverCaches, RoomHashCache};
 use crate::notification::circle_store::CircleStore;
 use crate::notification::models::notification_handle::NotificationHandle;
 use crate::notification::models::soap_holder::SoapHolder;
 use crate::switchboard::models::switchboard_handle::SwitchboardHandle;
 use crate::tachyon::alert::Alert;
+use crate::tachyon::config::paths::get_user_data;
 use crate::tachyon::config::tachyon_config::TachyonConfig;
 use crate::tachyon::switchboard_service::SwitchboardService;
 use dashmap::DashMap;
 use matrix_sdk::locks::RwLock;
 use matrix_sdk::ruma::OwnedRoomId;
 use msnp::msnp::models::contact_list::ContactList;
+use msnp::msnp::notification::models::msnp_version::MsnpVersion;
 use msnp::msnp::notification::command::command::NotificationServerCommand;
 use msnp::shared::models::msn_user::MsnUser;
 use msnp::shared::models::ticket_token::TicketToken;
 use std::sync::{Arc, Mutex, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::{broadcast, mpsc};
 use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
 use crate::p2p::client::session::{P2PSession, SessionId};
 use crate::p2p::client::transport::Transport;
 use crate::tachyon::client::voice_clip::VoiceClipStore;
+use crate::tachyon::client::custom_emoticon::CustomEmoticonStore;
+use crate::tachyon::client::task_supervisor::TaskSupervisor;
+use std::future::Future;
+use std::time::Duration;
 
 pub struct TachyonClientInner {
     matrix_client: matrix_sdk::Client,
@@ -30,12 +40,23 @@ pub struct TachyonClientInner {
     pub circle_store: CircleStore,
     pub notification_handle: NotificationHandle,
     pub config: TachyonConfig,
+    //Negotiated on the NS, switchboard connections serialize their commands for it too.
+    pub msnp_version: MsnpVersion,
     pub client_shutdown_snd: broadcast::Sender<()>,
     pub client_shutdown_recv: broadcast::Receiver<()>,
     pub transports: DashMap<OwnedRoomId, Transport>,
     pub sessions: DashMap<SessionId, P2PSession>,
     pub chunked_uploads: DashMap<SessionId, Vec<RawP2PPayload>>,
     pub voice_clips: VoiceClipStore,
+    pub custom_emoticons: CustomEmoticonStore,
+    //Profile extras are a single profile field, updates need to be serialized.
+    pub profile_extras_lock: tokio::sync::Mutex<()>,
+    //Cleared by the sync loop while it waits for the homeserver to come back.
+    pub homeserver_reachable: AtomicBool,
+    pub message_dedup: MessageDedup,
+    //Handed to the Room and Client extensions by whoever resolves a room for this account.
+    pub resolver_caches: ResolverCaches,
+    pub tasks: Arc<TaskSupervisor>,
 }
 
 #[derive(Clone)]
@@ -48,12 +69,24 @@ impl TachyonClient {
     pub fn new(
         matrix_client: matrix_sdk::Client,
         config: TachyonConfig,
+        msnp_version: MsnpVersion,
         user: MsnUser,
         token: TicketToken,
         notification_sender: mpsc::Sender<NotificationServerCommand>,
         client_shutdown_snd: broadcast::Sender<()>,
         client_shutdown_recv: broadcast::Receiver<()>,
     ) -> TachyonClient {
+        let tasks = Arc::new(TaskSupervisor::new(client_shutdown_recv.resubscribe()));
+        let user_data = get_user_data(matrix_client.user_id().expect("Matrix client to be logged in"));
+        let resolver_caches = ResolverCaches {
+            room_hashes: Arc::new(RoomHashCache::default()),
+            avatars: Arc::new(AvatarCache::new(user_data.join("avatars"), tasks.clone())),
+        };
+
+        let voice_clip_spill_dir = config.voice_clip_spill_to_disk.then(|| user_data.join("voice_clips"));
+        let voice_clips = VoiceClipStore::new(voice_clip_spill_dir, config.voice_clip_store_size, config.voice_clip_ttl);
+        let custom_emoticons = CustomEmoticonStore::new(config.custom_emoticon_store_size, config.custom_emoticon_ttl);
+
         TachyonClient {
             inner: Arc::new(TachyonClientInner {
                 matrix_client,
@@ -66,18 +99,57 @@ impl TachyonClient {
                 circle_store: CircleStore::new(),
                 notification_handle: NotificationHandle::new(notification_sender),
                 config,
+                msnp_version,
                 client_shutdown_snd,
+                tasks,
                 client_shutdown_recv,
                 transports: Default::default(),
                 sessions: Default::default(),
                 chunked_uploads: Default::default(),
-                voice_clips: Default::default(),
+                voice_clips,
+                custom_emoticons,
+                profile_extras_lock: Default::default(),
+                homeserver_reachable: AtomicBool::new(true),
+                message_dedup: Default::default(),
+                resolver_caches,
             })
         }
     }
 
     pub fn switchboards(&self) -> SwitchboardService {
-        SwitchboardService::new(self.clone(), self.inner.config.switchboard_port)
+        SwitchboardService::new(self.clone(), self.inner.config.public_host, self.inner.config.switchboard_port)
+    }
+
+    pub fn config(&self) -> &TachyonConfig {
+        &self.inner.config
+    }
+
+    pub fn msnp_version(&self) -> MsnpVersion {
+        self.inner.msnp_version
+    }
+
+    pub fn is_homeserver_reachable(&self) -> bool {
+        self.inner.homeserver_reachable.load(Ordering::Relaxed)
+    }
+
+    pub fn set_homeserver_reachable(&self, reachable: bool) {
+        self.inner.homeserver_reachable.store(reachable, Ordering::Relaxed);
+    }
+
+    pub fn resolver_caches(&self) -> &ResolverCaches {
+        &self.inner.resolver_caches
+    }
+
+    pub fn room_hashes(&self) -> &RoomHashCache {
+        &self.inner.resolver_caches.room_hashes
+    }
+
+    pub fn avatar_cache(&self) -> &Arc<AvatarCache> {
+        &self.inner.resolver_caches.avatars
+    }
+
+    pub fn message_dedup(&self) -> &MessageDedup {
+        &self.inner.message_dedup
     }
 
     pub fn alerts(&self) -> &DashMap<i32, Alert> {
@@ -112,6 +184,18 @@ impl TachyonClient {
         let _ = self.inner.client_shutdown_snd.send(());
     }
 
+    //Anything outliving the command that started it goes through here, so it stops with the client.
+    pub fn spawn<F>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.inner.tasks.spawn(name, task);
+    }
+
+    pub async fn shutdown_tasks(&self, timeout: Duration) {
+        self.inner.tasks.shutdown(timeout).await;
+    }
+
     pub fn shutdown_receiver(&self) -> broadcast::Receiver<()> {
         self.inner.client_shutdown_recv.resubscribe()
     }
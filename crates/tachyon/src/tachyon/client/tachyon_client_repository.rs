@@ -16,17 +16,16 @@ impl TachyonClientRepository {
         }
     }
 
-    pub(crate) fn single(&self) -> Option<TachyonClient> {
-
-        if self.clients.len() > 1 {
-            return None;
-        }
+    pub fn find_by_email(&self, email: &EmailAddress) -> Option<TachyonClient> {
+        self.clients.iter().find(|entry| entry.value().own_user().get_email_address() == email).map(|client| client.value().clone())
+    }
 
-        self.clients.iter().next().map(|x| x.value().clone())
+    pub fn all(&self) -> Vec<TachyonClient> {
+        self.clients.iter().map(|entry| entry.value().clone()).collect()
     }
 
-    pub fn find_by_email(&self, email: &EmailAddress) -> Option<TachyonClient> {
-        self.clients.iter().find(|entry| entry.value().own_user().get_email_address() == email).map(|client| client.value().clone())
+    pub fn count(&self) -> usize {
+        self.clients.len()
     }
 }
 
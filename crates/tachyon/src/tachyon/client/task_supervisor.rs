@@ -0,0 +1,120 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::{debug, warn};
+use tokio::sync::broadcast;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+//Background work spawned on behalf of a client, everything is cancelled once the client shutdown is broadcast.
+pub struct TaskSupervisor {
+    tasks: Mutex<JoinSet<()>>,
+    cancellation: CancellationToken,
+    //Moved into a watcher task on the first spawn, we may not be inside the runtime when the client is created.
+    shutdown_recv: Mutex<Option<broadcast::Receiver<()>>>,
+}
+
+impl TaskSupervisor {
+    pub fn new(shutdown_recv: broadcast::Receiver<()>) -> Self {
+        Self {
+            tasks: Mutex::new(JoinSet::new()),
+            cancellation: CancellationToken::new(),
+            shutdown_recv: Mutex::new(Some(shutdown_recv)),
+        }
+    }
+
+    pub fn spawn<F>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if self.cancellation.is_cancelled() {
+            debug!("Not spawning {}, the client is shutting down", name);
+            return;
+        }
+
+        let mut tasks = self.tasks.lock().expect("Not to be poisonned");
+        while tasks.try_join_next().is_some() {}
+
+        if let Some(mut shutdown_recv) = self.shutdown_recv.lock().expect("Not to be poisonned").take() {
+            let cancellation = self.cancellation.clone();
+            tasks.spawn(async move {
+                tokio::select! {
+                    _ = shutdown_recv.recv() => cancellation.cancel(),
+                    _ = cancellation.cancelled() => {}
+                }
+            });
+        }
+
+        let cancellation = self.cancellation.clone();
+        tasks.spawn(async move {
+            tokio::select! {
+                _ = task => {},
+                _ = cancellation.cancelled() => debug!("{} cancelled by client shutdown", name),
+            }
+        });
+    }
+
+    //Cancelled tasks stop at their next await point, the timeout only covers ones stuck in blocking code.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.cancellation.cancel();
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().expect("Not to be poisonned"));
+
+        let joined = tokio::time::timeout(timeout, async {
+            while tasks.join_next().await.is_some() {}
+        }).await;
+
+        if joined.is_err() {
+            warn!("{} client tasks still running after {:?}, aborting them", tasks.len(), timeout);
+            tasks.shutdown().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::broadcast;
+
+    use super::TaskSupervisor;
+
+    #[tokio::test]
+    async fn client_shutdown_cancels_tasks() {
+        let (shutdown_snd, shutdown_recv) = broadcast::channel(1);
+        let supervisor = TaskSupervisor::new(shutdown_recv);
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let finished_clone = finished.clone();
+        supervisor.spawn("never ending", async move {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            finished_clone.store(true, Ordering::SeqCst);
+        });
+
+        shutdown_snd.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while !supervisor.cancellation.is_cancelled() {
+                tokio::task::yield_now().await;
+            }
+        }).await.expect("shutdown broadcast to cancel the tasks");
+
+        tokio::time::timeout(Duration::from_secs(1), supervisor.shutdown(Duration::from_secs(5))).await.expect("tasks to be joined");
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn no_spawn_after_shutdown() {
+        let (_shutdown_snd, shutdown_recv) = broadcast::channel(1);
+        let supervisor = TaskSupervisor::new(shutdown_recv);
+        supervisor.shutdown(Duration::from_secs(1)).await;
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let ran_clone = ran.clone();
+        supervisor.spawn("late", async move { ran_clone.store(true, Ordering::SeqCst) });
+        tokio::task::yield_now().await;
+
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+}
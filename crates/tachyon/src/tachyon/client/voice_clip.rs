@@ -1,58 +1,253 @@
-use crate::audio::{self, siren_wav_duration, MAX_VOICE_CLIP_SIZE};
+use crate::audio::{self, siren, siren_wav_duration, MAX_VOICE_CLIP_SIZE};
 use crate::tachyon::client::tachyon_client::TachyonClient;
 use anyhow::anyhow;
-use log::debug;
+use log::{debug, warn};
 use matrix_sdk::media::{MediaFormat, MediaRequestParameters};
 use matrix_sdk::ruma::events::room::message::AudioMessageEventContent;
 use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory, MsnObject};
 use msnp::shared::models::msn_user::MsnUser;
 use dashmap::DashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+use sha1::{Digest, Sha1};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 const MAX_VOICE_CLIP_DURATION: Duration = siren_wav_duration(MAX_VOICE_CLIP_SIZE);
 
+//Past this many parts (about two minutes), a file transfer is less annoying than a wall of clips.
+const MAX_VOICE_CLIP_PARTS: u32 = 8;
+
+//Spilled clips are only there so history can be replayed, they don't need to outlive a week.
+const SPILLED_VOICE_CLIP_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+//About 1500 clips at the maximum size, the oldest ones go first past this.
+const SPILLED_VOICE_CLIP_DIR_SIZE: u64 = 50_000_000;
+
+struct StoredVoiceClip {
+    inserted_at: Instant,
+    clip: Vec<u8>,
+}
+
+struct SpilledVoiceClip {
+    path: PathBuf,
+    size: u64,
+    spilled_at: SystemTime,
+}
+
+//What's in the spill dir, read once then kept up to date so spilling never has to rescan it.
 #[derive(Default)]
+struct SpilledVoiceClips {
+    loaded: bool,
+    //Oldest first
+    files: VecDeque<SpilledVoiceClip>,
+    size: u64,
+}
+
+impl SpilledVoiceClips {
+
+    async fn load(&mut self, dir: &Path) {
+        self.loaded = true;
+
+        let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+            return;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if let Ok(metadata) = entry.metadata().await {
+                let spilled_at = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                self.files.push_back(SpilledVoiceClip { path: entry.path(), size: metadata.len(), spilled_at });
+            }
+        }
+
+        self.files.make_contiguous().sort_by_key(|file| file.spilled_at);
+        self.size = self.files.iter().map(|file| file.size).sum();
+    }
+
+    fn record(&mut self, path: PathBuf, size: u64, spilled_at: SystemTime) {
+        //A clip spilled again under the same key overwrites its file.
+        self.forget(&path);
+        self.files.push_back(SpilledVoiceClip { path, size, spilled_at });
+        self.size += size;
+    }
+
+    fn forget(&mut self, path: &Path) {
+        if let Some(index) = self.files.iter().position(|file| file.path == path) {
+            if let Some(file) = self.files.remove(index) {
+                self.size -= file.size;
+            }
+        }
+    }
+
+    //Returns the files to delete.
+    fn prune(&mut self, now: SystemTime) -> Vec<PathBuf> {
+        let mut pruned = Vec::new();
+
+        while let Some(oldest) = self.files.front() {
+            let expired = now.duration_since(oldest.spilled_at).is_ok_and(|age| age > SPILLED_VOICE_CLIP_TTL);
+            if !expired && self.size <= SPILLED_VOICE_CLIP_DIR_SIZE {
+                break;
+            }
+
+            if let Some(file) = self.files.pop_front() {
+                self.size -= file.size;
+                pruned.push(file.path);
+            }
+        }
+
+        pruned
+    }
+}
+
+//Transcoded clips waiting for WLM to request them over P2P, keyed by voice_clip_key.
+//Clips stay fetchable after they were served, so reopening a conversation can play them again.
+//The sweeper evicts expired clips, inserts also enforce the size bound.
 pub struct VoiceClipStore {
-    clips: DashMap<String, (u64, Vec<u8>)>,
-    next_sequence: AtomicU64,
+    clips: DashMap<String, StoredVoiceClip>,
+    spill_dir: Option<PathBuf>,
+    spilled: tokio::sync::Mutex<SpilledVoiceClips>,
+    max_size: usize,
+    ttl: Duration,
 }
 
 impl VoiceClipStore {
-    fn insert(&self, sha1d: String, clip: Vec<u8>) {
-        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
-        self.clips.insert(sha1d, (sequence, clip));
 
-/*        while self.clips.len() > MAX_PENDING_VOICE_CLIPS {
-            let stalest = self.clips.iter().min_by_key(|entry| entry.value().0).map(|entry| entry.key().clone());
+    pub fn new(spill_dir: Option<PathBuf>, max_size: usize, ttl: Duration) -> Self {
+        Self {
+            clips: DashMap::new(),
+            spill_dir,
+            spilled: Default::default(),
+            max_size,
+            ttl,
+        }
+    }
+
+    async fn insert(&self, key: String, clip: Vec<u8>) {
+        let now = Instant::now();
+        self.clips.insert(key, StoredVoiceClip { inserted_at: now, clip });
+
+        let evicted = self.evict(now);
+        self.spill_all(evicted).await;
+    }
+
+    pub fn len(&self) -> usize {
+        self.clips.len()
+    }
+
+    async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        if let Some(stored) = self.clips.get(key) {
+            return Some(stored.clip.clone());
+        }
+
+        let path = self.spill_path(key)?;
+        tokio::fs::read(path).await.ok()
+    }
 
-            let Some(stalest) = stalest else {
+    //Expired clips first, then the oldest ones until we're back under the size bound.
+    pub fn evict(&self, now: Instant) -> Vec<(String, Vec<u8>)> {
+        let mut evicted = Vec::new();
+
+        let expired: Vec<String> = self.clips.iter()
+            .filter(|entry| now.saturating_duration_since(entry.inserted_at) >= self.ttl)
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        for key in expired {
+            if let Some((key, stored)) = self.clips.remove(&key) {
+                evicted.push((key, stored.clip));
+            }
+        }
+
+        let mut by_age: Vec<(Instant, String, usize)> = self.clips.iter()
+            .map(|entry| (entry.inserted_at, entry.key().clone(), entry.clip.len()))
+            .collect();
+        by_age.sort();
+
+        let mut size: usize = by_age.iter().map(|(_, _, len)| len).sum();
+        for (_, key, len) in by_age {
+            if size <= self.max_size {
                 break;
-            };
+            }
+
+            if let Some((key, stored)) = self.clips.remove(&key) {
+                evicted.push((key, stored.clip));
+            }
+            size -= len;
+        }
 
-            self.clips.remove(&stalest);
-        }*/
+        evicted
     }
 
-    fn take(&self, sha1d: &str) -> Option<Vec<u8>> {
-        self.clips.remove(sha1d).map(|(_, (_, clip))| clip)
+    pub async fn spill_all(&self, evicted: Vec<(String, Vec<u8>)>) {
+        for (key, clip) in evicted {
+            self.spill(&key, &clip).await;
+        }
+    }
+
+    //Clips only reach the disk once evicted, whatever is still in memory is written out before the client goes away.
+    pub async fn spill_remaining(&self) {
+        let keys: Vec<String> = self.clips.iter().map(|entry| entry.key().clone()).collect();
+        let remaining = keys.into_iter()
+            .filter_map(|key| self.clips.remove(&key))
+            .map(|(key, stored)| (key, stored.clip))
+            .collect();
+
+        self.spill_all(remaining).await;
+    }
+
+    async fn spill(&self, key: &str, clip: &[u8]) {
+        let (Some(dir), Some(path)) = (self.spill_dir.as_ref(), self.spill_path(key)) else {
+            debug!("Dropped voice clip {}", key);
+            return;
+        };
+
+        if let Err(e) = tokio::fs::create_dir_all(dir).await {
+            warn!("Could not create voice clip spill dir {}: {}", dir.display(), e);
+            return;
+        }
+
+        let mut spilled = self.spilled.lock().await;
+        if !spilled.loaded {
+            spilled.load(dir).await;
+        }
+
+        if let Err(e) = tokio::fs::write(&path, clip).await {
+            warn!("Could not spill voice clip {} to disk: {}", key, e);
+            return;
+        }
+
+        debug!("Spilled voice clip {} to {}", key, path.display());
+        let now = SystemTime::now();
+        spilled.record(path, clip.len() as u64, now);
+
+        for pruned in spilled.prune(now) {
+            let _ = tokio::fs::remove_file(pruned).await;
+        }
+    }
+
+    //Keys embed the base64 sha1d, which can contain slashes.
+    fn spill_path(&self, key: &str) -> Option<PathBuf> {
+        let mut hasher = Sha1::new();
+        hasher.update(key.as_bytes());
+        self.spill_dir.as_ref().map(|dir| dir.join(format!("{}.wav", hex::encode(hasher.finalize()))))
     }
 }
 
 impl TachyonClient {
 
-    pub async fn prepare_voice_clip(
+    //Long audio is split at the voice clip size limit, each part goes out as its own clip.
+    pub async fn prepare_voice_clips(
         &self,
         creator: &MsnUser,
         content: &AudioMessageEventContent,
-    ) -> Result<MsnObject, anyhow::Error> {
+    ) -> Result<Vec<MsnObject>, anyhow::Error> {
 
         if let Some(duration) = content.info.as_ref().and_then(|info| info.duration) {
-            if duration > MAX_VOICE_CLIP_DURATION {
+            if duration > MAX_VOICE_CLIP_DURATION * MAX_VOICE_CLIP_PARTS {
                 return Err(anyhow!(
-                    "Audio message is {:?} long, over the {:?} that fits in a voice clip",
+                    "Audio message is {:?} long, over the {:?} that fits in {} voice clips",
                     duration,
-                    MAX_VOICE_CLIP_DURATION
+                    MAX_VOICE_CLIP_DURATION * MAX_VOICE_CLIP_PARTS,
+                    MAX_VOICE_CLIP_PARTS
                 ));
             }
         }
@@ -67,28 +262,146 @@ impl TachyonClient {
             .await?;
 
         let clip = audio::to_siren_wav(media).await?;
+        let parts = siren::split(&clip, MAX_VOICE_CLIP_SIZE);
 
-        if clip.len() > MAX_VOICE_CLIP_SIZE {
+        if parts.len() > MAX_VOICE_CLIP_PARTS as usize {
             return Err(anyhow!(
-                "Transcoded voice clip is {} bytes, over the {} bytes WLM accepts",
-                clip.len(),
-                MAX_VOICE_CLIP_SIZE
+                "Transcoded audio message needs {} voice clips, over the {} we send",
+                parts.len(),
+                MAX_VOICE_CLIP_PARTS
             ));
         }
 
-        let msn_object = MSNObjectFactory::get_voice_message(
-            &clip,
-            creator.get_email_address().to_string(),
-            FriendlyName::default(),
-        );
+        let part_count = parts.len();
+        let mut msn_objects = Vec::with_capacity(part_count);
+        for (index, part) in parts.into_iter().enumerate() {
+            let mut msn_object = MSNObjectFactory::get_voice_message(
+                &part,
+                creator.get_email_address().to_string(),
+                FriendlyName::default(),
+            );
+            msn_object.location = index.to_string();
 
-        debug!("Prepared voice clip: {} bytes, sha1d {}", clip.len(), &msn_object.sha1d);
-        self.inner.voice_clips.insert(msn_object.sha1d.clone(), clip);
+            debug!("Prepared voice clip {}/{}: {} bytes, sha1d {}", index + 1, part_count, part.len(), &msn_object.sha1d);
+            self.inner.voice_clips.insert(voice_clip_key(&msn_object), part).await;
+            msn_objects.push(msn_object);
+        }
+
+        Ok(msn_objects)
+    }
 
-        Ok(msn_object)
+    pub async fn get_voice_clip(&self, msn_object: &MsnObject) -> Option<Vec<u8>> {
+        self.inner.voice_clips.get(&voice_clip_key(msn_object)).await
     }
 
-    pub fn take_voice_clip(&self, sha1d: &str) -> Option<Vec<u8>> {
-        self.inner.voice_clips.take(sha1d)
+    pub async fn spill_voice_clips(&self) {
+        self.inner.voice_clips.spill_remaining().await;
+    }
+}
+
+//Parts of a long clip can be byte for byte identical (silence), their index in the location keeps them apart.
+fn voice_clip_key(msn_object: &MsnObject) -> String {
+    format!("{}:{}", msn_object.location, msn_object.sha1d)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tachyon::client::voice_clip::{voice_clip_key, SpilledVoiceClips, VoiceClipStore, SPILLED_VOICE_CLIP_DIR_SIZE, SPILLED_VOICE_CLIP_TTL};
+    use msnp::shared::models::msn_object::{FriendlyName, MSNObjectFactory};
+    use msnp::shared::models::uuid::Uuid;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant, SystemTime};
+
+    #[tokio::test]
+    async fn clips_can_be_fetched_more_than_once() {
+        let store = VoiceClipStore::new(None, 1_000, Duration::from_secs(60));
+        store.insert("sha1d".into(), vec![1, 2, 3]).await;
+
+        assert_eq!(store.get("sha1d").await, Some(vec![1, 2, 3]));
+        assert_eq!(store.get("sha1d").await, Some(vec![1, 2, 3]));
+    }
+
+    #[tokio::test]
+    async fn oldest_clips_are_dropped_over_the_size_bound() {
+        let store = VoiceClipStore::new(None, 10, Duration::from_secs(60));
+        store.insert("first".into(), vec![0; 6]).await;
+        store.insert("second".into(), vec![0; 6]).await;
+
+        assert!(store.get("first").await.is_none());
+        assert!(store.get("second").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn expired_clips_spill_to_disk() {
+        let dir = std::env::temp_dir().join(format!("tachyon-voice-clips-{}", Uuid::new()));
+        let store = VoiceClipStore::new(Some(dir.clone()), 1_000, Duration::ZERO);
+
+        store.insert("a/b+c=".into(), vec![4, 5, 6]).await;
+
+        assert!(store.clips.is_empty());
+        assert_eq!(store.get("a/b+c=").await, Some(vec![4, 5, 6]));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn clips_left_in_memory_are_spilled_at_shutdown() {
+        let dir = std::env::temp_dir().join(format!("tachyon-voice-clips-{}", Uuid::new()));
+        let store = VoiceClipStore::new(Some(dir.clone()), 1_000, Duration::from_secs(60));
+        store.insert("first".into(), vec![1, 2]).await;
+        store.insert("second".into(), vec![3, 4, 5]).await;
+
+        store.spill_remaining().await;
+
+        assert_eq!(store.len(), 0);
+        assert_eq!(store.get("first").await, Some(vec![1, 2]));
+        assert_eq!(store.spilled.lock().await.size, 5);
+
+        //A new session picks up what the last one spilled
+        let reopened = VoiceClipStore::new(Some(dir.clone()), 1_000, Duration::ZERO);
+        reopened.insert("third".into(), vec![6]).await;
+        assert_eq!(reopened.spilled.lock().await.size, 6);
+        assert_eq!(reopened.spilled.lock().await.files.len(), 3);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn spilled_clips_are_pruned_by_age_then_size() {
+        let now = SystemTime::now();
+        let mut spilled = SpilledVoiceClips::default();
+        spilled.record(PathBuf::from("expired.wav"), 10, now - SPILLED_VOICE_CLIP_TTL - Duration::from_secs(1));
+        spilled.record(PathBuf::from("old.wav"), SPILLED_VOICE_CLIP_DIR_SIZE, now - Duration::from_secs(10));
+        spilled.record(PathBuf::from("new.wav"), 10, now);
+
+        assert_eq!(spilled.prune(now), vec![PathBuf::from("expired.wav"), PathBuf::from("old.wav")]);
+        assert_eq!(spilled.size, 10);
+
+        spilled.record(PathBuf::from("new.wav"), 20, now);
+        assert_eq!(spilled.files.len(), 1);
+        assert_eq!(spilled.size, 20);
+    }
+
+    #[tokio::test]
+    async fn sweeping_evicts_expired_clips_without_an_insert() {
+        let store = VoiceClipStore::new(None, 1_000, Duration::from_secs(60));
+        store.insert("sha1d".into(), vec![1, 2, 3]).await;
+
+        assert!(store.evict(Instant::now()).is_empty());
+
+        let evicted = store.evict(Instant::now() + Duration::from_secs(60));
+        assert_eq!(evicted, vec![("sha1d".to_string(), vec![1, 2, 3])]);
+        assert_eq!(store.len(), 0);
+    }
+
+    #[test]
+    fn identical_parts_get_their_own_key() {
+        let mut first = MSNObjectFactory::get_voice_message(&[0; 10], "aeon@test.com".into(), FriendlyName::default());
+        let mut second = first.clone();
+        first.location = "0".into();
+        second.location = "1".into();
+
+        assert_eq!(first.sha1d, second.sha1d);
+        assert_ne!(voice_clip_key(&first), voice_clip_key(&second));
     }
 }
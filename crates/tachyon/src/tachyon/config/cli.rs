@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::tachyon::config::tachyon_config::{ConfigOverride, ENV_CONFIG_PATH};
+
+//Layered over config.ini and the TACHYON_* environment overrides, flags always win.
+#[derive(Parser, Debug, Default)]
+#[command(name = "tachyon", version, about = "Windows Live Messenger to Matrix bridge")]
+pub struct CliArgs {
+    #[arg(long, env = ENV_CONFIG_PATH, value_name = "PATH", help = "Config file to read instead of config.ini in the Tachyon config directory")]
+    pub config: Option<PathBuf>,
+
+    #[arg(long, value_name = "PORT", help = "[server] notification_port")]
+    pub notification_port: Option<String>,
+
+    #[arg(long, value_name = "PORT", help = "[server] switchboard_port")]
+    pub switchboard_port: Option<String>,
+
+    #[arg(long, value_name = "PORT", help = "[server] http_port")]
+    pub http_port: Option<String>,
+
+    #[arg(long, value_name = "IP", help = "[server] bind_address")]
+    pub bind_address: Option<String>,
+
+    #[arg(long, value_name = "IPV4", help = "[server] public_host")]
+    pub public_host: Option<String>,
+
+    #[arg(long, value_name = "URL", help = "[matrix] homeserver_url")]
+    pub homeserver_url: Option<String>,
+
+    #[arg(long, value_name = "LEVEL", help = "[tachyon_logs] level, also turns logs on")]
+    pub log_level: Option<String>,
+
+    #[arg(long = "set", value_name = "SECTION.KEY=VALUE", help = "Any other config key, can be repeated")]
+    pub set: Vec<String>,
+}
+
+impl CliArgs {
+    pub fn overrides(&self) -> Result<Vec<ConfigOverride>, anyhow::Error> {
+        let mut overrides = Vec::new();
+
+        let flags = [
+            ("server", "notification_port", &self.notification_port),
+            ("server", "switchboard_port", &self.switchboard_port),
+            ("server", "http_port", &self.http_port),
+            ("server", "bind_address", &self.bind_address),
+            ("server", "public_host", &self.public_host),
+            ("matrix", "homeserver_url", &self.homeserver_url),
+            ("tachyon_logs", "level", &self.log_level),
+        ];
+
+        for (section, key, value) in flags {
+            if let Some(value) = value {
+                overrides.push(ConfigOverride::new(section, key, value.as_str())?);
+            }
+        }
+
+        if self.log_level.is_some() {
+            overrides.push(ConfigOverride::new("tachyon_logs", "enabled", "true")?);
+        }
+
+        //--set comes last so it can still override the dedicated flags
+        for raw in &self.set {
+            overrides.push(ConfigOverride::parse(raw)?);
+        }
+
+        Ok(overrides)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::tachyon::config::cli::CliArgs;
+    use crate::tachyon::config::tachyon_config::ConfigOverride;
+
+    #[test]
+    fn flags_to_overrides() {
+        let args = CliArgs::try_parse_from(["tachyon", "--http-port", "9090", "--log-level", "debug", "--set", "features.webcam=false"]).unwrap();
+        let overrides = args.overrides().unwrap();
+
+        assert_eq!(overrides, vec![
+            ConfigOverride::new("server", "http_port", "9090").unwrap(),
+            ConfigOverride::new("tachyon_logs", "level", "debug").unwrap(),
+            ConfigOverride::new("tachyon_logs", "enabled", "true").unwrap(),
+            ConfigOverride::new("features", "webcam", "false").unwrap(),
+        ]);
+    }
+
+    #[test]
+    fn malformed_set_is_rejected() {
+        let args = CliArgs::try_parse_from(["tachyon", "--set", "http_port=9090"]).unwrap();
+        assert!(args.overrides().is_err());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod tachyon_config;
+pub mod cli;
 pub mod secret_encryptor;
 pub mod paths;
\ No newline at end of file
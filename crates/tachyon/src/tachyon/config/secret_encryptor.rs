@@ -36,6 +36,10 @@ impl SecretEncryptor {
 
     pub fn decrypt(&self, encrypted: &str) -> Result<String, anyhow::Error> {
         let bytes = hex::decode(encrypted)?;
+        if bytes.len() < 12 {
+            return Err(anyhow::anyhow!("TicketToken Decryption failed: token is too short"));
+        }
+
         let (nonce_bytes, ciphertext) = bytes.split_at(12);
         let nonce = Nonce::from_slice(nonce_bytes);
         let plaintext = self.cipher.decrypt(nonce, ciphertext)
@@ -76,4 +80,35 @@ mod tests {
         assert_eq!(decrypted.unwrap(), TEST_TOKEN);
     }
 
+    #[test]
+    fn decryption_fails_with_tampered_token() {
+        let encryptor = SecretEncryptor::new(&TEST_SECRET).unwrap();
+        let mut bytes = hex::decode(encryptor.encrypt(TEST_TOKEN).unwrap()).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+
+        assert!(encryptor.decrypt(&hex::encode(bytes)).is_err());
+    }
+
+    #[test]
+    fn decryption_fails_with_truncated_token() {
+        let encryptor = SecretEncryptor::new(&TEST_SECRET).unwrap();
+        let encrypted = encryptor.encrypt(TEST_TOKEN).unwrap();
+
+        assert!(encryptor.decrypt(&encrypted[..16]).is_err());
+        assert!(encryptor.decrypt("").is_err());
+        assert!(encryptor.decrypt("not_hex").is_err());
+    }
+
+    #[test]
+    fn decryption_fails_with_other_secret() {
+        let encrypted = SecretEncryptor::new(&TEST_SECRET).unwrap().encrypt(TEST_TOKEN).unwrap();
+
+        let mut other_secret = TEST_SECRET;
+        other_secret[0] = 0;
+        let other_encryptor = SecretEncryptor::new(&other_secret).unwrap();
+
+        assert!(other_encryptor.decrypt(&encrypted).is_err());
+    }
+
 }
\ No newline at end of file
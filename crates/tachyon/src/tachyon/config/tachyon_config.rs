@@ -1,7 +1,12 @@
 use std::fmt::{Display, Formatter};
 use anyhow::anyhow;
 use configparser::ini::Ini;
+use log::LevelFilter;
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct TachyonConfig {
@@ -9,11 +14,356 @@ pub struct TachyonConfig {
     pub notification_port: u32,
     pub switchboard_port: u32,
     pub http_port: u32,
+    //Where the servers listen, 0.0.0.0 to accept clients from the LAN.
+    pub bind_address: IpAddr,
+    //Handed to the client in XFR, RNG and every URL it opens, must be reachable from where WLM runs.
+    //MSNP only carries IPv4 addresses for the switchboard.
+    pub public_host: Ipv4Addr,
+    //Used instead of public_host in the web URLs, so HTTPS can use a certificate issued for a domain.
+    pub public_web_host: Option<String>,
+    //The web server only speaks HTTPS when set.
+    pub tls: Option<TlsConfig>,
+    pub hosted: HostedConfig,
     pub strict_ssl: bool,
-    pub logs_enabled: bool,
+    pub sync_mode: SyncMode,
+    //Skips .well-known discovery, for homeservers that don't serve it or are only reachable on another address.
+    pub homeserver_url: Option<Url>,
+    pub logging: LoggingConfig,
+    pub features: FeatureConfig,
+    pub image_strategy: ImageStrategy,
+    //Images over this size are always sent as a regular file transfer.
+    pub inline_image_max_size: usize,
+    //Voice clips evicted from memory are kept in the user data dir so they can be played again.
+    pub voice_clip_spill_to_disk: bool,
+    //Clips WLM never came to fetch are dropped from memory after this, or spilled to disk when enabled.
+    pub voice_clip_ttl: Duration,
+    //Oldest clips are evicted past this many bytes held in memory.
+    pub voice_clip_store_size: usize,
+    //Same as voice clips for the pictures of the custom emoticons we send, nothing is spilled.
+    pub custom_emoticon_ttl: Duration,
+    pub custom_emoticon_store_size: usize,
+    //Typing in a conversation shows up as typing in the Matrix room.
+    pub send_typing_notifications: bool,
+    pub sweeper: SweeperConfig,
 
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggingConfig {
+    pub enabled: bool,
+    //Applies to every target not listed below
+    pub level: LevelFilter,
+    pub targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            level: LevelFilter::Warn,
+            targets: vec![
+                ("v2".to_string(), LevelFilter::Debug),
+                ("tachyon".to_string(), LevelFilter::Debug),
+                ("msnp".to_string(), LevelFilter::Debug),
+                ("matrix-sdk".to_string(), LevelFilter::Off),
+                ("yaserde".to_string(), LevelFilter::Warn),
+            ],
+        }
+    }
+}
+
+//Client features that can be turned off, invites for a disabled feature are declined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureConfig {
+    pub webcam: bool,
+    pub file_transfers: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            webcam: true,
+            file_transfers: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsConfig {
+    //PEM encoded, the chain is read from the same file
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+//One Tachyon shared by several people who don't have access to the machine it runs on.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HostedConfig {
+    pub enabled: bool,
+    //0 is unlimited, limits only apply in hosted mode
+    pub max_clients: usize,
+    pub max_switchboards_per_client: usize,
+    pub max_p2p_sessions_per_client: usize,
+}
+
+impl HostedConfig {
+    pub fn client_limit(&self) -> Option<usize> {
+        self.limit(self.max_clients)
+    }
+
+    pub fn switchboard_limit(&self) -> Option<usize> {
+        self.limit(self.max_switchboards_per_client)
+    }
+
+    pub fn p2p_session_limit(&self) -> Option<usize> {
+        self.limit(self.max_p2p_sessions_per_client)
+    }
+
+    fn limit(&self, max: usize) -> Option<usize> {
+        (self.enabled && max > 0).then_some(max)
+    }
+}
+
+//How long unclaimed entries are kept before the sweeper drops them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweeperConfig {
+    pub interval: Duration,
+    //Tickets waiting for the client to log in on the notification server
+    pub pending_ticket_ttl: Duration,
+    //Web login alerts, confirm device alerts carry their own expiration
+    pub alert_ttl: Duration,
+    pub verification_request_ttl: Duration,
+    //Established webcams are left alone, they end when the client stops sharing
+    pub p2p_session_ttl: Duration,
+}
+
+impl Default for SweeperConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            pending_ticket_ttl: Duration::from_mins(5),
+            alert_ttl: Duration::from_mins(10),
+            verification_request_ttl: Duration::from_mins(10),
+            p2p_session_ttl: Duration::from_hours(1),
+        }
+    }
+}
+
+//Which Matrix sync API the bridge uses.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum SyncMode {
+    //Simplified Sliding Sync when the homeserver advertises it, classic /sync otherwise
+    #[default]
+    Auto,
+    SlidingSync,
+    Classic,
+}
+
+impl Display for SyncMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let out = match self {
+            SyncMode::Auto => "auto",
+            SyncMode::SlidingSync => "sliding_sync",
+            SyncMode::Classic => "classic",
+        };
+        write!(f, "{}", out)
+    }
+}
+
+impl FromStr for SyncMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(SyncMode::Auto),
+            "sliding_sync" => Ok(SyncMode::SlidingSync),
+            "classic" => Ok(SyncMode::Classic),
+            _ => Err(anyhow!("Unknown sync mode: {}, expected one of auto, sliding_sync, classic", s))
+        }
+    }
+}
+
+//How images coming from Matrix are delivered to the client.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ImageStrategy {
+    //Shown in the Photo Sharing pane of the conversation
+    #[default]
+    PhotoSharing,
+    //Shown inline in the conversation as a custom emoticon
+    Emoticon,
+    //Always sent as a file transfer the user has to accept
+    FileTransfer,
+}
+
+impl Display for ImageStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let out = match self {
+            ImageStrategy::PhotoSharing => "photo_sharing",
+            ImageStrategy::Emoticon => "emoticon",
+            ImageStrategy::FileTransfer => "file_transfer",
+        };
+        write!(f, "{}", out)
+    }
+}
+
+impl FromStr for ImageStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "photo_sharing" => Ok(ImageStrategy::PhotoSharing),
+            "emoticon" => Ok(ImageStrategy::Emoticon),
+            "file_transfer" => Ok(ImageStrategy::FileTransfer),
+            _ => Err(anyhow!("Unknown image strategy: {}, expected one of photo_sharing, emoticon, file_transfer", s))
+        }
+    }
+}
+
+//Every key that can be overridden from the environment or the command line.
+//The ini file itself is also read by Zathras, so unknown keys there are left alone.
+const KNOWN_KEYS: &[(&str, &[&str])] = &[
+    ("server", &["notification_port", "switchboard_port", "http_port", "bind_address", "public_host", "public_web_host", "tls_cert", "tls_key"]),
+    ("hosted", &["enabled", "max_clients", "max_switchboards_per_client", "max_p2p_sessions_per_client"]),
+    ("matrix", &["strict_ssl", "sync_mode", "homeserver_url"]),
+    ("tachyon_logs", &["enabled", "level", "targets"]),
+    ("features", &["webcam", "file_transfers"]),
+    ("bridge", &["image_strategy", "inline_image_max_size", "voice_clip_spill_to_disk", "voice_clip_ttl_secs", "voice_clip_store_size", "custom_emoticon_ttl_secs", "custom_emoticon_store_size", "send_typing_notifications"]),
+    ("sweeper", &["interval_secs", "pending_ticket_ttl_secs", "alert_ttl_secs", "verification_request_ttl_secs", "p2p_session_ttl_secs"]),
+];
+
+pub const ENV_PREFIX: &str = "TACHYON_";
+//Points to the config file rather than overriding a key in it.
+pub const ENV_CONFIG_PATH: &str = "TACHYON_CONFIG";
+
+//A single ini value set from outside the config file, applied before parsing so it goes through the same checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOverride {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+}
+
+impl ConfigOverride {
+    pub fn new(section: &str, key: &str, value: impl Into<String>) -> Result<Self, anyhow::Error> {
+        let section = section.trim().to_lowercase();
+        let key = key.trim().to_lowercase();
+
+        let Some((_, keys)) = KNOWN_KEYS.iter().find(|(known, _)| *known == section) else {
+            return Err(anyhow!("Unknown config section [{}], expected one of {}", section, KNOWN_KEYS.iter().map(|(known, _)| *known).collect::<Vec<_>>().join(", ")));
+        };
+
+        if !keys.contains(&key.as_str()) {
+            return Err(anyhow!("Unknown config key {} in [{}], expected one of {}", key, section, keys.join(", ")));
+        }
+
+        Ok(Self { section, key, value: value.into() })
+    }
+
+    //section.key=value
+    pub fn parse(raw: &str) -> Result<Self, anyhow::Error> {
+        let (name, value) = raw.split_once('=').ok_or(anyhow!("Expected section.key=value, got {}", raw))?;
+        let (section, key) = name.split_once('.').ok_or(anyhow!("Expected section.key=value, got {}", raw))?;
+        Self::new(section, key, value.trim())
+    }
+
+    //TACHYON_<SECTION>_<KEY>, e.g. TACHYON_SERVER_HTTP_PORT. Sections and keys both contain underscores, so names are matched against the known keys.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<Self>, anyhow::Error> {
+        let mut overrides = Vec::new();
+
+        for (name, value) in vars {
+            if name == ENV_CONFIG_PATH {
+                continue;
+            }
+
+            let Some(stripped) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            let stripped = stripped.to_lowercase();
+            let found = KNOWN_KEYS.iter()
+                .flat_map(|(section, keys)| keys.iter().map(move |key| (*section, *key)))
+                .find(|(section, key)| stripped == format!("{}_{}", section, key));
+
+            match found {
+                Some((section, key)) => overrides.push(Self::new(section, key, value)?),
+                //Other tools can share the prefix, a typo shouldn't keep the server from starting either. Logs aren't up yet.
+                None => eprintln!("Ignoring unknown environment override {}, expected {}<SECTION>_<KEY> such as TACHYON_SERVER_HTTP_PORT", name, ENV_PREFIX),
+            }
+        }
+
+        Ok(overrides)
+    }
+}
+
+impl TachyonConfig {
+    //Overrides are applied in order, later ones win.
+    pub fn load(ini_content: &str, overrides: &[ConfigOverride]) -> Result<Self, anyhow::Error> {
+        let mut ini = Ini::new();
+        ini.read(ini_content.into()).map_err(|e| anyhow!("Couldn't parse config: {}", e))?;
+
+        for config_override in overrides {
+            ini.set(&config_override.section, &config_override.key, Some(config_override.value.clone()));
+        }
+
+        Self::from_ini(&ini)
+    }
+
+    //Reports every problem at once rather than making the user fix them one run at a time.
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let mut problems = Vec::new();
+
+        let ports = [("notification_port", self.notification_port), ("switchboard_port", self.switchboard_port), ("http_port", self.http_port)];
+        for (name, port) in ports {
+            if port == 0 || port > u16::MAX as u32 {
+                problems.push(format!("{} must be between 1 and {}, got {}", name, u16::MAX, port));
+            }
+        }
+
+        for (index, (name, port)) in ports.iter().enumerate() {
+            if let Some((other_name, _)) = ports[index + 1..].iter().find(|(_, other_port)| other_port == port) {
+                problems.push(format!("{} and {} are both set to {}", name, other_name, port));
+            }
+        }
+
+        if self.public_host.is_unspecified() {
+            problems.push("public_host can't be 0.0.0.0, it has to be an address the client can reach".to_string());
+        }
+
+        if let Some(public_web_host) = &self.public_web_host {
+            if public_web_host.contains(['/', ':', ' ']) {
+                problems.push(format!("public_web_host must be a bare host name without scheme or port, got {}", public_web_host));
+            }
+        }
+
+        if let Some(homeserver_url) = &self.homeserver_url {
+            if !matches!(homeserver_url.scheme(), "http" | "https") {
+                problems.push(format!("homeserver_url must be an http or https url, got {}", homeserver_url));
+            }
+        }
+
+        if self.sweeper.interval.is_zero() {
+            problems.push("interval_secs must be greater than 0".to_string());
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(anyhow!("Invalid config:\n  - {}", problems.join("\n  - ")))
+    }
+
+    pub fn web_scheme(&self) -> &'static str {
+        if self.tls.is_some() { "https" } else { "http" }
+    }
+
+    pub fn web_host(&self) -> String {
+        self.public_web_host.clone().unwrap_or_else(|| self.public_host.to_string())
+    }
+
+    pub fn web_url(&self, path: &str) -> String {
+        format!("{}://{}:{}{}", self.web_scheme(), self.web_host(), self.http_port, path)
+    }
+}
+
 impl Default for TachyonConfig {
     fn default() -> Self {
         
@@ -21,8 +371,26 @@ impl Default for TachyonConfig {
             notification_port: 11863,
             switchboard_port: 11864,
             http_port: 11866,
+            bind_address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            public_host: Ipv4Addr::LOCALHOST,
+            public_web_host: None,
+            tls: None,
+            hosted: HostedConfig::default(),
             strict_ssl: true,
-            logs_enabled: false,
+            sync_mode: SyncMode::default(),
+            homeserver_url: None,
+            logging: LoggingConfig::default(),
+            features: FeatureConfig::default(),
+            image_strategy: ImageStrategy::default(),
+            inline_image_max_size: 512_000,
+            voice_clip_spill_to_disk: true,
+            voice_clip_ttl: Duration::from_mins(30),
+            //About 60 clips at the maximum size.
+            voice_clip_store_size: 2_000_000,
+            custom_emoticon_ttl: Duration::from_mins(30),
+            custom_emoticon_store_size: 2_000_000,
+            send_typing_notifications: true,
+            sweeper: SweeperConfig::default(),
         }
     }
 }
@@ -33,8 +401,42 @@ impl Display for TachyonConfig {
         ini.set("server", "notification_port", Some(self.notification_port.to_string()));
         ini.set("server", "switchboard_port", Some(self.switchboard_port.to_string()));
         ini.set("server", "http_port", Some(self.http_port.to_string()));
+        ini.set("server", "bind_address", Some(self.bind_address.to_string()));
+        ini.set("server", "public_host", Some(self.public_host.to_string()));
+        if let Some(public_web_host) = &self.public_web_host {
+            ini.set("server", "public_web_host", Some(public_web_host.clone()));
+        }
+        if let Some(tls) = &self.tls {
+            ini.set("server", "tls_cert", Some(tls.cert_path.display().to_string()));
+            ini.set("server", "tls_key", Some(tls.key_path.display().to_string()));
+        }
+        ini.set("hosted", "enabled", Some(self.hosted.enabled.to_string()));
+        ini.set("hosted", "max_clients", Some(self.hosted.max_clients.to_string()));
+        ini.set("hosted", "max_switchboards_per_client", Some(self.hosted.max_switchboards_per_client.to_string()));
+        ini.set("hosted", "max_p2p_sessions_per_client", Some(self.hosted.max_p2p_sessions_per_client.to_string()));
         ini.set("matrix", "strict_ssl", Some(self.strict_ssl.to_string()));
-        ini.set("tachyon_logs", "enabled", Some(self.logs_enabled.to_string()));
+        ini.set("matrix", "sync_mode", Some(self.sync_mode.to_string()));
+        if let Some(homeserver_url) = &self.homeserver_url {
+            ini.set("matrix", "homeserver_url", Some(homeserver_url.to_string()));
+        }
+        ini.set("tachyon_logs", "enabled", Some(self.logging.enabled.to_string()));
+        ini.set("tachyon_logs", "level", Some(self.logging.level.to_string().to_lowercase()));
+        ini.set("tachyon_logs", "targets", Some(self.logging.targets.iter().map(|(target, level)| format!("{}={}", target, level.to_string().to_lowercase())).collect::<Vec<_>>().join(",")));
+        ini.set("features", "webcam", Some(self.features.webcam.to_string()));
+        ini.set("features", "file_transfers", Some(self.features.file_transfers.to_string()));
+        ini.set("bridge", "image_strategy", Some(self.image_strategy.to_string()));
+        ini.set("bridge", "inline_image_max_size", Some(self.inline_image_max_size.to_string()));
+        ini.set("bridge", "voice_clip_spill_to_disk", Some(self.voice_clip_spill_to_disk.to_string()));
+        ini.set("bridge", "voice_clip_ttl_secs", Some(self.voice_clip_ttl.as_secs().to_string()));
+        ini.set("bridge", "voice_clip_store_size", Some(self.voice_clip_store_size.to_string()));
+        ini.set("bridge", "custom_emoticon_ttl_secs", Some(self.custom_emoticon_ttl.as_secs().to_string()));
+        ini.set("bridge", "custom_emoticon_store_size", Some(self.custom_emoticon_store_size.to_string()));
+        ini.set("bridge", "send_typing_notifications", Some(self.send_typing_notifications.to_string()));
+        ini.set("sweeper", "interval_secs", Some(self.sweeper.interval.as_secs().to_string()));
+        ini.set("sweeper", "pending_ticket_ttl_secs", Some(self.sweeper.pending_ticket_ttl.as_secs().to_string()));
+        ini.set("sweeper", "alert_ttl_secs", Some(self.sweeper.alert_ttl.as_secs().to_string()));
+        ini.set("sweeper", "verification_request_ttl_secs", Some(self.sweeper.verification_request_ttl.as_secs().to_string()));
+        ini.set("sweeper", "p2p_session_ttl_secs", Some(self.sweeper.p2p_session_ttl.as_secs().to_string()));
         write!(f, "{}", ini.writes())
     }
 }
@@ -43,33 +445,145 @@ impl FromStr for TachyonConfig {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::load(s, &[])
+    }
+}
 
-        let mut config = Ini::new();
-        config.read(s.into()).map_err(|e| anyhow!("Couldn't parse config: {}", e))?;
+impl TachyonConfig {
+    fn from_ini(config: &Ini) -> Result<Self, anyhow::Error> {
 
         let notification_port : u32 = config.getuint("server", "notification_port").map_err(|e| anyhow!("Couldn't parse notification_port: {}", e))?.ok_or(anyhow!("notification_port is mandatory"))?.try_into().map_err(|e| anyhow!("notification_port is not a valid port: {}", e))?;
         let switchboard_port: u32 = config.getuint("server", "switchboard_port").map_err(|e| anyhow!("Couldn't parse switchboard_port: {}", e))?.ok_or(anyhow!("switchboard_port is mandatory"))?.try_into().map_err(|e| anyhow!("switchboard_port is not a valid port: {}", e))?;
         let http_port: u32 = config.getuint("server", "http_port").map_err(|e| anyhow!("Couldn't parse http_port: {}", e))?.ok_or(anyhow!("http_port is mandatory"))?.try_into().map_err(|e| anyhow!("http_port is not a valid port: {}", e))?;
+        let bind_address = config.get("server", "bind_address").map(|s| IpAddr::from_str(s.trim()).map_err(|e| anyhow!("bind_address is not a valid ip address: {}", e))).transpose()?.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let public_host = config.get("server", "public_host").map(|s| Ipv4Addr::from_str(s.trim()).map_err(|e| anyhow!("public_host is not a valid ipv4 address: {}", e))).transpose()?.unwrap_or(Ipv4Addr::LOCALHOST);
+        let public_web_host = config.get("server", "public_web_host").map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+        let tls = match (config.get("server", "tls_cert"), config.get("server", "tls_key")) {
+            (Some(cert_path), Some(key_path)) => Some(TlsConfig { cert_path: PathBuf::from(cert_path.trim()), key_path: PathBuf::from(key_path.trim()) }),
+            (None, None) => None,
+            _ => return Err(anyhow!("tls_cert and tls_key have to be set together"))
+        };
+
+        let hosted = HostedConfig {
+            enabled: config.getbool("hosted", "enabled").map_err(|e| anyhow!("Couldn't parse hosted enabled: {}", e))?.unwrap_or(false),
+            max_clients: get_usize(config, "hosted", "max_clients")?,
+            max_switchboards_per_client: get_usize(config, "hosted", "max_switchboards_per_client")?,
+            max_p2p_sessions_per_client: get_usize(config, "hosted", "max_p2p_sessions_per_client")?,
+        };
 
         let strict_ssl = config.getbool("matrix", "strict_ssl").map_err(|e| anyhow!("Couldn't parse strict_ssl: {}", e))?.unwrap_or(true);
+        let sync_mode = config.get("matrix", "sync_mode").map(|s| SyncMode::from_str(&s)).transpose()?.unwrap_or_default();
+        let homeserver_url = config.get("matrix", "homeserver_url").filter(|s| !s.trim().is_empty()).map(|s| Url::parse(s.trim()).map_err(|e| anyhow!("homeserver_url is not a valid url: {}", e))).transpose()?;
 
-        let logs_enabled = config.getbool("tachyon_logs", "enabled").map_err(|e| anyhow!("Couldn't parse strict_ssl: {}", e))?.unwrap_or(false);
+        let default_logging = LoggingConfig::default();
+        let logging = LoggingConfig {
+            enabled: config.getbool("tachyon_logs", "enabled").map_err(|e| anyhow!("Couldn't parse tachyon_logs enabled: {}", e))?.unwrap_or(false),
+            level: config.get("tachyon_logs", "level").map(|s| parse_level(&s)).transpose()?.unwrap_or(default_logging.level),
+            targets: config.get("tachyon_logs", "targets").map(|s| parse_log_targets(&s)).transpose()?.unwrap_or(default_logging.targets),
+        };
 
+        let features = FeatureConfig {
+            webcam: get_bool(config, "features", "webcam", true)?,
+            file_transfers: get_bool(config, "features", "file_transfers", true)?,
+        };
 
-        Ok(Self {
+        let image_strategy = config.get("bridge", "image_strategy").map(|s| ImageStrategy::from_str(&s)).transpose()?.unwrap_or_default();
+        let inline_image_max_size: usize = config.getuint("bridge", "inline_image_max_size").map_err(|e| anyhow!("Couldn't parse inline_image_max_size: {}", e))?.unwrap_or(512_000).try_into().map_err(|e| anyhow!("inline_image_max_size is too big: {}", e))?;
+        let voice_clip_spill_to_disk = config.getbool("bridge", "voice_clip_spill_to_disk").map_err(|e| anyhow!("Couldn't parse voice_clip_spill_to_disk: {}", e))?.unwrap_or(true);
+        let default_config = TachyonConfig::default();
+        let voice_clip_ttl = get_secs(config, "bridge", "voice_clip_ttl_secs", default_config.voice_clip_ttl)?;
+        let voice_clip_store_size = get_size(config, "bridge", "voice_clip_store_size", default_config.voice_clip_store_size)?;
+        let custom_emoticon_ttl = get_secs(config, "bridge", "custom_emoticon_ttl_secs", default_config.custom_emoticon_ttl)?;
+        let custom_emoticon_store_size = get_size(config, "bridge", "custom_emoticon_store_size", default_config.custom_emoticon_store_size)?;
+        let send_typing_notifications = get_bool(config, "bridge", "send_typing_notifications", true)?;
+
+        let default_sweeper = SweeperConfig::default();
+        let sweeper = SweeperConfig {
+            interval: get_secs(config, "sweeper", "interval_secs", default_sweeper.interval)?,
+            pending_ticket_ttl: get_secs(config, "sweeper", "pending_ticket_ttl_secs", default_sweeper.pending_ticket_ttl)?,
+            alert_ttl: get_secs(config, "sweeper", "alert_ttl_secs", default_sweeper.alert_ttl)?,
+            verification_request_ttl: get_secs(config, "sweeper", "verification_request_ttl_secs", default_sweeper.verification_request_ttl)?,
+            p2p_session_ttl: get_secs(config, "sweeper", "p2p_session_ttl_secs", default_sweeper.p2p_session_ttl)?,
+        };
+
+        let parsed = Self {
             notification_port,
             switchboard_port,
             http_port,
+            bind_address,
+            public_host,
+            public_web_host,
+            tls,
+            hosted,
             strict_ssl,
-            logs_enabled,
-        })
+            sync_mode,
+            homeserver_url,
+            logging,
+            features,
+            image_strategy,
+            inline_image_max_size,
+            voice_clip_spill_to_disk,
+            voice_clip_ttl,
+            voice_clip_store_size,
+            custom_emoticon_ttl,
+            custom_emoticon_store_size,
+            send_typing_notifications,
+            sweeper,
+        };
+
+        parsed.validate()?;
+        Ok(parsed)
+    }
+}
+
+fn get_usize(config: &Ini, section: &str, key: &str) -> Result<usize, anyhow::Error> {
+    let value = config.getuint(section, key).map_err(|e| anyhow!("Couldn't parse {}: {}", key, e))?.unwrap_or(0);
+    value.try_into().map_err(|e| anyhow!("{} is too big: {}", key, e))
+}
+
+fn get_size(config: &Ini, section: &str, key: &str, default: usize) -> Result<usize, anyhow::Error> {
+    match config.getuint(section, key).map_err(|e| anyhow!("Couldn't parse {}: {}", key, e))? {
+        Some(size) => size.try_into().map_err(|e| anyhow!("{} is too big: {}", key, e)),
+        None => Ok(default),
     }
 }
 
+fn get_bool(config: &Ini, section: &str, key: &str, default: bool) -> Result<bool, anyhow::Error> {
+    let value = config.getbool(section, key).map_err(|e| anyhow!("Couldn't parse {}: {}", key, e))?;
+    Ok(value.unwrap_or(default))
+}
+
+fn parse_level(raw: &str) -> Result<LevelFilter, anyhow::Error> {
+    LevelFilter::from_str(raw.trim()).map_err(|_| anyhow!("Unknown log level: {}, expected one of off, error, warn, info, debug, trace", raw.trim()))
+}
+
+//target=level,target=level
+fn parse_log_targets(raw: &str) -> Result<Vec<(String, LevelFilter)>, anyhow::Error> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (target, level) = entry.split_once('=').ok_or(anyhow!("Log target {} should look like target=level", entry))?;
+            Ok((target.trim().to_string(), parse_level(level)?))
+        })
+        .collect()
+}
+
+fn get_secs(config: &Ini, section: &str, key: &str, default: Duration) -> Result<Duration, anyhow::Error> {
+    let secs = config.getuint(section, key).map_err(|e| anyhow!("Couldn't parse {}: {}", key, e))?;
+    Ok(secs.map(Duration::from_secs).unwrap_or(default))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
-    use crate::tachyon::config::tachyon_config::TachyonConfig;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+    use std::path::PathBuf;
+    use log::LevelFilter;
+    use reqwest::Url;
+    use crate::tachyon::config::tachyon_config::{ConfigOverride, FeatureConfig, HostedConfig, ImageStrategy, LoggingConfig, SweeperConfig, SyncMode, TachyonConfig, TlsConfig};
 
     #[test]
     fn deserialize_config() {
@@ -77,10 +591,22 @@ mod tests {
 notification_port = 1863
 switchboard_port = 1864
 http_port = 8080
+bind_address = 0.0.0.0
+public_host = 192.168.1.20
+public_web_host = tachyon.example.org
+tls_cert = /etc/tachyon/cert.pem
+tls_key = /etc/tachyon/key.pem
+
+[hosted]
+enabled = true
+max_clients = 50
+max_p2p_sessions_per_client = 8
 
 
 [matrix]
 strict_ssl = true
+sync_mode = classic
+homeserver_url = https://matrix.example.org:8448
 
 
 [zathras_logs]
@@ -88,6 +614,23 @@ enabled = true
 
 [tachyon_logs]
 enabled = true
+level = info
+targets = tachyon=trace, matrix_sdk=warn
+
+[features]
+webcam = false
+
+[bridge]
+image_strategy = emoticon
+inline_image_max_size = 1024
+voice_clip_spill_to_disk = false
+voice_clip_ttl_secs = 600
+custom_emoticon_store_size = 100000
+send_typing_notifications = false
+
+[sweeper]
+interval_secs = 30
+alert_ttl_secs = 120
 
 
 "#;
@@ -97,8 +640,157 @@ enabled = true
         assert_eq!(config.notification_port, 1863);
         assert_eq!(config.switchboard_port, 1864);
         assert_eq!(config.http_port, 8080);
+        assert_eq!(config.bind_address, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(config.public_host, Ipv4Addr::new(192, 168, 1, 20));
+        assert_eq!(config.tls, Some(TlsConfig { cert_path: PathBuf::from("/etc/tachyon/cert.pem"), key_path: PathBuf::from("/etc/tachyon/key.pem") }));
+        assert_eq!(config.public_web_host.as_deref(), Some("tachyon.example.org"));
+        assert_eq!(config.web_url("/tachyon"), "https://tachyon.example.org:8080/tachyon");
+        assert_eq!(config.hosted.client_limit(), Some(50));
+        assert_eq!(config.hosted.switchboard_limit(), None);
+        assert_eq!(config.hosted.p2p_session_limit(), Some(8));
         assert_eq!(config.strict_ssl, true);
-        assert_eq!(config.logs_enabled, true);
+        assert_eq!(config.sync_mode, SyncMode::Classic);
+        assert_eq!(config.homeserver_url, Some(Url::parse("https://matrix.example.org:8448").unwrap()));
+        assert_eq!(config.logging.enabled, true);
+        assert_eq!(config.logging.level, LevelFilter::Info);
+        assert_eq!(config.logging.targets, vec![("tachyon".to_string(), LevelFilter::Trace), ("matrix_sdk".to_string(), LevelFilter::Warn)]);
+        assert_eq!(config.features, FeatureConfig { webcam: false, ..Default::default() });
+        assert_eq!(config.image_strategy, ImageStrategy::Emoticon);
+        assert_eq!(config.inline_image_max_size, 1024);
+        assert_eq!(config.voice_clip_spill_to_disk, false);
+        assert_eq!(config.voice_clip_ttl, Duration::from_secs(600));
+        assert_eq!(config.voice_clip_store_size, TachyonConfig::default().voice_clip_store_size);
+        assert_eq!(config.custom_emoticon_store_size, 100_000);
+        assert_eq!(config.send_typing_notifications, false);
+        assert_eq!(config.sweeper.interval, Duration::from_secs(30));
+        assert_eq!(config.sweeper.alert_ttl, Duration::from_secs(120));
+        assert_eq!(config.sweeper.pending_ticket_ttl, SweeperConfig::default().pending_ticket_ttl);
+    }
+
+    #[test]
+    fn deserialize_config_defaults_to_loopback() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1864
+http_port = 8080
+"#;
+
+        let config = TachyonConfig::from_str(config).expect("config to be valid");
+        assert_eq!(config.bind_address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(config.public_host, Ipv4Addr::LOCALHOST);
+        assert_eq!(config.web_url("/tachyon"), "http://127.0.0.1:8080/tachyon");
+        assert_eq!(config.hosted, HostedConfig::default());
+        assert_eq!(config.homeserver_url, None);
+        assert_eq!(config.logging, LoggingConfig::default());
+        assert_eq!(config.features, FeatureConfig::default());
+    }
+
+    #[test]
+    fn overrides_win_over_ini() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1864
+http_port = 8080
+"#;
+
+        let env = vec![
+            ("TACHYON_SERVER_HTTP_PORT".to_string(), "9090".to_string()),
+            ("TACHYON_TACHYON_LOGS_LEVEL".to_string(), "debug".to_string()),
+            ("TACHYON_CONFIG".to_string(), "/etc/tachyon/config.ini".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+        let mut overrides = ConfigOverride::from_env(env).expect("env overrides to be valid");
+        overrides.push(ConfigOverride::parse("server.http_port=9191").unwrap());
+
+        let config = TachyonConfig::load(config, &overrides).expect("config to be valid");
+        assert_eq!(config.notification_port, 1863);
+        assert_eq!(config.http_port, 9191);
+        assert_eq!(config.logging.level, LevelFilter::Debug);
+    }
+
+    #[test]
+    fn unknown_overrides_are_rejected() {
+        assert_eq!(ConfigOverride::from_env(vec![("TACHYON_SERVER_HTTP_PROT".to_string(), "9090".to_string())]).unwrap(), vec![]);
+        assert!(ConfigOverride::parse("server.http_port").is_err());
+        assert!(ConfigOverride::parse("zathras_logs.enabled=true").is_err());
+        assert_eq!(ConfigOverride::parse("Matrix.Sync_Mode = classic").unwrap(), ConfigOverride { section: "matrix".into(), key: "sync_mode".into(), value: "classic".into() });
+    }
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1863
+http_port = 70000
+public_host = 0.0.0.0
+"#;
+
+        let error = TachyonConfig::from_str(config).unwrap_err().to_string();
+        assert!(error.contains("notification_port and switchboard_port are both set to 1863"));
+        assert!(error.contains("http_port must be between 1 and 65535"));
+        assert!(error.contains("public_host can't be 0.0.0.0"));
+    }
+
+    #[test]
+    fn deserialize_config_rejects_bad_log_settings() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1864
+http_port = 8080
+
+[tachyon_logs]
+targets = tachyon
+"#;
+
+        assert!(TachyonConfig::from_str(config).is_err());
+        assert!(TachyonConfig::load(config, &[ConfigOverride::parse("tachyon_logs.targets=tachyon=loud").unwrap()]).is_err());
+        assert!(TachyonConfig::load(config, &[ConfigOverride::parse("tachyon_logs.targets=").unwrap(), ConfigOverride::parse("matrix.homeserver_url=ftp://matrix.org").unwrap()]).is_err());
+    }
+
+    #[test]
+    fn deserialize_config_rejects_cert_without_key() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1864
+http_port = 8080
+tls_cert = /etc/tachyon/cert.pem
+"#;
+
+        assert!(TachyonConfig::from_str(config).is_err());
+    }
+
+    #[test]
+    fn hosted_limits_only_apply_in_hosted_mode() {
+        let hosted = HostedConfig { enabled: false, max_clients: 10, max_switchboards_per_client: 10, max_p2p_sessions_per_client: 10 };
+        assert_eq!(hosted.client_limit(), None);
+        assert_eq!(hosted.switchboard_limit(), None);
+        assert_eq!(hosted.p2p_session_limit(), None);
+    }
+
+    #[test]
+    fn deserialize_config_rejects_unreachable_public_host() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1864
+http_port = 8080
+public_host = 0.0.0.0
+"#;
+
+        assert!(TachyonConfig::from_str(config).is_err());
+    }
+
+    #[test]
+    fn deserialize_config_rejects_zero_sweep_interval() {
+        let config = r#"[server]
+notification_port = 1863
+switchboard_port = 1864
+http_port = 8080
+
+[sweeper]
+interval_secs = 0
+"#;
+
+        assert!(TachyonConfig::from_str(config).is_err());
     }
 
     #[test]
@@ -108,8 +800,31 @@ enabled = true
             notification_port: 1863,
             switchboard_port: 1864,
             http_port: 8080,
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            public_host: Ipv4Addr::new(10, 0, 0, 2),
+            public_web_host: Some("tachyon.example.org".to_string()),
+            tls: None,
+            hosted: HostedConfig { enabled: true, max_clients: 20, max_switchboards_per_client: 30, max_p2p_sessions_per_client: 4 },
             strict_ssl: false,
-            logs_enabled: true,
+            sync_mode: SyncMode::SlidingSync,
+            homeserver_url: Some(Url::parse("https://matrix.example.org").unwrap()),
+            logging: LoggingConfig { enabled: true, level: LevelFilter::Info, targets: vec![("tachyon".to_string(), LevelFilter::Trace)] },
+            features: FeatureConfig { webcam: false, file_transfers: true },
+            image_strategy: ImageStrategy::FileTransfer,
+            inline_image_max_size: 2048,
+            voice_clip_spill_to_disk: true,
+            voice_clip_ttl: Duration::from_secs(300),
+            voice_clip_store_size: 500_000,
+            custom_emoticon_ttl: Duration::from_secs(900),
+            custom_emoticon_store_size: 250_000,
+            send_typing_notifications: false,
+            sweeper: SweeperConfig {
+                interval: Duration::from_secs(15),
+                pending_ticket_ttl: Duration::from_secs(60),
+                alert_ttl: Duration::from_secs(90),
+                verification_request_ttl: Duration::from_secs(120),
+                p2p_session_ttl: Duration::from_secs(600),
+            },
         };
 
         let ser = config.to_string();
@@ -120,8 +835,42 @@ enabled = true
         assert!(ser.contains("notification_port=1863"));
         assert!(ser.contains("switchboard_port=1864"));
         assert!(ser.contains("http_port=8080"));
+        assert!(ser.contains("bind_address=0.0.0.0"));
+        assert!(ser.contains("public_host=10.0.0.2"));
+        assert!(ser.contains("public_web_host=tachyon.example.org"));
+        assert!(!ser.contains("tls_cert"));
+        assert!(ser.contains("[hosted]"));
+        assert!(ser.contains("max_clients=20"));
+        assert!(ser.contains("max_switchboards_per_client=30"));
+        assert!(ser.contains("max_p2p_sessions_per_client=4"));
         assert!(ser.contains("strict_ssl=false"));
+        assert!(ser.contains("sync_mode=sliding_sync"));
         assert!(ser.contains("enabled=true"));
+        assert!(ser.contains("homeserver_url=https://matrix.example.org/"));
+        assert!(ser.contains("level=info"));
+        assert!(ser.contains("targets=tachyon=trace"));
+        assert!(ser.contains("[features]"));
+        assert!(ser.contains("webcam=false"));
+        assert!(ser.contains("send_typing_notifications=false"));
+        assert!(ser.contains("[bridge]"));
+        assert!(ser.contains("image_strategy=file_transfer"));
+        assert!(ser.contains("inline_image_max_size=2048"));
+        assert!(ser.contains("voice_clip_spill_to_disk=true"));
+        assert!(ser.contains("voice_clip_ttl_secs=300"));
+        assert!(ser.contains("voice_clip_store_size=500000"));
+        assert!(ser.contains("custom_emoticon_ttl_secs=900"));
+        assert!(ser.contains("custom_emoticon_store_size=250000"));
+        assert!(ser.contains("[sweeper]"));
+        assert!(ser.contains("interval_secs=15"));
+        assert!(ser.contains("pending_ticket_ttl_secs=60"));
+        assert!(ser.contains("alert_ttl_secs=90"));
+        assert!(ser.contains("verification_request_ttl_secs=120"));
+        assert!(ser.contains("p2p_session_ttl_secs=600"));
+
+        let round_trip = TachyonConfig::from_str(&ser).expect("serialized config to be valid");
+        assert_eq!(round_trip.logging, config.logging);
+        assert_eq!(round_trip.features, config.features);
+        assert_eq!(round_trip.homeserver_url, config.homeserver_url);
 
     }
 }
\ No newline at end of file
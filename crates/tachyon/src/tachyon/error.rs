@@ -1,5 +1,9 @@
 use matrix_sdk::{ClientBuildError, HttpError};
 use matrix_sdk::event_cache::EventCacheError;
+use matrix_sdk::ruma::api::client::error::ErrorKind;
+use msnp::msnp::error::{CommandError, PayloadError};
+use msnp::shared::command::err::MsnpError;
+use msnp::shared::errors::IdentifierError;
 use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum TachyonError {
@@ -17,7 +21,108 @@ pub enum TachyonError {
     #[error(transparent)]
     Any(#[from] anyhow::Error),
     #[error("Could not send message to Notification Client")]
-    NotificationChannelError
+    NotificationChannelError,
+    #[error("{} is not in the contact list", .email)]
+    PrincipalNotOnList { email: String },
+    #[error("Credentials were refused")]
+    AuthenticationFailed,
+    #[error("Command is not allowed before signing in")]
+    NotLoggedIn,
+    #[error("Server is full, {} clients are already signed in", .max)]
+    TooManyClients { max: usize },
+    #[error("Session limit of {} reached", .max)]
+    TooManySessions { max: usize }
+}
+
+impl TachyonError {
+    pub fn to_msnp_error(&self) -> MsnpError {
+        self.to_msnp_error_for_command(None)
+    }
+
+    //The same Matrix error means different things depending on the command that failed.
+    pub fn to_msnp_error_for_command(&self, operand: Option<&str>) -> MsnpError {
+        match self {
+            TachyonError::MatrixConversion(MatrixConversionError::EmailToMatrixId { .. }) => MsnpError::InvalidPrincipal,
+            TachyonError::MatrixError(e) => matrix_error_kind_to_msnp(e.client_api_error_kind(), operand),
+            TachyonError::HttpError(e) => matrix_error_kind_to_msnp(e.client_api_error_kind(), operand),
+            TachyonError::PrincipalNotOnList { .. } => MsnpError::PrincipalNotOnList,
+            TachyonError::AuthenticationFailed => MsnpError::ServerIsBusy2,
+            TachyonError::NotLoggedIn => MsnpError::NotLoggedIn,
+            TachyonError::TooManyClients { .. } => MsnpError::NotAcceptingNewPrincipals,
+            TachyonError::TooManySessions { .. } => MsnpError::TooManySessions,
+            TachyonError::Any(e) => to_msnp_error_for_command(e, operand),
+            _ => MsnpError::InternalServerError
+        }
+    }
+
+    pub fn is_authentication_failure(&self) -> bool {
+        match self {
+            TachyonError::AuthenticationFailed => true,
+            TachyonError::MatrixError(e) => e.client_api_error_kind().is_some_and(is_authentication_error_kind),
+            TachyonError::HttpError(e) => e.client_api_error_kind().is_some_and(is_authentication_error_kind),
+            TachyonError::Any(e) => e.downcast_ref::<TachyonError>().is_some_and(TachyonError::is_authentication_failure),
+            _ => false
+        }
+    }
+}
+
+//Handlers bubble up anyhow errors, this finds the closest code the client knows how to display.
+pub fn to_msnp_error(error: &anyhow::Error) -> MsnpError {
+    to_msnp_error_for_command(error, None)
+}
+
+pub fn to_msnp_error_for_command(error: &anyhow::Error, operand: Option<&str>) -> MsnpError {
+    if let Some(e) = error.downcast_ref::<TachyonError>() {
+        return e.to_msnp_error_for_command(operand);
+    }
+
+    if let Some(e) = error.downcast_ref::<CommandError>() {
+        return command_error_to_msnp(e);
+    }
+
+    if error.downcast_ref::<PayloadError>().is_some() {
+        return MsnpError::InvalidParameter;
+    }
+
+    if error.downcast_ref::<IdentifierError>().is_some() {
+        return MsnpError::InvalidPrincipal;
+    }
+
+    if let Some(e) = error.downcast_ref::<matrix_sdk::Error>() {
+        return matrix_error_kind_to_msnp(e.client_api_error_kind(), operand);
+    }
+
+    if let Some(e) = error.downcast_ref::<HttpError>() {
+        return matrix_error_kind_to_msnp(e.client_api_error_kind(), operand);
+    }
+
+    MsnpError::InternalServerError
+}
+
+pub fn command_error_to_msnp(error: &CommandError) -> MsnpError {
+    match error {
+        CommandError::UnsupportedCommand { .. } | CommandError::MalformedPayloadCommand { .. } => MsnpError::InvalidSyntax,
+        CommandError::WrongArgumentCount { .. } | CommandError::MissingArgument(..) => MsnpError::RequiredFieldMissing,
+        CommandError::IdentifierError(_) => MsnpError::InvalidPrincipal,
+        CommandError::ArgumentParseError { .. } | CommandError::ParseIntError(_) | CommandError::ParseError(_) | CommandError::PayloadError(_) => MsnpError::InvalidParameter,
+        CommandError::Anyhow(e) => to_msnp_error(e),
+        _ => MsnpError::InternalServerError
+    }
+}
+
+fn matrix_error_kind_to_msnp(error_kind: Option<&ErrorKind>, operand: Option<&str>) -> MsnpError {
+    match (error_kind, operand) {
+        (Some(ErrorKind::LimitExceeded { .. }), _) => MsnpError::CallingTooRapidly,
+        //911 is the code WLM shows as "sign-in name or password incorrect", it only makes sense while signing in
+        (Some(error_kind), Some("USR")) if is_authentication_error_kind(error_kind) => MsnpError::ServerIsBusy2,
+        //Contact list commands point at a contact whose room or profile is gone
+        (Some(ErrorKind::NotFound), Some("ADL" | "RML" | "FQY" | "CAL")) => MsnpError::PrincipalNotOnList,
+        _ => MsnpError::InternalServerError
+    }
+}
+
+fn is_authentication_error_kind(error_kind: &ErrorKind) -> bool {
+    matches!(error_kind, ErrorKind::UnknownToken { .. } | ErrorKind::Forbidden { .. } | ErrorKind::MissingToken)
 }
 
 #[derive(Error, Debug)]
@@ -27,4 +132,68 @@ pub enum MatrixConversionError {
     #[error("Could not generate Device Id")]
     DeviceIdGeneration { source: anyhow::Error}
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::anyhow;
+    use msnp::msnp::error::CommandError;
+    use msnp::shared::command::err::MsnpError;
+    use msnp::shared::models::email_address::EmailAddress;
+
+    use matrix_sdk::ruma::api::client::error::ErrorKind;
+
+    use crate::tachyon::error::{matrix_error_kind_to_msnp, to_msnp_error, TachyonError};
+
+    #[test]
+    fn tachyon_error_to_msnp_error() {
+        let error = anyhow::Error::from(TachyonError::PrincipalNotOnList { email: "aeon@test.com".into() });
+        assert_eq!(MsnpError::PrincipalNotOnList, to_msnp_error(&error));
+    }
+
+    #[test]
+    fn auth_errors_to_msnp_error() {
+        assert_eq!(MsnpError::ServerIsBusy2, to_msnp_error(&anyhow::Error::from(TachyonError::AuthenticationFailed)));
+        assert_eq!(MsnpError::NotLoggedIn, to_msnp_error(&anyhow::Error::from(TachyonError::NotLoggedIn)));
+        assert!(TachyonError::AuthenticationFailed.is_authentication_failure());
+        assert!(!TachyonError::NotLoggedIn.is_authentication_failure());
+    }
+
+    #[test]
+    fn hosted_limit_errors_to_msnp_error() {
+        assert_eq!(MsnpError::NotAcceptingNewPrincipals, to_msnp_error(&anyhow::Error::from(TachyonError::TooManyClients { max: 10 })));
+        assert_eq!(MsnpError::TooManySessions, to_msnp_error(&anyhow::Error::from(TachyonError::TooManySessions { max: 4 })));
+    }
+
+    #[test]
+    fn command_error_to_msnp_error() {
+        let error = anyhow::Error::from(CommandError::MissingArgument("CAL 1".into(), "email_addr".into(), 2));
+        assert_eq!(MsnpError::RequiredFieldMissing, to_msnp_error(&error));
+
+        let identifier_error = EmailAddress::from_str("not an email").unwrap_err();
+        assert_eq!(MsnpError::InvalidPrincipal, to_msnp_error(&anyhow::Error::from(identifier_error)));
+    }
+
+    #[test]
+    fn matrix_errors_depend_on_the_command() {
+        let not_found = ErrorKind::NotFound;
+        assert_eq!(MsnpError::PrincipalNotOnList, matrix_error_kind_to_msnp(Some(&not_found), Some("ADL")));
+        assert_eq!(MsnpError::PrincipalNotOnList, matrix_error_kind_to_msnp(Some(&not_found), Some("CAL")));
+        assert_eq!(MsnpError::InternalServerError, matrix_error_kind_to_msnp(Some(&not_found), Some("UUX")));
+        assert_eq!(MsnpError::InternalServerError, matrix_error_kind_to_msnp(Some(&not_found), None));
+
+        let forbidden = ErrorKind::forbidden();
+        assert_eq!(MsnpError::ServerIsBusy2, matrix_error_kind_to_msnp(Some(&forbidden), Some("USR")));
+        assert_eq!(MsnpError::InternalServerError, matrix_error_kind_to_msnp(Some(&forbidden), Some("MSG")));
+        assert_eq!(MsnpError::InternalServerError, matrix_error_kind_to_msnp(Some(&forbidden), Some("XFR")));
+
+        assert_eq!(MsnpError::InternalServerError, matrix_error_kind_to_msnp(None, Some("ADL")));
+    }
+
+    #[test]
+    fn unknown_error_to_msnp_error() {
+        assert_eq!(MsnpError::InternalServerError, to_msnp_error(&anyhow!("Room should be here by now")));
+    }
+}
@@ -9,18 +9,30 @@ use matrix_sdk::Client;
 use msnp::shared::models::email_address::EmailAddress;
 use msnp::shared::models::ticket_token::TicketToken;
 use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use std::time::{Duration, Instant};
 use crate::matrix::services::login::MatrixLoginService;
 use crate::tachyon::config::tachyon_config::TachyonConfig;
+use crate::tachyon::error::TachyonError;
 use crate::tachyon::mappers::user_id::MatrixIdCompatible;
 
 pub struct GlobalStateInner {
     config: TachyonConfig,
     tachyon_clients: TachyonClientRepository,
     token_validator: SecretEncryptor,
-    pending_ticket: DashMap<String, TicketToken>,
-    pending_alerts: DashMap<i32, AlertReceiver>,
+    pending_ticket: DashMap<String, PendingTicket>,
+    pending_alerts: DashMap<i32, (AlertReceiver, Instant)>,
     pending_verification_requests: VerificationRequestRepository,
-    matrix_login_service: Box<dyn MatrixLoginService>
+    matrix_login_service: Box<dyn MatrixLoginService>,
+    //Only set when hosted mode limits the number of clients.
+    client_slots: Option<Arc<Semaphore>>,
+}
+
+//In hosted mode the web login shows a one time code, RST2 only hands the ticket to whoever sends it as the password.
+struct PendingTicket {
+    ticket: TicketToken,
+    login_code: Option<String>,
+    stored_at: Instant,
 }
 
 #[derive(Clone)]
@@ -30,12 +42,14 @@ pub struct GlobalState {
 
 pub struct ClientDropGuard {
     global_state: GlobalState,
-    key: String
+    key: String,
+    //Released with the guard, which frees the slot for the next client.
+    _client_slot: Option<OwnedSemaphorePermit>,
 }
 
 impl ClientDropGuard {
-    pub fn new(global_state: GlobalState, key: String) -> Self {
-        Self { global_state, key }
+    pub fn new(global_state: GlobalState, key: String, client_slot: Option<OwnedSemaphorePermit>) -> Self {
+        Self { global_state, key, _client_slot: client_slot }
     }
 }
 
@@ -44,7 +58,7 @@ impl Drop for ClientDropGuard {
         let tachyon_client = self.global_state.tachyon_clients().remove(&self.key);
         if let Some(client) = tachyon_client {
             client.shutdown();
-            self.global_state.take_pending_ticket(client.own_user().get_email_address());
+            self.global_state.remove_pending_ticket(client.own_user().get_email_address());
 
             //Todo change this so we use a neutral key
             let own_user_id = client.own_user().get_email_address().to_owned_user_id();
@@ -58,8 +72,11 @@ impl Drop for ClientDropGuard {
 impl GlobalState {
 
     pub fn new(config: TachyonConfig, token_validator: SecretEncryptor, matrix_login_service: Box<dyn MatrixLoginService>) -> Self {
+        let client_slots = config.hosted.client_limit().map(|max| Arc::new(Semaphore::new(max)));
+
         Self {
             inner: Arc::new(GlobalStateInner {
+                client_slots,
                 config,
                 tachyon_clients: Default::default(),
                 token_validator,
@@ -75,38 +92,76 @@ impl GlobalState {
         &self.inner.config
     }
 
-    //FIXME: remove this and fix everywhere it's called to get the client using the key.
-    pub fn get_single_client(&self) -> Option<TachyonClient> {
-        self.tachyon_clients().single()
-    }
-
     pub fn tachyon_clients(&self) -> &TachyonClientRepository {
         &self.inner.tachyon_clients
     }
 
-    pub fn insert_clients(&self, key: String, tachyon_client: TachyonClient) -> ClientDropGuard {
+    //Taken before the Matrix login so concurrent sign ins can't go past the limit, released when the guard drops.
+    pub fn reserve_client_slot(&self) -> Result<Option<OwnedSemaphorePermit>, TachyonError> {
+        let Some(client_slots) = &self.inner.client_slots else {
+            return Ok(None);
+        };
+
+        client_slots.clone().try_acquire_owned()
+            .map(Some)
+            .map_err(|_| TachyonError::TooManyClients { max: self.inner.config.hosted.max_clients })
+    }
+
+    pub fn insert_clients(&self, key: String, tachyon_client: TachyonClient, client_slot: Option<OwnedSemaphorePermit>) -> ClientDropGuard {
         self.inner.tachyon_clients.insert(key.clone(), tachyon_client);
-        ClientDropGuard::new(self.clone(), key)
+        ClientDropGuard::new(self.clone(), key, client_slot)
     }
 
     pub fn get_clients(&self, key: &str) -> Option<TachyonClient> {
         self.inner.tachyon_clients.get(key)
     }
 
-    pub fn store_pending_ticket(&self, key: EmailAddress,  ticket: TicketToken) {
-        self.inner.pending_ticket.insert(key.to_string(), ticket);
+    pub fn store_pending_ticket(&self, key: EmailAddress, ticket: TicketToken, login_code: Option<String>) {
+        self.inner.pending_ticket.insert(key.to_string(), PendingTicket { ticket, login_code, stored_at: Instant::now() });
+    }
+
+    pub fn has_pending_ticket(&self, key: &EmailAddress) -> bool {
+        self.inner.pending_ticket.contains_key(key.as_str())
+    }
+
+    //A wrong password leaves the ticket in place, the real owner can still claim it.
+    pub fn take_pending_ticket(&self, key: &EmailAddress, password: &str) -> Option<TicketToken> {
+        self.inner.pending_ticket
+            .remove_if(key.as_str(), |_, pending| pending.login_code.as_deref().is_none_or(|code| constant_time_eq(code.as_bytes(), password.as_bytes())))
+            .map(|(_, pending)| pending.ticket)
     }
 
-    pub fn take_pending_ticket(&self, key: &EmailAddress) -> Option<TicketToken> {
-        self.inner.pending_ticket.remove(key.as_str()).map(|(_, ticket)| ticket)
+    pub fn remove_pending_ticket(&self, key: &EmailAddress) {
+        self.inner.pending_ticket.remove(key.as_str());
+    }
+
+    pub fn remove_expired_tickets(&self, now: Instant, ttl: Duration) -> usize {
+        let before = self.inner.pending_ticket.len();
+        self.inner.pending_ticket.retain(|_, pending| now < pending.stored_at + ttl);
+        before - self.inner.pending_ticket.len()
+    }
+
+    pub fn pending_ticket_count(&self) -> usize {
+        self.inner.pending_ticket.len()
     }
 
     pub fn store_pending_alert(&self, key: i32, receiver: AlertReceiver) {
-        self.inner.pending_alerts.insert(key, receiver);
+        self.inner.pending_alerts.insert(key, (receiver, Instant::now()));
     }
 
     pub fn take_pending_alert(&self, key: &i32) -> Option<AlertReceiver> {
-        self.inner.pending_alerts.remove(key).map(|(_, recv)| recv)
+        self.inner.pending_alerts.remove(key).map(|(_, (recv, _))| recv)
+    }
+
+    //The polling page puts the receiver back after each poll, only abandoned ones get old.
+    pub fn remove_expired_alerts(&self, now: Instant, ttl: Duration) -> usize {
+        let before = self.inner.pending_alerts.len();
+        self.inner.pending_alerts.retain(|_, (_, stored_at)| now < *stored_at + ttl);
+        before - self.inner.pending_alerts.len()
+    }
+
+    pub fn pending_alert_count(&self) -> usize {
+        self.inner.pending_alerts.len()
     }
 
     pub fn pending_verification_requests(&self) -> &VerificationRequestRepository {
@@ -121,4 +176,108 @@ impl GlobalState {
         &self.inner.token_validator
     }
 
-}
\ No newline at end of file
+}
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).fold(0u8, |diff, (l, r)| diff | (l ^ r)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use matrix_sdk::ruma::{device_id, owned_event_id, room_id, user_id, UserId};
+    use matrix_sdk::test_utils::mocks::MatrixMockServer;
+    use msnp::msnp::notification::models::msnp_version::MsnpVersion;
+    use msnp::shared::models::email_address::EmailAddress;
+    use msnp::shared::models::msn_user::MsnUser;
+    use msnp::shared::models::ticket_token::TicketToken;
+
+    use crate::matrix::services::login::MatrixLoginServiceImpl;
+    use crate::tachyon::client::tachyon_client::TachyonClient;
+    use crate::tachyon::config::secret_encryptor::SecretEncryptor;
+    use crate::tachyon::config::tachyon_config::{HostedConfig, TachyonConfig};
+    use crate::tachyon::error::TachyonError;
+    use crate::tachyon::global_state::{ClientDropGuard, GlobalState};
+
+    const TEST_SECRET: [u8; 32] = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32];
+
+    async fn login(state: GlobalState, server: &MatrixMockServer, user_id: &UserId, email: &str, ticket: &str) -> ClientDropGuard {
+        let matrix_client = server.client_builder()
+            .logged_in_with_token(format!("{}_access_token", ticket), user_id.to_owned(), device_id!("TACHYON").to_owned())
+            .build()
+            .await;
+
+        let (notification_snd, _notification_rcv) = tokio::sync::mpsc::channel(10);
+        let (shutdown_snd, shutdown_rcv) = tokio::sync::broadcast::channel(1);
+
+        let tachyon_client = TachyonClient::new(
+            matrix_client,
+            state.get_config().clone(),
+            MsnpVersion::MSNP18,
+            MsnUser::with_email_addr(EmailAddress::from_str(email).unwrap()),
+            TicketToken(ticket.to_string()),
+            notification_snd,
+            shutdown_snd,
+            shutdown_rcv,
+        );
+
+        state.insert_clients(ticket.to_string(), tachyon_client, None)
+    }
+
+    #[tokio::test]
+    async fn two_accounts_logged_in_concurrently() {
+        let server = MatrixMockServer::new().await;
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(MatrixLoginServiceImpl::new()));
+
+        let (guard_a, guard_b) = tokio::join!(
+            login(state.clone(), &server, user_id!("@alice:localhost"), "alice@localhost", "ticket_a"),
+            login(state.clone(), &server, user_id!("@bob:localhost"), "bob@localhost", "ticket_b"),
+        );
+
+        let client_a = state.get_clients("ticket_a").unwrap();
+        let client_b = state.get_clients("ticket_b").unwrap();
+
+        assert_eq!(client_a.own_user().get_email_address().as_str(), "alice@localhost");
+        assert_eq!(client_b.own_user().get_email_address().as_str(), "bob@localhost");
+        assert_eq!(client_a.matrix_client().user_id(), Some(user_id!("@alice:localhost")));
+        assert_eq!(client_b.matrix_client().user_id(), Some(user_id!("@bob:localhost")));
+
+        //An event Alice sent in a room she shares with Bob is still a new message for Bob
+        let room_id = room_id!("!shared:localhost");
+        let event_id = owned_event_id!("$sent_by_alice:localhost");
+        assert!(!client_b.message_dedup().is_event_deduped(room_id, &event_id).await);
+
+        drop(guard_a);
+        assert!(state.get_clients("ticket_a").is_none());
+        assert!(state.get_clients("ticket_b").is_some());
+
+        drop(guard_b);
+        assert!(state.get_clients("ticket_b").is_none());
+    }
+
+    #[tokio::test]
+    async fn hosted_ticket_is_only_taken_with_the_login_code() {
+        let state = GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(MatrixLoginServiceImpl::new()));
+        let email = EmailAddress::from_str("aeon@test.com").unwrap();
+        state.store_pending_ticket(email.clone(), TicketToken("ticket".to_string()), Some("K7QW2M9XHR4P".to_string()));
+
+        assert!(state.take_pending_ticket(&email, "tachyon").is_none());
+        assert!(state.has_pending_ticket(&email));
+        assert_eq!(state.take_pending_ticket(&email, "K7QW2M9XHR4P").map(|ticket| ticket.0), Some("ticket".to_string()));
+        assert!(!state.has_pending_ticket(&email));
+    }
+
+    #[tokio::test]
+    async fn client_slot_is_released_with_its_permit() {
+        let mut config = TachyonConfig::default();
+        config.hosted = HostedConfig { enabled: true, max_clients: 1, ..Default::default() };
+        let state = GlobalState::new(config, SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(MatrixLoginServiceImpl::new()));
+
+        let first = state.reserve_client_slot().unwrap();
+        assert!(first.is_some());
+        assert!(matches!(state.reserve_client_slot(), Err(TachyonError::TooManyClients { max: 1 })));
+
+        drop(first);
+        assert!(state.reserve_client_slot().unwrap().is_some());
+    }
+}
@@ -6,4 +6,5 @@ pub mod alert;
 pub mod mappers;
 pub mod switchboard_service;
 pub mod client;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod sweeper;
@@ -0,0 +1,244 @@
+use std::fmt::{Display, Formatter};
+use std::time::Instant;
+
+use anyhow::anyhow;
+use log::{debug, info, warn};
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+
+use crate::tachyon::alert::AlertNotify;
+use crate::tachyon::client::tachyon_client::TachyonClient;
+use crate::tachyon::config::tachyon_config::SweeperConfig;
+use crate::tachyon::global_state::GlobalState;
+
+//Entry counts per store, used both for what a sweep removed and for what is still held.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SweepCounts {
+    pub pending_tickets: usize,
+    pub pending_alerts: usize,
+    pub verification_requests: usize,
+    pub alerts: usize,
+    pub p2p_sessions: usize,
+    pub chunked_uploads: usize,
+    pub voice_clips: usize,
+    pub custom_emoticons: usize,
+}
+
+impl SweepCounts {
+    pub fn is_empty(&self) -> bool {
+        *self == SweepCounts::default()
+    }
+
+    fn add_client(&mut self, other: SweepCounts) {
+        self.alerts += other.alerts;
+        self.p2p_sessions += other.p2p_sessions;
+        self.chunked_uploads += other.chunked_uploads;
+        self.voice_clips += other.voice_clips;
+        self.custom_emoticons += other.custom_emoticons;
+    }
+}
+
+impl Display for SweepCounts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "pending_tickets={} pending_alerts={} verification_requests={} alerts={} p2p_sessions={} chunked_uploads={} voice_clips={} custom_emoticons={}",
+               self.pending_tickets, self.pending_alerts, self.verification_requests, self.alerts, self.p2p_sessions, self.chunked_uploads, self.voice_clips, self.custom_emoticons)
+    }
+}
+
+impl GlobalState {
+    pub fn held_counts(&self) -> SweepCounts {
+        let mut counts = SweepCounts {
+            pending_tickets: self.pending_ticket_count(),
+            pending_alerts: self.pending_alert_count(),
+            verification_requests: self.pending_verification_requests().count(),
+            ..Default::default()
+        };
+
+        for client in self.tachyon_clients().all() {
+            counts.add_client(client.held_counts());
+        }
+
+        counts
+    }
+
+    pub fn sweep(&self, now: Instant) -> SweepCounts {
+        let config = &self.get_config().sweeper;
+
+        let mut removed = SweepCounts {
+            pending_tickets: self.remove_expired_tickets(now, config.pending_ticket_ttl),
+            pending_alerts: self.remove_expired_alerts(now, config.alert_ttl),
+            verification_requests: self.pending_verification_requests().remove_expired(now, config.verification_request_ttl),
+            ..Default::default()
+        };
+
+        for client in self.tachyon_clients().all() {
+            removed.add_client(client.sweep(now, config));
+        }
+
+        removed
+    }
+}
+
+impl TachyonClient {
+    fn held_counts(&self) -> SweepCounts {
+        SweepCounts {
+            alerts: self.inner.alerts.len(),
+            p2p_sessions: self.inner.sessions.len(),
+            chunked_uploads: self.inner.chunked_uploads.len(),
+            voice_clips: self.inner.voice_clips.len(),
+            custom_emoticons: self.inner.custom_emoticons.len(),
+            ..Default::default()
+        }
+    }
+
+    fn sweep(&self, now: Instant, config: &SweeperConfig) -> SweepCounts {
+        let expired_alert_ids: Vec<i32> = self.inner.alerts.iter()
+            .filter(|entry| entry.value().is_expired(now, config.alert_ttl))
+            .map(|entry| *entry.key())
+            .collect();
+
+        let mut removed = SweepCounts::default();
+        for alert_id in expired_alert_ids {
+            //Whoever answers the alert in the meantime takes it out of the map first.
+            let Some((_, alert)) = self.inner.alerts.remove(&alert_id) else {
+                continue;
+            };
+
+            removed.alerts += 1;
+            if let Err(e) = alert.notify_failure(anyhow!("Alert timed out")) {
+                debug!("Nobody was waiting on expired alert {}: {}", alert_id, e);
+            }
+        }
+
+        let sessions_before = self.inner.sessions.len();
+        self.inner.sessions.retain(|_, session| !session.is_expired(now, config.p2p_session_ttl));
+        removed.p2p_sessions = sessions_before - self.inner.sessions.len();
+
+        //Uploads always belong to a session, once it is gone the chunks can't be completed.
+        let uploads_before = self.inner.chunked_uploads.len();
+        self.inner.chunked_uploads.retain(|session_id, _| self.inner.sessions.contains_key(session_id));
+        removed.chunked_uploads = uploads_before - self.inner.chunked_uploads.len();
+
+        removed.custom_emoticons = self.inner.custom_emoticons.evict(now);
+
+        //Spilling hits the disk, it's done off the sweep.
+        let evicted_clips = self.inner.voice_clips.evict(now);
+        removed.voice_clips = evicted_clips.len();
+        if !evicted_clips.is_empty() {
+            let client = self.clone();
+            self.spawn("voice clip spill", async move {
+                client.inner.voice_clips.spill_all(evicted_clips).await;
+            });
+        }
+
+        removed
+    }
+}
+
+pub async fn run_sweeper(global_state: GlobalState, mut kill_signal: broadcast::Receiver<()>) {
+    let mut interval = tokio::time::interval(global_state.get_config().sweeper.interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let removed = global_state.sweep(Instant::now());
+                if !removed.is_empty() {
+                    info!("Sweeper removed expired entries: {}", removed);
+                }
+                debug!("Sweeper held entries: {}", global_state.held_counts());
+            }
+            _ = kill_signal.recv() => {
+                warn!("Sweeper received kill signal");
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::{Duration, Instant};
+
+    use matrix_sdk::ruma::{device_id, user_id};
+    use matrix_sdk::test_utils::mocks::MatrixMockServer;
+    use msnp::p2p::v2::raw_p2p_payload::RawP2PPayload;
+    use msnp::msnp::notification::models::msnp_version::MsnpVersion;
+    use msnp::shared::models::email_address::EmailAddress;
+    use msnp::shared::models::msn_user::MsnUser;
+    use msnp::shared::models::ticket_token::TicketToken;
+
+    use crate::matrix::services::login::MatrixLoginServiceImpl;
+    use crate::tachyon::alert::Alert;
+    use crate::tachyon::client::tachyon_client::TachyonClient;
+    use crate::tachyon::config::secret_encryptor::SecretEncryptor;
+    use crate::tachyon::global_state::GlobalState;
+    use crate::tachyon::sweeper::SweepCounts;
+
+    const TEST_SECRET: [u8; 32] = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,32];
+
+    fn global_state() -> GlobalState {
+        GlobalState::new(Default::default(), SecretEncryptor::new(&TEST_SECRET).unwrap(), Box::new(MatrixLoginServiceImpl::new()))
+    }
+
+    #[tokio::test]
+    async fn pending_entries_expire_after_ttl() {
+        let state = global_state();
+        let ticket_ttl = state.get_config().sweeper.pending_ticket_ttl;
+        let alert_ttl = state.get_config().sweeper.alert_ttl;
+
+        state.store_pending_ticket(EmailAddress::from_str("aeon@test.com").unwrap(), TicketToken("ticket".to_string()), None);
+        let (_alert, receiver) = Alert::new_weblogin();
+        state.store_pending_alert(1, receiver);
+
+        let now = Instant::now();
+        assert!(state.sweep(now).is_empty());
+        assert_eq!(state.held_counts().pending_tickets, 1);
+        assert_eq!(state.held_counts().pending_alerts, 1);
+
+        let removed = state.sweep(now + ticket_ttl.max(alert_ttl));
+        assert_eq!(removed, SweepCounts { pending_tickets: 1, pending_alerts: 1, ..Default::default() });
+        assert!(state.held_counts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn expired_client_alert_notifies_receiver() {
+        let server = MatrixMockServer::new().await;
+        let state = global_state();
+
+        let matrix_client = server.client_builder()
+            .logged_in_with_token("sweeper_access_token".to_string(), user_id!("@sweeper:localhost").to_owned(), device_id!("TACHYON").to_owned())
+            .build()
+            .await;
+
+        let (notification_snd, _notification_rcv) = tokio::sync::mpsc::channel(10);
+        let (shutdown_snd, shutdown_rcv) = tokio::sync::broadcast::channel(1);
+        let tachyon_client = TachyonClient::new(
+            matrix_client,
+            state.get_config().clone(),
+            MsnpVersion::MSNP18,
+            MsnUser::with_email_addr(EmailAddress::from_str("sweeper@localhost").unwrap()),
+            TicketToken("sweeper_ticket".to_string()),
+            notification_snd,
+            shutdown_snd,
+            shutdown_rcv,
+        );
+        let _guard = state.insert_clients("sweeper_ticket".to_string(), tachyon_client.clone(), None);
+
+        let (alert, receiver) = Alert::new_confirm_device(Duration::from_secs(60));
+        tachyon_client.alerts().insert(1, alert);
+
+        //Chunks whose session was never created or already cleared
+        tachyon_client.inner.chunked_uploads.entry(42).or_default().push(RawP2PPayload::new(0, 0, 42));
+
+        let now = Instant::now();
+        let removed = state.sweep(now);
+        assert_eq!(removed, SweepCounts { chunked_uploads: 1, ..Default::default() });
+        assert_eq!(state.held_counts().alerts, 1);
+
+        let removed = state.sweep(now + Duration::from_secs(60));
+        assert_eq!(removed, SweepCounts { alerts: 1, ..Default::default() });
+        assert!(receiver.recv().await.is_err_and(|e| e.to_string() == "Alert timed out"));
+    }
+}
@@ -14,15 +14,17 @@ use tokio_retry2::RetryError;
 #[derive(Clone)]
 pub struct SwitchboardService {
     tachyon_client: TachyonClient,
+    switchboard_host: Ipv4Addr,
     switchboard_port: u32
 }
 
 impl SwitchboardService {
 
     //TODO Better locks on switchboard.
-    pub fn new(tachyon_client: TachyonClient, switchboard_port: u32) -> Self {
+    pub fn new(tachyon_client: TachyonClient, switchboard_host: Ipv4Addr, switchboard_port: u32) -> Self {
         SwitchboardService {
             tachyon_client,
+            switchboard_host,
             switchboard_port,
         }
     }
@@ -74,13 +76,14 @@ impl SwitchboardService {
         let token = self.tachyon_client.ticket_token().0;
         let room_id = room_id.to_owned();
 
+        let switchboard_host = self.switchboard_host;
         let switchboard_port = self.switchboard_port;
-        tokio::spawn(async move {
+        self.tachyon_client.spawn("switchboard ring", async move {
             let _ = notification_client_clone.send(
                 NotificationServerCommand::RNG(
                     RngServer::new(
                         SessionId::random(),
-                        IpAddress::new(Ipv4Addr::new(127, 0, 0, 1), switchboard_port),
+                        IpAddress::new(switchboard_host, switchboard_port),
                         SwitchboardToken::new(room_id.clone(), token.clone()).into(),
                         inviter_clone.get_email_address().clone(),
                         inviter_clone.compute_display_name().to_string()
@@ -9,6 +9,7 @@ use lazy_static_include::lazy_static_include_bytes;
 use reqwest::StatusCode;
 use yaserde::ser;
 use yaserde_derive::YaSerialize;
+use crate::tachyon::config::tachyon_config::TachyonConfig;
 use crate::tachyon::global_state::GlobalState;
 use crate::web::matrix_today::get_msn_today;
 
@@ -26,12 +27,12 @@ lazy_static! {
         Ads{
             tab_ads: vec![
                 TabAd {
-                    image: "http://127.0.0.1:%port%/ads/matrix-icon.png".to_string(),
+                    image: "%base_url%/ads/matrix-icon.png".to_string(),
                     name: "Matrix Today".to_string(),
                     tab_type: "matrix".to_string(),
                     tooltip: "Find out what's up in the Matrix ecosystem".to_string(),
-                    content_url: "http://127.0.0.1:%port%/ads/msn-today".to_string(),
-                    hit_url: "http://127.0.0.1:%port%/".to_string(),
+                    content_url: "%base_url%/ads/msn-today".to_string(),
+                    hit_url: "%base_url%/".to_string(),
                     site_id: 0,
                     notification_id: 0,
                 }
@@ -81,11 +82,12 @@ struct Ads {
 }
 
 impl Ads {
-    pub fn replace_port(&mut self, port: u32) {
+    pub fn replace_base_url(&mut self, config: &TachyonConfig) {
+        let base_url = config.web_url("");
         for tab_ad in &mut self.tab_ads {
-            tab_ad.image = tab_ad.image.replace("%port%", &port.to_string());
-            tab_ad.content_url = tab_ad.content_url.replace("%port%", &port.to_string());
-            tab_ad.hit_url = tab_ad.hit_url.replace("%port%", &port.to_string());
+            tab_ad.image = tab_ad.image.replace("%base_url%", &base_url);
+            tab_ad.content_url = tab_ad.content_url.replace("%base_url%", &base_url);
+            tab_ad.hit_url = tab_ad.hit_url.replace("%base_url%", &base_url);
         }
     }
 }
@@ -100,7 +102,7 @@ pub async fn get_tab_ad(Path(tab_index): Path<u32>, State(state): State<GlobalSt
                 .unwrap()
         }
         Some(mut tab_ad) => {
-            tab_ad.replace_port(state.get_config().http_port);
+            tab_ad.replace_base_url(state.get_config());
             Response::builder()
                 .status(StatusCode::OK)
                 .header(CONTENT_TYPE, "text/xml")
@@ -8,7 +8,6 @@ use matrix_sdk::{Client, Room, RoomState};
 use matrix_sdk::ruma::events::room::member::MembershipState;
 use matrix_sdk::ruma::events::room::message::RoomMessageEventContent;
 use matrix_sdk::ruma::UserId;
-use tokio::task;
 use tokio::time::sleep;
 use msnp::msnp::notification::command::command::NotificationServerCommand;
 use msnp::msnp::notification::command::not::factories::NotificationFactory;
@@ -24,8 +23,7 @@ use msnp::soap::abch::msnab_datatypes::RoleId::Email;
 use msnp::soap::abch::msnab_faults::SoapFaultResponseEnvelope;
 use msnp::soap::traits::xml::ToXml;
 use crate::matrix::extensions::direct::DirectRoom;
-use crate::matrix::extensions::message_dedup::SendWithDedup;
-use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, ToEmailAddress, ToMsnUser};
+use crate::matrix::extensions::msn_user_resolver::{FindRoomFromEmail, RoomHashCache, ToEmailAddress, ToMsnUser};
 use crate::notification::models::soap_holder::AddressBookContact;
 use crate::tachyon::mappers::uuid::ToUuid;
 use crate::tachyon::client::tachyon_client::TachyonClient;
@@ -51,7 +49,7 @@ pub(super) async fn ab_contact_add(request : AbcontactAddMessageSoapEnvelope, _t
                  if let Some(Ok(contact_email)) = contact_info.passport_name.map(|p| EmailAddress::from_str(&p)) {
 
                      //We were sent a room sha1d email
-                     if let Ok(Some(room)) = client.find_room_from_email(&contact_email) {
+                     if let Ok(Some(room)) = client.find_room_from_email(&contact_email, tachyon_client.room_hashes()) {
                         if let Ok(invite) = room.invite_details().await {
                             room.join().await?;
                             return Ok(contact_create(&contact_email, &cache_key, soap_action, tachyon_client).await?);
@@ -65,7 +63,7 @@ pub(super) async fn ab_contact_add(request : AbcontactAddMessageSoapEnvelope, _t
                              let dm = client.create_dm(&contact_user_id).await?;
                              if let Some(invite_msg) = invite_msg {
                                  let message = RoomMessageEventContent::text_plain(invite_msg);
-                                 let _ = dm.send_with_dedup(message).await;
+                                 let _ = tachyon_client.message_dedup().send_with_dedup(&dm, message).await;
                              }
                              return Ok(contact_create(&contact_email, &cache_key, soap_action, tachyon_client).await?);
                          }
@@ -74,7 +72,7 @@ pub(super) async fn ab_contact_add(request : AbcontactAddMessageSoapEnvelope, _t
                                     let dm = client.create_dm(&contact_user_id).await?;
                                     if let Some(invite_msg) = invite_msg {
                                         let message = RoomMessageEventContent::text_plain(invite_msg);
-                                        let _ = dm.send_with_dedup(message).await;
+                                        let _ = tachyon_client.message_dedup().send_with_dedup(&dm, message).await;
                                     }
                                     return Ok(contact_create(&contact_email, &cache_key, soap_action, tachyon_client).await?);
                                 } else {
@@ -88,7 +86,7 @@ pub(super) async fn ab_contact_add(request : AbcontactAddMessageSoapEnvelope, _t
                                                 dm.invite_user_by_id(&contact_user_id).await?;
                                             }
                                         }
-                                        return Ok(contact_already_exists(&dm, &soap_action)?);
+                                        return Ok(contact_already_exists(&dm, tachyon_client.room_hashes(), &soap_action)?);
                                     }
 
                                     //FIXME: Doesnt work, we cannot rejoined invited room
@@ -153,7 +151,8 @@ async fn contact_create(contact_email_addr: &EmailAddress, soap_action: &str,  c
     let contact_email_addr_clone = contact_email_addr.clone();
 
     //Delete user contact mapping
-    task::spawn(async move {
+    let client = tachyon_client.clone();
+    client.spawn("delete user contact mapping", async move {
         let _ = delete_user_contact(contact_email_addr_clone, tachyon_client).await;
     });
 
@@ -166,8 +165,8 @@ async fn contact_create(contact_email_addr: &EmailAddress, soap_action: &str,  c
 
 }
 
-fn contact_already_exists(room: &Room, soap_action: &str) -> Result<Response, anyhow::Error> {
-    let uuid = room.to_email_address()?.to_uuid();
+fn contact_already_exists(room: &Room, room_hashes: &RoomHashCache, soap_action: &str) -> Result<Response, anyhow::Error> {
+    let uuid = room.to_email_address(room_hashes)?.to_uuid();
     Ok(shared::build_soap_response(SoapFaultResponseEnvelope::new_contact_already_exists(soap_action, &uuid).to_xml()?, StatusCode::OK))
 
 }
\ No newline at end of file
@@ -43,7 +43,7 @@ pub(super) async fn ab_contact_delete(request : AbcontactDeleteMessageSoapEnvelo
         }
     };
 
-    match client.find_room_from_email(&contact.email_address)? {
+    match client.find_room_from_email(&contact.email_address, tachyon_client.room_hashes())? {
         Some(room) => {
             room.leave().await?;
 
@@ -46,7 +46,7 @@ pub(super) async fn ab_contact_update(request : AbcontactUpdateMessageSoapEnvelo
 
     if let Some(messenger_user) = contact_info.is_messenger_user {
         if !messenger_user {
-            match client.find_room_from_email(&contact.email_address)? {
+            match client.find_room_from_email(&contact.email_address, tachyon_client.room_hashes())? {
                 Some(room) => {
                     room.leave().await?;
                     let soap_body = AbcontactUpdateResponseMessageSoapEnvelope::get_response(&cache_key);
@@ -113,7 +113,9 @@ pub enum RST2Error {
     #[error(transparent)]
     TachyonError(#[from] TachyonError),
     #[error("An internal server error has occured")]
-    InternalServerError{source: anyhow::Error}
+    InternalServerError{source: anyhow::Error},
+    #[error("Web login required at {url}")]
+    WebLoginRequired{url: String}
 }
 
 impl IntoResponse for RST2Error {
@@ -137,6 +139,9 @@ impl IntoResponse for RST2Error {
             },
             RST2Error::InternalServerError { .. } => {
                 shared::build_soap_response(RST2ResponseFactory::get_bad_request(), StatusCode::INTERNAL_SERVER_ERROR)
+            },
+            RST2Error::WebLoginRequired { url } => {
+                shared::build_soap_response(RST2ResponseFactory::get_web_login_required_response(&url), StatusCode::OK)
             }
         }
     }
@@ -27,10 +27,19 @@ pub async fn rst2_handler(headers: HeaderMap, State(state): State<GlobalState>,
 
     let email = EmailAddress::from_str(&creds.username)?;
 
-    match state.take_pending_ticket(&email) {
+    if !state.has_pending_ticket(&email) {
+        let web_login_url = state.get_config().web_url(&format!("/tachyon/auth?username={}", urlencoding::encode(email.as_str())));
+        if state.get_config().hosted.enabled {
+            return Err(RST2Error::WebLoginRequired { url: web_login_url });
+        }
+
+        url_open::open(&Url::from_str(&web_login_url).unwrap());
+        return Err(RST2Error::InternalServerError { source: anyhow!("Used magic password, opening web login.") });
+    }
+
+    match state.take_pending_ticket(&email, &creds.password) {
             None => {
-                url_open::open(&Url::from_str(format!("http://127.0.0.1:{}/tachyon/auth?username={}", state.get_config().http_port, email.as_str()).as_str()).unwrap());
-                Err(RST2Error::InternalServerError { source: anyhow!("Used magic password, opening web login.") })
+                Err(RST2Error::AuthenticationFailed { source: anyhow!("Wrong web login code for {}", email) })
             }
             Some(ticket_token) => {
                 let soap_body = RST2ResponseFactory::get_rst2_success_response(
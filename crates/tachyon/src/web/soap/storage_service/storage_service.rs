@@ -1,4 +1,6 @@
+use crate::tachyon::client::tachyon_client::TachyonClient;
 use crate::tachyon::global_state::GlobalState;
+use crate::tachyon::config::tachyon_config::TachyonConfig;
 use crate::tachyon::mappers::user_id;
 use crate::tachyon::mappers::user_id::MatrixIdCompatible;
 use crate::tachyon::mappers::uuid::ToUuid;
@@ -14,7 +16,6 @@ use base64::engine::general_purpose;
 use base64::Engine;
 use log::error;
 use matrix_sdk::Client;
-use mime::Mime;
 use msnp::shared::models::email_address::EmailAddress;
 use msnp::shared::models::ticket_token::TicketToken;
 use msnp::soap::storage_service::delete_relationships::request::DeleteRelationshipsMessageSoapEnvelope;
@@ -41,13 +42,13 @@ pub async fn storage_service(headers: HeaderMap, State(state): State<GlobalState
 
     match soap_action {
         "http://www.msn.com/webservices/storage/2008/GetProfile" => {
-            get_profile(GetProfileMessageSoapEnvelope::try_from_xml(&body)?, token, client).await
+            get_profile(GetProfileMessageSoapEnvelope::try_from_xml(&body)?, token, client, state.get_config()).await
         },
         "http://www.msn.com/webservices/storage/2008/UpdateProfile" => {
             update_profile(UpdateProfileMessageSoapEnvelope::try_from_xml(&body)?, token, client).await
         },
         "http://www.msn.com/webservices/storage/2008/UpdateDocument" => {
-            update_document(UpdateDocumentMessageSoapEnvelope::try_from_xml(&body)?, token, client).await
+            update_document(UpdateDocumentMessageSoapEnvelope::try_from_xml(&body)?, token, tachyon_client.clone()).await
         }
         "http://www.msn.com/webservices/storage/2008/DeleteRelationships" => {
             delete_relationships(DeleteRelationshipsMessageSoapEnvelope::try_from_xml(&body)?, token, client).await
@@ -93,21 +94,16 @@ async fn delete_relationships(request: DeleteRelationshipsMessageSoapEnvelope, _
 
 }
 
-async fn update_document(request: UpdateDocumentMessageSoapEnvelope, _token: TicketToken, client: Client) -> Result<Response, ABError> {
+async fn update_document(request: UpdateDocumentMessageSoapEnvelope, _token: TicketToken, tachyon_client: TachyonClient) -> Result<Response, ABError> {
     let document_streams = request.body.body.document.document_streams.document_stream;
 
     for document_stream in document_streams {
         if document_stream.document_stream_type == "UserTileStatic" {
-            //We need to figure out the filetype from the content, because msn always sends png.
             let data_vector = general_purpose::STANDARD.decode(document_stream.data.ok_or(anyhow!("Document stream contained no data"))
                 ?)
                 .map_err(|e| anyhow!("Failed to decode base64 document stream data: {}", e))?;
 
-            let mime = get_mime_type(&data_vector);
-
-            let mtx_upload_response = client.account().upload_avatar(&mime, data_vector).await?;
-
-            let mtx_avatar_response = client.account().set_avatar_url(Some(mtx_upload_response.as_ref())).await?;
+            tachyon_client.set_own_avatar(data_vector).await?;
         }
     }
 
@@ -116,19 +112,9 @@ async fn update_document(request: UpdateDocumentMessageSoapEnvelope, _token: Tic
 
 }
 
-fn get_mime_type(data_vector: &Vec<u8>) -> Mime {
-    if &data_vector[0..3] == b"GIF" {
-        return mime::IMAGE_GIF
-    } else if &data_vector[0..2] == b"\xff\xd8" {
-        return mime::IMAGE_JPEG
-    } else if &data_vector[0..8] == b"\x89PNG\x0d\x0a\x1a\x0a"{
-        return mime::IMAGE_PNG;
-    }
-    return mime::IMAGE_BMP;
-}
 
 
-async fn get_profile(_request: GetProfileMessageSoapEnvelope, _token: TicketToken, matrix_client: Client) -> Result<Response, ABError> {
+async fn get_profile(_request: GetProfileMessageSoapEnvelope, token: TicketToken, matrix_client: Client, config: &TachyonConfig) -> Result<Response, ABError> {
     let user_id = matrix_client.user_id().ok_or(anyhow!("Expected to have user_id in matrix client"))?;
     let msn_addr = EmailAddress::from_user_id(user_id);
     let uuid = msn_addr.to_uuid();
@@ -137,7 +123,8 @@ async fn get_profile(_request: GetProfileMessageSoapEnvelope, _token: TicketToke
 
     let avatar_mxid = matrix_client.account().get_avatar_url().await?.map(|a| general_purpose::STANDARD.encode(a.as_str()));
 
-    let soap_body = GetProfileResponseMessageSoapEnvelope::new(uuid, DEFAULT_CACHE_KEY.to_string(), display_name, String::new(), avatar_mxid);
+    //The usertile route has no SOAP header, the ticket tells it which account's media to fetch.
+    let soap_body = GetProfileResponseMessageSoapEnvelope::new(uuid, DEFAULT_CACHE_KEY.to_string(), display_name, String::new(), avatar_mxid, &config.web_url("/storage/usertile"), Some(format!("t={}", token.as_str())));
     Ok(shared::build_soap_response(soap_body.to_xml()?, StatusCode::OK))
 
 }
@@ -43,17 +43,17 @@ pub async fn post_login_request(
     client.alerts().insert(notification_id, alert);
     state.store_pending_alert(notification_id, recv);
 
-    let nfy_url = format!(
-        "http://127.0.0.1:{}/tachyon/login/nfy?t={}&notification_id={}&email={}",
-        state.get_config().http_port ,&token, notification_id, username
-    );
+    let nfy_url = state.get_config().web_url(&format!(
+        "/tachyon/login/nfy?t={}&notification_id={}&email={}",
+        &token, notification_id, username
+    ));
 
     let secret_not = NotificationServerCommand::NOT(NotServer {
         payload: NotificationPayloadType::Normal(NotificationFactory::alert(
             &user.uuid,
             user.get_email_address(),
             "Login request to Tachyon Web",
-            format!("http://127.0.0.1:{}/tachyon", state.get_config().http_port).as_str(),
+            state.get_config().web_url("/tachyon").as_str(),
             &nfy_url,
             &nfy_url,
             None,
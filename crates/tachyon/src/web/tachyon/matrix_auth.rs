@@ -38,6 +38,7 @@ pub async fn post_auth(
     let email = EmailAddress::from_str(username).unwrap();
     let matrix_id = email.to_owned_user_id();
 
+    let mut login_code = None;
     let login_successful =
         if let Ok((matrix_token, _)) = state.matrix_login_service().login_with_password(&matrix_id, password, !state.get_config().strict_ssl).await {
             let ticket_token = TicketToken(
@@ -51,7 +52,11 @@ pub async fn post_auth(
                     })
                     .unwrap(),
             );
-            state.store_pending_ticket(email, ticket_token);
+            //Anyone can post RST2 for any username on a shared server, only this browser gets to see the code.
+            if state.get_config().hosted.enabled {
+                login_code = Some(generate_login_code());
+            }
+            state.store_pending_ticket(email, ticket_token, login_code.clone());
             true
         } else {
             false
@@ -63,6 +68,10 @@ pub async fn post_auth(
                 h2 { "Log-in Result" }
                 @if login_successful {
                     p { "Login successful for " (username) "!" }
+                    @if let Some(login_code) = &login_code {
+                        p { "Sign in to Messenger with this code as your password:" }
+                        p { strong { (login_code) } }
+                    }
                 } @else {
                     p { "Login failed. Please try again." }
                 }
@@ -71,4 +80,14 @@ pub async fn post_auth(
     };
 
     Html(page.into_string())
-}
\ No newline at end of file
+}
+
+//No 0/O or 1/I, the code is typed by hand. 12 characters out of 32 is 60 bits.
+const LOGIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const LOGIN_CODE_LENGTH: usize = 12;
+
+fn generate_login_code() -> String {
+    (0..LOGIN_CODE_LENGTH)
+        .map(|_| LOGIN_CODE_ALPHABET[rand::random::<u32>() as usize % LOGIN_CODE_ALPHABET.len()] as char)
+        .collect()
+}
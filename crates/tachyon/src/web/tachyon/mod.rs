@@ -1,4 +1,4 @@
-mod middleware;
+pub(crate) mod middleware;
 mod layout;
 mod matrix_auth;
 mod login;
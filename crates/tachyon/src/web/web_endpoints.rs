@@ -1,12 +1,13 @@
 use std::str::from_utf8;
 use axum::body::Body;
-use axum::extract::{Path, State};
+use axum::extract::{Extension, Path, State};
 use axum::http::{HeaderMap, Response, StatusCode};
 use axum::http::header::{CONTENT_TYPE, LOCATION, SET_COOKIE};
 use base64::Engine;
 use base64::engine::general_purpose;
 use lazy_static::lazy_static;
 use lazy_static_include::lazy_static_include_bytes;
+use log::warn;
 use matrix_sdk::media::{MediaFormat, MediaRequestParameters, MediaThumbnailSettings};
 use matrix_sdk::ruma::events::room::MediaSource;
 use matrix_sdk::ruma::{OwnedMxcUri, UInt};
@@ -35,7 +36,10 @@ pub async fn firewall_test() -> StatusCode {
 pub async fn get_msgr_config(State(state): State<GlobalState>) -> Response<Body> {
     let data: &'static [u8] = *MSGR_CONFIG_XML;
     let str = from_utf8(data).expect("MsgrConfig to be valid").to_string();
-    let out = str.replace("%port%", state.get_config().http_port.to_string().as_str());
+    let out = str
+        .replace("%scheme%", state.get_config().web_scheme())
+        .replace("%host%", state.get_config().web_host().as_str())
+        .replace("%port%", state.get_config().http_port.to_string().as_str());
 
     build_soap_response(out, StatusCode::OK)
 }
@@ -78,21 +82,40 @@ pub async fn ppcrlcheck() -> Response<Body> {
 
 }
 
-pub async fn get_profile_pic(Path((image_mxid, _image_type)): Path<(String, String)>, State(state): State<GlobalState>) -> Response<Body> {
+pub async fn get_profile_pic(Path((image_mxid, _image_type)): Path<(String, String)>, State(state): State<GlobalState>, token: Option<Extension<String>>) -> Response<Body> {
 
-    //Todo handle errors
-    let image_mxid = String::from_utf8(general_purpose::STANDARD.decode(image_mxid.as_bytes()).unwrap()).unwrap();
-    let parsed_mxc = OwnedMxcUri::from(image_mxid);
+    let Some(client) = token.and_then(|Extension(token)| state.tachyon_clients().get(&token)) else {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::empty())
+            .expect("Response to be valid");
+    };
 
-    let client_data = state.get_single_client().unwrap();
+    let decoded = general_purpose::STANDARD.decode(image_mxid.as_bytes()).ok().and_then(|raw| String::from_utf8(raw).ok());
+    let Some(parsed_mxc) = decoded.map(OwnedMxcUri::from).filter(|mxc| mxc.is_valid()) else {
+        warn!("Profile picture requested with an invalid mxc: {}", &image_mxid);
+        return Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::empty())
+            .expect("Response to be valid");
+    };
 
-    let client= state.tachyon_clients().get(client_data.ticket_token().as_str()).unwrap().matrix_client();
+    let client = client.matrix_client();
 
 
     let thumbnail_settings = MediaThumbnailSettings::new(UInt::new(200).unwrap(), UInt::new(200).unwrap() );
 
-    let media_request = MediaRequestParameters{ source: MediaSource::Plain(parsed_mxc), format: MediaFormat::Thumbnail(thumbnail_settings)};
-    let image = client.media().get_media_content(&media_request, true).await.unwrap();
+    let media_request = MediaRequestParameters{ source: MediaSource::Plain(parsed_mxc.clone()), format: MediaFormat::Thumbnail(thumbnail_settings)};
+    let image = match client.media().get_media_content(&media_request, true).await {
+        Ok(image) => image,
+        Err(e) => {
+            warn!("Could not get profile picture {}: {}", &parsed_mxc, e);
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .expect("Response to be valid");
+        }
+    };
 
     Response::builder()
         .header(CONTENT_TYPE, "image/jpeg")
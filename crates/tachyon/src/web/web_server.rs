@@ -1,4 +1,6 @@
-use std::str::from_utf8;
+use std::net::SocketAddr;
+use std::str::{from_utf8, FromStr};
+use std::time::Duration;
 
 use anyhow::anyhow;
 use axum::body::Body;
@@ -8,6 +10,8 @@ use axum::middleware::Next;
 use axum::response::Response;
 use axum::routing::{get, post};
 use axum::{middleware, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
 use http_body_util::BodyExt;
 use log::{debug, info, log_enabled, warn, Level};
 use tokio::net::TcpListener;
@@ -22,11 +26,14 @@ use crate::web::soap::sharing_service::sharing_service::sharing_service;
 
 use crate::web::soap::rst2::rst2_handler;
 use crate::web::soap::storage_service::storage_service::storage_service;
+use crate::web::tachyon::middleware::extract_token;
 use crate::web::tachyon::tachyon_router;
 use crate::web::web_endpoints::{firewall_test, get_msgr_config, get_profile_pic, ppcrlcheck, ppcrlconfigsrf, sha1auth, wlidsvcconfig};
 
 pub struct WebServer;
 
+const TLS_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
 
 impl WebServer {
     pub async fn listen(ip_addr: &str, port: u32, global_kill_recv: Receiver<()>, tachyon_state: GlobalState) -> Result<(), anyhow::Error> {
@@ -34,6 +41,7 @@ impl WebServer {
 
 
         let state = tachyon_state;
+        let state_config = state.get_config().clone();
 
         let app = Router::new()
             .route("/", post(firewall_test))
@@ -47,7 +55,7 @@ impl WebServer {
             .route("/wlidsvcconfig.xml", get(wlidsvcconfig))
             .route("/pcrlcheck.srf", get(ppcrlcheck))
             .route("/RST2.srf", post(rst2_handler))
-            .route("/storage/usertile/{image_mxid}/{image_type}", get(get_profile_pic))
+            .route("/storage/usertile/{image_mxid}/{image_type}", get(get_profile_pic).layer(middleware::from_fn(extract_token)))
             //SOAP
             .route("/abservice/abservice.asmx", post(address_book_service))
             .route("/abservice/SharingService.asmx", post(sharing_service))
@@ -57,6 +65,21 @@ impl WebServer {
             .layer(middleware::from_fn(my_middleware))
             .fallback(fallback);
 
+        if let Some(tls) = state_config.tls {
+            let address = SocketAddr::from_str(&format!("{}:{}", ip_addr, port))?;
+            let rustls_config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await
+                .map_err(|e| anyhow!("Couldn't load TLS certificate {} and key {}: {}", tls.cert_path.display(), tls.key_path.display(), e))?;
+
+            let handle = Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signals(global_kill_recv).await;
+                shutdown_handle.graceful_shutdown(Some(TLS_SHUTDOWN_GRACE_PERIOD));
+            });
+
+            return axum_server::bind_rustls(address, rustls_config).handle(handle).serve(app.into_make_service()).await.map_err(|e| e.into());
+        }
+
         let listener = TcpListener::bind(format!("{}:{}", ip_addr, port))
             .await.map_err(|e| anyhow!(e))?;
 